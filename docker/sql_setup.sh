@@ -64,6 +64,7 @@ port = 5433
 ssl = on
 ssl_cert_file = 'server.crt'
 ssl_key_file = 'server.key'
+max_prepared_transactions = 10
 EOCONF
 
 cat > "$PGDATA/pg_hba.conf" <<-EOCONF
@@ -61,7 +61,7 @@ use tokio::io::{AsyncRead, AsyncWrite, BufReader, ReadBuf};
 use tokio_postgres::tls;
 #[cfg(feature = "runtime")]
 use tokio_postgres::tls::MakeTlsConnect;
-use tokio_postgres::tls::{ChannelBinding, TlsConnect};
+use tokio_postgres::tls::{ChannelBinding, TlsConnect, TlsInfo};
 
 #[cfg(test)]
 mod test;
@@ -179,4 +179,18 @@ where
             None => ChannelBinding::none(),
         }
     }
+
+    fn tls_info(&self) -> TlsInfo {
+        // native-tls only exposes the server's own certificate, not the protocol version or cipher
+        let certificate = self
+            .0
+            .get_ref()
+            .peer_certificate()
+            .ok()
+            .flatten()
+            .and_then(|c| c.to_der().ok());
+        TlsInfo::new()
+            .peer_certificates(certificate.into_iter().collect())
+            .channel_binding(tls::TlsStream::channel_binding(self))
+    }
 }
@@ -68,7 +68,7 @@ use tokio_openssl::SslStream;
 use tokio_postgres::tls;
 #[cfg(feature = "runtime")]
 use tokio_postgres::tls::MakeTlsConnect;
-use tokio_postgres::tls::{ChannelBinding, TlsConnect};
+use tokio_postgres::tls::{ChannelBinding, TlsConnect, TlsInfo};
 
 #[cfg(test)]
 mod test;
@@ -244,6 +244,20 @@ where
             None => ChannelBinding::none(),
         }
     }
+
+    fn tls_info(&self) -> TlsInfo {
+        let ssl = self.0.ssl();
+        let mut info = TlsInfo::new()
+            .protocol_version(ssl.version_str())
+            .channel_binding(tls::TlsStream::channel_binding(self));
+        if let Some(cipher) = ssl.current_cipher() {
+            info = info.cipher(cipher.name());
+        }
+        if let Some(chain) = ssl.peer_cert_chain() {
+            info = info.peer_certificates(chain.iter().filter_map(|c| c.to_der().ok()).collect());
+        }
+        info
+    }
 }
 
 fn tls_server_end_point(ssl: &SslRef) -> Option<Vec<u8>> {
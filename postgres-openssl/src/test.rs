@@ -49,6 +49,32 @@ async fn prefer() {
     .await;
 }
 
+#[tokio::test]
+async fn tls_info() {
+    let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+    builder.set_ca_file("../test/server.crt").unwrap();
+    let ctx = builder.build();
+    let connector = TlsConnector::new(ctx.configure().unwrap(), "localhost");
+
+    let stream = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let config = "user=ssl_user dbname=postgres sslmode=require"
+        .parse::<tokio_postgres::Config>()
+        .unwrap();
+    let (client, connection) = config.connect_raw(stream, connector).await.unwrap();
+    let connection = connection.map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    let info = client.tls_info().unwrap();
+    assert!(info.get_protocol_version().unwrap().starts_with("TLS"));
+    assert!(info.get_cipher().is_some());
+    let server_cert = openssl::x509::X509::from_pem(&std::fs::read("../test/server.crt").unwrap())
+        .unwrap()
+        .to_der()
+        .unwrap();
+    assert_eq!(info.get_peer_certificates()[0], server_cert);
+    assert!(info.get_tls_server_end_point().is_some());
+}
+
 #[tokio::test]
 async fn scram_user() {
     let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
@@ -7,7 +7,8 @@ use hmac::{Hmac, Mac};
 use rand::{self, Rng};
 use sha2::digest::FixedOutput;
 use sha2::{Digest, Sha256};
-use std::fmt::Write;
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
 use std::io;
 use std::iter;
 use std::mem;
@@ -35,6 +36,34 @@ fn normalize(pass: &[u8]) -> Vec<u8> {
     }
 }
 
+fn nonce() -> String {
+    // rand 0.5's ThreadRng is cryptographically secure
+    let mut rng = rand::thread_rng();
+    (0..NONCE_LENGTH)
+        .map(|_| {
+            let mut v = rng.gen_range(0x21u8..0x7e);
+            if v == 0x2c {
+                v = 0x7e
+            }
+            v as char
+        })
+        .collect()
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut hmac =
+        Hmac::<Sha256>::new_from_slice(key).expect("HMAC is able to accept all key sizes");
+    hmac.update(message);
+    hmac.finalize().into_bytes().into()
+}
+
+fn invalid_input<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
 pub(crate) fn hi(str: &[u8], salt: &[u8], i: u32) -> [u8; 32] {
     let mut hmac =
         Hmac::<Sha256>::new_from_slice(str).expect("HMAC is able to accept all key sizes");
@@ -135,19 +164,7 @@ pub struct ScramSha256 {
 impl ScramSha256 {
     /// Constructs a new instance which will use the provided password for authentication.
     pub fn new(password: &[u8], channel_binding: ChannelBinding) -> ScramSha256 {
-        // rand 0.5's ThreadRng is cryptographically secure
-        let mut rng = rand::thread_rng();
-        let nonce = (0..NONCE_LENGTH)
-            .map(|_| {
-                let mut v = rng.gen_range(0x21u8..0x7e);
-                if v == 0x2c {
-                    v = 0x7e
-                }
-                v as char
-            })
-            .collect::<String>();
-
-        ScramSha256::new_inner(password, channel_binding, nonce)
+        ScramSha256::new_inner(password, channel_binding, nonce())
     }
 
     fn new_inner(password: &[u8], channel_binding: ChannelBinding, nonce: String) -> ScramSha256 {
@@ -288,6 +305,246 @@ impl ScramSha256 {
     }
 }
 
+/// The secret from which a server verifies SCRAM-SHA-256 authentication, as stored by Postgres in `pg_authid`.
+///
+/// Its text representation has the form `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`, with the salt
+/// and keys base64-encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScramSecret {
+    iterations: u32,
+    salt: Vec<u8>,
+    stored_key: [u8; 32],
+    server_key: [u8; 32],
+}
+
+impl ScramSecret {
+    /// Derives the secret of a password with the provided salt and iteration count.
+    pub fn new(password: &[u8], salt: &[u8], iterations: u32) -> ScramSecret {
+        let salted_password = hi(&normalize(password), salt, iterations);
+        let client_key = hmac(&salted_password, b"Client Key");
+
+        ScramSecret {
+            iterations,
+            salt: salt.to_vec(),
+            stored_key: Sha256::digest(client_key).into(),
+            server_key: hmac(&salted_password, b"Server Key"),
+        }
+    }
+
+    /// Parses a secret from its text representation.
+    pub fn parse(s: &str) -> io::Result<ScramSecret> {
+        let rest = s
+            .strip_prefix("SCRAM-SHA-256$")
+            .ok_or_else(|| invalid_input("invalid SCRAM secret"))?;
+        let (iterations, rest) = rest
+            .split_once(':')
+            .ok_or_else(|| invalid_input("invalid SCRAM secret"))?;
+        let (salt, rest) = rest
+            .split_once('$')
+            .ok_or_else(|| invalid_input("invalid SCRAM secret"))?;
+        let (stored_key, server_key) = rest
+            .split_once(':')
+            .ok_or_else(|| invalid_input("invalid SCRAM secret"))?;
+
+        let key = |key: &str| -> io::Result<[u8; 32]> {
+            let key = STANDARD.decode(key).map_err(invalid_input)?;
+            <[u8; 32]>::try_from(key.as_slice())
+                .map_err(|_| invalid_input("invalid SCRAM key length"))
+        };
+
+        Ok(ScramSecret {
+            iterations: iterations.parse().map_err(invalid_input)?,
+            salt: STANDARD.decode(salt).map_err(invalid_input)?,
+            stored_key: key(stored_key)?,
+            server_key: key(server_key)?,
+        })
+    }
+
+    /// Returns the iteration count of the secret.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    /// Returns the salt of the secret.
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Returns the `StoredKey` of the secret, the hash of the client key.
+    pub fn stored_key(&self) -> &[u8; 32] {
+        &self.stored_key
+    }
+
+    /// Returns the `ServerKey` of the secret.
+    pub fn server_key(&self) -> &[u8; 32] {
+        &self.server_key
+    }
+}
+
+impl fmt::Display for ScramSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SCRAM-SHA-256${}:{}${}:{}",
+            self.iterations,
+            Base64Display::new(&self.salt, &STANDARD),
+            Base64Display::new(&self.stored_key, &STANDARD),
+            Base64Display::new(&self.server_key, &STANDARD)
+        )
+    }
+}
+
+enum ServerState {
+    Update {
+        secret: ScramSecret,
+        cbind_input: String,
+        nonce: String,
+        client_first_bare: String,
+    },
+    Done,
+}
+
+/// A type which handles the server side of the SCRAM-SHA-256/SCRAM-SHA-256-PLUS authentication
+/// process, for servers and proxies which authenticate clients themselves.
+///
+/// After sending an `AuthenticationSASL` message listing `SCRAM-SHA-256`, the server receives a
+/// `SASLInitialResponse` message. Its data should be passed to `new()` along with the secret of
+/// the user, after which the buffer returned by the `message()` method should be sent to the client
+/// in an `AuthenticationSASLContinue` message.
+///
+/// The client will reply with a `SASLResponse` message. Its data should be passed to the
+/// `finish()` method. If it succeeds, the client has proven knowledge of the password and the
+/// buffer returned by the `message()` method should be sent to the client in an
+/// `AuthenticationSASLFinal` message. Otherwise authentication has failed.
+pub struct ScramSha256Server {
+    message: String,
+    state: ServerState,
+}
+
+impl ScramSha256Server {
+    /// Constructs a new instance from the client's first message.
+    ///
+    /// `tls_server_end_point` is the `tls-server-end-point` channel binding data of the connection,
+    /// which should only be provided if the server offered `SCRAM-SHA-256-PLUS`. If the client
+    /// requests channel binding, it is required, and if it is present, the client may not claim
+    /// that the server does not support channel binding.
+    pub fn new(
+        secret: ScramSecret,
+        client_first: &[u8],
+        tls_server_end_point: Option<&[u8]>,
+    ) -> io::Result<ScramSha256Server> {
+        ScramSha256Server::new_inner(secret, client_first, tls_server_end_point, nonce())
+    }
+
+    fn new_inner(
+        secret: ScramSecret,
+        client_first: &[u8],
+        tls_server_end_point: Option<&[u8]>,
+        server_nonce: String,
+    ) -> io::Result<ScramSha256Server> {
+        let client_first = str::from_utf8(client_first).map_err(invalid_input)?;
+        let parsed = Parser::new(client_first).client_first_message()?;
+
+        let cbind_data: &[u8] = match (parsed.channel_binding, tls_server_end_point) {
+            (ClientChannelBinding::Unsupported, _) => &[],
+            (ClientChannelBinding::Unrequested, None) => &[],
+            (ClientChannelBinding::Unrequested, Some(_)) => {
+                return Err(invalid_input(
+                    "client does not use channel binding although the server supports it",
+                ))
+            }
+            (ClientChannelBinding::Required("tls-server-end-point"), Some(data)) => data,
+            (ClientChannelBinding::Required(name), _) => {
+                return Err(invalid_input(format!(
+                    "unsupported channel binding type `{}`",
+                    name
+                )))
+            }
+        };
+
+        // the client echoes its GS2 header followed by the channel binding data in its final message
+        let gs2_header = &client_first[..client_first.len() - parsed.bare.len()];
+        let mut cbind_input = gs2_header.as_bytes().to_vec();
+        cbind_input.extend(cbind_data);
+
+        let nonce = format!("{}{}", parsed.nonce, server_nonce);
+        let message = format!(
+            "r={},s={},i={}",
+            nonce,
+            Base64Display::new(&secret.salt, &STANDARD),
+            secret.iterations
+        );
+
+        Ok(ScramSha256Server {
+            message,
+            state: ServerState::Update {
+                secret,
+                cbind_input: STANDARD.encode(cbind_input),
+                nonce,
+                client_first_bare: parsed.bare.to_string(),
+            },
+        })
+    }
+
+    /// Returns the message which should be sent to the client.
+    pub fn message(&self) -> &[u8] {
+        self.message.as_bytes()
+    }
+
+    /// Verifies the client's final message.
+    ///
+    /// This should be called when a `SASLResponse` message is received. Authentication has only
+    /// succeeded if this method returns `Ok(())`.
+    pub fn finish(&mut self, client_final: &[u8]) -> io::Result<()> {
+        let (secret, cbind_input, nonce, client_first_bare) =
+            match mem::replace(&mut self.state, ServerState::Done) {
+                ServerState::Update {
+                    secret,
+                    cbind_input,
+                    nonce,
+                    client_first_bare,
+                } => (secret, cbind_input, nonce, client_first_bare),
+                ServerState::Done => return Err(io::Error::other("invalid SCRAM state")),
+            };
+
+        let client_final = str::from_utf8(client_final).map_err(invalid_input)?;
+        let parsed = Parser::new(client_final).client_final_message()?;
+
+        if parsed.channel_binding != cbind_input {
+            return Err(invalid_input("invalid channel binding"));
+        }
+        if parsed.nonce != nonce {
+            return Err(invalid_input("invalid nonce"));
+        }
+        let proof = STANDARD.decode(parsed.proof).map_err(invalid_input)?;
+        let mut client_key = <[u8; 32]>::try_from(proof.as_slice())
+            .map_err(|_| invalid_input("invalid proof length"))?;
+
+        let auth_message = format!(
+            "{},{},{}",
+            client_first_bare, self.message, parsed.without_proof
+        );
+        let client_signature = hmac(&secret.stored_key, auth_message.as_bytes());
+        for (key, signature) in client_key.iter_mut().zip(client_signature) {
+            *key ^= signature;
+        }
+
+        // compare in constant time so the proof can't be guessed byte by byte
+        let stored_key: [u8; 32] = Sha256::digest(client_key).into();
+        let diff = stored_key
+            .iter()
+            .zip(&secret.stored_key)
+            .fold(0, |diff, (a, b)| diff | (a ^ b));
+        if diff != 0 {
+            return Err(invalid_input("SCRAM verification error"));
+        }
+
+        let server_signature = hmac(&secret.server_key, auth_message.as_bytes());
+        self.message = format!("v={}", Base64Display::new(&server_signature, &STANDARD));
+        Ok(())
+    }
+}
+
 struct Parser<'a> {
     s: &'a str,
     it: iter::Peekable<str::CharIndices<'a>>,
@@ -424,6 +681,71 @@ impl<'a> Parser<'a> {
         self.eof()?;
         Ok(message)
     }
+
+    fn saslname(&mut self) -> io::Result<&'a str> {
+        self.take_while(|c| c != ',')
+    }
+
+    fn client_first_message(&mut self) -> io::Result<ClientFirstMessage<'a>> {
+        let channel_binding =
+            match self.it.next() {
+                Some((_, 'n')) => ClientChannelBinding::Unsupported,
+                Some((_, 'y')) => ClientChannelBinding::Unrequested,
+                Some((_, 'p')) => {
+                    self.eat('=')?;
+                    ClientChannelBinding::Required(self.take_while(
+                        |c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-'),
+                    )?)
+                }
+                _ => return Err(invalid_input("invalid channel binding flag")),
+            };
+        self.eat(',')?;
+        if !self.saslname()?.is_empty() {
+            return Err(invalid_input("authorization identities are not supported"));
+        }
+        self.eat(',')?;
+
+        let bare = match self.it.peek() {
+            Some(&(i, _)) => &self.s[i..],
+            None => "",
+        };
+        self.eat('n')?;
+        self.eat('=')?;
+        self.saslname()?;
+        self.eat(',')?;
+        let nonce = self.nonce()?;
+        self.eof()?;
+
+        Ok(ClientFirstMessage {
+            channel_binding,
+            bare,
+            nonce,
+        })
+    }
+
+    fn client_final_message(&mut self) -> io::Result<ClientFinalMessage<'a>> {
+        self.eat('c')?;
+        self.eat('=')?;
+        let channel_binding = self.base64()?;
+        self.eat(',')?;
+        let nonce = self.nonce()?;
+        let without_proof = match self.it.peek() {
+            Some(&(i, _)) => &self.s[..i],
+            None => self.s,
+        };
+        self.eat(',')?;
+        self.eat('p')?;
+        self.eat('=')?;
+        let proof = self.base64()?;
+        self.eof()?;
+
+        Ok(ClientFinalMessage {
+            channel_binding,
+            nonce,
+            without_proof,
+            proof,
+        })
+    }
 }
 
 struct ServerFirstMessage<'a> {
@@ -437,6 +759,25 @@ enum ServerFinalMessage<'a> {
     Verifier(&'a str),
 }
 
+enum ClientChannelBinding<'a> {
+    Unsupported,
+    Unrequested,
+    Required(&'a str),
+}
+
+struct ClientFirstMessage<'a> {
+    channel_binding: ClientChannelBinding<'a>,
+    bare: &'a str,
+    nonce: &'a str,
+}
+
+struct ClientFinalMessage<'a> {
+    channel_binding: &'a str,
+    nonce: &'a str,
+    without_proof: &'a str,
+    proof: &'a str,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -477,4 +818,89 @@ mod test {
 
         scram.finish(server_final.as_bytes()).unwrap();
     }
+
+    // the recorded exchange above, from the server's side
+    #[test]
+    fn server_exchange() {
+        let salt = STANDARD.decode("fs3IXBy7U7+IvVjZ").unwrap();
+        let secret = ScramSecret::new(b"foobar", &salt, 4096);
+
+        let client_first = "n,,n=,r=9IZ2O01zb9IgiIZ1WJ/zgpJB";
+        let server_first =
+            "r=9IZ2O01zb9IgiIZ1WJ/zgpJBjx/oIRLs02gGSHcw1KEty3eY,s=fs3IXBy7U7+IvVjZ,i\
+             =4096";
+        let client_final =
+            "c=biws,r=9IZ2O01zb9IgiIZ1WJ/zgpJBjx/oIRLs02gGSHcw1KEty3eY,p=AmNKosjJzS3\
+             1NTlQYNs5BTeQjdHdk7lOflDo5re2an8=";
+        let server_final = "v=U+ppxD5XUKtradnv8e2MkeupiA8FU87Sg8CXzXHDAzw=";
+
+        let mut server = ScramSha256Server::new_inner(
+            secret.clone(),
+            client_first.as_bytes(),
+            None,
+            "jx/oIRLs02gGSHcw1KEty3eY".to_string(),
+        )
+        .unwrap();
+        assert_eq!(str::from_utf8(server.message()).unwrap(), server_first);
+
+        server.finish(client_final.as_bytes()).unwrap();
+        assert_eq!(str::from_utf8(server.message()).unwrap(), server_final);
+
+        let mut server = ScramSha256Server::new(secret, client_first.as_bytes(), None).unwrap();
+        assert!(server.finish(client_final.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn server_client_exchange() {
+        let secret = ScramSecret::new(b"secret", b"salt", 4096);
+        let cbind_data = b"certificate hash";
+
+        let exchange = |password: &[u8], channel_binding, server_data: Option<&[u8]>| {
+            let mut client = ScramSha256::new(password, channel_binding);
+            let mut server = ScramSha256Server::new(secret.clone(), client.message(), server_data)?;
+            client.update(server.message())?;
+            server.finish(client.message())?;
+            client.finish(server.message())
+        };
+
+        exchange(b"secret", ChannelBinding::unsupported(), None).unwrap();
+        exchange(b"secret", ChannelBinding::unrequested(), None).unwrap();
+        exchange(
+            b"secret",
+            ChannelBinding::tls_server_end_point(cbind_data.to_vec()),
+            Some(cbind_data),
+        )
+        .unwrap();
+
+        assert!(exchange(b"wrong", ChannelBinding::unsupported(), None).is_err());
+        assert!(exchange(b"secret", ChannelBinding::unrequested(), Some(cbind_data)).is_err());
+        assert!(exchange(
+            b"secret",
+            ChannelBinding::tls_server_end_point(b"other hash".to_vec()),
+            Some(cbind_data),
+        )
+        .is_err());
+        assert!(exchange(
+            b"secret",
+            ChannelBinding::tls_server_end_point(cbind_data.to_vec()),
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn scram_secret() {
+        let secret = "SCRAM-SHA-256$4096:AQIDBAUGBwgJCgsMDQ4PEA==$8rrDg00OqaiWXJ7p+sCgHEIaBSHY89ZJl3mfIsf32oY=:05L1f+yZbiN8O0AnO40Og85NNRhvzTS57naKRWCcsIA=";
+        let parsed = ScramSecret::parse(secret).unwrap();
+        assert_eq!(parsed.iterations(), 4096);
+        assert_eq!(
+            parsed.salt(),
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]
+        );
+        assert_eq!(parsed.to_string(), secret);
+        assert_eq!(ScramSecret::new(b"secret", parsed.salt(), 4096), parsed);
+
+        assert!(ScramSecret::parse("md54ab2c5d00339c4b2a4e921d2dc4edec7").is_err());
+        assert!(ScramSecret::parse("SCRAM-SHA-256$4096:AQID$AQID:AQID").is_err());
+    }
 }
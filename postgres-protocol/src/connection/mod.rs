@@ -0,0 +1,81 @@
+//! Sans-IO state machines for the phases of a connection.
+//!
+//! The types in this module implement the logic of the protocol without performing any IO
+//! themselves: the caller reads bytes from the server and feeds them in, and writes the bytes
+//! they produce to the server. This allows the same logic to be driven by any runtime, or by
+//! none at all as in fuzzers and tests.
+//!
+//! * [`decode_frame`] splits the bytes received from the server into batches of messages.
+//! * [`Startup`] performs the startup and authentication of a connection.
+
+use crate::message::backend;
+use bytes::BytesMut;
+use std::io;
+
+pub use self::startup::{Startup, StartupError, Step};
+
+mod startup;
+
+#[cfg(test)]
+mod test;
+
+/// A batch of messages received from the server.
+pub enum Frame {
+    /// Messages sent in response to requests.
+    Messages {
+        /// The raw messages, which can be parsed with [`backend::Message::parse`].
+        messages: BytesMut,
+        /// Whether the messages end with a `ReadyForQuery` message, which completes a request.
+        request_complete: bool,
+    },
+    /// A message the server may send at any time, unrelated to a request: a `NoticeResponse`,
+    /// `NotificationResponse` or `ParameterStatus` message.
+    Async(backend::Message),
+}
+
+/// Splits the next batch of complete messages off the front of `src`.
+///
+/// Messages are batched until the end of a request, or until a message the server sends
+/// asynchronously, which is returned on its own. Returns `None` if `src` does not start with a
+/// complete message.
+pub fn decode_frame(src: &mut BytesMut) -> io::Result<Option<Frame>> {
+    let mut idx = 0;
+    let mut request_complete = false;
+
+    while let Some(header) = backend::Header::parse(&src[idx..])? {
+        let len = header.len() as usize + 1;
+        if src[idx..].len() < len {
+            break;
+        }
+
+        match header.tag() {
+            backend::NOTICE_RESPONSE_TAG
+            | backend::NOTIFICATION_RESPONSE_TAG
+            | backend::PARAMETER_STATUS_TAG => {
+                if idx == 0 {
+                    let message = backend::Message::parse(src)?.unwrap();
+                    return Ok(Some(Frame::Async(message)));
+                } else {
+                    break;
+                }
+            }
+            _ => {}
+        }
+
+        idx += len;
+
+        if header.tag() == backend::READY_FOR_QUERY_TAG {
+            request_complete = true;
+            break;
+        }
+    }
+
+    if idx == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(Frame::Messages {
+            messages: src.split_to(idx),
+            request_complete,
+        }))
+    }
+}
@@ -0,0 +1,354 @@
+use crate::authentication::md5_hash;
+use crate::authentication::sasl::{self, ChannelBinding, ScramSha256};
+use crate::message::backend::{
+    AuthenticationSaslBody, ErrorResponseBody, Message, NoticeResponseBody,
+};
+use crate::message::frontend;
+use bytes::BytesMut;
+use fallible_iterator::FallibleIterator;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::mem;
+
+/// An error which aborted the startup of a connection.
+#[non_exhaustive]
+pub enum StartupError {
+    /// The server returned an error.
+    Db(ErrorResponseBody),
+    /// The server requested a password, but none was provided.
+    PasswordMissing,
+    /// Authentication failed, or the server requested an unsupported authentication method.
+    Authentication(Box<dyn Error + Sync + Send>),
+    /// The server sent an unexpected message.
+    UnexpectedMessage,
+    /// A message from the server could not be parsed.
+    Parse(io::Error),
+    /// A message to the server could not be encoded.
+    Encode(io::Error),
+}
+
+impl fmt::Debug for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartupError::Db(_) => f.write_str("Db(..)"),
+            StartupError::PasswordMissing => f.write_str("PasswordMissing"),
+            StartupError::Authentication(e) => f.debug_tuple("Authentication").field(e).finish(),
+            StartupError::UnexpectedMessage => f.write_str("UnexpectedMessage"),
+            StartupError::Parse(e) => f.debug_tuple("Parse").field(e).finish(),
+            StartupError::Encode(e) => f.debug_tuple("Encode").field(e).finish(),
+        }
+    }
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartupError::Db(_) => f.write_str("error returned by the server"),
+            StartupError::PasswordMissing => f.write_str("password missing"),
+            StartupError::Authentication(e) => write!(f, "authentication error: {}", e),
+            StartupError::UnexpectedMessage => f.write_str("unexpected message from server"),
+            StartupError::Parse(e) => write!(f, "error parsing response from server: {}", e),
+            StartupError::Encode(e) => write!(f, "error encoding message to server: {}", e),
+        }
+    }
+}
+
+impl Error for StartupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StartupError::Authentication(e) => Some(&**e),
+            StartupError::Parse(e) | StartupError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of handling a message with [`Startup::handle`].
+pub enum Step {
+    /// The startup continues. Any messages written to the buffer should be sent to the server
+    /// before reading the next message.
+    Continue,
+    /// The server sent a notice, which may be reported to the user. The startup continues.
+    Notice(NoticeResponseBody),
+    /// The connection is ready for queries.
+    Ready,
+}
+
+enum State {
+    Initial,
+    Authenticating,
+    AwaitingOk,
+    SaslContinue(ScramSha256),
+    SaslFinal(ScramSha256),
+    ReadingInfo,
+    Ready,
+    Failed,
+}
+
+/// A state machine which handles the startup of a connection, from the startup message until the
+/// first `ReadyForQuery` message.
+///
+/// The startup message is written with the `start()` method. Each message received from the server
+/// should then be passed to the `handle()` method, sending whatever it writes to the buffer to the
+/// server, until it returns `Step::Ready`. The backend key data and runtime parameters reported by
+/// the server are then available.
+///
+/// TLS negotiation happens before the startup message, and is left to the caller.
+pub struct Startup {
+    user: String,
+    password: Option<Vec<u8>>,
+    tls_server_end_point: Option<Vec<u8>>,
+    require_channel_binding: bool,
+    state: State,
+    process_id: i32,
+    secret_key: i32,
+    parameters: Vec<(String, String)>,
+}
+
+impl Startup {
+    /// Creates a new state machine connecting as `user`.
+    pub fn new(user: &str) -> Startup {
+        Startup {
+            user: user.to_string(),
+            password: None,
+            tls_server_end_point: None,
+            require_channel_binding: false,
+            state: State::Initial,
+            process_id: 0,
+            secret_key: 0,
+            parameters: vec![],
+        }
+    }
+
+    /// Sets the password used if the server requests one.
+    pub fn password(mut self, password: &[u8]) -> Startup {
+        self.password = Some(password.to_vec());
+        self
+    }
+
+    /// Sets the `tls-server-end-point` channel binding data of the connection, used for
+    /// SCRAM-SHA-256-PLUS authentication if the server supports it.
+    pub fn tls_server_end_point(mut self, data: Vec<u8>) -> Startup {
+        self.tls_server_end_point = Some(data);
+        self
+    }
+
+    /// Sets whether authentication must use channel binding.
+    ///
+    /// Defaults to `false`.
+    pub fn require_channel_binding(mut self, require: bool) -> Startup {
+        self.require_channel_binding = require;
+        self
+    }
+
+    /// Writes the startup message to `buf`, with the user and the additional `parameters`.
+    pub fn start<'a, I>(&mut self, parameters: I, buf: &mut BytesMut) -> Result<(), StartupError>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        if !matches!(self.state, State::Initial) {
+            return Err(StartupError::UnexpectedMessage);
+        }
+
+        let mut params = vec![("user", &*self.user)];
+        for param in parameters {
+            params.push(param);
+        }
+        frontend::startup_message(params, buf).map_err(StartupError::Encode)?;
+        self.state = State::Authenticating;
+        Ok(())
+    }
+
+    /// Handles a message received from the server, writing the messages to send in response to
+    /// `buf`.
+    ///
+    /// Once an error has been returned, the startup has failed and every later call fails.
+    pub fn handle(&mut self, message: Message, buf: &mut BytesMut) -> Result<Step, StartupError> {
+        let result = self.handle_inner(message, buf);
+        if result.is_err() {
+            self.state = State::Failed;
+        }
+        result
+    }
+
+    fn handle_inner(&mut self, message: Message, buf: &mut BytesMut) -> Result<Step, StartupError> {
+        let message = match message {
+            Message::ErrorResponse(body) => return Err(StartupError::Db(body)),
+            message => message,
+        };
+
+        match (mem::replace(&mut self.state, State::Failed), message) {
+            (State::Authenticating, message) => self.authenticate(message, buf)?,
+            (State::AwaitingOk, Message::AuthenticationOk) => self.state = State::ReadingInfo,
+            (State::SaslContinue(mut scram), Message::AuthenticationSaslContinue(body)) => {
+                scram
+                    .update(body.data())
+                    .map_err(|e| StartupError::Authentication(e.into()))?;
+                frontend::sasl_response(scram.message(), buf).map_err(StartupError::Encode)?;
+                self.state = State::SaslFinal(scram);
+            }
+            (State::SaslFinal(mut scram), Message::AuthenticationSaslFinal(body)) => {
+                scram
+                    .finish(body.data())
+                    .map_err(|e| StartupError::Authentication(e.into()))?;
+                self.state = State::AwaitingOk;
+            }
+            (State::ReadingInfo, message) => {
+                self.state = State::ReadingInfo;
+                return self.read_info(message);
+            }
+            _ => return Err(StartupError::UnexpectedMessage),
+        }
+
+        Ok(Step::Continue)
+    }
+
+    fn authenticate(&mut self, message: Message, buf: &mut BytesMut) -> Result<(), StartupError> {
+        match message {
+            Message::AuthenticationOk => {
+                self.can_skip_channel_binding()?;
+                self.state = State::ReadingInfo;
+            }
+            Message::AuthenticationCleartextPassword => {
+                self.can_skip_channel_binding()?;
+                let password = self
+                    .password
+                    .as_ref()
+                    .ok_or(StartupError::PasswordMissing)?;
+                frontend::password_message(password, buf).map_err(StartupError::Encode)?;
+                self.state = State::AwaitingOk;
+            }
+            Message::AuthenticationMd5Password(body) => {
+                self.can_skip_channel_binding()?;
+                let password = self
+                    .password
+                    .as_ref()
+                    .ok_or(StartupError::PasswordMissing)?;
+                let output = md5_hash(self.user.as_bytes(), password, body.salt());
+                frontend::password_message(output.as_bytes(), buf).map_err(StartupError::Encode)?;
+                self.state = State::AwaitingOk;
+            }
+            Message::AuthenticationSasl(body) => {
+                let scram = self.authenticate_sasl(body, buf)?;
+                self.state = State::SaslContinue(scram);
+            }
+            Message::AuthenticationKerberosV5
+            | Message::AuthenticationScmCredential
+            | Message::AuthenticationGss
+            | Message::AuthenticationSspi => {
+                return Err(StartupError::Authentication(
+                    "unsupported authentication method".into(),
+                ))
+            }
+            _ => return Err(StartupError::UnexpectedMessage),
+        }
+
+        Ok(())
+    }
+
+    fn can_skip_channel_binding(&self) -> Result<(), StartupError> {
+        if self.require_channel_binding {
+            Err(StartupError::Authentication(
+                "server did not use channel binding".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn authenticate_sasl(
+        &mut self,
+        body: AuthenticationSaslBody,
+        buf: &mut BytesMut,
+    ) -> Result<ScramSha256, StartupError> {
+        let password = self
+            .password
+            .as_ref()
+            .ok_or(StartupError::PasswordMissing)?;
+
+        let mut has_scram = false;
+        let mut has_scram_plus = false;
+        let mut mechanisms = body.mechanisms();
+        while let Some(mechanism) = mechanisms.next().map_err(StartupError::Parse)? {
+            match mechanism {
+                sasl::SCRAM_SHA_256 => has_scram = true,
+                sasl::SCRAM_SHA_256_PLUS => has_scram_plus = true,
+                _ => {}
+            }
+        }
+
+        let channel_binding = self
+            .tls_server_end_point
+            .clone()
+            .map(ChannelBinding::tls_server_end_point);
+
+        let (channel_binding, mechanism) = if has_scram_plus {
+            match channel_binding {
+                Some(channel_binding) => (channel_binding, sasl::SCRAM_SHA_256_PLUS),
+                None => (ChannelBinding::unsupported(), sasl::SCRAM_SHA_256),
+            }
+        } else if has_scram {
+            match channel_binding {
+                Some(_) => (ChannelBinding::unrequested(), sasl::SCRAM_SHA_256),
+                None => (ChannelBinding::unsupported(), sasl::SCRAM_SHA_256),
+            }
+        } else {
+            return Err(StartupError::Authentication(
+                "unsupported SASL mechanism".into(),
+            ));
+        };
+
+        if mechanism != sasl::SCRAM_SHA_256_PLUS {
+            self.can_skip_channel_binding()?;
+        }
+
+        let scram = ScramSha256::new(password, channel_binding);
+        frontend::sasl_initial_response(mechanism, scram.message(), buf)
+            .map_err(StartupError::Encode)?;
+        Ok(scram)
+    }
+
+    fn read_info(&mut self, message: Message) -> Result<Step, StartupError> {
+        match message {
+            Message::BackendKeyData(body) => {
+                self.process_id = body.process_id();
+                self.secret_key = body.secret_key();
+            }
+            Message::ParameterStatus(body) => {
+                self.parameters.push((
+                    body.name().map_err(StartupError::Parse)?.to_string(),
+                    body.value().map_err(StartupError::Parse)?.to_string(),
+                ));
+            }
+            Message::NoticeResponse(body) => return Ok(Step::Notice(body)),
+            Message::ReadyForQuery(_) => {
+                self.state = State::Ready;
+                return Ok(Step::Ready);
+            }
+            _ => return Err(StartupError::UnexpectedMessage),
+        }
+
+        Ok(Step::Continue)
+    }
+
+    /// Determines if the connection is ready for queries.
+    pub fn is_ready(&self) -> bool {
+        matches!(self.state, State::Ready)
+    }
+
+    /// Returns the process ID of the backend, as needed to cancel its queries.
+    pub fn process_id(&self) -> i32 {
+        self.process_id
+    }
+
+    /// Returns the secret key of the backend, as needed to cancel its queries.
+    pub fn secret_key(&self) -> i32 {
+        self.secret_key
+    }
+
+    /// Returns the runtime parameters reported by the server, in the order they were received.
+    pub fn parameters(&self) -> &[(String, String)] {
+        &self.parameters
+    }
+}
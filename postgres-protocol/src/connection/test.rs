@@ -0,0 +1,181 @@
+use super::*;
+use crate::authentication::sasl::{ScramSecret, ScramSha256Server};
+use crate::message::backend::Message;
+use bytes::{BufMut, BytesMut};
+
+fn backend(tag: u8, body: &[u8]) -> Message {
+    let mut buf = BytesMut::new();
+    buf.put_u8(tag);
+    buf.put_i32(body.len() as i32 + 4);
+    buf.put_slice(body);
+    Message::parse(&mut buf).unwrap().unwrap()
+}
+
+fn authentication(code: i32, data: &[u8]) -> Message {
+    let mut body = code.to_be_bytes().to_vec();
+    body.extend_from_slice(data);
+    backend(b'R', &body)
+}
+
+fn ready(status: u8) -> Message {
+    backend(b'Z', &[status])
+}
+
+fn parameter_status(name: &str, value: &str) -> Message {
+    backend(b'S', format!("{}\0{}\0", name, value).as_bytes())
+}
+
+// splits a frontend message written by the state machine into its tag and body
+fn frontend(buf: &mut BytesMut) -> (u8, BytesMut) {
+    let tag = buf[0];
+    let len = i32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize;
+    let mut message = buf.split_to(len + 1);
+    (tag, message.split_off(5))
+}
+
+fn finish(startup: &mut Startup, buf: &mut BytesMut) {
+    assert!(matches!(
+        startup.handle(backend(b'K', &[0, 0, 0, 42, 0, 0, 0, 7]), buf),
+        Ok(Step::Continue)
+    ));
+    assert!(matches!(
+        startup.handle(parameter_status("server_version", "15.0"), buf),
+        Ok(Step::Continue)
+    ));
+    assert!(matches!(startup.handle(ready(b'I'), buf), Ok(Step::Ready)));
+    assert!(startup.is_ready());
+    assert_eq!(startup.process_id(), 42);
+    assert_eq!(startup.secret_key(), 7);
+    assert_eq!(
+        startup.parameters(),
+        &[("server_version".to_string(), "15.0".to_string())]
+    );
+}
+
+#[test]
+fn startup_trust() {
+    let mut buf = BytesMut::new();
+    let mut startup = Startup::new("postgres");
+    startup.start(vec![("database", "db")], &mut buf).unwrap();
+    assert_eq!(&buf[4..], b"\0\x03\0\0user\0postgres\0database\0db\0\0");
+    buf.clear();
+
+    assert!(matches!(
+        startup.handle(authentication(0, &[]), &mut buf),
+        Ok(Step::Continue)
+    ));
+    assert!(buf.is_empty());
+    finish(&mut startup, &mut buf);
+}
+
+#[test]
+fn startup_md5() {
+    let mut buf = BytesMut::new();
+    let mut startup = Startup::new("md5_user").password(b"password");
+    startup.start(None, &mut buf).unwrap();
+    buf.clear();
+
+    startup
+        .handle(authentication(5, &[0x2a, 0x3d, 0x8f, 0xe0]), &mut buf)
+        .unwrap();
+    let (tag, body) = frontend(&mut buf);
+    assert_eq!(tag, b'p');
+    assert_eq!(&body[..], b"md562af4dd09bbb41884907a838a3233294\0");
+
+    startup.handle(authentication(0, &[]), &mut buf).unwrap();
+    finish(&mut startup, &mut buf);
+}
+
+#[test]
+fn startup_scram() {
+    let mut buf = BytesMut::new();
+    let mut startup = Startup::new("postgres").password(b"secret");
+    startup.start(None, &mut buf).unwrap();
+    buf.clear();
+
+    startup
+        .handle(authentication(10, b"SCRAM-SHA-256\0\0"), &mut buf)
+        .unwrap();
+    let (tag, body) = frontend(&mut buf);
+    assert_eq!(tag, b'p');
+    let (mechanism, rest) = body.split_at(b"SCRAM-SHA-256\0".len());
+    assert_eq!(mechanism, b"SCRAM-SHA-256\0");
+    let client_first = &rest[4..];
+
+    let secret = ScramSecret::new(b"secret", b"salt", 4096);
+    let mut server = ScramSha256Server::new(secret, client_first, None).unwrap();
+    startup
+        .handle(authentication(11, server.message()), &mut buf)
+        .unwrap();
+    let (tag, client_final) = frontend(&mut buf);
+    assert_eq!(tag, b'p');
+
+    server.finish(&client_final).unwrap();
+    startup
+        .handle(authentication(12, server.message()), &mut buf)
+        .unwrap();
+    assert!(buf.is_empty());
+    startup.handle(authentication(0, &[]), &mut buf).unwrap();
+    finish(&mut startup, &mut buf);
+}
+
+#[test]
+fn startup_errors() {
+    let mut buf = BytesMut::new();
+
+    let mut startup = Startup::new("postgres");
+    startup.start(None, &mut buf).unwrap();
+    assert!(matches!(
+        startup.handle(authentication(3, &[]), &mut buf),
+        Err(StartupError::PasswordMissing)
+    ));
+    assert!(matches!(
+        startup.handle(authentication(0, &[]), &mut buf),
+        Err(StartupError::UnexpectedMessage)
+    ));
+
+    let mut startup = Startup::new("postgres").require_channel_binding(true);
+    startup.start(None, &mut buf).unwrap();
+    assert!(matches!(
+        startup.handle(authentication(0, &[]), &mut buf),
+        Err(StartupError::Authentication(_))
+    ));
+
+    let mut startup = Startup::new("postgres");
+    startup.start(None, &mut buf).unwrap();
+    assert!(matches!(
+        startup.handle(backend(b'E', b"SFATAL\0C28000\0Mdenied\0\0"), &mut buf),
+        Err(StartupError::Db(_))
+    ));
+}
+
+#[test]
+fn frames() {
+    let mut buf = BytesMut::new();
+    buf.put_slice(b"1\0\0\0\x04");
+    buf.put_slice(b"2\0\0\0\x04");
+    buf.put_slice(b"N\0\0\0\x05\0");
+    buf.put_slice(b"Z\0\0\0\x05I");
+    buf.put_slice(b"Z\0\0");
+
+    match decode_frame(&mut buf).unwrap() {
+        Some(Frame::Messages {
+            messages,
+            request_complete: false,
+        }) => assert_eq!(messages.len(), 10),
+        _ => panic!("expected messages"),
+    }
+    assert!(matches!(
+        decode_frame(&mut buf).unwrap(),
+        Some(Frame::Async(Message::NoticeResponse(_)))
+    ));
+    assert!(matches!(
+        decode_frame(&mut buf).unwrap(),
+        Some(Frame::Messages {
+            request_complete: true,
+            ..
+        })
+    ));
+    assert!(decode_frame(&mut buf).unwrap().is_none());
+    assert_eq!(buf.len(), 3);
+}
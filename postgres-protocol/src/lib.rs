@@ -16,6 +16,7 @@ use bytes::{BufMut, BytesMut};
 use std::io;
 
 pub mod authentication;
+pub mod connection;
 pub mod escape;
 pub mod message;
 pub mod password;
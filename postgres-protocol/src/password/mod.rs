@@ -7,13 +7,8 @@
 //! end up in logs pg_stat displays, etc.
 
 use crate::authentication::sasl;
-use base64::display::Base64Display;
-use base64::engine::general_purpose::STANDARD;
-use hmac::{Hmac, Mac};
-use md5::Md5;
+use md5::{Digest, Md5};
 use rand::RngCore;
-use sha2::digest::FixedOutput;
-use sha2::{Digest, Sha256};
 
 #[cfg(test)]
 mod test;
@@ -36,56 +31,9 @@ pub fn scram_sha_256(password: &[u8]) -> String {
 // Internal implementation of scram_sha_256 with a caller-provided
 // salt. This is useful for testing.
 pub(crate) fn scram_sha_256_salt(password: &[u8], salt: [u8; SCRAM_DEFAULT_SALT_LEN]) -> String {
-    // Prepare the password, per [RFC
-    // 4013](https://tools.ietf.org/html/rfc4013), if possible.
-    //
-    // Postgres treats passwords as byte strings (without embedded NUL
-    // bytes), but SASL expects passwords to be valid UTF-8.
-    //
-    // Follow the behavior of libpq's PQencryptPasswordConn(), and
-    // also the backend. If the password is not valid UTF-8, or if it
-    // contains prohibited characters (such as non-ASCII whitespace),
-    // just skip the SASLprep step and use the original byte
-    // sequence.
-    let prepared: Vec<u8> = match std::str::from_utf8(password) {
-        Ok(password_str) => {
-            match stringprep::saslprep(password_str) {
-                Ok(p) => p.into_owned().into_bytes(),
-                // contains invalid characters; skip saslprep
-                Err(_) => Vec::from(password),
-            }
-        }
-        // not valid UTF-8; skip saslprep
-        Err(_) => Vec::from(password),
-    };
-
-    // salt password
-    let salted_password = sasl::hi(&prepared, &salt, SCRAM_DEFAULT_ITERATIONS);
-
-    // client key
-    let mut hmac = Hmac::<Sha256>::new_from_slice(&salted_password)
-        .expect("HMAC is able to accept all key sizes");
-    hmac.update(b"Client Key");
-    let client_key = hmac.finalize().into_bytes();
-
-    // stored key
-    let mut hash = Sha256::default();
-    hash.update(client_key.as_slice());
-    let stored_key = hash.finalize_fixed();
-
-    // server key
-    let mut hmac = Hmac::<Sha256>::new_from_slice(&salted_password)
-        .expect("HMAC is able to accept all key sizes");
-    hmac.update(b"Server Key");
-    let server_key = hmac.finalize().into_bytes();
-
-    format!(
-        "SCRAM-SHA-256${}:{}${}:{}",
-        SCRAM_DEFAULT_ITERATIONS,
-        Base64Display::new(&salt, &STANDARD),
-        Base64Display::new(&stored_key, &STANDARD),
-        Base64Display::new(&server_key, &STANDARD)
-    )
+    // The password is prepared per [RFC 4013](https://tools.ietf.org/html/rfc4013) if possible,
+    // following libpq's PQencryptPasswordConn(), and used as is otherwise.
+    sasl::ScramSecret::new(password, &salt, SCRAM_DEFAULT_ITERATIONS).to_string()
 }
 
 /// **Not recommended, as MD5 is not considered to be secure.**
@@ -0,0 +1,42 @@
+use chrono_04::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone};
+
+use crate::Shift;
+
+impl Shift for NaiveDateTime {
+    type Delta = Duration;
+
+    fn checked_shift(&self, delta: &Duration) -> Option<NaiveDateTime> {
+        self.checked_add_signed(*delta)
+    }
+
+    fn checked_delta(&self, earlier: &NaiveDateTime) -> Option<Duration> {
+        Some(self.signed_duration_since(*earlier))
+    }
+}
+
+impl<Tz> Shift for DateTime<Tz>
+where
+    Tz: TimeZone,
+{
+    type Delta = Duration;
+
+    fn checked_shift(&self, delta: &Duration) -> Option<DateTime<Tz>> {
+        self.clone().checked_add_signed(*delta)
+    }
+
+    fn checked_delta(&self, earlier: &DateTime<Tz>) -> Option<Duration> {
+        Some(self.clone().signed_duration_since(earlier.clone()))
+    }
+}
+
+impl Shift for NaiveDate {
+    type Delta = Duration;
+
+    fn checked_shift(&self, delta: &Duration) -> Option<NaiveDate> {
+        self.checked_add_signed(*delta)
+    }
+
+    fn checked_delta(&self, earlier: &NaiveDate) -> Option<Duration> {
+        Some(self.signed_duration_since(*earlier))
+    }
+}
@@ -126,7 +126,7 @@ where
 mod test {
     use std::fmt;
 
-    use chrono::{Duration, TimeZone, Utc};
+    use chrono_04::{Duration, TimeZone, Utc};
 
     use postgres::{
         types::{FromSql, ToSql},
@@ -103,7 +103,14 @@ macro_rules! range {
     };
 }
 
+#[cfg(feature = "with-chrono-0_4")]
+mod chrono_04;
 mod impls;
+mod shift;
+#[cfg(feature = "with-time-0_3")]
+mod time_03;
+
+pub use shift::{OverflowError, Shift};
 
 /// The possible sides of a bound.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
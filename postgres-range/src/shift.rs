@@ -0,0 +1,158 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{BoundSided, Range, RangeBound};
+
+/// A type whose values can be moved by a duration, such as the timestamps and dates of time ranges.
+///
+/// Implementations are provided for the types of the `chrono` and `time` crates with the
+/// `with-chrono-0_4` and `with-time-0_3` Cargo features.
+pub trait Shift: PartialOrd + Sized {
+    /// The type of the distance between two values.
+    type Delta;
+
+    /// Adds `delta` to the value, returning `None` if the result is out of range.
+    fn checked_shift(&self, delta: &Self::Delta) -> Option<Self>;
+
+    /// Returns the distance from `earlier` to the value, or `None` if it is out of range.
+    fn checked_delta(&self, earlier: &Self) -> Option<Self::Delta>;
+}
+
+/// An error returned when moving a bound of a range overflows its type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError(());
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("range bound out of range")
+    }
+}
+
+impl Error for OverflowError {}
+
+fn shift_bound<S, T>(
+    bound: Option<&RangeBound<S, T>>,
+    delta: &T::Delta,
+) -> Result<Option<RangeBound<S, T>>, OverflowError>
+where
+    S: BoundSided,
+    T: Shift,
+{
+    match bound {
+        Some(bound) => {
+            let value = bound.value.checked_shift(delta).ok_or(OverflowError(()))?;
+            Ok(Some(RangeBound::new(value, bound.type_)))
+        }
+        None => Ok(None),
+    }
+}
+
+impl<T> Range<T>
+where
+    T: Shift + Clone,
+{
+    /// Returns the range moved by `delta`, keeping its length.
+    ///
+    /// Unbounded sides stay unbounded, and the empty range stays empty.
+    pub fn shift(&self, delta: T::Delta) -> Result<Range<T>, OverflowError> {
+        if self.is_empty() {
+            return Ok(Range::empty());
+        }
+
+        Ok(Range::new(
+            shift_bound(self.lower(), &delta)?,
+            shift_bound(self.upper(), &delta)?,
+        ))
+    }
+
+    /// Returns the range with its upper bound moved by `delta`.
+    ///
+    /// A negative `delta` shortens the range, which becomes empty if the upper bound moves below
+    /// the lower bound. An unbounded upper side stays unbounded, and the empty range stays empty.
+    pub fn extend_upper(&self, delta: T::Delta) -> Result<Range<T>, OverflowError> {
+        if self.is_empty() {
+            return Ok(Range::empty());
+        }
+
+        Ok(Range::new(
+            self.lower().cloned(),
+            shift_bound(self.upper(), &delta)?,
+        ))
+    }
+
+    /// Returns the distance between the bounds of the range.
+    ///
+    /// Whether the bounds are inclusive is not taken into account. Returns `None` if the range is
+    /// empty or unbounded, or if the distance is out of range.
+    pub fn duration(&self) -> Option<T::Delta> {
+        match (self.lower(), self.upper()) {
+            (Some(lower), Some(upper)) => upper.value.checked_delta(&lower.value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "with-chrono-0_4"))]
+mod test {
+    use chrono_04::{Duration, NaiveDate, NaiveDateTime};
+
+    use crate::Range;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn shift() {
+        let r = range!('[' at(9), at(11); ')');
+        assert_eq!(
+            r.shift(Duration::hours(2)),
+            Ok(range!('[' at(11), at(13); ')'))
+        );
+        assert_eq!(
+            r.shift(Duration::hours(-9)),
+            Ok(range!('[' at(0), at(2); ')'))
+        );
+        assert_eq!(
+            range!('(', at(9); ']').shift(Duration::hours(1)),
+            Ok(range!('(', at(10); ']'))
+        );
+        assert_eq!(
+            Range::<NaiveDateTime>::empty().shift(Duration::hours(1)),
+            Ok(Range::empty())
+        );
+        assert!(range!('[' NaiveDateTime::MAX,; ')')
+            .shift(Duration::hours(1))
+            .is_err());
+    }
+
+    #[test]
+    fn extend_upper() {
+        let r = range!('[' at(9), at(11); ')');
+        assert_eq!(
+            r.extend_upper(Duration::hours(1)),
+            Ok(range!('[' at(9), at(12); ')'))
+        );
+        assert!(r.extend_upper(Duration::hours(-2)).unwrap().is_empty());
+        assert_eq!(
+            range!('[' at(9),; ')').extend_upper(Duration::hours(1)),
+            Ok(range!('[' at(9),; ')'))
+        );
+        assert!(range!('[' at(9), NaiveDateTime::MAX; ']')
+            .extend_upper(Duration::hours(1))
+            .is_err());
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(
+            range!('[' at(9), at(11); ')').duration(),
+            Some(Duration::hours(2))
+        );
+        assert_eq!(range!('[' at(9),; ')').duration(), None);
+        assert_eq!(Range::<NaiveDateTime>::empty().duration(), None);
+    }
+}
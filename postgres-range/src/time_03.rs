@@ -0,0 +1,23 @@
+use time_03::{Date, Duration, OffsetDateTime, PrimitiveDateTime};
+
+use crate::Shift;
+
+macro_rules! impl_shift {
+    ($t:ty) => {
+        impl Shift for $t {
+            type Delta = Duration;
+
+            fn checked_shift(&self, delta: &Duration) -> Option<$t> {
+                self.checked_add(*delta)
+            }
+
+            fn checked_delta(&self, earlier: &$t) -> Option<Duration> {
+                Some(*self - *earlier)
+            }
+        }
+    };
+}
+
+impl_shift!(PrimitiveDateTime);
+impl_shift!(OffsetDateTime);
+impl_shift!(Date);
@@ -0,0 +1,45 @@
+use crate::{FromSql, ToSql};
+
+// the conversions are the same for every version of chrono-tz, as time zones are stored by name
+macro_rules! impl_tz {
+    ($chrono_tz:ident) => {
+        impl<'a> FromSql<'a> for $chrono_tz::Tz {
+            fn from_sql(
+                ty: &crate::Type,
+                raw: &'a [u8],
+            ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+                let string = <&str>::from_sql(ty, raw)?;
+                Ok(string.parse()?)
+            }
+
+            fn accepts(ty: &crate::Type) -> bool {
+                <&str as FromSql>::accepts(ty)
+            }
+        }
+
+        impl ToSql for $chrono_tz::Tz {
+            fn to_sql(
+                &self,
+                ty: &crate::Type,
+                out: &mut bytes::BytesMut,
+            ) -> Result<crate::IsNull, Box<dyn std::error::Error + Sync + Send>>
+            where
+                Self: Sized,
+            {
+                let string = self.to_string();
+                string.to_sql(ty, out)
+            }
+
+            fn accepts(ty: &crate::Type) -> bool {
+                <&str as ToSql>::accepts(ty)
+            }
+
+            to_sql_checked!();
+        }
+    };
+}
+
+#[cfg(feature = "with-chrono-tz-0_9")]
+impl_tz!(chrono_tz_09);
+#[cfg(feature = "with-chrono-tz-0_10")]
+impl_tz!(chrono_tz_0_10);
@@ -0,0 +1,64 @@
+use crate::connection::ConnectionRef;
+use crate::{Error, Row, Statement, ToStatement};
+use futures_util::future;
+use std::future::Future;
+use std::pin::Pin;
+use tokio_postgres::types::ToSql;
+
+type StatementFuture<'a> = Pin<Box<dyn Future<Output = Result<Statement, Error>> + 'a>>;
+
+/// A builder for a batch of statements which are sent to the server together.
+///
+/// Each statement runs in its own implicit transaction, so statements which completed before a failing one are not
+/// rolled back.
+pub struct Batch<'a> {
+    connection: ConnectionRef<'a>,
+    client: &'a tokio_postgres::Client,
+    statements: Vec<StatementFuture<'a>>,
+    params: Vec<&'a [&'a (dyn ToSql + Sync)]>,
+}
+
+impl<'a> Batch<'a> {
+    pub(crate) fn new(
+        connection: ConnectionRef<'a>,
+        client: &'a tokio_postgres::Client,
+    ) -> Batch<'a> {
+        Batch {
+            connection,
+            client,
+            statements: vec![],
+            params: vec![],
+        }
+    }
+
+    /// Adds a statement to the batch.
+    ///
+    /// The `query` argument can either be a `Statement`, or a raw query string.
+    pub fn query<T>(mut self, query: &'a T, params: &'a [&'a (dyn ToSql + Sync)]) -> Self
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.statements
+            .push(Box::pin(self.client.__private_api_to_statement(query)));
+        self.params.push(params);
+        self
+    }
+
+    /// Executes the statements in the order they were added, returning the resulting rows of each.
+    ///
+    /// Any raw query strings are prepared together first, so the batch takes two round trips to the server rather than
+    /// one unless all of its statements were prepared up front. If any statement fails, the first error is returned.
+    pub fn run(mut self) -> Result<Vec<Vec<Row>>, Error> {
+        let client = self.client;
+        let params = self.params;
+        let statements = self.statements;
+        self.connection.block_on(async move {
+            let statements = future::try_join_all(statements).await?;
+            let queries = statements
+                .iter()
+                .zip(params)
+                .map(|(statement, params)| client.query(statement, params));
+            future::try_join_all(queries).await
+        })
+    }
+}
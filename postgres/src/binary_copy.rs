@@ -6,9 +6,9 @@ use crate::{CopyInWriter, CopyOutReader, Error};
 use fallible_iterator::FallibleIterator;
 use futures_util::StreamExt;
 use std::pin::Pin;
-#[doc(inline)]
-pub use tokio_postgres::binary_copy::BinaryCopyOutRow;
 use tokio_postgres::binary_copy::{self, BinaryCopyOutStream};
+#[doc(inline)]
+pub use tokio_postgres::binary_copy::{BinaryCopyOutRow, ToCopyRow};
 
 /// A type which serializes rows into the PostgreSQL binary copy format.
 ///
@@ -41,6 +41,18 @@ impl<'a> BinaryCopyInWriter<'a> {
         self.connection.block_on(self.sink.as_mut().write(values))
     }
 
+    /// Writes a single row from a [`ToCopyRow`] type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values of the row does not match the number expected.
+    pub fn write_row<T>(&mut self, row: &T) -> Result<(), Error>
+    where
+        T: ?Sized + ToCopyRow,
+    {
+        self.connection.block_on(self.sink.as_mut().write_row(row))
+    }
+
     /// A maximally-flexible version of `write`.
     ///
     /// # Panics
@@ -22,10 +22,17 @@ impl CancelToken {
     /// cancellation request will reach the server before the query terminates
     /// normally, or that the connection associated with this token is still
     /// active.
-    pub fn cancel_query<T>(&self, tls: T) -> Result<(), Error>
+    ///
+    /// Unless the request has to be sent over TLS, it is performed over a plain blocking socket without creating a
+    /// runtime, so it can be used from watchdog threads or other contexts without access to one.
+    pub fn cancel_query<T>(&self, mut tls: T) -> Result<(), Error>
     where
         T: MakeTlsConnect<Socket>,
     {
+        if let Some(result) = self.0.__private_api_cancel_query_blocking(&mut tls) {
+            return result;
+        }
+
         runtime::Builder::new_current_thread()
             .enable_all()
             .build()
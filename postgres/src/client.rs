@@ -1,19 +1,31 @@
 use crate::connection::Connection;
+#[cfg(feature = "with-csv-1")]
+use crate::csv::{self, CsvOptions};
 use crate::{
-    CancelToken, Config, CopyInWriter, CopyOutReader, Notifications, RowIter, Statement,
+    Batch, CancelToken, Config, CopyInWriter, CopyOutReader, Notifications, RowIter, Statement,
     ToStatement, Transaction, TransactionBuilder,
 };
 use std::fmt;
+use std::future::Future;
+#[cfg(feature = "with-csv-1")]
+use std::io;
 use std::task::Poll;
 use std::time::Duration;
-use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
-use tokio_postgres::types::{BorrowToSql, ToSql, Type};
-use tokio_postgres::{Error, Row, SimpleQueryMessage, Socket};
+use tokio_postgres::error::Severity;
+use tokio_postgres::tls::{MakeTlsConnect, TlsConnect, TlsInfo};
+use tokio_postgres::types::{BorrowToSql, PgLsn, ToSql, Type};
+use tokio_postgres::{
+    Error, IntoParams, PreparedTransaction, QueryOptions, Row, SimpleQueryMessage, Socket,
+};
+
+pub(crate) type Reconnect =
+    Box<dyn FnMut() -> Result<(Connection, tokio_postgres::Client), Error> + Send>;
 
 /// A synchronous PostgreSQL client.
 pub struct Client {
     connection: Connection,
     client: tokio_postgres::Client,
+    reconnect: Option<Reconnect>,
 }
 
 impl fmt::Debug for Client {
@@ -30,7 +42,16 @@ impl Drop for Client {
 
 impl Client {
     pub(crate) fn new(connection: Connection, client: tokio_postgres::Client) -> Client {
-        Client { connection, client }
+        Client {
+            connection,
+            client,
+            reconnect: None,
+        }
+    }
+
+    pub(crate) fn with_reconnect(mut self, reconnect: Reconnect) -> Client {
+        self.reconnect = Some(reconnect);
+        self
     }
 
     /// A convenience function which parses a configuration string into a `Config` and then connects to the database.
@@ -89,7 +110,25 @@ impl Client {
     where
         T: ?Sized + ToStatement + fmt::Debug,
     {
-        self.connection.block_on(self.client.execute(query, params))
+        self.retry(|connection, client| connection.block_on(client.execute(query, params)))
+    }
+
+    /// Like `execute`, but runs the statement as described by `options`.
+    ///
+    /// This allows a timeout to be set for the statement.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn execute_with_options<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.retry(|connection, client| {
+            connection.block_on(client.execute_with_options(query, params, options))
+        })
     }
 
     /// Executes a statement, returning the resulting rows.
@@ -122,7 +161,26 @@ impl Client {
     where
         T: ?Sized + ToStatement + fmt::Debug,
     {
-        self.connection.block_on(self.client.query(query, params))
+        self.retry(|connection, client| connection.block_on(client.query(query, params)))
+    }
+
+    /// Like `query`, but returns the results as described by `options`.
+    ///
+    /// This allows the values of types without a binary output function to be fetched in the text format, and a
+    /// timeout to be set for the query.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_with_options<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.retry(|connection, client| {
+            connection.block_on(client.query_with_options(query, params, options))
+        })
     }
 
     /// Executes a statement which returns a single row, returning it.
@@ -156,8 +214,7 @@ impl Client {
     where
         T: ?Sized + ToStatement + fmt::Debug,
     {
-        self.connection
-            .block_on(self.client.query_one(query, params))
+        self.retry(|connection, client| connection.block_on(client.query_one(query, params)))
     }
 
     /// Executes a statement which returns zero or one rows, returning it.
@@ -200,14 +257,106 @@ impl Client {
     where
         T: ?Sized + ToStatement + fmt::Debug,
     {
+        self.retry(|connection, client| connection.block_on(client.query_opt(query, params)))
+    }
+
+    /// Like `query`, but takes the parameters as any [`IntoParams`], such as a tuple or a struct deriving
+    /// [`ToParams`](crate::ToParams).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let rows = client.query_with("SELECT foo FROM bar WHERE baz = $1 AND biz > $2", (true, 30i32))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_with<T, P>(&mut self, query: &T, params: P) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query(query, &params)
+    }
+
+    /// Like `query_one`, but takes the parameters as any [`IntoParams`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_one_with<T, P>(&mut self, query: &T, params: P) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query_one(query, &params)
+    }
+
+    /// Like `query_opt`, but takes the parameters as any [`IntoParams`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_opt_with<T, P>(&mut self, query: &T, params: P) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query_opt(query, &params)
+    }
+
+    /// Like `execute`, but takes the parameters as any [`IntoParams`].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn execute_with<T, P>(&mut self, query: &T, params: P) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.execute(query, &params)
+    }
+
+    /// Executes a statement once for each set of parameters, returning the number of rows modified by each execution.
+    ///
+    /// All executions are sent to the server at once and answered in a single round trip. They run in a single
+    /// implicit transaction (or as part of the current one), so if one of them fails none of them take effect.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let users = [("alice", 30), ("bob", 25)];
+    /// let rows = client.execute_many(
+    ///     "INSERT INTO users (name, age) VALUES ($1, $2)",
+    ///     users.iter().map(|(name, age)| (name, age)),
+    /// )?;
+    /// assert_eq!(rows, [1, 1]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn execute_many<T, P, I>(&mut self, query: &T, params: I) -> Result<Vec<u64>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        self.ensure_connected()?;
         self.connection
-            .block_on(self.client.query_opt(query, params))
+            .block_on(self.client.execute_many(query, params))
     }
 
     /// A maximally-flexible version of `query`.
     ///
     /// It takes an iterator of parameters rather than a slice, and returns an iterator of rows rather than collecting
-    /// them into an array.
+    /// them into an array. If a fetch size has been configured with [`Config::fetch_size`], the rows are fetched from
+    /// the server in batches of that size as the iterator advances.
     ///
     /// # Examples
     ///
@@ -264,16 +413,20 @@ impl Client {
         I: IntoIterator<Item = P>,
         I::IntoIter: ExactSizeIterator,
     {
-        let stream = self
-            .connection
-            .block_on(self.client.query_raw(query, params))?;
+        self.ensure_connected()?;
+        let fetch_size = self.connection.fetch_size();
+        let stream = self.connection.block_on(
+            self.client
+                .__private_api_query_raw_chunked(query, params, fetch_size),
+        )?;
         Ok(RowIter::new(self.connection.as_ref(), stream))
     }
 
     /// Creates a new prepared statement.
     ///
     /// Prepared statements can be executed repeatedly, and may contain query parameters (indicated by `$1`, `$2`, etc),
-    /// which are set when executed. Prepared statements can only be used with the connection that created them.
+    /// which are set when executed. Prepared statements live on the connection that created them, and are prepared again
+    /// when used with another connection.
     ///
     /// # Examples
     ///
@@ -295,7 +448,7 @@ impl Client {
     /// ```
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
-        self.connection.block_on(self.client.prepare(query))
+        self.prepare_typed(query, &[])
     }
 
     /// Like `prepare`, but allows the types of query parameters to be explicitly specified.
@@ -327,8 +480,9 @@ impl Client {
     /// ```
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn prepare_typed(&mut self, query: &str, types: &[Type]) -> Result<Statement, Error> {
-        self.connection
-            .block_on(self.client.prepare_typed(query, types))
+        self.retry_read_only(|connection, client| {
+            connection.block_on(client.prepare_typed(query, types))
+        })
     }
 
     /// Executes a `COPY FROM STDIN` statement, returning the number of rows created.
@@ -359,6 +513,7 @@ impl Client {
     where
         T: ?Sized + ToStatement + fmt::Debug,
     {
+        self.ensure_connected()?;
         let sink = self.connection.block_on(self.client.copy_in(query))?;
         Ok(CopyInWriter::new(self.connection.as_ref(), sink))
     }
@@ -388,10 +543,90 @@ impl Client {
     where
         T: ?Sized + ToStatement + fmt::Debug,
     {
+        self.ensure_connected()?;
         let stream = self.connection.block_on(self.client.copy_out(query))?;
         Ok(CopyOutReader::new(self.connection.as_ref(), stream))
     }
 
+    /// Inserts rows into the columns `columns` of the table `table` with a binary `COPY`, returning the number of rows
+    /// inserted.
+    ///
+    /// This is much faster than `INSERT` statements when loading many rows. The table and column names are quoted, so
+    /// they are matched exactly, case included. If a row can't be encoded or doesn't have one value per column, the
+    /// copy is aborted and no rows are inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let rows = (0..100_000).map(|i| (i, format!("user {}", i)));
+    /// let inserted = client.copy_insert("users", &["id", "name"], rows)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(rows)))]
+    pub fn copy_insert<P, I>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        rows: I,
+    ) -> Result<u64, Error>
+    where
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        self.ensure_connected()?;
+        self.connection
+            .block_on(self.client.copy_insert(table, columns, rows))
+    }
+
+    /// Copies the records of a CSV reader into the columns `columns` of the table `table`, returning the number of
+    /// rows created.
+    ///
+    /// The records are sent in the format described by `options`, whatever the format the reader was configured to
+    /// read, and a field equal to its `null` string is inserted as `NULL`. The table and column names are quoted, so
+    /// they are matched exactly, case included. If `columns` is empty, the records fill all the columns of the table.
+    ///
+    /// Requires the `with-csv-1` Cargo feature.
+    #[cfg(feature = "with-csv-1")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(reader)))]
+    pub fn copy_in_csv<R>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        reader: &mut csv_1::Reader<R>,
+        options: &CsvOptions,
+    ) -> Result<u64, Error>
+    where
+        R: io::Read,
+    {
+        let statement = csv::copy_in_statement(table, columns, options)?;
+        let writer = self.copy_in(&statement)?;
+        csv::copy_in(writer, reader, options)
+    }
+
+    /// Runs a query with a CSV `COPY`, returning a CSV reader of its results.
+    ///
+    /// The reader is configured to read the format described by `options`. PostgreSQL does not support parameters in
+    /// `COPY` statements, so this method does not take any.
+    ///
+    /// Requires the `with-csv-1` Cargo feature.
+    #[cfg(feature = "with-csv-1")]
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub fn copy_out_csv(
+        &mut self,
+        query: &str,
+        options: &CsvOptions,
+    ) -> Result<csv_1::Reader<CopyOutReader<'_>>, Error> {
+        let statement = csv::copy_out_statement(query, options)?;
+        let reader = self.copy_out(&statement)?;
+        Ok(csv::reader(reader, options))
+    }
+
     /// Executes a sequence of SQL statements using the simple query protocol.
     ///
     /// Statements should be separated by semicolons. If an error occurs, execution of the sequence will stop at that
@@ -409,20 +644,31 @@ impl Client {
     /// them to this method!
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryMessage>, Error> {
-        self.connection.block_on(self.client.simple_query(query))
+        self.retry(|connection, client| connection.block_on(client.simple_query(query)))
     }
 
-    /// Validates the connection by performing a simple no-op query.
+    /// Validates the connection by sending a `Sync` message and waiting for the server to respond.
     ///
     /// If the specified timeout is reached before the backend responds, an error will be returned.
     pub fn is_valid(&mut self, timeout: Duration) -> Result<(), Error> {
-        let inner_client = &self.client;
-        self.connection.block_on(async {
-            let trivial_query = inner_client.simple_query("");
-            tokio::time::timeout(timeout, trivial_query)
-                .await
-                .map_err(|_| Error::__private_api_timeout())?
-                .map(|_| ())
+        self.connection.block_on(self.client.is_valid(timeout))
+    }
+
+    /// Returns the current write-ahead log insert location of the server.
+    ///
+    /// To read your own writes from a replica, capture the location on the primary after committing a write and pass
+    /// it to [`Client::wait_for_lsn`] on the replica before reading.
+    pub fn current_wal_lsn(&mut self) -> Result<PgLsn, Error> {
+        self.retry_read_only(|connection, client| connection.block_on(client.current_wal_lsn()))
+    }
+
+    /// Waits until the server has replayed the write-ahead log up to `lsn`.
+    ///
+    /// On a standby, this returns an error if the location isn't reached within `timeout`. A primary has all of its own
+    /// writes, so this returns immediately.
+    pub fn wait_for_lsn(&mut self, lsn: PgLsn, timeout: Duration) -> Result<(), Error> {
+        self.retry_read_only(|connection, client| {
+            connection.block_on(client.wait_for_lsn(lsn, timeout))
         })
     }
 
@@ -438,7 +684,33 @@ impl Client {
     /// them to this method!
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn batch_execute(&mut self, query: &str) -> Result<(), Error> {
-        self.connection.block_on(self.client.batch_execute(query))
+        self.retry(|connection, client| connection.block_on(client.batch_execute(query)))
+    }
+
+    /// Returns a builder for a batch of statements which are sent to the server together.
+    ///
+    /// The statements are pipelined rather than waiting for the response to each one before sending the next, which
+    /// avoids paying the latency of a round trip to the server per statement.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let results = client
+    ///     .batch()
+    ///     .query("INSERT INTO foo (bar) VALUES ($1)", &[&1i32])
+    ///     .query("SELECT bar FROM foo", &[])
+    ///     .run()?;
+    /// let rows = &results[1];
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn batch(&mut self) -> Batch<'_> {
+        Batch::new(self.connection.as_ref(), &self.client)
     }
 
     /// Begins a new database transaction.
@@ -462,6 +734,7 @@ impl Client {
     /// # }
     /// ```
     pub fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
+        self.ensure_connected()?;
         let transaction = self.connection.block_on(self.client.transaction())?;
         Ok(Transaction::new(self.connection.as_ref(), transaction))
     }
@@ -493,6 +766,34 @@ impl Client {
         TransactionBuilder::new(self.connection.as_ref(), self.client.build_transaction())
     }
 
+    /// Commits a transaction prepared for two-phase commit with
+    /// [`Transaction::prepare_transaction`](crate::Transaction::prepare_transaction).
+    ///
+    /// The transaction can have been prepared by any session in the current database, including one that has since
+    /// ended.
+    pub fn commit_prepared(&mut self, gid: &str) -> Result<(), Error> {
+        self.ensure_connected()?;
+        self.connection.block_on(self.client.commit_prepared(gid))
+    }
+
+    /// Rolls back a transaction prepared for two-phase commit with
+    /// [`Transaction::prepare_transaction`](crate::Transaction::prepare_transaction).
+    pub fn rollback_prepared(&mut self, gid: &str) -> Result<(), Error> {
+        self.ensure_connected()?;
+        self.connection.block_on(self.client.rollback_prepared(gid))
+    }
+
+    /// Returns the transactions which are prepared for two-phase commit, and waiting to be committed or rolled back.
+    ///
+    /// This lists the transactions of every database, oldest first. After a crash of the coordinator of a distributed
+    /// transaction, it can be used to find the in-doubt transactions and resolve them with
+    /// [`Client::commit_prepared`] or [`Client::rollback_prepared`].
+    pub fn prepared_transactions(&mut self) -> Result<Vec<PreparedTransaction>, Error> {
+        self.retry_read_only(|connection, client| {
+            connection.block_on(client.prepared_transactions())
+        })
+    }
+
     /// Returns a structure providing access to asynchronous notifications.
     ///
     /// Use the `LISTEN` command to register this connection for notifications.
@@ -540,6 +841,51 @@ impl Client {
         CancelToken::new(self.client.cancel_token())
     }
 
+    /// Returns details of the TLS session of the connection, or `None` if it is not encrypted.
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        self.client.tls_info()
+    }
+
+    /// Returns a shared reference to the underlying asynchronous client.
+    ///
+    /// Futures created from the asynchronous client only make progress while the connection is being driven, which
+    /// happens when they are passed to [`Client::block_on`].
+    pub fn async_client(&self) -> &tokio_postgres::Client {
+        &self.client
+    }
+
+    /// Runs a future created from the underlying asynchronous client to completion.
+    ///
+    /// This allows asynchronous-only functionality, such as pipelining several requests, to be used on the same
+    /// connection without opening a second one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::future;
+    /// use postgres::{Client, NoTls};
+    ///
+    /// # fn main() -> Result<(), postgres::Error> {
+    /// let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+    ///
+    /// let (select, insert) = client.block_on(|client| {
+    ///     future::try_join(
+    ///         client.prepare("SELECT bar FROM foo"),
+    ///         client.prepare("INSERT INTO foo (bar) VALUES ($1)"),
+    ///     )
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn block_on<'a, F, U, T>(&'a mut self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&'a tokio_postgres::Client) -> U,
+        U: Future<Output = Result<T, Error>>,
+    {
+        let future = f(&self.client);
+        self.connection.block_on(future)
+    }
+
     /// Clears the client's type information cache.
     ///
     /// When user-defined types are used in a query, the client loads their definitions from the database and caches
@@ -553,7 +899,7 @@ impl Client {
     ///
     /// If this returns `true`, the client is no longer usable.
     pub fn is_closed(&self) -> bool {
-        self.client.is_closed()
+        self.client.is_closed() || self.connection.is_closed()
     }
 
     /// Closes the client's connection to the server.
@@ -564,6 +910,75 @@ impl Client {
         self.close_inner()
     }
 
+    /// Runs an operation, retrying it on a new connection if auto-reconnect is enabled and the connection was found to
+    /// be lost before the operation was sent.
+    ///
+    /// If the connection is lost while the operation runs, the server may already have executed it, so the client
+    /// reconnects for the next operation and the original error is returned.
+    fn retry<F, T>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut Connection, &tokio_postgres::Client) -> Result<T, Error>,
+    {
+        self.run(false, f)
+    }
+
+    /// Like `retry`, but also retries the operation if the connection is lost while it runs, for operations which
+    /// don't modify the database.
+    fn retry_read_only<F, T>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut Connection, &tokio_postgres::Client) -> Result<T, Error>,
+    {
+        self.run(true, f)
+    }
+
+    fn run<F, T>(&mut self, read_only: bool, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut Connection, &tokio_postgres::Client) -> Result<T, Error>,
+    {
+        self.ensure_connected()?;
+        match f(&mut self.connection, &self.client) {
+            Err(e) if self.reconnect.is_some() && (self.is_closed() || is_connection_lost(&e)) => {
+                if self.reconnect().is_err() || !read_only {
+                    return Err(e);
+                }
+                f(&mut self.connection, &self.client)
+            }
+            result => result,
+        }
+    }
+
+    /// Reconnects before starting an operation if the connection has been lost since the last one.
+    fn ensure_connected(&mut self) -> Result<(), Error> {
+        if self.reconnect.is_none() {
+            return Ok(());
+        }
+
+        // the connection is only polled while an operation runs, so handle what the server sent in the meantime to
+        // notice if it has closed the connection. Yielding lets the runtime pick up the readiness of the socket first.
+        if !self.is_closed() {
+            let mut yield_now = Box::pin(tokio::task::yield_now());
+            let result = self
+                .connection
+                .poll_block_on_untimed(|cx, _, _| yield_now.as_mut().poll(cx).map(Ok));
+            if !self.is_closed() {
+                return result;
+            }
+        }
+        self.reconnect()
+    }
+
+    fn reconnect(&mut self) -> Result<(), Error> {
+        let reconnect = match &mut self.reconnect {
+            Some(reconnect) => reconnect,
+            None => return Ok(()),
+        };
+        // existing `Statement`s are prepared again on the new connection when they are next used
+        let (connection, client) = reconnect()?;
+        self.connection = connection;
+        self.client = client;
+        Ok(())
+    }
+
     fn close_inner(&mut self) -> Result<(), Error> {
         self.client.__private_api_close();
 
@@ -576,3 +991,12 @@ impl Client {
         })
     }
 }
+
+/// Determines if an error means that the connection can no longer be used.
+fn is_connection_lost(error: &Error) -> bool {
+    error.is_closed()
+        || matches!(
+            error.as_db_error().and_then(|e| e.parsed_severity()),
+            Some(Severity::Fatal | Severity::Panic)
+        )
+}
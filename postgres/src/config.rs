@@ -1,7 +1,7 @@
 //! Connection configuration.
 
 use crate::connection::Connection;
-use crate::{info, Client};
+use crate::{info, Client, Notification};
 use std::fmt;
 use std::net::IpAddr;
 use std::path::Path;
@@ -14,8 +14,9 @@ pub use tokio_postgres::config::{
     ChannelBinding, Host, LoadBalanceHosts, SslMode, TargetSessionAttrs,
 };
 use tokio_postgres::error::DbError;
+use tokio_postgres::resolve::Resolve;
 use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
-use tokio_postgres::{Error, Socket};
+use tokio_postgres::{Error, OverflowPolicy, Socket};
 
 /// Connection configuration.
 ///
@@ -52,6 +53,9 @@ use tokio_postgres::{Error, Socket};
 ///         * If both `host` and `hostaddr` are specified, the value for `hostaddr` gives the server network address.
 ///             The value for `host` is ignored unless the authentication method requires it,
 ///             in which case it will be used as the host name.
+/// * `passfile` - The path to the password file, which is read when no password is given. Defaults to the file named
+///     by the `PGPASSFILE` environment variable, or `~/.pgpass` (`%APPDATA%\postgresql\pgpass.conf` on Windows).
+///     See [`Config::passfile`] for details.
 /// * `port` - The port to connect to. Multiple ports can be specified, separated by commas. The number of ports must be
 ///     either 1, in which case it will be used for all hosts, or the same as the number of hosts. Defaults to 5432 if
 ///     omitted or the empty string.
@@ -74,6 +78,11 @@ use tokio_postgres::{Error, Socket};
 /// * `channel_binding` - Controls usage of channel binding in the authentication process. If set to `disable`, channel
 ///     binding will not be used. If set to `prefer`, channel binding will be used if available, but not used otherwise.
 ///     If set to `require`, the authentication process will fail if channel binding is not used. Defaults to `prefer`.
+/// * `service` - The name of a service, whose parameters are read from the service file. Parameters given in the
+///     connection string take precedence over those of the service. The service is looked up in the per-user service
+///     file, and then in the `pg_service.conf` file of the directory named by the `PGSYSCONFDIR` environment variable.
+/// * `servicefile` - The path to the per-user service file. Defaults to the file named by the `PGSERVICEFILE`
+///     environment variable, or `~/.pg_service.conf` (`%APPDATA%\postgresql\.pg_service.conf` on Windows).
 /// * `load_balance_hosts` - Controls the order in which the client tries to connect to the available hosts and
 ///     addresses. Once a connection attempt is successful no other hosts and addresses will be tried. This parameter
 ///     is typically used in combination with multiple host names or a DNS record that returns multiple IPs. If set to
@@ -99,6 +108,10 @@ use tokio_postgres::{Error, Socket};
 /// host=host1,host2,host3 port=1234,,5678 user=postgres target_session_attrs=read-write
 /// ```
 ///
+/// ```not_rust
+/// service=reporting dbname=sales
+/// ```
+///
 /// # Url
 ///
 /// This format resembles a URL with a scheme of either `postgres://` or `postgresql://`. All components are optional,
@@ -125,14 +138,23 @@ use tokio_postgres::{Error, Socket};
 /// ```
 #[derive(Clone)]
 pub struct Config {
-    config: tokio_postgres::Config,
-    notice_callback: Arc<dyn Fn(DbError) + Send + Sync>,
+    pub(crate) config: tokio_postgres::Config,
+    pub(crate) notice_callback: Arc<dyn Fn(DbError) + Send + Sync>,
+    pub(crate) notification_callback: Option<Arc<dyn Fn(Notification) + Send + Sync>>,
+    pub(crate) fetch_size: i32,
+    pub(crate) query_timeout: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
+    auto_reconnect: bool,
 }
 
 impl fmt::Debug for Config {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt.debug_struct("Config")
             .field("config", &self.config)
+            .field("fetch_size", &self.fetch_size)
+            .field("query_timeout", &self.query_timeout)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("auto_reconnect", &self.auto_reconnect)
             .finish()
     }
 }
@@ -216,6 +238,27 @@ impl Config {
         self.config.get_application_name()
     }
 
+    /// Sets the value of an additional runtime parameter, which is sent to the server when connecting.
+    ///
+    /// This sets parameters such as `search_path` or `statement_timeout` for the whole session of every connection
+    /// made with the configuration, including those made when reconnecting. Setting a parameter again replaces its
+    /// value.
+    pub fn runtime_param(&mut self, name: &str, value: &str) -> &mut Config {
+        self.config.runtime_param(name, value);
+        self
+    }
+
+    /// Gets the value of an additional runtime parameter, if it has been set with the `runtime_param` method.
+    pub fn get_runtime_param(&self, name: &str) -> Option<&str> {
+        self.config.get_runtime_param(name)
+    }
+
+    /// Gets the names and values of the additional runtime parameters set with the `runtime_param` method, in the
+    /// order they were first set.
+    pub fn get_runtime_params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.config.get_runtime_params()
+    }
+
     /// Sets the SSL configuration.
     ///
     /// Defaults to `prefer`.
@@ -245,10 +288,18 @@ impl Config {
     }
 
     /// Gets the hostaddrs that have been added to the configuration with `hostaddr`.
+    ///
+    /// The empty hostaddrs added with `hostaddr_lookup` are skipped, so the hostaddrs don't necessarily line up with
+    /// the hosts. Use `get_hostaddr_entries` to get both.
     pub fn get_hostaddrs(&self) -> &[IpAddr] {
         self.config.get_hostaddrs()
     }
 
+    /// Gets the hostaddrs that have been added to the configuration with `hostaddr` and `hostaddr_lookup`, in order.
+    pub fn get_hostaddr_entries(&self) -> &[Option<IpAddr>] {
+        self.config.get_hostaddr_entries()
+    }
+
     /// Adds a Unix socket host to the configuration.
     ///
     /// Unlike `host`, this method allows non-UTF8 paths.
@@ -270,6 +321,14 @@ impl Config {
         self
     }
 
+    /// Adds an empty hostaddr to the configuration, so that the name of the host at the same position is looked up.
+    ///
+    /// This allows hosts whose address is known to be mixed with hosts which must be looked up.
+    pub fn hostaddr_lookup(&mut self) -> &mut Config {
+        self.config.hostaddr_lookup();
+        self
+    }
+
     /// Adds a port to the configuration.
     ///
     /// Multiple ports can be specified by calling this method multiple times. There must either be no ports, in which
@@ -413,6 +472,80 @@ impl Config {
         self.config.get_load_balance_hosts()
     }
 
+    /// Sets the path to the password file.
+    ///
+    /// When no password has been set, the password for each connection attempt is looked up in this file, as libpq
+    /// does. Each line of the file has the format `hostname:port:database:username:password`, where any of the first
+    /// four fields can be `*` to match anything. On Unix, the file is ignored if it can be accessed by the group or
+    /// others.
+    ///
+    /// Defaults to the file named by the `PGPASSFILE` environment variable, or `~/.pgpass`
+    /// (`%APPDATA%\postgresql\pgpass.conf` on Windows).
+    pub fn passfile<T>(&mut self, passfile: T) -> &mut Config
+    where
+        T: AsRef<Path>,
+    {
+        self.config.passfile(passfile);
+        self
+    }
+
+    /// Gets the path to the password file, if one has been set with the `passfile` method.
+    pub fn get_passfile(&self) -> Option<&Path> {
+        self.config.get_passfile()
+    }
+
+    /// Sets the resolver used to look up the addresses of TCP hosts.
+    ///
+    /// The resolver runs on the client's runtime. It is used instead of the system resolver for hosts without a
+    /// `hostaddr`, unless connecting through a proxy.
+    ///
+    /// Defaults to [`SystemResolver`](crate::resolve::SystemResolver).
+    pub fn resolver<R>(&mut self, resolver: R) -> &mut Config
+    where
+        R: Resolve + 'static,
+    {
+        self.config.resolver(resolver);
+        self
+    }
+
+    /// Gets the resolver used to look up the addresses of TCP hosts, if one has been set with the `resolver` method.
+    pub fn get_resolver(&self) -> Option<&dyn Resolve> {
+        self.config.get_resolver()
+    }
+
+    /// Sets a SOCKS5 proxy through which TCP connections to the hosts are tunneled.
+    ///
+    /// Host names are resolved by the proxy rather than locally. Unix socket hosts are connected to directly.
+    ///
+    /// Requires the `socks` Cargo feature. Defaults to no proxy.
+    #[cfg(feature = "socks")]
+    pub fn proxy(&mut self, host: &str, port: u16) -> &mut Config {
+        self.config.proxy(host, port);
+        self
+    }
+
+    /// Gets the host and port of the SOCKS5 proxy, if one has been set with the `proxy` method.
+    #[cfg(feature = "socks")]
+    pub fn get_proxy(&self) -> Option<(&str, u16)> {
+        self.config.get_proxy()
+    }
+
+    /// Sets the username and password to authenticate with the SOCKS5 proxy.
+    ///
+    /// Requires the `socks` Cargo feature. Defaults to no authentication.
+    #[cfg(feature = "socks")]
+    pub fn proxy_credentials(&mut self, username: &str, password: &str) -> &mut Config {
+        self.config.proxy_credentials(username, password);
+        self
+    }
+
+    /// Gets the username used to authenticate with the SOCKS5 proxy, if one has been set with the
+    /// `proxy_credentials` method.
+    #[cfg(feature = "socks")]
+    pub fn get_proxy_username(&self) -> Option<&str> {
+        self.config.get_proxy_username()
+    }
+
     /// Sets the notice callback.
     ///
     /// This callback will be invoked with the contents of every
@@ -432,6 +565,136 @@ impl Config {
         self
     }
 
+    /// Sets the notification callback.
+    ///
+    /// When set, this callback will be invoked with every [`Notification`] received by the connection instead of
+    /// buffering it for the [`Notifications`] API. Notifications are received whenever the client is communicating
+    /// with the server, such as while executing a query.
+    ///
+    /// [`Notifications`]: crate::Notifications
+    pub fn notification_callback<F>(&mut self, f: F) -> &mut Config
+    where
+        F: Fn(Notification) + Send + Sync + 'static,
+    {
+        self.notification_callback = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets the number of notifications buffered for the [`Notifications`] API.
+    ///
+    /// Notifications received while the client is communicating with the server are otherwise buffered without limit
+    /// until they are read. When the buffer is full, the [`notification_overflow`](Config::notification_overflow)
+    /// policy applies. Notifications passed to a [`notification_callback`](Config::notification_callback) are not
+    /// buffered.
+    ///
+    /// Defaults to no limit.
+    ///
+    /// [`Notifications`]: crate::Notifications
+    pub fn notification_buffer_size(&mut self, notification_buffer_size: usize) -> &mut Config {
+        self.config
+            .notification_buffer_size(notification_buffer_size);
+        self
+    }
+
+    /// Gets the number of notifications buffered for the `Notifications` API, if limited.
+    pub fn get_notification_buffer_size(&self) -> Option<usize> {
+        self.config.get_notification_buffer_size()
+    }
+
+    /// Sets what happens to a notification when the buffer of notifications is full.
+    ///
+    /// With [`OverflowPolicy::Error`], the operation during which the notification was received fails. The number of
+    /// discarded notifications is available from [`Notifications::dropped`](crate::Notifications::dropped).
+    ///
+    /// Defaults to [`OverflowPolicy::DropOldest`].
+    pub fn notification_overflow(&mut self, notification_overflow: OverflowPolicy) -> &mut Config {
+        self.config.notification_overflow(notification_overflow);
+        self
+    }
+
+    /// Gets what happens to a notification when the buffer of notifications is full.
+    pub fn get_notification_overflow(&self) -> OverflowPolicy {
+        self.config.get_notification_overflow()
+    }
+
+    /// Sets the number of rows fetched from the server at a time by `query_raw`.
+    ///
+    /// When set, the `RowIter` returned by `query_raw` requests the next batch of rows only once the previous batch
+    /// has been consumed, bounding memory use for large result sets. A value of 0 or less fetches all rows at once.
+    ///
+    /// Defaults to 0.
+    pub fn fetch_size(&mut self, fetch_size: i32) -> &mut Config {
+        self.fetch_size = fetch_size;
+        self
+    }
+
+    /// Gets the number of rows fetched from the server at a time by `query_raw`.
+    pub fn get_fetch_size(&self) -> i32 {
+        self.fetch_size
+    }
+
+    /// Sets the time limit applied to each blocking operation performed by the client.
+    ///
+    /// Operations which do not complete in time fail with a timeout error. Note that the query is not canceled on the
+    /// server; use a [`CancelToken`] or the `statement_timeout` setting for that. Iterators such as `RowIter` apply
+    /// the limit to each call to `next`. Connection establishment is instead bounded by `connect_timeout`.
+    ///
+    /// Defaults to no limit.
+    ///
+    /// [`CancelToken`]: crate::CancelToken
+    pub fn query_timeout(&mut self, query_timeout: Duration) -> &mut Config {
+        self.query_timeout = Some(query_timeout);
+        self
+    }
+
+    /// Gets the query timeout, if one has been set with the `query_timeout` method.
+    pub fn get_query_timeout(&self) -> Option<&Duration> {
+        self.query_timeout.as_ref()
+    }
+
+    /// Sets the time limit a blocking operation may wait without any activity on the connection.
+    ///
+    /// Unlike `query_timeout`, this allows long running operations as long as the server keeps sending data, such as
+    /// while iterating over a large result set or reading from a `COPY ... TO STDOUT` query.
+    ///
+    /// Defaults to no limit.
+    pub fn idle_timeout(&mut self, idle_timeout: Duration) -> &mut Config {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Gets the idle timeout, if one has been set with the `idle_timeout` method.
+    pub fn get_idle_timeout(&self) -> Option<&Duration> {
+        self.idle_timeout.as_ref()
+    }
+
+    /// Controls whether the client transparently reconnects when its connection is lost.
+    ///
+    /// When enabled, the client checks the connection before each operation, and reconnects with this configuration
+    /// if the server has closed it. Statements prepared through the client are prepared again on the new connection,
+    /// so existing `Statement`s remain usable.
+    ///
+    /// If the connection is lost while an operation runs, the client reconnects and returns the original error, since
+    /// the server may have executed the operation already. Only operations which don't modify the database, such as
+    /// preparing a statement, are retried once on the new connection. If reconnecting fails, the original error is
+    /// returned.
+    ///
+    /// Session state, such as settings changed with `SET`, is lost along with the connection. Settings which should
+    /// survive a reconnect are best set with [`Config::runtime_param`], which applies them to every new connection.
+    ///
+    /// Transactions and iterators such as `RowIter` are never retried.
+    ///
+    /// Defaults to `false`.
+    pub fn auto_reconnect(&mut self, auto_reconnect: bool) -> &mut Config {
+        self.auto_reconnect = auto_reconnect;
+        self
+    }
+
+    /// Reports whether the client will transparently reconnect when its connection is lost.
+    pub fn get_auto_reconnect(&self) -> bool {
+        self.auto_reconnect
+    }
+
     /// Opens a connection to a PostgreSQL database.
     pub fn connect<T>(&self, tls: T) -> Result<Client, Error>
     where
@@ -439,6 +702,25 @@ impl Config {
         T::TlsConnect: Send,
         T::Stream: Send,
         <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+    {
+        if !self.auto_reconnect {
+            let (connection, client) = self.connect_raw(tls)?;
+            return Ok(Client::new(connection, client));
+        }
+
+        let config = self.clone();
+        let mut tls = tls;
+        let mut connect = move || config.connect_raw(TlsRef(&mut tls));
+        let (connection, client) = connect()?;
+        Ok(Client::new(connection, client).with_reconnect(Box::new(connect)))
+    }
+
+    fn connect_raw<T>(&self, tls: T) -> Result<(Connection, tokio_postgres::Client), Error>
+    where
+        T: MakeTlsConnect<Socket>,
+        T::TlsConnect: Send,
+        T::Stream: Send + 'static,
+        <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
     {
         let runtime = runtime::Builder::new_current_thread()
             .enable_all()
@@ -447,8 +729,24 @@ impl Config {
 
         let (client, connection) = runtime.block_on(self.config.connect(tls))?;
 
-        let connection = Connection::new(runtime, connection, self.notice_callback.clone());
-        Ok(Client::new(connection, client))
+        let connection = Connection::new(runtime, connection, self);
+        Ok((connection, client))
+    }
+}
+
+/// Lends a `MakeTlsConnect` implementation to each reconnection attempt.
+struct TlsRef<'a, T>(&'a mut T);
+
+impl<T> MakeTlsConnect<Socket> for TlsRef<'_, T>
+where
+    T: MakeTlsConnect<Socket>,
+{
+    type Stream = T::Stream;
+    type TlsConnect = T::TlsConnect;
+    type Error = T::Error;
+
+    fn make_tls_connect(&mut self, domain: &str) -> Result<T::TlsConnect, T::Error> {
+        self.0.make_tls_connect(domain)
     }
 }
 
@@ -467,6 +765,11 @@ impl From<tokio_postgres::Config> for Config {
             notice_callback: Arc::new(|notice| {
                 info!("{}: {}", notice.severity(), notice.message())
             }),
+            notification_callback: None,
+            fetch_size: 0,
+            query_timeout: None,
+            idle_timeout: None,
+            auto_reconnect: false,
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::{Error, Notification};
+use crate::{Config, Error, Notification};
 use futures_util::{future, pin_mut, Stream};
 use std::collections::VecDeque;
 use std::future::Future;
@@ -6,23 +6,33 @@ use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::runtime::Runtime;
+use tokio::time::{self, Instant};
 use tokio_postgres::error::DbError;
-use tokio_postgres::AsyncMessage;
+use tokio_postgres::{AsyncMessage, OverflowPolicy};
 
 pub struct Connection {
     runtime: Runtime,
     connection: Pin<Box<dyn Stream<Item = Result<AsyncMessage, Error>> + Send>>,
     notifications: VecDeque<Notification>,
+    notification_buffer_size: usize,
+    notification_overflow: OverflowPolicy,
+    dropped_notifications: u64,
     notice_callback: Arc<dyn Fn(DbError) + Sync + Send>,
+    notification_callback: Option<Arc<dyn Fn(Notification) + Sync + Send>>,
+    fetch_size: i32,
+    query_timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    closed: bool,
 }
 
 impl Connection {
     pub fn new<S, T>(
         runtime: Runtime,
         connection: tokio_postgres::Connection<S, T>,
-        notice_callback: Arc<dyn Fn(DbError) + Sync + Send>,
+        config: &Config,
     ) -> Connection
     where
         S: AsyncRead + AsyncWrite + Unpin + 'static + Send,
@@ -32,10 +42,25 @@ impl Connection {
             runtime,
             connection: Box::pin(ConnectionStream { connection }),
             notifications: VecDeque::new(),
-            notice_callback,
+            notification_buffer_size: config
+                .config
+                .get_notification_buffer_size()
+                .unwrap_or(usize::MAX),
+            notification_overflow: config.config.get_notification_overflow(),
+            dropped_notifications: 0,
+            notice_callback: config.notice_callback.clone(),
+            notification_callback: config.notification_callback.clone(),
+            fetch_size: config.fetch_size,
+            query_timeout: config.query_timeout,
+            idle_timeout: config.idle_timeout,
+            closed: false,
         }
     }
 
+    pub fn fetch_size(&self) -> i32 {
+        self.fetch_size
+    }
+
     pub fn as_ref(&mut self) -> ConnectionRef<'_> {
         ConnectionRef { connection: self }
     }
@@ -56,26 +81,97 @@ impl Connection {
         self.poll_block_on(|cx, _, _| future.as_mut().poll(cx))
     }
 
+    /// Like `poll_block_on_untimed`, but bounded by the configured query and idle timeouts.
     pub fn poll_block_on<F, T>(&mut self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut Context<'_>, &mut VecDeque<Notification>, bool) -> Poll<Result<T, Error>>,
+    {
+        if self.query_timeout.is_none() && self.idle_timeout.is_none() {
+            return self.poll_block_on_untimed(f);
+        }
+
+        let query_timeout = self.query_timeout;
+        let idle_timeout = self.idle_timeout;
+        let mut timers = None;
+        self.poll_block_on_untimed(|cx, notifications, done| {
+            // the timers have to be created from within the runtime
+            let (deadline, idle) = timers.get_or_insert_with(|| {
+                (
+                    query_timeout.map(|timeout| Box::pin(time::sleep(timeout))),
+                    idle_timeout.map(|timeout| Box::pin(time::sleep(timeout))),
+                )
+            });
+
+            for timer in deadline.iter_mut().chain(idle.iter_mut()) {
+                if timer.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(Err(Error::__private_api_timeout()));
+                }
+            }
+
+            let poll = f(cx, notifications, done);
+            if poll.is_pending() {
+                // every wakeup is activity on the connection, so push the idle deadline back
+                if let (Some(idle), Some(idle_timeout)) = (idle, idle_timeout) {
+                    idle.as_mut().reset(Instant::now() + idle_timeout);
+                    let _ = idle.as_mut().poll(cx);
+                }
+            }
+            poll
+        })
+    }
+
+    pub fn poll_block_on_untimed<F, T>(&mut self, mut f: F) -> Result<T, Error>
     where
         F: FnMut(&mut Context<'_>, &mut VecDeque<Notification>, bool) -> Poll<Result<T, Error>>,
     {
         let connection = &mut self.connection;
         let notifications = &mut self.notifications;
+        let notification_buffer_size = self.notification_buffer_size;
+        let notification_overflow = self.notification_overflow;
+        let dropped_notifications = &mut self.dropped_notifications;
         let notice_callback = &mut self.notice_callback;
+        let notification_callback = &mut self.notification_callback;
+        let closed = &mut self.closed;
         self.runtime.block_on({
             future::poll_fn(|cx| {
                 let done = loop {
                     match connection.as_mut().poll_next(cx) {
                         Poll::Ready(Some(Ok(AsyncMessage::Notification(notification)))) => {
-                            notifications.push_back(notification);
+                            match notification_callback {
+                                Some(callback) => callback(notification),
+                                None => {
+                                    if notifications.len() >= notification_buffer_size {
+                                        *dropped_notifications += 1;
+                                        match notification_overflow {
+                                            OverflowPolicy::DropOldest => {
+                                                notifications.pop_front();
+                                            }
+                                            OverflowPolicy::DropNewest => continue,
+                                            _ => {
+                                                return Poll::Ready(Err(
+                                                    Error::__private_api_overflow(),
+                                                ))
+                                            }
+                                        }
+                                    }
+                                    if notification_buffer_size > 0 {
+                                        notifications.push_back(notification);
+                                    }
+                                }
+                            }
                         }
                         Poll::Ready(Some(Ok(AsyncMessage::Notice(notice)))) => {
                             notice_callback(notice)
                         }
                         Poll::Ready(Some(Ok(_))) => {}
-                        Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(e)),
-                        Poll::Ready(None) => break true,
+                        Poll::Ready(Some(Err(e))) => {
+                            *closed = true;
+                            return Poll::Ready(Err(e));
+                        }
+                        Poll::Ready(None) => {
+                            *closed = true;
+                            break true;
+                        }
                         Poll::Pending => break false,
                     }
                 };
@@ -85,6 +181,11 @@ impl Connection {
         })
     }
 
+    /// Determines if the connection has terminated, either normally or with an error.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
     pub fn notifications(&self) -> &VecDeque<Notification> {
         &self.notifications
     }
@@ -92,6 +193,10 @@ impl Connection {
     pub fn notifications_mut(&mut self) -> &mut VecDeque<Notification> {
         &mut self.notifications
     }
+
+    pub fn dropped_notifications(&self) -> u64 {
+        self.dropped_notifications
+    }
 }
 
 pub struct ConnectionRef<'a> {
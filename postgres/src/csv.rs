@@ -0,0 +1,219 @@
+//! CSV `COPY` support.
+//!
+//! Requires the `with-csv-1` Cargo feature.
+//!
+//! [`Client::copy_in_csv`](crate::Client::copy_in_csv) pipes the records of a [`csv::Reader`](csv_1::Reader) into a
+//! table, and [`Client::copy_out_csv`](crate::Client::copy_out_csv) returns the results of a query as one. The format
+//! of the data exchanged with the server is described by [`CsvOptions`].
+//!
+//! ```no_run
+//! # use csv_1 as csv;
+//! use postgres::csv::CsvOptions;
+//! use postgres::{Client, NoTls};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = Client::connect("host=localhost user=postgres", NoTls)?;
+//!
+//! let mut reader = csv::Reader::from_path("users.csv")?;
+//! client.copy_in_csv("users", &["name", "age"], &mut reader, &CsvOptions::new())?;
+//!
+//! let mut reader = client.copy_out_csv("SELECT name, age FROM users", &CsvOptions::new().header(true))?;
+//! for record in reader.records() {
+//!     let record = record?;
+//!     println!("{} is {}", &record[0], &record[1]);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::{CopyInWriter, CopyOutReader, Error};
+use std::fmt::Write as _;
+use std::io::{self, Read};
+use tokio_postgres::escape::{escape_identifier, escape_literal};
+
+/// The options of the CSV format exchanged with the server.
+///
+/// The defaults match those of Postgres: fields are separated by commas and quoted with double quotes, which are
+/// escaped by doubling them, and an empty field is `NULL`.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    escape: Option<u8>,
+    header: bool,
+    null: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            header: false,
+            null: String::new(),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Creates the default options.
+    pub fn new() -> CsvOptions {
+        CsvOptions::default()
+    }
+
+    /// Sets the ASCII character separating the fields of a record.
+    ///
+    /// Defaults to `,`.
+    pub fn delimiter(mut self, delimiter: u8) -> CsvOptions {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the ASCII character quoting fields.
+    ///
+    /// Defaults to `"`.
+    pub fn quote(mut self, quote: u8) -> CsvOptions {
+        self.quote = quote;
+        self
+    }
+
+    /// Sets the ASCII character escaping quote characters within quoted fields.
+    ///
+    /// Defaults to the quote character, which escapes quotes by doubling them.
+    pub fn escape(mut self, escape: u8) -> CsvOptions {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// Sets whether the data returned by [`Client::copy_out_csv`](crate::Client::copy_out_csv) starts with a record
+    /// of the names of the columns, which is then available from the reader's `headers` method.
+    ///
+    /// Data copied in never has a header, as the reader it comes from has already consumed it if it had one.
+    ///
+    /// Defaults to `false`.
+    pub fn header(mut self, header: bool) -> CsvOptions {
+        self.header = header;
+        self
+    }
+
+    /// Sets the string representing `NULL`.
+    ///
+    /// When copying in, a field equal to it is `NULL` whether or not it was quoted, so with the default empty string
+    /// there is no way to insert an empty string. When copying out, `NULL` is written unquoted as this string, while
+    /// an empty string is written as `""`, which a [`csv::Reader`](csv_1::Reader) reads as an empty field as well.
+    ///
+    /// Defaults to the empty string.
+    pub fn null(mut self, null: &str) -> CsvOptions {
+        self.null = null.to_string();
+        self
+    }
+
+    fn escape_char(&self) -> u8 {
+        self.escape.unwrap_or(self.quote)
+    }
+
+    fn copy_options(&self, header: bool) -> Result<String, Error> {
+        let char = |c: u8| escape_literal(&(c as char).to_string());
+
+        let mut options = format!(
+            "FORMAT csv, DELIMITER {}, QUOTE {}, ESCAPE {}, NULL {}",
+            char(self.delimiter)?,
+            char(self.quote)?,
+            char(self.escape_char())?,
+            escape_literal(&self.null)?,
+        );
+        if header {
+            options.push_str(", HEADER true");
+        }
+        Ok(options)
+    }
+
+    fn reader_builder(&self) -> csv_1::ReaderBuilder {
+        let mut builder = csv_1::ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .has_headers(self.header);
+        if self.escape_char() != self.quote {
+            builder.escape(self.escape).double_quote(false);
+        }
+        builder
+    }
+
+    fn writer_builder(&self) -> csv_1::WriterBuilder {
+        let mut builder = csv_1::WriterBuilder::new();
+        builder.delimiter(self.delimiter).quote(self.quote);
+        if self.escape_char() != self.quote {
+            builder.escape(self.escape_char()).double_quote(false);
+        }
+        builder
+    }
+}
+
+pub(crate) fn copy_in_statement(
+    table: &str,
+    columns: &[&str],
+    options: &CsvOptions,
+) -> Result<String, Error> {
+    let mut statement = format!("COPY {}", escape_identifier(table)?);
+    let mut options = options.copy_options(false)?;
+
+    if !columns.is_empty() {
+        let columns = columns
+            .iter()
+            .map(|c| escape_identifier(c))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(", ");
+        let _ = write!(statement, " ({})", columns);
+        let _ = write!(options, ", FORCE_NULL ({})", columns);
+    }
+
+    let _ = write!(statement, " FROM STDIN ({})", options);
+    Ok(statement)
+}
+
+pub(crate) fn copy_in<R>(
+    mut writer: CopyInWriter<'_>,
+    reader: &mut csv_1::Reader<R>,
+    options: &CsvOptions,
+) -> Result<u64, Error>
+where
+    R: Read,
+{
+    {
+        let mut csv_writer = options.writer_builder().from_writer(&mut writer);
+        let mut record = csv_1::ByteRecord::new();
+        while reader.read_byte_record(&mut record).map_err(csv_error)? {
+            csv_writer.write_byte_record(&record).map_err(csv_error)?;
+        }
+        csv_writer.flush().map_err(io_error)?;
+    }
+    writer.finish()
+}
+
+pub(crate) fn copy_out_statement(query: &str, options: &CsvOptions) -> Result<String, Error> {
+    Ok(format!(
+        "COPY ({}) TO STDOUT ({})",
+        query,
+        options.copy_options(options.header)?
+    ))
+}
+
+pub(crate) fn reader<'a>(
+    reader: CopyOutReader<'a>,
+    options: &CsvOptions,
+) -> csv_1::Reader<CopyOutReader<'a>> {
+    options.reader_builder().from_reader(reader)
+}
+
+fn csv_error(e: csv_1::Error) -> Error {
+    io_error(e.into())
+}
+
+// errors of the copy itself are passed through the writer as IO errors
+fn io_error(e: io::Error) -> Error {
+    if matches!(e.get_ref(), Some(inner) if inner.is::<Error>()) {
+        return *e.into_inner().unwrap().downcast::<Error>().unwrap();
+    }
+    Error::__private_api_encode(e)
+}
@@ -53,8 +53,13 @@
 //!
 //! | Feature | Description | Extra dependencies | Default |
 //! | ------- | ----------- | ------------------ | ------- |
+//! | `derive` | Enable `#[derive(FromRow)]`, `#[derive(ToParams)]` and `#[derive(ToCopyRow)]`. | | no |
+//! | `socks` | Enable connecting through a SOCKS5 proxy with `Config::proxy`. | | no |
 //! | `with-bit-vec-0_6` | Enable support for the `bit-vec` crate. | [bit-vec](https://crates.io/crates/bit-vec) 0.6 | no |
 //! | `with-chrono-0_4` | Enable support for the `chrono` crate. | [chrono](https://crates.io/crates/chrono) 0.4 | no |
+//! | `with-chrono-tz-0_9` | Enable support for the 0.9 version of the `chrono-tz` crate. | [chrono-tz](https://crates.io/crates/chrono-tz/0.9.0) 0.9 | no |
+//! | `with-chrono-tz-0_10` | Enable support for the 0.10 version of the `chrono-tz` crate. | [chrono-tz](https://crates.io/crates/chrono-tz/0.10.0) 0.10 | no |
+//! | `with-csv-1` | Enable CSV `COPY` support with the `csv` crate. | [csv](https://crates.io/crates/csv) 1.0 | no |
 //! | `with-eui48-0_4` | Enable support for the 0.4 version of the `eui48` crate. This is deprecated and will be removed. | [eui48](https://crates.io/crates/eui48) 0.4 | no |
 //! | `with-eui48-1` | Enable support for the 1.0 version of the `eui48` crate. | [eui48](https://crates.io/crates/eui48) 1.0 | no |
 //! | `with-geo-types-0_6` | Enable support for the 0.6 version of the `geo-types` crate. | [geo-types](https://crates.io/crates/geo-types/0.6.0) 0.6 | no |
@@ -68,10 +73,12 @@
 
 pub use fallible_iterator;
 pub use tokio_postgres::{
-    error, row, tls, types, Column, IsolationLevel, Notification, Portal, SimpleQueryMessage,
-    Socket, Statement, ToStatement,
+    error, escape, resolve, row, tls, types, Column, FromRow, IntoParams, IsolationLevel,
+    Notification, OverflowPolicy, ParamsVec, Portal, QueryOptions, SimpleQueryMessage, Socket,
+    Statement, ToParams, ToStatement, TypedQuery,
 };
 
+pub use crate::batch::Batch;
 pub use crate::cancel_token::CancelToken;
 pub use crate::client::*;
 pub use crate::config::Config;
@@ -90,6 +97,7 @@ pub use crate::tls::NoTls;
 pub use crate::transaction::*;
 pub use crate::transaction_builder::TransactionBuilder;
 
+mod batch;
 pub mod binary_copy;
 mod cancel_token;
 mod client;
@@ -97,6 +105,8 @@ pub mod config;
 mod connection;
 mod copy_in_writer;
 mod copy_out_reader;
+#[cfg(feature = "with-csv-1")]
+pub mod csv;
 mod generic_client;
 mod lazy_pin;
 pub mod notifications;
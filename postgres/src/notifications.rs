@@ -24,6 +24,13 @@ impl<'a> Notifications<'a> {
         self.connection.notifications().len()
     }
 
+    /// Returns the number of notifications which were discarded because the buffer was full.
+    ///
+    /// See [`Config::notification_buffer_size`](crate::Config::notification_buffer_size).
+    pub fn dropped(&self) -> u64 {
+        self.connection.dropped_notifications()
+    }
+
     /// Determines if there are any already buffered pending notifications.
     pub fn is_empty(&self) -> bool {
         self.connection.notifications().is_empty()
@@ -87,7 +94,7 @@ impl<'a> FallibleIterator for Iter<'a> {
         }
 
         self.connection
-            .poll_block_on(|_, notifications, _| Poll::Ready(Ok(notifications.pop_front())))
+            .poll_block_on_untimed(|_, notifications, _| Poll::Ready(Ok(notifications.pop_front())))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -110,7 +117,7 @@ impl<'a> FallibleIterator for BlockingIter<'a> {
         }
 
         self.connection
-            .poll_block_on(|_, notifications, done| match notifications.pop_front() {
+            .poll_block_on_untimed(|_, notifications, done| match notifications.pop_front() {
                 Some(notification) => Poll::Ready(Ok(Some(notification))),
                 None if done => Poll::Ready(Ok(None)),
                 None => Poll::Pending,
@@ -141,19 +148,20 @@ impl<'a> FallibleIterator for TimeoutIter<'a> {
 
         let delay = &mut self.delay;
         let timeout = self.timeout;
-        self.connection.poll_block_on(|cx, notifications, done| {
-            match notifications.pop_front() {
-                Some(notification) => {
-                    delay.as_mut().reset(Instant::now() + timeout);
-                    return Poll::Ready(Ok(Some(notification)));
+        self.connection
+            .poll_block_on_untimed(|cx, notifications, done| {
+                match notifications.pop_front() {
+                    Some(notification) => {
+                        delay.as_mut().reset(Instant::now() + timeout);
+                        return Poll::Ready(Ok(Some(notification)));
+                    }
+                    None if done => return Poll::Ready(Ok(None)),
+                    None => {}
                 }
-                None if done => return Poll::Ready(Ok(None)),
-                None => {}
-            }
 
-            ready!(delay.poll_unpin(cx));
-            Poll::Ready(Ok(None))
-        })
+                ready!(delay.poll_unpin(cx));
+                Poll::Ready(Ok(None))
+            })
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -2,7 +2,7 @@ use crate::connection::ConnectionRef;
 use fallible_iterator::FallibleIterator;
 use futures_util::StreamExt;
 use std::pin::Pin;
-use tokio_postgres::{Error, Row, RowStream};
+use tokio_postgres::{Error, FromRow, Row, RowStream};
 
 /// The iterator returned by `query_raw`.
 pub struct RowIter<'a> {
@@ -24,6 +24,22 @@ impl<'a> RowIter<'a> {
     pub fn rows_affected(&self) -> Option<u64> {
         self.it.rows_affected()
     }
+
+    /// Returns an iterator which converts each row into a `T` as it is read.
+    pub fn map_as<T>(self) -> impl FallibleIterator<Item = T, Error = Error> + 'a
+    where
+        T: FromRow,
+    {
+        self.map(|row| T::from_row(&row))
+    }
+
+    /// Reads the remaining rows, converting each into a `T`.
+    pub fn collect_as<T>(self) -> Result<Vec<T>, Error>
+    where
+        T: FromRow,
+    {
+        self.map_as().collect()
+    }
 }
 
 impl FallibleIterator for RowIter<'_> {
@@ -1,4 +1,5 @@
 use std::io::{Read, Write};
+use std::iter;
 use std::str::FromStr;
 use std::sync::mpsc;
 use std::thread;
@@ -41,6 +42,31 @@ fn query_unprepared() {
     assert_eq!(rows[0].get::<_, &str>(0), "hello");
 }
 
+#[test]
+fn query_with_params() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, name TEXT)")
+        .unwrap();
+    let name = "steven".to_string();
+    let mut transaction = client.transaction().unwrap();
+    transaction
+        .execute_with("INSERT INTO foo (id, name) VALUES ($1, $2)", (1i32, &name))
+        .unwrap();
+    transaction.commit().unwrap();
+
+    let row = client
+        .query_one_with("SELECT name FROM foo WHERE id = $1", (1i32,))
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "steven");
+    assert!(client
+        .query_opt_with("SELECT name FROM foo WHERE id = $1", (2i32,))
+        .unwrap()
+        .is_none());
+    assert_eq!(client.query_with("SELECT * FROM foo", ()).unwrap().len(), 1);
+}
+
 #[test]
 fn transaction_commit() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -178,6 +204,35 @@ fn nested_transactions() {
     assert_eq!(rows[2].get::<_, i32>(0), 4);
 }
 
+#[test]
+fn transaction_scope() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY)")
+        .unwrap();
+
+    let mut transaction = client.transaction().unwrap();
+
+    let mut scope = transaction.scope().unwrap();
+    scope
+        .execute("INSERT INTO foo (id) VALUES (1)", &[])
+        .unwrap();
+    drop(scope);
+
+    let mut scope = transaction.scope().unwrap();
+    scope
+        .execute("INSERT INTO foo (id) VALUES (2)", &[])
+        .unwrap();
+    scope.commit().unwrap();
+
+    transaction.commit().unwrap();
+
+    let rows = client.query("SELECT id FROM foo", &[]).unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 2);
+}
+
 #[test]
 fn savepoints() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -370,6 +425,290 @@ fn portal() {
     assert_eq!(rows[0].get::<_, i32>(0), 3);
 }
 
+#[test]
+fn query_raw_fetch_size() {
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .fetch_size(2)
+        .connect(NoTls)
+        .unwrap();
+
+    client
+        .simple_query(
+            "CREATE TEMPORARY TABLE foo (id INT);
+             INSERT INTO foo (id) VALUES (1), (2), (3), (4), (5);",
+        )
+        .unwrap();
+
+    let mut it = client
+        .query_raw("SELECT id FROM foo ORDER BY id", iter::empty::<i32>())
+        .unwrap();
+    let mut ids = vec![];
+    while let Some(row) = it.next().unwrap() {
+        ids.push(row.get::<_, i32>(0));
+    }
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    assert_eq!(it.rows_affected(), Some(5));
+    drop(it);
+
+    // dropping a partially consumed iterator leaves the connection usable
+    let mut it = client
+        .query_raw("SELECT id FROM foo ORDER BY id", iter::empty::<i32>())
+        .unwrap();
+    assert_eq!(it.next().unwrap().unwrap().get::<_, i32>(0), 1);
+    drop(it);
+
+    // errors from later chunks surface while iterating
+    let mut it = client
+        .query_raw(
+            "SELECT 1 / (3 - n) FROM generate_series(1, 5) n",
+            iter::empty::<i32>(),
+        )
+        .unwrap();
+    it.next().unwrap().unwrap();
+    it.next().unwrap().unwrap();
+    assert!(it.next().is_err());
+    drop(it);
+
+    let rows = client.query("SELECT count(*) FROM foo", &[]).unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 5);
+}
+
+#[test]
+fn query_timeout() {
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .query_timeout(Duration::from_millis(100))
+        .connect(NoTls)
+        .unwrap();
+
+    let err = client.batch_execute("SELECT pg_sleep(0.5)").unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    // the server still finishes the timed out query before handling the next one
+    thread::sleep(Duration::from_millis(600));
+    let row = client.query_one("SELECT 1", &[]).unwrap();
+    assert_eq!(row.get::<_, i32>(0), 1);
+}
+
+#[test]
+fn runtime_params() {
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .runtime_param("statement_timeout", "100ms")
+        .runtime_param("search_path", "pg_catalog")
+        .connect(NoTls)
+        .unwrap();
+
+    let row = client.query_one("SHOW search_path", &[]).unwrap();
+    assert_eq!(row.get::<_, &str>(0), "pg_catalog");
+
+    let err = client.batch_execute("SELECT pg_sleep(10)").unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::QUERY_CANCELED));
+}
+
+#[test]
+fn is_valid() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client.is_valid(Duration::from_secs(5)).unwrap();
+
+    // a failed transaction leaves the connection usable once it has been rolled back
+    let mut transaction = client.transaction().unwrap();
+    transaction.batch_execute("SELECT 1/0").unwrap_err();
+    drop(transaction);
+    client.is_valid(Duration::from_secs(5)).unwrap();
+}
+
+#[test]
+fn idle_timeout() {
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .idle_timeout(Duration::from_millis(100))
+        .connect(NoTls)
+        .unwrap();
+
+    let err = client.batch_execute("SELECT pg_sleep(0.5)").unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    thread::sleep(Duration::from_millis(600));
+    let rows = client
+        .query("SELECT * FROM generate_series(1, 10000)", &[])
+        .unwrap();
+    assert_eq!(rows.len(), 10000);
+}
+
+#[test]
+fn execute_with_options_timeout() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let options = QueryOptions::new().timeout(Duration::from_millis(100));
+    let err = client
+        .execute_with_options("SELECT pg_sleep(10)", &[], &options)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    let rows = client.query("SELECT 1", &[]).unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[test]
+fn auto_reconnect() {
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .auto_reconnect(true)
+        .runtime_param("statement_timeout", "12345")
+        .connect(NoTls)
+        .unwrap();
+
+    let stmt = client.prepare("SELECT $1::INT, pg_backend_pid()").unwrap();
+    let pid = client.query_one(&stmt, &[&1i32]).unwrap().get::<_, i32>(1);
+
+    // waits for the backend to exit, so that the client finds the connection closed before sending the query
+    let mut other = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+    other
+        .execute("SELECT pg_terminate_backend($1, 5000)", &[&pid])
+        .unwrap();
+
+    let row = client.query_one(&stmt, &[&2i32]).unwrap();
+    assert_eq!(row.get::<_, i32>(0), 2);
+    assert_ne!(row.get::<_, i32>(1), pid);
+    assert!(!client.is_closed());
+
+    // the settings of the configuration apply to the new connection as well
+    let row = client.query_one("SHOW statement_timeout", &[]).unwrap();
+    assert_eq!(row.get::<_, &str>(0), "12345ms");
+}
+
+#[test]
+fn auto_reconnect_after_sending() {
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .auto_reconnect(true)
+        .connect(NoTls)
+        .unwrap();
+    client
+        .batch_execute(
+            "DROP SEQUENCE IF EXISTS auto_reconnect_seq; CREATE SEQUENCE auto_reconnect_seq",
+        )
+        .unwrap();
+
+    // the connection is lost after the statement was sent, so it is not run again on the new connection
+    client
+        .execute(
+            "SELECT nextval('auto_reconnect_seq'), pg_terminate_backend(pg_backend_pid())",
+            &[],
+        )
+        .unwrap_err();
+    assert!(!client.is_closed());
+
+    let row = client
+        .query_one("SELECT last_value FROM auto_reconnect_seq", &[])
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 1);
+    client
+        .batch_execute("DROP SEQUENCE auto_reconnect_seq")
+        .unwrap();
+}
+
+#[test]
+fn async_client() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let (a, b) = client
+        .block_on(|client| {
+            futures_util::future::try_join(
+                client.query_one("SELECT 1::INT", &[]),
+                client.query_one("SELECT 2::INT", &[]),
+            )
+        })
+        .unwrap();
+    assert_eq!(a.get::<_, i32>(0), 1);
+    assert_eq!(b.get::<_, i32>(0), 2);
+
+    assert!(!client.async_client().is_closed());
+    let row = client.query_one("SELECT 3::INT", &[]).unwrap();
+    assert_eq!(row.get::<_, i32>(0), 3);
+}
+
+#[test]
+fn batch() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .unwrap();
+
+    let stmt = client.prepare("SELECT count(*) FROM foo").unwrap();
+    let results = client
+        .batch()
+        .query("INSERT INTO foo (id) VALUES ($1), ($2)", &[&1i32, &2i32])
+        .query(&stmt, &[])
+        .query("SELECT id FROM foo ORDER BY id", &[])
+        .run()
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_empty());
+    assert_eq!(results[1][0].get::<_, i64>(0), 2);
+    assert_eq!(
+        results[2].iter().map(|r| r.get(0)).collect::<Vec<i32>>(),
+        [1, 2]
+    );
+
+    let err = client
+        .batch()
+        .query("SELECT 1", &[])
+        .query("SELECT * FROM nonexistent", &[])
+        .run()
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_TABLE));
+    client.query_one("SELECT 1", &[]).unwrap();
+}
+
+#[test]
+fn row_iter_as() {
+    struct Person {
+        id: i32,
+        name: String,
+    }
+
+    impl FromRow for Person {
+        fn from_row(row: &Row) -> Result<Person, Error> {
+            Ok(Person {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+            })
+        }
+    }
+
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let query = "SELECT * FROM (VALUES (1, 'alice'), (2, 'bob')) t (id, name)";
+    let mut it = client
+        .query_raw(query, iter::empty::<i32>())
+        .unwrap()
+        .map_as::<Person>();
+    let person = it.next().unwrap().unwrap();
+    assert_eq!((person.id, &*person.name), (1, "alice"));
+    let person = it.next().unwrap().unwrap();
+    assert_eq!((person.id, &*person.name), (2, "bob"));
+    assert!(it.next().unwrap().is_none());
+    drop(it);
+
+    let people = client
+        .query_raw(query, iter::empty::<i32>())
+        .unwrap()
+        .collect_as::<Person>()
+        .unwrap();
+    assert_eq!(people.len(), 2);
+
+    let res = client
+        .query_raw("SELECT 1 AS id", iter::empty::<i32>())
+        .unwrap()
+        .collect_as::<Person>();
+    assert!(res.is_err());
+}
+
 #[test]
 fn cancel_query() {
     let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -478,6 +817,84 @@ fn notifications_timeout_iter() {
     assert_eq!(notifications[1].payload(), "world");
 }
 
+#[test]
+fn transaction_snapshot() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+    let mut other = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let mut transaction = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .start()
+        .unwrap();
+    let snapshot = transaction.export_snapshot().unwrap();
+
+    let mut imported = other
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .use_snapshot(&snapshot)
+        .start()
+        .unwrap();
+    let exported = imported
+        .query_one("SELECT txid_current_snapshot()::TEXT", &[])
+        .unwrap()
+        .get::<_, String>(0);
+    let original = transaction
+        .query_one("SELECT txid_current_snapshot()::TEXT", &[])
+        .unwrap()
+        .get::<_, String>(0);
+    assert_eq!(exported, original);
+}
+
+#[test]
+fn two_phase_commit() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let mut transaction = client.transaction().unwrap();
+    transaction.query("SELECT txid_current()", &[]).unwrap();
+    transaction.prepare_transaction("sync-tpc").unwrap();
+
+    let prepared = client.prepared_transactions().unwrap();
+    assert!(prepared.iter().any(|t| t.gid() == "sync-tpc"));
+
+    client.rollback_prepared("sync-tpc").unwrap();
+    let prepared = client.prepared_transactions().unwrap();
+    assert!(!prepared.iter().any(|t| t.gid() == "sync-tpc"));
+}
+
+#[test]
+fn notification_buffer() {
+    let notify = "\
+        NOTIFY notification_buffer, '1';
+        NOTIFY notification_buffer, '2';
+        NOTIFY notification_buffer, '3';
+    ";
+
+    let mut config = Config::from_str("host=localhost port=5433 user=postgres").unwrap();
+    config.notification_buffer_size(2);
+    assert_eq!(config.get_notification_buffer_size(), Some(2));
+    let mut client = config.connect(NoTls).unwrap();
+
+    client.batch_execute("LISTEN notification_buffer").unwrap();
+    client.batch_execute(notify).unwrap();
+    let mut notifications = client.notifications();
+    assert_eq!(notifications.dropped(), 1);
+    let payloads = notifications
+        .iter()
+        .map(|n| Ok(n.payload().to_string()))
+        .collect::<Vec<_>>()
+        .unwrap();
+    assert_eq!(payloads, ["2", "3"]);
+
+    config.notification_overflow(OverflowPolicy::Error);
+    let mut client = config.connect(NoTls).unwrap();
+
+    client.batch_execute("LISTEN notification_buffer").unwrap();
+    let error = client.batch_execute(notify).unwrap_err();
+    assert_eq!(error.to_string(), "notification buffer overflowed");
+    assert_eq!(client.notifications().dropped(), 1);
+}
+
 #[test]
 fn notice_callback() {
     let (notice_tx, notice_rx) = mpsc::sync_channel(64);
@@ -494,6 +911,26 @@ fn notice_callback() {
     assert_eq!(notice_rx.recv().unwrap().message(), "custom");
 }
 
+#[test]
+fn notification_callback() {
+    let (notification_tx, notification_rx) = mpsc::sync_channel(64);
+    let mut client = Config::from_str("host=localhost port=5433 user=postgres")
+        .unwrap()
+        .notification_callback(move |n| notification_tx.send(n).unwrap())
+        .connect(NoTls)
+        .unwrap();
+
+    client
+        .batch_execute(
+            "LISTEN notification_callback;
+             NOTIFY notification_callback, 'hello';",
+        )
+        .unwrap();
+
+    assert_eq!(notification_rx.recv().unwrap().payload(), "hello");
+    assert_eq!(client.notifications().len(), 0);
+}
+
 #[test]
 fn explicit_close() {
     let client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
@@ -508,3 +945,74 @@ fn check_send() {
     is_send::<Statement>();
     is_send::<Transaction<'_>>();
 }
+
+#[test]
+fn wait_for_lsn() {
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    let lsn = client.current_wal_lsn().unwrap();
+    client.wait_for_lsn(lsn, Duration::from_secs(1)).unwrap();
+}
+
+#[cfg(feature = "with-csv-1")]
+#[test]
+fn copy_csv() {
+    use crate::csv::CsvOptions;
+
+    let mut client = Client::connect("host=localhost port=5433 user=postgres", NoTls).unwrap();
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, \"Name\" TEXT, note TEXT)")
+        .unwrap();
+
+    let data = "id;name;note\n1;'a;b';\n2;'it''s';''\n3;;\\N\n";
+    let mut reader = csv_1::ReaderBuilder::new()
+        .delimiter(b';')
+        .quote(b'\'')
+        .from_reader(data.as_bytes());
+    let rows = client
+        .copy_in_csv(
+            "foo",
+            &["id", "Name", "note"],
+            &mut reader,
+            &CsvOptions::new().null("\\N"),
+        )
+        .unwrap();
+    assert_eq!(rows, 3);
+
+    let rows = client
+        .query("SELECT \"Name\", note FROM foo ORDER BY id", &[])
+        .unwrap();
+    let values = rows
+        .iter()
+        .map(|r| (r.get(0), r.get(1)))
+        .collect::<Vec<(Option<&str>, Option<&str>)>>();
+    assert_eq!(
+        values,
+        [
+            (Some("a;b"), Some("")),
+            (Some("it's"), Some("")),
+            (Some(""), None)
+        ]
+    );
+
+    let mut reader = client
+        .copy_out_csv(
+            "SELECT id, \"Name\" FROM foo ORDER BY id",
+            &CsvOptions::new().delimiter(b'|').escape(b'\\').header(true),
+        )
+        .unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["id", "Name"]);
+    let records = reader
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(records, [["1", "a;b"], ["2", "it's"], ["3", ""]]);
+    drop(reader);
+
+    let mut reader = csv_1::Reader::from_reader("id\nx\n".as_bytes());
+    let err = client
+        .copy_in_csv("foo", &["id"], &mut reader, &CsvOptions::new())
+        .unwrap_err();
+    assert!(err.as_db_error().is_some(), "{}", err);
+}
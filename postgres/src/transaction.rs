@@ -1,9 +1,13 @@
 use std::fmt;
+#[cfg(feature = "with-csv-1")]
+use std::io;
 
 use crate::connection::ConnectionRef;
+#[cfg(feature = "with-csv-1")]
+use crate::csv::{self, CsvOptions};
 use crate::{CancelToken, CopyInWriter, CopyOutReader, Portal, RowIter, Statement, ToStatement};
 use tokio_postgres::types::{BorrowToSql, ToSql, Type};
-use tokio_postgres::{Error, Row, SimpleQueryMessage};
+use tokio_postgres::{Error, IntoParams, QueryOptions, Row, SimpleQueryMessage};
 
 /// A representation of a PostgreSQL database transaction.
 ///
@@ -55,6 +59,21 @@ impl<'a> Transaction<'a> {
             .block_on(self.transaction.take().unwrap().rollback())
     }
 
+    /// Consumes the transaction, preparing it for two-phase commit with the global identifier `gid`.
+    ///
+    /// The transaction is dissociated from the session and stored on the server, where it survives crashes and
+    /// restarts until it is committed with [`Client::commit_prepared`](crate::Client::commit_prepared) or rolled back
+    /// with [`Client::rollback_prepared`](crate::Client::rollback_prepared), possibly from another session. This
+    /// requires the `max_prepared_transactions` setting of the server to be above 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transaction is a nested transaction, as only a whole transaction can be prepared.
+    pub fn prepare_transaction(mut self, gid: &str) -> Result<(), Error> {
+        self.connection
+            .block_on(self.transaction.take().unwrap().prepare_transaction(gid))
+    }
+
     /// Like `Client::prepare`.
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
@@ -83,6 +102,25 @@ impl<'a> Transaction<'a> {
             .block_on(self.transaction.as_ref().unwrap().execute(query, params))
     }
 
+    /// Like `Client::execute_with_options`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn execute_with_options<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.connection.block_on(
+            self.transaction
+                .as_ref()
+                .unwrap()
+                .execute_with_options(query, params, options),
+        )
+    }
+
     /// Like `Client::query`.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub fn query<T>(&mut self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error>
@@ -93,6 +131,25 @@ impl<'a> Transaction<'a> {
             .block_on(self.transaction.as_ref().unwrap().query(query, params))
     }
 
+    /// Like `Client::query_with_options`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_with_options<T>(
+        &mut self,
+        query: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.connection.block_on(
+            self.transaction
+                .as_ref()
+                .unwrap()
+                .query_with_options(query, params, options),
+        )
+    }
+
     /// Like `Client::query_one`.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub fn query_one<T>(&mut self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error>
@@ -117,6 +174,66 @@ impl<'a> Transaction<'a> {
             .block_on(self.transaction.as_ref().unwrap().query_opt(query, params))
     }
 
+    /// Like `Client::query_with`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_with<T, P>(&mut self, query: &T, params: P) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query(query, &params)
+    }
+
+    /// Like `Client::query_one_with`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_one_with<T, P>(&mut self, query: &T, params: P) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query_one(query, &params)
+    }
+
+    /// Like `Client::query_opt_with`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn query_opt_with<T, P>(&mut self, query: &T, params: P) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query_opt(query, &params)
+    }
+
+    /// Like `Client::execute_with`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn execute_with<T, P>(&mut self, query: &T, params: P) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.execute(query, &params)
+    }
+
+    /// Like `Client::execute_many`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub fn execute_many<T, P, I>(&mut self, query: &T, params: I) -> Result<Vec<u64>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        self.connection.block_on(
+            self.transaction
+                .as_ref()
+                .unwrap()
+                .execute_many(query, params),
+        )
+    }
+
     /// Like `Client::query_raw`.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub fn query_raw<T, P, I>(&mut self, query: &T, params: I) -> Result<RowIter<'_>, Error>
@@ -126,9 +243,13 @@ impl<'a> Transaction<'a> {
         I: IntoIterator<Item = P>,
         I::IntoIter: ExactSizeIterator,
     {
-        let stream = self
-            .connection
-            .block_on(self.transaction.as_ref().unwrap().query_raw(query, params))?;
+        let fetch_size = self.connection.fetch_size();
+        let stream = self.connection.block_on(
+            self.transaction
+                .as_ref()
+                .unwrap()
+                .__private_api_query_raw_chunked(query, params, fetch_size),
+        )?;
         Ok(RowIter::new(self.connection.as_ref(), stream))
     }
 
@@ -205,6 +326,57 @@ impl<'a> Transaction<'a> {
         Ok(CopyOutReader::new(self.connection.as_ref(), stream))
     }
 
+    /// Like `Client::copy_insert`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(rows)))]
+    pub fn copy_insert<P, I>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        rows: I,
+    ) -> Result<u64, Error>
+    where
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        self.connection.block_on(
+            self.transaction
+                .as_ref()
+                .unwrap()
+                .copy_insert(table, columns, rows),
+        )
+    }
+
+    /// Like `Client::copy_in_csv`.
+    #[cfg(feature = "with-csv-1")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(reader)))]
+    pub fn copy_in_csv<R>(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        reader: &mut csv_1::Reader<R>,
+        options: &CsvOptions,
+    ) -> Result<u64, Error>
+    where
+        R: io::Read,
+    {
+        let statement = csv::copy_in_statement(table, columns, options)?;
+        let writer = self.copy_in(&statement)?;
+        csv::copy_in(writer, reader, options)
+    }
+
+    /// Like `Client::copy_out_csv`.
+    #[cfg(feature = "with-csv-1")]
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub fn copy_out_csv(
+        &mut self,
+        query: &str,
+        options: &CsvOptions,
+    ) -> Result<csv_1::Reader<CopyOutReader<'_>>, Error> {
+        let statement = csv::copy_out_statement(query, options)?;
+        let reader = self.copy_out(&statement)?;
+        Ok(csv::reader(reader, options))
+    }
+
     /// Like `Client::simple_query`.
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryMessage>, Error> {
@@ -219,6 +391,17 @@ impl<'a> Transaction<'a> {
             .block_on(self.transaction.as_ref().unwrap().batch_execute(query))
     }
 
+    /// Exports the snapshot of the transaction, returning its identifier.
+    ///
+    /// Transactions on other connections can use the snapshot with
+    /// [`TransactionBuilder::use_snapshot`](crate::TransactionBuilder::use_snapshot) to see the same data as this
+    /// transaction, for example to read a consistent view of the database with several connections in parallel. The
+    /// snapshot can only be used while this transaction is open.
+    pub fn export_snapshot(&mut self) -> Result<String, Error> {
+        self.connection
+            .block_on(self.transaction.as_ref().unwrap().export_snapshot())
+    }
+
     /// Like `Client::cancel_token`.
     pub fn cancel_token(&self) -> CancelToken {
         CancelToken::new(self.transaction.as_ref().unwrap().cancel_token())
@@ -232,6 +415,17 @@ impl<'a> Transaction<'a> {
         Ok(Transaction::new(self.connection.as_ref(), transaction))
     }
 
+    /// Creates a scope within the transaction, returning a guard for it.
+    ///
+    /// The scope is a nested transaction, whose changes are kept by consuming the guard with `commit`. If the guard is
+    /// dropped instead, the enclosing transaction is rolled back to the state it was in before the scope was created.
+    pub fn scope(&mut self) -> Result<Transaction<'_>, Error> {
+        let transaction = self
+            .connection
+            .block_on(self.transaction.as_mut().unwrap().scope())?;
+        Ok(Transaction::new(self.connection.as_ref(), transaction))
+    }
+
     /// Like `Client::transaction`, but creates a nested transaction via a savepoint with the specified name.
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub fn savepoint<I>(&mut self, name: I) -> Result<Transaction<'_>, Error>
@@ -40,6 +40,17 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
+    /// Sets the snapshot of the transaction to one exported by another transaction with
+    /// [`Transaction::export_snapshot`].
+    ///
+    /// The transaction then sees the same data as the exporting transaction, which must still be open. This requires
+    /// the `RepeatableRead` or `Serializable` isolation level, and a serializable transaction can only import a
+    /// snapshot from another serializable transaction.
+    pub fn use_snapshot(mut self, snapshot: &str) -> Self {
+        self.builder = self.builder.use_snapshot(snapshot);
+        self
+    }
+
     /// Begins the transaction.
     ///
     /// The transaction will roll back by default - use the `commit` method to commit it.
@@ -1,15 +1,11 @@
-use futures_util::FutureExt;
+use futures_util::TryStreamExt;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use tokio_postgres::error::Kind;
+use tokio_postgres::types::FromSql;
+use tokio_postgres::{Error, FromRow, Row};
 
-use tokio_postgres::FromRow;
-use tokio_postgres::{Client, NoTls};
-
-async fn connect(s: &str) -> Client {
-    let (client, connection) = tokio_postgres::connect(s, NoTls).await.unwrap();
-    let connection = connection.map(|e| e.unwrap());
-    tokio::spawn(connection);
-
-    client
-}
+use crate::connect;
 
 async fn query_row<R: FromRow>() -> Result<Vec<R>, tokio_postgres::Error> {
     let client = connect("user=postgres host=localhost port=5433").await;
@@ -63,6 +59,38 @@ async fn query_all_as_generic() {
     assert_eq!(user.age, 18);
 }
 
+#[tokio::test]
+async fn query_all_as_bound() {
+    struct NotDefault;
+
+    #[derive(FromRow)]
+    #[from_row(bound = "A: for<'a> FromSql<'a>")]
+    struct Person<A, B> {
+        name: String,
+        age: A,
+        #[from_row(skip)]
+        extra: Vec<B>,
+    }
+
+    #[derive(FromRow)]
+    struct Entry<B> {
+        name: String,
+        #[from_row(skip, bound = "")]
+        extra: Vec<B>,
+    }
+
+    let users = query_row::<Person<i32, NotDefault>>().await.unwrap();
+    let user = users.first().unwrap();
+    assert_eq!(user.name, "steven");
+    assert_eq!(user.age, 18);
+    assert!(user.extra.is_empty());
+
+    let entries = query_row::<Entry<NotDefault>>().await.unwrap();
+    let entry = entries.first().unwrap();
+    assert_eq!(entry.name, "steven");
+    assert!(entry.extra.is_empty());
+}
+
 #[tokio::test]
 async fn query_all_as_from() {
     #[derive(Debug, PartialEq)]
@@ -141,3 +169,506 @@ async fn query_all_as_skip() {
     assert_eq!(user.age, 18);
     assert_eq!(user.no_from_sql.message, "no from sql");
 }
+
+#[tokio::test]
+async fn query_all_as_rename() {
+    #[derive(Debug, PartialEq)]
+    struct Age(i32);
+
+    impl From<i32> for Age {
+        fn from(value: i32) -> Self {
+            Self(value)
+        }
+    }
+
+    #[derive(FromRow)]
+    struct Person {
+        #[from_row(rename = "name")]
+        full_name: String,
+        #[from_row(rename = "age", from = "i32")]
+        years: Age,
+        #[from_row(skip)]
+        nickname: String,
+    }
+
+    let users = query_row::<Person>().await.unwrap();
+
+    assert_eq!(users.len(), 1);
+    let user = users.first().unwrap();
+    assert_eq!(user.full_name, "steven");
+    assert_eq!(user.years, Age(18));
+    assert_eq!(user.nickname, "");
+}
+
+#[tokio::test]
+async fn query_all_as_rename_all() {
+    #[derive(FromRow)]
+    #[from_row(rename_all = "camelCase")]
+    struct Person {
+        full_name: String,
+        user_age: i32,
+        #[from_row(rename = "is_admin")]
+        admin: bool,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let users = client
+        .query_as::<Person, _>(
+            "SELECT 'steven' AS \"fullName\", 18 AS \"userAge\", false AS is_admin",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(users.len(), 1);
+    let user = users.first().unwrap();
+    assert_eq!(user.full_name, "steven");
+    assert_eq!(user.user_age, 18);
+    assert!(!user.admin);
+}
+
+#[tokio::test]
+async fn query_all_as_default() {
+    fn unknown() -> String {
+        "unknown".to_string()
+    }
+
+    #[derive(FromRow)]
+    struct Person {
+        name: String,
+        #[from_row(default)]
+        age: i32,
+        #[from_row(default = "unknown")]
+        city: String,
+        #[from_row(default, rename = "nickname")]
+        alias: Option<String>,
+    }
+
+    let users = query_row::<Person>().await.unwrap();
+
+    assert_eq!(users.len(), 1);
+    let user = users.first().unwrap();
+    assert_eq!(user.name, "steven");
+    assert_eq!(user.age, 18);
+    assert_eq!(user.city, "unknown");
+    assert_eq!(user.alias, None);
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let users = client
+        .query_as::<Person, _>("SELECT 'anna' AS name, 'Oslo' AS city", &[])
+        .await
+        .unwrap();
+    let user = users.first().unwrap();
+    assert_eq!(user.name, "anna");
+    assert_eq!(user.age, 0);
+    assert_eq!(user.city, "Oslo");
+}
+
+#[tokio::test]
+async fn query_all_as_try_from() {
+    #[derive(Debug, PartialEq)]
+    struct Age(u8);
+
+    impl TryFrom<i32> for Age {
+        type Error = std::num::TryFromIntError;
+
+        fn try_from(value: i32) -> Result<Self, Self::Error> {
+            u8::try_from(value).map(Self)
+        }
+    }
+
+    #[derive(FromRow)]
+    struct Person {
+        name: String,
+        #[from_row(try_from = "i32")]
+        age: Age,
+    }
+
+    let users = query_row::<Person>().await.unwrap();
+
+    assert_eq!(users.len(), 1);
+    let user = users.first().unwrap();
+    assert_eq!(user.name, "steven");
+    assert_eq!(user.age, Age(18));
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let err = client
+        .query_as::<Person, _>("SELECT 'anna' AS name, 300 AS age", &[])
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err.kind(), Kind::FromSql(1, _)));
+    assert_eq!(
+        err.to_string(),
+        "error reading field `Person::age` from column `age`: \
+         error deserializing column 1: out of range integral type conversion attempted"
+    );
+}
+
+#[tokio::test]
+async fn query_all_as_prefix() {
+    #[derive(FromRow)]
+    struct Address {
+        street: String,
+        city: String,
+    }
+
+    #[derive(FromRow)]
+    #[from_row(prefix = "person_")]
+    struct Person {
+        name: String,
+        #[from_row(flatten, prefix = "home_")]
+        home: Address,
+        #[from_row(flatten, prefix = "work_")]
+        work: Address,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let users = client
+        .query_as::<Person, _>(
+            "SELECT 'steven' AS person_name,
+                'Main St' AS person_home_street, 'Springfield' AS person_home_city,
+                'Side St' AS person_work_street, 'Shelbyville' AS person_work_city",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    let user = users.first().unwrap();
+    assert_eq!(user.name, "steven");
+    assert_eq!(user.home.street, "Main St");
+    assert_eq!(user.home.city, "Springfield");
+    assert_eq!(user.work.street, "Side St");
+    assert_eq!(user.work.city, "Shelbyville");
+}
+
+#[tokio::test]
+async fn query_all_as_enum() {
+    #[derive(Debug, PartialEq, FromRow)]
+    #[from_row(rename_all = "snake_case")]
+    enum Status {
+        Active,
+        OnHold,
+        #[from_row(rename = "gone")]
+        Deleted,
+    }
+
+    #[derive(Debug, PartialEq, FromRow)]
+    enum Level {
+        Low = 1,
+        High = 10,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let statuses = client
+        .query_as::<Status, _>(
+            "SELECT * FROM (VALUES ('active'), ('on_hold'), ('gone')) AS t (status)",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(statuses, [Status::Active, Status::OnHold, Status::Deleted]);
+
+    let err = client
+        .query_as::<Status, _>("SELECT 'Active'", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "error deserializing column 0: invalid value `Active` for enum `Status`"
+    );
+
+    let levels = client
+        .query_as::<Level, _>("SELECT 10::INT2 UNION ALL SELECT 1::INT8", &[])
+        .await
+        .unwrap();
+    assert_eq!(levels, [Level::High, Level::Low]);
+
+    let err = client
+        .query_as::<Level, _>("SELECT 2", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "error deserializing column 0: invalid value 2 for enum `Level`"
+    );
+}
+
+#[tokio::test]
+async fn query_all_as_tuple_struct() {
+    #[derive(FromRow)]
+    struct Count(i64);
+
+    #[derive(FromRow)]
+    struct Pair(
+        String,
+        #[from_row(skip)] Vec<u8>,
+        #[from_row(try_from = "i32")] u8,
+        #[from_row(default)] Option<bool>,
+    );
+
+    let count = query_row::<Count>().await.err().unwrap();
+    assert!(matches!(count.kind(), Kind::FromSql(0, _)));
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let count = client
+        .query_one_as::<Count, _>("SELECT count(*) FROM generate_series(1, 3)", &[])
+        .await
+        .unwrap();
+    assert_eq!(count.0, 3);
+
+    let pairs = query_row::<Pair>().await.unwrap();
+    assert_eq!(pairs.len(), 1);
+    let pair = pairs.first().unwrap();
+    assert_eq!(pair.0, "steven");
+    assert!(pair.1.is_empty());
+    assert_eq!(pair.2, 18);
+    assert_eq!(pair.3, None);
+}
+
+#[tokio::test]
+async fn query_all_as_by_index() {
+    #[derive(FromRow)]
+    #[from_row(by_index)]
+    struct Stats {
+        total: i64,
+        #[from_row(skip)]
+        label: String,
+        average: f64,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let stats = client
+        .query_one_as::<Stats, _>(
+            "SELECT count(*), avg(x)::FLOAT8 FROM generate_series(1, 4) AS t (x)",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(stats.total, 4);
+    assert_eq!(stats.label, "");
+    assert_eq!(stats.average, 2.5);
+}
+
+#[tokio::test]
+async fn query_all_as_with() {
+    fn split(row: &Row, column: &str) -> Result<Vec<String>, Error> {
+        let value = row.try_get::<_, &str>(column)?;
+        Ok(value.split(';').map(str::to_string).collect())
+    }
+
+    fn doubled(row: &Row, idx: usize) -> Result<i32, Error> {
+        row.try_get::<_, i32>(idx).map(|v| v * 2)
+    }
+
+    #[derive(FromRow)]
+    struct Person {
+        name: String,
+        #[from_row(with = "split", rename = "tags_csv")]
+        tags: Vec<String>,
+        #[from_row(with = "split", default)]
+        aliases: Vec<String>,
+    }
+
+    #[derive(FromRow)]
+    struct Doubled(#[from_row(with = "doubled")] i32);
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let person = client
+        .query_one_as::<Person, _>("SELECT 'steven' AS name, 'a;b;c' AS tags_csv", &[])
+        .await
+        .unwrap();
+    assert_eq!(person.name, "steven");
+    assert_eq!(person.tags, ["a", "b", "c"]);
+    assert!(person.aliases.is_empty());
+
+    let doubled = client
+        .query_one_as::<Doubled, _>("SELECT 21", &[])
+        .await
+        .unwrap();
+    assert_eq!(doubled.0, 42);
+}
+
+#[tokio::test]
+async fn query_all_as_error_context() {
+    #[derive(Debug, FromRow)]
+    struct Details {
+        #[allow(dead_code)]
+        age: String,
+    }
+
+    #[derive(Debug, FromRow)]
+    struct Person {
+        #[allow(dead_code)]
+        name: String,
+        #[allow(dead_code)]
+        #[from_row(flatten)]
+        details: Details,
+    }
+
+    #[derive(Debug, FromRow)]
+    struct Pair(#[allow(dead_code)] String, #[allow(dead_code)] String);
+
+    let err = query_row::<Person>().await.err().unwrap();
+    let field = err.field().unwrap();
+    assert_eq!(field.type_name(), "Details");
+    assert_eq!(field.field(), "age");
+    assert_eq!(field.column(), "age");
+    assert!(matches!(err.kind(), Kind::FromSql(1, _)));
+    assert!(err
+        .to_string()
+        .starts_with("error reading field `Details::age` from column `age`: "));
+
+    let err = query_row::<Pair>().await.err().unwrap();
+    let field = err.field().unwrap();
+    assert_eq!(field.type_name(), "Pair");
+    assert_eq!(field.field(), "1");
+    assert_eq!(field.column(), "age");
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let err = client
+        .query_one_as::<Person, _>("SELECT 'anna' AS name", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "error reading field `Details::age` from column `age`: invalid column `age`"
+    );
+}
+
+#[tokio::test]
+async fn query_all_as_option() {
+    #[derive(Debug, PartialEq, FromRow)]
+    struct Customer {
+        id: i32,
+        name: String,
+    }
+
+    #[derive(Debug, PartialEq, FromRow)]
+    struct Order {
+        id: i32,
+        #[from_row(flatten, prefix = "customer_")]
+        customer: Option<Customer>,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let orders = client
+        .query_as::<Order, _>(
+            "SELECT o.id, c.id AS customer_id, c.name AS customer_name
+             FROM (VALUES (1, 10), (2, NULL)) AS o (id, customer)
+             LEFT JOIN (VALUES (10, 'steven')) AS c (id, name) ON c.id = o.customer
+             ORDER BY o.id",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        orders,
+        [
+            Order {
+                id: 1,
+                customer: Some(Customer {
+                    id: 10,
+                    name: "steven".to_string(),
+                }),
+            },
+            Order {
+                id: 2,
+                customer: None,
+            },
+        ]
+    );
+
+    let customer = client
+        .query_one_as::<Option<Customer>, _>("SELECT NULL::INT AS id, 'anna' AS name", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(customer.field().unwrap().field(), "id");
+
+    let customer = client
+        .query_one_as::<Option<Customer>, _>("SELECT NULL::INT AS id, NULL::TEXT AS name", &[])
+        .await
+        .unwrap();
+    assert_eq!(customer, None);
+}
+
+#[tokio::test]
+async fn query_all_as_json() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Settings {
+        theme: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, FromRow)]
+    struct Person {
+        name: String,
+        #[from_row(json)]
+        settings: Settings,
+        #[from_row(json)]
+        previous: Option<Settings>,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let person = client
+        .query_one_as::<Person, _>(
+            r#"SELECT 'steven' AS name, '{"theme": "dark", "tags": ["a"]}'::JSONB AS settings,
+                NULL::JSON AS previous"#,
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        person,
+        Person {
+            name: "steven".to_string(),
+            settings: Settings {
+                theme: "dark".to_string(),
+                tags: vec!["a".to_string()],
+            },
+            previous: None,
+        }
+    );
+
+    let err = client
+        .query_one_as::<Person, _>(
+            r#"SELECT 'steven' AS name, NULL::JSONB AS settings, NULL::JSON AS previous"#,
+            &[],
+        )
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.field().unwrap().field(), "settings");
+    assert!(matches!(err.kind(), Kind::FromSql(1, _)));
+}
+
+#[tokio::test]
+async fn stream_as() {
+    #[derive(Debug, PartialEq, FromRow)]
+    struct Person {
+        name: String,
+        age: i32,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let stream = client
+        .stream_as::<Person, _>(
+            "SELECT 'person' || i AS name, i AS age FROM generate_series(1, 3) AS i",
+            &[],
+        )
+        .await
+        .unwrap();
+    let people = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(people.iter().map(|p| p.age).collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(people[2].name, "person3");
+
+    let mut stream = client
+        .stream_as::<Person, _>("SELECT 'steven' AS name, 'old' AS age", &[])
+        .await
+        .unwrap();
+    assert!(stream.try_next().await.is_err());
+}
@@ -1,3 +1,16 @@
 #![cfg(test)]
 
+use futures_util::FutureExt;
+use tokio_postgres::{Client, NoTls};
+
 mod from_row;
+mod to_copy_row;
+mod to_params;
+
+async fn connect(s: &str) -> Client {
+    let (client, connection) = tokio_postgres::connect(s, NoTls).await.unwrap();
+    let connection = connection.map(|e| e.unwrap());
+    tokio::spawn(connection);
+
+    client
+}
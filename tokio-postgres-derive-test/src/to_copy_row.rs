@@ -0,0 +1,108 @@
+use futures_util::pin_mut;
+use tokio_postgres::binary_copy::{BinaryCopyInWriter, ToCopyRow};
+use tokio_postgres::types::{Json, Type};
+
+use crate::connect;
+
+#[derive(ToCopyRow)]
+struct Event<'a> {
+    id: i64,
+    #[to_copy_row(rename = "label")]
+    name: &'a str,
+    tags: Vec<String>,
+    payload: Option<Vec<u8>>,
+    #[to_copy_row(type = "jsonb")]
+    data: Json<Vec<i32>>,
+    #[to_copy_row(skip)]
+    #[allow(dead_code)]
+    ignored: (),
+}
+
+#[test]
+fn columns_and_types() {
+    assert_eq!(Event::columns(), ["id", "label", "tags", "payload", "data"]);
+    assert_eq!(
+        Event::types(),
+        [
+            Type::INT8,
+            Type::TEXT,
+            Type::TEXT_ARRAY,
+            Type::BYTEA,
+            Type::JSONB
+        ]
+    );
+
+    let event = Event {
+        id: 1,
+        name: "a",
+        tags: vec![],
+        payload: None,
+        data: Json(vec![]),
+        ignored: (),
+    };
+    assert_eq!(event.values().len(), 5);
+}
+
+#[tokio::test]
+async fn write_rows() {
+    let client = connect("user=postgres host=localhost port=5433").await;
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE events (
+                id INT8,
+                label TEXT,
+                tags TEXT[],
+                payload BYTEA,
+                data JSONB
+            )",
+        )
+        .await
+        .unwrap();
+
+    let events = vec![
+        Event {
+            id: 1,
+            name: "first",
+            tags: vec!["a".to_string(), "b".to_string()],
+            payload: Some(vec![1, 2]),
+            data: Json(vec![1]),
+            ignored: (),
+        },
+        Event {
+            id: 2,
+            name: "second",
+            tags: vec![],
+            payload: None,
+            data: Json(vec![]),
+            ignored: (),
+        },
+    ];
+
+    let sink = client
+        .copy_in(&format!(
+            "COPY events ({}) FROM STDIN BINARY",
+            Event::columns().join(", ")
+        ))
+        .await
+        .unwrap();
+    let writer = BinaryCopyInWriter::new(sink, &Event::types());
+    pin_mut!(writer);
+    for event in &events {
+        writer.as_mut().write_row(event).await.unwrap();
+    }
+    assert_eq!(writer.finish().await.unwrap(), 2);
+
+    let rows = client
+        .query(
+            "SELECT label, tags, payload, data::TEXT FROM events ORDER BY id",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, &str>(0), "first");
+    assert_eq!(rows[0].get::<_, Vec<String>>(1), ["a", "b"]);
+    assert_eq!(rows[0].get::<_, Option<Vec<u8>>>(2), Some(vec![1, 2]));
+    assert_eq!(rows[0].get::<_, &str>(3), "[1]");
+    assert_eq!(rows[1].get::<_, &str>(0), "second");
+    assert_eq!(rows[1].get::<_, Option<Vec<u8>>>(2), None);
+}
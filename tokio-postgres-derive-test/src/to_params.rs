@@ -0,0 +1,102 @@
+use tokio_postgres::{IntoParams, ToParams};
+
+use crate::connect;
+
+#[derive(ToParams)]
+struct Person {
+    name: String,
+    #[to_params(rename = "years")]
+    age: i32,
+    #[to_params(skip)]
+    #[allow(dead_code)]
+    nickname: String,
+}
+
+fn person() -> Person {
+    Person {
+        name: "steven".to_string(),
+        age: 18,
+        nickname: "steve".to_string(),
+    }
+}
+
+#[test]
+fn param_names() {
+    assert_eq!(Person::param_names(), ["name", "years"]);
+
+    let person = person();
+    assert!(person.param("years").is_some());
+    assert!(person.param("age").is_none());
+    assert!(person.param("nickname").is_none());
+    assert_eq!(person.to_params().len(), 2);
+}
+
+#[tokio::test]
+async fn execute_with_struct() {
+    let client = connect("user=postgres host=localhost port=5433").await;
+    client
+        .batch_execute("CREATE TEMPORARY TABLE person (name text, age integer)")
+        .await
+        .unwrap();
+
+    let person = person();
+    client
+        .execute_with("INSERT INTO person (name, age) VALUES ($1, $2)", &person)
+        .await
+        .unwrap();
+
+    let row = client
+        .query_one("SELECT name, age FROM person", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "steven");
+    assert_eq!(row.get::<_, i32>(1), 18);
+}
+
+#[tokio::test]
+async fn generic() {
+    #[derive(ToParams)]
+    struct Pair<A> {
+        a: A,
+        b: i64,
+    }
+
+    let client = connect("user=postgres host=localhost port=5433").await;
+    let row = client
+        .query_one_with("SELECT $1::TEXT || $2::INT8", Pair { a: "x", b: 1 })
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "x1");
+}
+
+#[tokio::test]
+async fn execute_named_struct() {
+    let client = connect("user=postgres host=localhost port=5433").await;
+    client
+        .batch_execute("CREATE TEMPORARY TABLE person (name text, age integer)")
+        .await
+        .unwrap();
+
+    let person = person();
+    client
+        .execute_named(
+            "INSERT INTO person (name, age) VALUES (:name, :years)",
+            &person,
+        )
+        .await
+        .unwrap();
+
+    let rows = client
+        .query_named(
+            "SELECT name, age FROM person WHERE name = :name AND age = :years",
+            &person,
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+
+    client
+        .execute_named("SELECT :nickname::TEXT", &person)
+        .await
+        .unwrap_err();
+}
@@ -1,42 +1,82 @@
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Literal, TokenStream as TokenStream2};
 use quote::quote;
 
+use crate::case::{RenameRule, RENAME_RULES};
+
 /// Calls the fallible entry point and writes any errors to the token stream.
 /// Fallible entry point for generating a `FromRow` implementation
 pub fn derive_from_row(input: syn::DeriveInput) -> syn::Result<TokenStream2> {
+    if let syn::Data::Enum(_) = input.data {
+        return Ok(DeriveFromRowEnum::parse(input)?.generate());
+    }
+
     Ok(DeriveFromRow::parse(input)?.generate())
 }
 
 struct DeriveFromRow {
     ident: syn::Ident,
     generics: syn::Generics,
+    attrs: FromRowAttrs,
     fields: Vec<FromRowField>,
 }
 
 impl DeriveFromRow {
     fn parse(input: syn::DeriveInput) -> syn::Result<Self> {
-        let syn::Data::Struct(syn::DataStruct {
-            fields: syn::Fields::Named(fields),
-            ..
-        }) = input.data
-        else {
-            let span = syn::spanned::Spanned::span(&input);
-
-            return Err(syn::Error::new(
-                span,
-                "derive macro `FromRow` is only supported on structs with named fields",
-            ));
+        let (fields, by_index) = match input.data {
+            syn::Data::Struct(syn::DataStruct {
+                fields: syn::Fields::Named(fields),
+                ..
+            }) => (fields.named, false),
+            syn::Data::Struct(syn::DataStruct {
+                fields: syn::Fields::Unnamed(fields),
+                ..
+            }) => (fields.unnamed, true),
+            _ => {
+                let span = syn::spanned::Spanned::span(&input);
+
+                return Err(syn::Error::new(
+                    span,
+                    "derive macro `FromRow` is only supported on structs with fields and fieldless enums",
+                ));
+            }
         };
 
-        let fields = fields
-            .named
+        let attrs = FromRowAttrs::parse(&input.attrs)?;
+        let by_index = by_index || attrs.by_index;
+        let mut fields = fields
             .into_iter()
-            .map(FromRowField::parse)
-            .collect::<syn::Result<_>>()?;
+            .enumerate()
+            .map(|(idx, field)| FromRowField::parse(idx, field))
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        if by_index {
+            if attrs.rename_all.is_some() || !attrs.prefix.is_empty() {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "column names are not supported when mapping fields by index",
+                ));
+            }
+
+            // skipped fields don't consume a column
+            let mut idx = 0;
+            for field in &mut fields {
+                if field.attrs.flatten || field.attrs.rename.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &field.ident,
+                        "`flatten` and `rename` are not supported when mapping fields by index",
+                    ));
+                }
+                if !field.attrs.skip {
+                    field.index = Some(idx);
+                    idx += 1;
+                }
+            }
+        }
 
         Ok(Self {
             ident: input.ident,
             generics: input.generics,
+            attrs,
             fields,
         })
     }
@@ -49,24 +89,168 @@ impl DeriveFromRow {
         let original_predicates = where_clause.iter().flat_map(|w| &w.predicates);
 
         let mut predicates = Vec::new();
-        for field in &self.fields {
-            field.push_predicates(&mut predicates)
+        if let Some(bound) = &self.attrs.bound {
+            predicates.extend(bound.iter().map(|p| quote!(#p)));
+        } else {
+            for field in &self.fields {
+                field.push_predicates(&mut predicates)
+            }
         }
 
         let from_row_fields = self
             .fields
             .iter()
-            .map(FromRowField::generate)
+            .map(|field| field.generate(&ident.to_string(), &self.attrs))
+            .collect::<Vec<_>>();
+
+        let is_null_fields = self
+            .fields
+            .iter()
+            .filter_map(|field| field.generate_is_null(&self.attrs))
             .collect::<Vec<_>>();
 
         quote! {
             impl #impl_generics ::tokio_postgres::FromRow for #ident #ty_generics where #(#original_predicates,)* #(#predicates,)* {
 
                 fn from_row(row: &::tokio_postgres::Row) -> ::std::result::Result<Self, ::tokio_postgres::Error> {
+                    <Self as ::tokio_postgres::FromRow>::from_row_prefixed(row, "")
+                }
+
+                #[allow(unused_variables, clippy::needless_question_mark)]
+                fn from_row_prefixed(row: &::tokio_postgres::Row, prefix: &::std::primitive::str) -> ::std::result::Result<Self, ::tokio_postgres::Error> {
                     ::std::result::Result::Ok(Self {
                         #(#from_row_fields),*
                     })
                 }
+
+                #[allow(unused_variables)]
+                fn is_null(row: &::tokio_postgres::Row, prefix: &::std::primitive::str) -> ::std::primitive::bool {
+                    true #(&& #is_null_fields)*
+                }
+            }
+        }
+    }
+}
+
+/// A fieldless enum that derives `FromRow`, read from the first column of a row.
+struct DeriveFromRowEnum {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    bound: Option<Vec<syn::WherePredicate>>,
+    variants: Vec<(syn::Ident, String)>,
+}
+
+impl DeriveFromRowEnum {
+    fn parse(input: syn::DeriveInput) -> syn::Result<Self> {
+        let syn::Data::Enum(data) = input.data else {
+            unreachable!("must be an enum");
+        };
+
+        let attrs = FromRowAttrs::parse(&input.attrs)?;
+        if !attrs.prefix.is_empty() || attrs.by_index {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                r#"`#[from_row(prefix = "..")]` and `#[from_row(by_index)]` are not supported on enums"#,
+            ));
+        }
+
+        let mut variants = Vec::new();
+        for variant in data.variants {
+            if !variant.fields.is_empty() {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "derive macro `FromRow` is only supported on enums without fields",
+                ));
+            }
+
+            let mut rename = None;
+            for attr in &variant.attrs {
+                if !attr.path().is_ident("from_row") {
+                    continue;
+                }
+
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        rename = Some(lit.value());
+                        Ok(())
+                    } else {
+                        Err(meta.error("unexpected `from_row` variant attribute."))
+                    }
+                })?;
+            }
+
+            let name = match (rename, attrs.rename_all) {
+                (Some(rename), _) => rename,
+                (None, Some(rule)) => rule.apply_to_field(&variant.ident.to_string()),
+                (None, None) => variant.ident.to_string(),
+            };
+            variants.push((variant.ident, name));
+        }
+
+        Ok(Self {
+            ident: input.ident,
+            generics: input.generics,
+            bound: attrs.bound,
+            variants,
+        })
+    }
+
+    /// Generate the `FromRow` implementation.
+    ///
+    /// Integer columns are matched against the discriminants of the variants, and any other column is read as a
+    /// string and matched against their names.
+    fn generate(self) -> TokenStream2 {
+        let ident = &self.ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let original_predicates = where_clause.iter().flat_map(|w| &w.predicates);
+        let bound = self.bound.iter().flatten();
+
+        let variants = self.variants.iter().map(|(v, _)| v).collect::<Vec<_>>();
+        let names = self.variants.iter().map(|(_, n)| n).collect::<Vec<_>>();
+        let invalid_int = format!("invalid value {{}} for enum `{}`", ident);
+        let invalid_str = format!("invalid value `{{}}` for enum `{}`", ident);
+
+        quote! {
+            impl #impl_generics ::tokio_postgres::FromRow for #ident #ty_generics where #(#original_predicates,)* #(#bound,)* {
+                fn from_row(row: &::tokio_postgres::Row) -> ::std::result::Result<Self, ::tokio_postgres::Error> {
+                    use ::tokio_postgres::types::Type;
+
+                    let ty = ::tokio_postgres::Row::columns(row).first().map(|c| c.type_());
+                    let value = if ty == ::std::option::Option::Some(&Type::INT2) {
+                        ::std::option::Option::Some(::tokio_postgres::Row::try_get::<usize, i16>(row, 0)? as i64)
+                    } else if ty == ::std::option::Option::Some(&Type::INT4) {
+                        ::std::option::Option::Some(::tokio_postgres::Row::try_get::<usize, i32>(row, 0)? as i64)
+                    } else if ty == ::std::option::Option::Some(&Type::INT8) {
+                        ::std::option::Option::Some(::tokio_postgres::Row::try_get::<usize, i64>(row, 0)?)
+                    } else {
+                        ::std::option::Option::None
+                    };
+
+                    let message = match value {
+                        ::std::option::Option::Some(value) => {
+                            #(
+                                if value == Self::#variants as i64 {
+                                    return ::std::result::Result::Ok(Self::#variants);
+                                }
+                            )*
+                            ::std::format!(#invalid_int, value)
+                        }
+                        ::std::option::Option::None => {
+                            let value = ::tokio_postgres::Row::try_get::<usize, &::std::primitive::str>(row, 0)?;
+                            match value {
+                                #(#names => return ::std::result::Result::Ok(Self::#variants),)*
+                                _ => ::std::format!(#invalid_str, value),
+                            }
+                        }
+                    };
+
+                    ::std::result::Result::Err(::tokio_postgres::Error::__private_api_from_sql(row, 0, message.into()))
+                }
+
+                fn is_null(row: &::tokio_postgres::Row, _: &::std::primitive::str) -> ::std::primitive::bool {
+                    ::tokio_postgres::row::__private_api_is_null(row, 0usize)
+                }
             }
         }
     }
@@ -75,10 +259,12 @@ impl DeriveFromRow {
 /// A single field inside of a struct that derives `FromRow`
 // #[darling(attributes(from_row), forward_attrs(allow, doc, cfg))]
 struct FromRowField {
-    /// The identifier of this field.
-    ident: syn::Ident,
+    /// The identifier of this field, which is its position in tuple structs.
+    ident: syn::Member,
     /// The identifier of this field as a string.
     ident_str: String,
+    /// The index of the column of this field, when fields are mapped by index rather than by name.
+    index: Option<usize>,
     /// The type specified in this field.
     ty: syn::Type,
     /// Any attributes that are captured by this macro.
@@ -86,13 +272,16 @@ struct FromRowField {
 }
 
 impl FromRowField {
-    fn parse(input: syn::Field) -> syn::Result<Self> {
-        // This can't panic as long as we make sure we're working with named structs.
-        let ident = input.ident.expect("must be a named field");
+    fn parse(idx: usize, input: syn::Field) -> syn::Result<Self> {
+        let (ident, ident_str) = match input.ident {
+            Some(ident) => (syn::Member::Named(ident.clone()), ident.to_string()),
+            None => (syn::Member::Unnamed(syn::Index::from(idx)), idx.to_string()),
+        };
 
         Ok(Self {
-            ident_str: ident.to_string(),
             ident,
+            ident_str,
+            index: None,
             ty: input.ty,
             attrs: FromRowFieldAttrs::parse(input.attrs)?,
         })
@@ -111,13 +300,31 @@ impl FromRowField {
     }
 
     /// Returns the name that maps to the actual sql column.
-    /// By default this is the same as the rust field name but can be overwritten by `#[from_row(rename = "..")]`.
-    fn column_name(&self) -> &str {
-        if let Some(rename) = &self.attrs.rename {
-            rename
+    /// By default this is the same as the rust field name but can be overwritten by `#[from_row(rename = "..")]`,
+    /// or converted with the container's `#[from_row(rename_all = "..")]` rule. The container's
+    /// `#[from_row(prefix = "..")]` is prepended in any case.
+    fn column_name(&self, container: &FromRowAttrs) -> String {
+        let name = if let Some(rename) = &self.attrs.rename {
+            rename.clone()
+        } else if let Some(rule) = container.rename_all {
+            rule.apply_to_field(&self.ident_str)
         } else {
-            &self.ident_str
-        }
+            self.ident_str.clone()
+        };
+
+        format!("{}{}", container.prefix, name)
+    }
+
+    /// Returns the prefix of the columns of a flattened field, relative to the prefix of the struct.
+    fn nested_prefix(&self, container: &FromRowAttrs) -> String {
+        format!(
+            "{}{}",
+            container.prefix,
+            self.attrs
+                .prefix
+                .as_ref()
+                .map_or(String::new(), |p| p.value())
+        )
     }
 
     /// Pushes the needed where clause predicates for this field.
@@ -126,15 +333,26 @@ impl FromRowField {
     /// when using `flatten` it's: `T: postgres_from_row::FromRow`
     /// and when using either `from` or `try_from` attributes it additionally pushes this bound:
     /// `T: std::convert::From<R>`, where `T` is the type specified in the struct and `R` is the
-    /// type specified in the `[try]_from` attribute.
+    /// type specified in the `[try]_from` attribute. The error of a `try_from` conversion must
+    /// convert into a boxed error, or into `tokio_postgres::Error` for flattened fields.
+    /// A `#[from_row(bound = "..")]` attribute on the field replaces all of these.
     fn push_predicates(&self, predicates: &mut Vec<TokenStream2>) {
+        if let Some(bound) = &self.attrs.bound {
+            predicates.extend(bound.iter().map(|p| quote!(#p)));
+            return;
+        }
+
         let target_ty = self.target_ty();
         let ty = &self.ty;
 
         if self.attrs.flatten {
             predicates.push(quote! (#target_ty: ::tokio_postgres::FromRow))
+        } else if self.attrs.with.is_some() {
+            // the function determines the type
         } else if self.attrs.skip {
             predicates.push(quote! (#target_ty: ::std::default::Default))
+        } else if self.attrs.json {
+            predicates.push(quote! (::tokio_postgres::types::Json<#target_ty>: for<'a> ::tokio_postgres::types::FromSql<'a>))
         } else {
             predicates.push(quote! (#target_ty: for<'a> ::tokio_postgres::types::FromSql<'a>))
         };
@@ -144,35 +362,199 @@ impl FromRowField {
         } else if self.attrs.try_from.is_some() {
             let try_from = quote!(::std::convert::TryFrom<#target_ty>);
 
-            predicates.extend([
-                quote!(#ty: #try_from),
-                quote!(::tokio_postgres::Error: ::std::convert::From<<#ty as #try_from>::Error>),
-            ]);
+            predicates.push(quote!(#ty: #try_from));
+            if self.attrs.flatten {
+                predicates.push(
+                    quote!(::tokio_postgres::Error: ::std::convert::From<<#ty as #try_from>::Error>),
+                );
+            } else {
+                predicates.push(quote!(
+                    <#ty as #try_from>::Error: ::std::convert::Into<::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Sync + ::std::marker::Send>>
+                ));
+            }
+        }
+
+        if let Some(DefaultValue::Trait) = self.attrs.default {
+            predicates.push(quote!(#ty: ::std::default::Default))
         }
     }
 
+    /// Generate the expression checking that the columns of this field are `NULL` when calling `is_null`, if it has
+    /// any.
+    fn generate_is_null(&self, container: &FromRowAttrs) -> Option<TokenStream2> {
+        if self.attrs.skip {
+            return None;
+        }
+
+        let expr = if self.attrs.flatten {
+            let target_ty = self.target_ty();
+            let nested_prefix = self.nested_prefix(container);
+            quote! {
+                <#target_ty as ::tokio_postgres::FromRow>::is_null(
+                    row,
+                    &*::tokio_postgres::row::__private_api_prefixed(prefix, #nested_prefix),
+                )
+            }
+        } else if let Some(idx) = self.index {
+            let idx = Literal::usize_suffixed(idx);
+            quote!(::tokio_postgres::row::__private_api_is_null(row, #idx))
+        } else {
+            let column_name = self.column_name(container);
+            quote! {
+                ::tokio_postgres::row::__private_api_is_null(
+                    row,
+                    &*::tokio_postgres::row::__private_api_prefixed(prefix, #column_name),
+                )
+            }
+        };
+
+        Some(expr)
+    }
+
     /// Generate the line needed to retrieve this field from a row when calling `from_row`.
-    fn generate(&self) -> TokenStream2 {
+    fn generate(&self, type_name: &str, container: &FromRowAttrs) -> TokenStream2 {
         let ident = &self.ident;
-        let column_name = self.column_name();
         let field_ty = &self.ty;
         let target_ty = self.target_ty();
 
+        // how the column is looked up, and how to check that the row has it
+        let (column, present) = match self.index {
+            Some(idx) => {
+                let idx = Literal::usize_suffixed(idx);
+                (quote!(#idx), quote!(#idx < ::tokio_postgres::Row::len(row)))
+            }
+            None => (
+                quote!(&*column),
+                quote!(::tokio_postgres::Row::columns(row)
+                    .iter()
+                    .any(|c| c.name() == &*column)),
+            ),
+        };
+
         let mut base = if self.attrs.flatten {
-            quote!(<#target_ty as ::tokio_postgres::FromRow>::from_row(row)?)
+            let nested_prefix = self.nested_prefix(container);
+            quote! {
+                <#target_ty as ::tokio_postgres::FromRow>::from_row_prefixed(
+                    row,
+                    &*::tokio_postgres::row::__private_api_prefixed(prefix, #nested_prefix),
+                )?
+            }
+        } else if let Some(with) = &self.attrs.with {
+            quote!(#with(row, #column)?)
         } else if self.attrs.skip {
             quote!(<#field_ty as ::std::default::Default>::default())
+        } else if self.attrs.json {
+            quote!(::tokio_postgres::json::__private_api_from_row::<#target_ty, _>(row, #column)?)
         } else {
-            quote!(::tokio_postgres::Row::try_get::<&::std::primitive::str, #target_ty>(row, #column_name)?)
+            quote!(::tokio_postgres::Row::try_get::<_, #target_ty>(row, #column)?)
         };
 
         if self.attrs.from.is_some() {
             base = quote!(<#field_ty as ::std::convert::From<#target_ty>>::from(#base));
-        } else if self.attrs.try_from.is_some() {
+        } else if self.attrs.try_from.is_some() && self.attrs.flatten {
             base = quote!(<#field_ty as ::std::convert::TryFrom<#target_ty>>::try_from(#base)?);
+        } else if self.attrs.try_from.is_some() {
+            // conversion errors are reported like errors decoding the column
+            base = quote! {
+                <#field_ty as ::std::convert::TryFrom<#target_ty>>::try_from(#base).map_err(|e| {
+                    ::tokio_postgres::Error::__private_api_from_sql(row, #column, ::std::convert::Into::into(e))
+                })?
+            };
         };
 
-        quote!(#ident: #base)
+        // errors of flattened fields carry the context of the nested field
+        if !self.attrs.flatten && !self.attrs.skip {
+            let field_name = &self.ident_str;
+            base = quote! {
+                (|| ::std::result::Result::Ok::<#field_ty, ::tokio_postgres::Error>(#base))().map_err(|e| {
+                    ::tokio_postgres::Error::__private_api_field(e, row, #column, #type_name, #field_name)
+                })?
+            };
+        }
+
+        if let Some(default) = &self.attrs.default {
+            let default = match default {
+                DefaultValue::Trait => quote!(<#field_ty as ::std::default::Default>::default()),
+                DefaultValue::Function(path) => quote!(#path()),
+            };
+            base = quote! {
+                if #present {
+                    #base
+                } else {
+                    #default
+                }
+            };
+        }
+
+        if self.attrs.flatten || self.attrs.skip || self.index.is_some() {
+            quote!(#ident: #base)
+        } else {
+            let column_name = self.column_name(container);
+            quote! {
+                #ident: {
+                    let column = ::tokio_postgres::row::__private_api_prefixed(prefix, #column_name);
+                    #base
+                }
+            }
+        }
+    }
+}
+
+/// The attributes of a struct that derives `FromRow`.
+#[derive(Default)]
+struct FromRowAttrs {
+    /// Convert the names of all fields to column names with this rule, unless they are renamed explicitly.
+    rename_all: Option<RenameRule>,
+    /// Prepend this to the names of all columns, including those of flattened fields.
+    prefix: String,
+    /// Map the fields to columns by their position instead of their name, as for tuple structs.
+    by_index: bool,
+    /// Use these where clause predicates instead of the ones generated for the fields.
+    bound: Option<Vec<syn::WherePredicate>>,
+}
+
+impl FromRowAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut this = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("from_row") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    let rule = RenameRule::from_str(&lit.value()).ok_or_else(|| {
+                        syn::Error::new(
+                            lit.span(),
+                            format!(
+                                "invalid rename_all rule, expected one of: {}",
+                                RENAME_RULES
+                                    .iter()
+                                    .map(|rule| format!("\"{}\"", rule))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                        )
+                    })?;
+                    this.rename_all = Some(rule)
+                } else if meta.path.is_ident("prefix") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    this.prefix = lit.value()
+                } else if meta.path.is_ident("by_index") {
+                    this.by_index = true
+                } else if meta.path.is_ident("bound") {
+                    this.bound = Some(parse_bound(meta.value()?.parse()?)?)
+                } else {
+                    return Err(meta.error("unexpected `from_row` container attribute."));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(this)
     }
 }
 
@@ -194,6 +576,25 @@ struct FromRowFieldAttrs {
     /// Skip this field when looking for columns in the row, instead initialize it using
     /// `Default::default`.
     skip: bool,
+    /// Initialize this field with a default value when its column is missing from the row.
+    default: Option<DefaultValue>,
+    /// Prepend this to the names of the columns of a flattened field.
+    prefix: Option<syn::LitStr>,
+    /// Read this field by calling the function at this path with the row and the column.
+    with: Option<syn::ExprPath>,
+    /// Use these where clause predicates instead of the ones generated for this field.
+    bound: Option<Vec<syn::WherePredicate>>,
+    /// Deserialize this field from a `json` or `jsonb` column with serde.
+    json: bool,
+}
+
+/// The value of a field whose column is missing, as given by `#[from_row(default)]` or
+/// `#[from_row(default = "..")]`.
+enum DefaultValue {
+    /// Use `Default::default`.
+    Trait,
+    /// Call the function at this path.
+    Function(syn::ExprPath),
 }
 
 impl FromRowFieldAttrs {
@@ -219,6 +620,22 @@ impl FromRowFieldAttrs {
                 } else if meta.path.is_ident("rename") {
                     let lit: syn::LitStr = meta.value()?.parse()?;
                     this.rename = Some(lit.value())
+                } else if meta.path.is_ident("prefix") {
+                    this.prefix = Some(meta.value()?.parse()?)
+                } else if meta.path.is_ident("with") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    this.with = Some(lit.parse()?)
+                } else if meta.path.is_ident("bound") {
+                    this.bound = Some(parse_bound(meta.value()?.parse()?)?)
+                } else if meta.path.is_ident("json") {
+                    this.json = true
+                } else if meta.path.is_ident("default") {
+                    this.default = if meta.input.peek(syn::Token![=]) {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        Some(DefaultValue::Function(lit.parse()?))
+                    } else {
+                        Some(DefaultValue::Trait)
+                    }
                 } else {
                     return Err(meta.error("unexpected `from_row` attribute."));
                 }
@@ -229,10 +646,30 @@ impl FromRowFieldAttrs {
                     ));
                 }
 
-                if this.skip && (this.rename.is_some() || this.try_from.is_some() || this.flatten || this.rename.is_some()) {
+                if this.skip && (this.rename.is_some() || this.from.is_some() || this.try_from.is_some() || this.flatten) {
                     return Err(meta.error(r#"can't combine `#[from_row(skip)]` with other attributes"#))
                 }
 
+                if this.default.is_some() && (this.skip || this.flatten) {
+                    return Err(meta.error(
+                        r#"can't combine `#[from_row(default)]` with `#[from_row(skip)]` or `#[from_row(flatten)]`"#,
+                    ));
+                }
+
+                if this.with.is_some()
+                    && (this.skip || this.flatten || this.from.is_some() || this.try_from.is_some())
+                {
+                    return Err(meta.error(
+                        r#"can't combine `#[from_row(with = "..")]` with `skip`, `flatten`, `from` or `try_from`"#,
+                    ));
+                }
+
+                if this.json && (this.skip || this.flatten || this.with.is_some()) {
+                    return Err(meta.error(
+                        r#"can't combine `#[from_row(json)]` with `skip`, `flatten` or `with`"#,
+                    ));
+                }
+
                 if this.from.is_some() && this.try_from.is_some() {
                     return Err(meta.error(
                         r#"can't combine `#[from_row(try_from = "..")]` with `#[from_row(from = "..")]`"#,
@@ -243,6 +680,24 @@ impl FromRowFieldAttrs {
             })?;
         }
 
+        if let Some(prefix) = &this.prefix {
+            if !this.flatten {
+                return Err(syn::Error::new(
+                    prefix.span(),
+                    r#"`#[from_row(prefix = "..")]` is only supported on flattened fields"#,
+                ));
+            }
+        }
+
         Ok(this)
     }
 }
+
+/// Parses the where clause predicates of a `#[from_row(bound = "..")]` attribute, separated by commas. An empty
+/// string removes the generated bounds without adding any.
+fn parse_bound(lit: syn::LitStr) -> syn::Result<Vec<syn::WherePredicate>> {
+    let predicates = lit.parse_with(
+        syn::punctuated::Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated,
+    )?;
+    Ok(predicates.into_iter().collect())
+}
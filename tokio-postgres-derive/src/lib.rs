@@ -7,7 +7,11 @@ use proc_macro::TokenStream;
 
 use syn::{parse_macro_input, DeriveInput};
 
+mod case;
 mod from_row;
+mod migrate;
+mod to_copy_row;
+mod to_params;
 
 #[proc_macro_derive(FromRow, attributes(from_row))]
 pub fn derive_from_row(input: TokenStream) -> TokenStream {
@@ -17,3 +21,30 @@ pub fn derive_from_row(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|s| s.into_compile_error())
         .into()
 }
+
+#[proc_macro_derive(ToParams, attributes(to_params))]
+pub fn derive_to_params(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+
+    to_params::derive_to_params(derive_input)
+        .unwrap_or_else(|s| s.into_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(ToCopyRow, attributes(to_copy_row))]
+pub fn derive_to_copy_row(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+
+    to_copy_row::derive_to_copy_row(derive_input)
+        .unwrap_or_else(|s| s.into_compile_error())
+        .into()
+}
+
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let dir = parse_macro_input!(input as Option<syn::LitStr>);
+
+    migrate::embed_migrations(dir)
+        .unwrap_or_else(|e| e.into_compile_error())
+        .into()
+}
@@ -0,0 +1,69 @@
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::path::PathBuf;
+
+/// Fallible entry point for embedding the migrations of a directory.
+pub fn embed_migrations(input: Option<syn::LitStr>) -> syn::Result<TokenStream2> {
+    let (dir, span) = match &input {
+        Some(lit) => (lit.value(), lit.span()),
+        None => ("migrations".to_string(), Span::call_site()),
+    };
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new(span, "`CARGO_MANIFEST_DIR` is not set"))?;
+    let dir = PathBuf::from(manifest_dir).join(dir);
+
+    let entries = std::fs::read_dir(&dir).map_err(|e| {
+        syn::Error::new(
+            span,
+            format!("unable to read migrations from `{}`: {}", dir.display(), e),
+        )
+    })?;
+
+    let mut migrations = vec![];
+    for entry in entries {
+        let path = entry
+            .map_err(|e| syn::Error::new(span, format!("unable to read migration: {}", e)))?
+            .path();
+        if path.extension().is_none_or(|ext| ext != "sql") {
+            continue;
+        }
+
+        let file_name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let (version, name) = parse_file_name(file_name).ok_or_else(|| {
+            syn::Error::new(
+                span,
+                format!(
+                    "invalid migration file name `{}`, expected `V{{version}}__{{name}}.sql`",
+                    path.display()
+                ),
+            )
+        })?;
+
+        let path = path.to_str().ok_or_else(|| {
+            syn::Error::new(
+                span,
+                format!("migration path `{}` is not valid UTF-8", path.display()),
+            )
+        })?;
+
+        migrations.push((version, name.to_string(), path.to_string()));
+    }
+    migrations.sort_by_key(|(version, _, _)| *version);
+
+    let migrations = migrations.iter().map(|(version, name, path)| {
+        quote! {
+            ::tokio_postgres::migrate::Migration::new(#version, #name, ::std::include_str!(#path))
+        }
+    });
+
+    Ok(quote! {
+        ::tokio_postgres::migrate::Migrator::new(::std::vec![#(#migrations),*])
+    })
+}
+
+/// Splits a file name of the form `V{version}__{name}` into its parts.
+fn parse_file_name(file_name: &str) -> Option<(i64, &str)> {
+    let (version, name) = file_name.strip_prefix('V')?.split_once("__")?;
+    Some((version.parse().ok()?, name))
+}
@@ -0,0 +1,263 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+/// Fallible entry point for generating a `ToCopyRow` implementation
+pub fn derive_to_copy_row(input: syn::DeriveInput) -> syn::Result<TokenStream2> {
+    Ok(DeriveToCopyRow::parse(input)?.generate())
+}
+
+struct DeriveToCopyRow {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    fields: Vec<ToCopyRowField>,
+}
+
+impl DeriveToCopyRow {
+    fn parse(input: syn::DeriveInput) -> syn::Result<Self> {
+        let syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) = input.data
+        else {
+            let span = syn::spanned::Spanned::span(&input);
+
+            return Err(syn::Error::new(
+                span,
+                "derive macro `ToCopyRow` is only supported on structs with named fields",
+            ));
+        };
+
+        let mut parsed = Vec::new();
+        for field in fields.named {
+            let Some(field) = ToCopyRowField::parse(field)? else {
+                continue;
+            };
+            if parsed
+                .iter()
+                .any(|f: &ToCopyRowField| f.column_name() == field.column_name())
+            {
+                return Err(syn::Error::new(
+                    field.ident.span(),
+                    format!("duplicate column name `{}`", field.column_name()),
+                ));
+            }
+            parsed.push(field);
+        }
+
+        Ok(Self {
+            ident: input.ident,
+            generics: input.generics,
+            fields: parsed,
+        })
+    }
+
+    /// Generate the `ToCopyRow` implementation.
+    fn generate(self) -> TokenStream2 {
+        let ident = &self.ident;
+
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        let original_predicates = where_clause
+            .iter()
+            .flat_map(|w| &w.predicates)
+            .collect::<Vec<_>>();
+
+        let predicates = self
+            .fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty: ::tokio_postgres::types::ToSql + ::std::marker::Sync)
+            })
+            .collect::<Vec<_>>();
+
+        let names = self
+            .fields
+            .iter()
+            .map(ToCopyRowField::column_name)
+            .collect::<Vec<_>>();
+        let types = self.fields.iter().map(|f| &f.pg_type).collect::<Vec<_>>();
+        let idents = self.fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+
+        quote! {
+            impl #impl_generics ::tokio_postgres::binary_copy::ToCopyRow for #ident #ty_generics where #(#original_predicates,)* #(#predicates,)* {
+                fn columns() -> &'static [&'static ::std::primitive::str] {
+                    &[#(#names),*]
+                }
+
+                fn types() -> ::std::vec::Vec<::tokio_postgres::types::Type> {
+                    ::std::vec![#(::tokio_postgres::types::Type::#types),*]
+                }
+
+                fn values(&self) -> ::std::vec::Vec<&(dyn ::tokio_postgres::types::ToSql + ::std::marker::Sync)> {
+                    ::std::vec![#(&self.#idents as &(dyn ::tokio_postgres::types::ToSql + ::std::marker::Sync)),*]
+                }
+            }
+        }
+    }
+}
+
+/// A single field inside of a struct that derives `ToCopyRow`
+struct ToCopyRowField {
+    /// The identifier of this field.
+    ident: syn::Ident,
+    /// The identifier of this field as a string.
+    ident_str: String,
+    /// The type specified in this field.
+    ty: syn::Type,
+    /// The name of the constant of `Type` for the column, such as `INT4`.
+    pg_type: syn::Ident,
+    /// Any attributes that are captured by this macro.
+    attrs: ToCopyRowFieldAttrs,
+}
+
+impl ToCopyRowField {
+    /// Parses a field, returning `None` if it is skipped.
+    fn parse(input: syn::Field) -> syn::Result<Option<Self>> {
+        // This can't panic as long as we make sure we're working with named structs.
+        let ident = input.ident.expect("must be a named field");
+        let attrs = ToCopyRowFieldAttrs::parse(input.attrs)?;
+        if attrs.skip {
+            return Ok(None);
+        }
+
+        let pg_type = match &attrs.pg_type {
+            Some(pg_type) => pg_type.clone(),
+            None => match infer_type(&input.ty) {
+                Some(pg_type) => syn::Ident::new(&pg_type, ident.span()),
+                None => {
+                    return Err(syn::Error::new_spanned(
+                        &input.ty,
+                        r#"can't infer the Postgres type of this field, specify it with `#[to_copy_row(type = "..")]`"#,
+                    ))
+                }
+            },
+        };
+
+        Ok(Some(Self {
+            ident_str: ident.to_string(),
+            ident,
+            ty: input.ty,
+            pg_type,
+            attrs,
+        }))
+    }
+
+    /// Returns the name of the column.
+    /// By default this is the same as the rust field name but can be overwritten by `#[to_copy_row(rename = "..")]`.
+    fn column_name(&self) -> &str {
+        if let Some(rename) = &self.attrs.rename {
+            rename
+        } else {
+            &self.ident_str
+        }
+    }
+}
+
+/// Infers the name of the `Type` constant for the common Rust types, looking through references, `Box` and `Option`.
+/// A `Vec` or slice is an array of its element type, except for bytes which are a `bytea`.
+fn infer_type(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Reference(reference) => infer_type(&reference.elem),
+        syn::Type::Paren(paren) => infer_type(&paren.elem),
+        syn::Type::Slice(slice) => infer_array_type(&slice.elem),
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last()?;
+            let name = match &*segment.ident.to_string() {
+                "Option" | "Box" => return infer_type(generic_argument(segment)?),
+                "Vec" => return infer_array_type(generic_argument(segment)?),
+                "bool" => "BOOL",
+                "i8" => "CHAR",
+                "i16" => "INT2",
+                "i32" => "INT4",
+                "i64" => "INT8",
+                "u32" => "OID",
+                "f32" => "FLOAT4",
+                "f64" => "FLOAT8",
+                "str" | "String" => "TEXT",
+                _ => return None,
+            };
+            Some(name.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn infer_array_type(element: &syn::Type) -> Option<String> {
+    if matches!(element, syn::Type::Path(path) if path.path.is_ident("u8")) {
+        return Some("BYTEA".to_string());
+    }
+
+    // nested arrays are multidimensional arrays of the same type
+    let element = infer_type(element)?;
+    if element.ends_with("_ARRAY") {
+        Some(element)
+    } else {
+        Some(format!("{}_ARRAY", element))
+    }
+}
+
+/// Returns the single generic type argument of a path segment, as in `Option<T>`.
+fn generic_argument(segment: &syn::PathSegment) -> Option<&syn::Type> {
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct ToCopyRowFieldAttrs {
+    /// Override the name of the column instead of using `self.ident`.
+    rename: Option<String>,
+    /// Override the inferred type of the column.
+    pg_type: Option<syn::Ident>,
+    /// Leave this field out of the row.
+    skip: bool,
+}
+
+impl ToCopyRowFieldAttrs {
+    fn parse(attrs: Vec<syn::Attribute>) -> syn::Result<Self> {
+        let mut this = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("to_copy_row") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    this.skip = true
+                } else if meta.path.is_ident("rename") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    this.rename = Some(lit.value())
+                } else if meta.path.is_ident("type") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    let ident = syn::parse_str::<syn::Ident>(&lit.value().to_uppercase()).map_err(
+                        |_| {
+                            syn::Error::new(
+                                lit.span(),
+                                r#"expected the name of a type, such as "INT4""#,
+                            )
+                        },
+                    )?;
+                    this.pg_type = Some(syn::Ident::new(&ident.to_string(), lit.span()))
+                } else {
+                    return Err(meta.error("unexpected `to_copy_row` attribute."));
+                }
+
+                if this.skip && (this.rename.is_some() || this.pg_type.is_some()) {
+                    return Err(meta.error(
+                        "can't combine `#[to_copy_row(skip)]` with other `to_copy_row` attributes",
+                    ));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(this)
+    }
+}
@@ -0,0 +1,180 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+/// Fallible entry point for generating a `ToParams` implementation
+pub fn derive_to_params(input: syn::DeriveInput) -> syn::Result<TokenStream2> {
+    Ok(DeriveToParams::parse(input)?.generate())
+}
+
+struct DeriveToParams {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    fields: Vec<ToParamsField>,
+}
+
+impl DeriveToParams {
+    fn parse(input: syn::DeriveInput) -> syn::Result<Self> {
+        let syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) = input.data
+        else {
+            let span = syn::spanned::Spanned::span(&input);
+
+            return Err(syn::Error::new(
+                span,
+                "derive macro `ToParams` is only supported on structs with named fields",
+            ));
+        };
+
+        let mut parsed = Vec::new();
+        for field in fields.named {
+            let field = ToParamsField::parse(field)?;
+            if field.attrs.skip {
+                continue;
+            }
+            if parsed
+                .iter()
+                .any(|f: &ToParamsField| f.param_name() == field.param_name())
+            {
+                return Err(syn::Error::new(
+                    field.ident.span(),
+                    format!("duplicate parameter name `{}`", field.param_name()),
+                ));
+            }
+            parsed.push(field);
+        }
+
+        Ok(Self {
+            ident: input.ident,
+            generics: input.generics,
+            fields: parsed,
+        })
+    }
+
+    /// Generate the `ToParams` and `IntoParams` implementations.
+    fn generate(self) -> TokenStream2 {
+        let ident = &self.ident;
+
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        let original_predicates = where_clause
+            .iter()
+            .flat_map(|w| &w.predicates)
+            .collect::<Vec<_>>();
+
+        let predicates = self
+            .fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty: ::tokio_postgres::types::ToSql + ::std::marker::Sync)
+            })
+            .collect::<Vec<_>>();
+
+        let names = self
+            .fields
+            .iter()
+            .map(ToParamsField::param_name)
+            .collect::<Vec<_>>();
+        let idents = self.fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+
+        quote! {
+            impl #impl_generics ::tokio_postgres::ToParams for #ident #ty_generics where #(#original_predicates,)* #(#predicates,)* {
+                fn param_names() -> &'static [&'static ::std::primitive::str] {
+                    &[#(#names),*]
+                }
+
+                fn param(&self, name: &::std::primitive::str) -> ::std::option::Option<&(dyn ::tokio_postgres::types::ToSql + ::std::marker::Sync)> {
+                    match name {
+                        #(#names => ::std::option::Option::Some(&self.#idents),)*
+                        _ => ::std::option::Option::None,
+                    }
+                }
+            }
+
+            impl #impl_generics ::tokio_postgres::IntoParams for #ident #ty_generics where #(#original_predicates,)* #(#predicates,)* {
+                fn to_params(&self) -> ::std::vec::Vec<&(dyn ::tokio_postgres::types::ToSql + ::std::marker::Sync)> {
+                    ::std::vec![#(&self.#idents as &(dyn ::tokio_postgres::types::ToSql + ::std::marker::Sync)),*]
+                }
+            }
+        }
+    }
+}
+
+/// A single field inside of a struct that derives `ToParams`
+struct ToParamsField {
+    /// The identifier of this field.
+    ident: syn::Ident,
+    /// The identifier of this field as a string.
+    ident_str: String,
+    /// The type specified in this field.
+    ty: syn::Type,
+    /// Any attributes that are captured by this macro.
+    attrs: ToParamsFieldAttrs,
+}
+
+impl ToParamsField {
+    fn parse(input: syn::Field) -> syn::Result<Self> {
+        // This can't panic as long as we make sure we're working with named structs.
+        let ident = input.ident.expect("must be a named field");
+
+        Ok(Self {
+            ident_str: ident.to_string(),
+            ident,
+            ty: input.ty,
+            attrs: ToParamsFieldAttrs::parse(input.attrs)?,
+        })
+    }
+
+    /// Returns the name of the parameter.
+    /// By default this is the same as the rust field name but can be overwritten by `#[to_params(rename = "..")]`.
+    fn param_name(&self) -> &str {
+        if let Some(rename) = &self.attrs.rename {
+            rename
+        } else {
+            &self.ident_str
+        }
+    }
+}
+
+#[derive(Default)]
+struct ToParamsFieldAttrs {
+    /// Override the name of the parameter instead of using `self.ident`.
+    rename: Option<String>,
+    /// Leave this field out of the parameters.
+    skip: bool,
+}
+
+impl ToParamsFieldAttrs {
+    fn parse(attrs: Vec<syn::Attribute>) -> syn::Result<Self> {
+        let mut this = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("to_params") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    this.skip = true
+                } else if meta.path.is_ident("rename") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    this.rename = Some(lit.value())
+                } else {
+                    return Err(meta.error("unexpected `to_params` attribute."));
+                }
+
+                if this.skip && this.rename.is_some() {
+                    return Err(meta.error(
+                        r#"can't combine `#[to_params(skip)]` with `#[to_params(rename = "..")]`"#,
+                    ));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(this)
+    }
+}
@@ -0,0 +1,444 @@
+//! Conversion of query results to Apache Arrow record batches.
+//!
+//! A [`RecordBatchStream`] collects the rows of a [`RowStream`] or of a [`BinaryCopyOutStream`] into
+//! [`RecordBatch`]es of a fixed number of rows. The schema of the batches is inferred from the columns of the result,
+//! so it is known before the first row arrives:
+//!
+//! ```no_run
+//! # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! use futures_util::TryStreamExt;
+//! use tokio_postgres::arrow::RecordBatchStream;
+//!
+//! let rows = client.query_raw("SELECT * FROM users", std::iter::empty::<i32>()).await?;
+//! let batches = RecordBatchStream::from_rows(rows, 8192)?;
+//! let schema = batches.schema();
+//! let batches: Vec<_> = batches.try_collect().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Values are converted as follows:
+//!
+//! | Postgres type | Arrow type |
+//! |---------------|------------|
+//! | `BOOL` | `Boolean` |
+//! | `"char"` | `Int8` |
+//! | `INT2` | `Int16` |
+//! | `INT4` | `Int32` |
+//! | `INT8` | `Int64` |
+//! | `OID` | `UInt32` |
+//! | `FLOAT4` | `Float32` |
+//! | `FLOAT8` | `Float64` |
+//! | `TEXT`, `VARCHAR`, `BPCHAR`, `NAME`, `UNKNOWN`, `JSON`, `JSONB`, enums | `Utf8` |
+//! | `BYTEA` | `Binary` |
+//! | `UUID` | `FixedSizeBinary(16)` |
+//! | `DATE` | `Date32` |
+//! | `TIME` | `Time64(Microsecond)` |
+//! | `TIMESTAMP` | `Timestamp(Microsecond, None)` |
+//! | `TIMESTAMPTZ` | `Timestamp(Microsecond, "+00:00")` |
+//! | domains | the type of the underlying type |
+//!
+//! Columns returned in the text format, for example with [`QueryOptions::result_format`], are converted to `Utf8`
+//! whatever their type, which is a way to read types without a binary mapping, like `NUMERIC`. Columns of any other
+//! type, as well as infinite dates and timestamps, result in an error. All fields of the schema are nullable.
+//!
+//! Requires the `arrow` Cargo feature.
+//!
+//! [`QueryOptions::result_format`]: crate::QueryOptions::result_format
+use crate::binary_copy::BinaryCopyOutStream;
+use crate::types::{Format, Kind, Type};
+use crate::{Error, RowStream};
+use arrow_array::builder::{
+    ArrayBuilder, BinaryBuilder, BooleanBuilder, Date32Builder, FixedSizeBinaryBuilder,
+    Float32Builder, Float64Builder, Int16Builder, Int32Builder, Int64Builder, Int8Builder,
+    StringBuilder, Time64MicrosecondBuilder, TimestampMicrosecondBuilder, UInt32Builder,
+};
+use arrow_array::{RecordBatch, RecordBatchOptions};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use futures_util::{ready, Stream};
+use postgres_protocol::types;
+use std::error;
+use std::fmt;
+use std::pin::Pin;
+use std::str;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+type BoxError = Box<dyn error::Error + Sync + Send>;
+
+// days and microseconds between the Unix epoch and the Postgres epoch of 2000-01-01
+const EPOCH_DAYS: i32 = 10_957;
+const EPOCH_MICROS: i64 = 946_684_800_000_000;
+
+const UTC: &str = "+00:00";
+
+enum Source {
+    Rows(Pin<Box<RowStream>>),
+    Copy(Pin<Box<BinaryCopyOutStream>>),
+}
+
+/// A stream of [`RecordBatch`]es collected from the rows of a query or of a binary copy out.
+///
+/// Every batch but the last one has exactly the number of rows the stream was created with. A result without rows
+/// produces no batches, its schema is still available through [`RecordBatchStream::schema`]. The rows collected for
+/// the current batch are dropped if an error occurs, after which the stream ends.
+pub struct RecordBatchStream {
+    source: Source,
+    schema: SchemaRef,
+    columns: Vec<ColumnBuilder>,
+    batch_size: usize,
+    len: usize,
+    done: bool,
+}
+
+impl RecordBatchStream {
+    /// Creates a stream collecting the rows of `rows` into batches of `batch_size` rows.
+    ///
+    /// The schema is inferred from the columns of the query, and the fields are named after them. Returns an error
+    /// if a column has a type which cannot be converted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn from_rows(rows: RowStream, batch_size: usize) -> Result<RecordBatchStream, Error> {
+        let columns = rows
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| (column.name(), column.type_(), rows.column_format(idx)))
+            .collect::<Vec<_>>();
+        let (schema, columns) = builders(&columns, batch_size)?;
+
+        Ok(RecordBatchStream {
+            source: Source::Rows(Box::pin(rows)),
+            schema,
+            columns,
+            batch_size,
+            len: 0,
+            done: false,
+        })
+    }
+
+    /// Creates a stream collecting the rows of a binary copy out into batches of `batch_size` rows.
+    ///
+    /// The fields of the schema are named `names`, in the order of the types of the stream. Returns an error if a
+    /// column has a type which cannot be converted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero or if the number of names does not match the number of types of the stream.
+    pub fn from_binary_copy(
+        stream: BinaryCopyOutStream,
+        names: &[&str],
+        batch_size: usize,
+    ) -> Result<RecordBatchStream, Error> {
+        assert!(
+            names.len() == stream.types().len(),
+            "expected {} column names but got {}",
+            stream.types().len(),
+            names.len(),
+        );
+
+        let columns = names
+            .iter()
+            .zip(stream.types())
+            .map(|(name, ty)| (*name, ty, Format::Binary))
+            .collect::<Vec<_>>();
+        let (schema, columns) = builders(&columns, batch_size)?;
+
+        Ok(RecordBatchStream {
+            source: Source::Copy(Box::pin(stream)),
+            schema,
+            columns,
+            batch_size,
+            len: 0,
+            done: false,
+        })
+    }
+
+    /// Returns the schema of the batches of the stream.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn finish(&mut self) -> RecordBatch {
+        let columns = self.columns.iter_mut().map(ColumnBuilder::finish).collect();
+        let options = RecordBatchOptions::new().with_row_count(Some(self.len));
+        self.len = 0;
+        // the builders are created from the schema and hold one value per row
+        RecordBatch::try_new_with_options(self.schema.clone(), columns, &options)
+            .expect("columns should match the schema")
+    }
+}
+
+impl fmt::Debug for RecordBatchStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordBatchStream")
+            .field("schema", &self.schema)
+            .field("batch_size", &self.batch_size)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Stream for RecordBatchStream {
+    type Item = Result<RecordBatch, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            let columns = &mut this.columns;
+            let result = match &mut this.source {
+                Source::Rows(rows) => ready!(rows.as_mut().poll_next(cx))
+                    .map(|row| row.and_then(|row| append(columns, |idx| row.col_buffer(idx)))),
+                Source::Copy(stream) => ready!(stream.as_mut().poll_next(cx))
+                    .map(|row| row.and_then(|row| append(columns, |idx| row.col_buffer(idx)))),
+            };
+
+            match result {
+                Some(Ok(())) => {
+                    this.len += 1;
+                    if this.len == this.batch_size {
+                        return Poll::Ready(Some(Ok(this.finish())));
+                    }
+                }
+                Some(Err(e)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => {
+                    this.done = true;
+                    if this.len == 0 {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Ok(this.finish())));
+                }
+            }
+        }
+    }
+}
+
+fn builders(
+    columns: &[(&str, &Type, Format)],
+    batch_size: usize,
+) -> Result<(SchemaRef, Vec<ColumnBuilder>), Error> {
+    assert!(batch_size > 0, "batch size must be positive");
+
+    let mut fields = Vec::with_capacity(columns.len());
+    let mut builders = Vec::with_capacity(columns.len());
+    for (idx, (name, ty, format)) in columns.iter().enumerate() {
+        let builder = match format {
+            Format::Binary => ColumnBuilder::new(ty, batch_size),
+            Format::Text => Ok(ColumnBuilder::Text(
+                Decode::Text,
+                StringBuilder::with_capacity(batch_size, 0),
+            )),
+        }
+        .map_err(|e| Error::from_sql(e, idx))?;
+        fields.push(Field::new(*name, builder.data_type(), true));
+        builders.push(builder);
+    }
+
+    Ok((Arc::new(Schema::new(fields)), builders))
+}
+
+fn append<'a, F>(columns: &mut [ColumnBuilder], value: F) -> Result<(), Error>
+where
+    F: Fn(usize) -> Option<&'a [u8]>,
+{
+    for (idx, column) in columns.iter_mut().enumerate() {
+        column
+            .append(value(idx))
+            .map_err(|e| Error::from_sql(e, idx))?;
+    }
+    Ok(())
+}
+
+#[derive(Copy, Clone)]
+enum Decode {
+    // the binary format of text types
+    Binary,
+    // the text format of any type
+    Text,
+    Jsonb,
+}
+
+enum ColumnBuilder {
+    Bool(BooleanBuilder),
+    Char(Int8Builder),
+    Int2(Int16Builder),
+    Int4(Int32Builder),
+    Int8(Int64Builder),
+    Oid(UInt32Builder),
+    Float4(Float32Builder),
+    Float8(Float64Builder),
+    Text(Decode, StringBuilder),
+    Bytea(BinaryBuilder),
+    Uuid(FixedSizeBinaryBuilder),
+    Date(Date32Builder),
+    Time(Time64MicrosecondBuilder),
+    Timestamp(TimestampMicrosecondBuilder),
+    TimestampTz(TimestampMicrosecondBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(ty: &Type, capacity: usize) -> Result<ColumnBuilder, BoxError> {
+        let builder = match *ty {
+            Type::BOOL => ColumnBuilder::Bool(BooleanBuilder::with_capacity(capacity)),
+            Type::CHAR => ColumnBuilder::Char(Int8Builder::with_capacity(capacity)),
+            Type::INT2 => ColumnBuilder::Int2(Int16Builder::with_capacity(capacity)),
+            Type::INT4 => ColumnBuilder::Int4(Int32Builder::with_capacity(capacity)),
+            Type::INT8 => ColumnBuilder::Int8(Int64Builder::with_capacity(capacity)),
+            Type::OID => ColumnBuilder::Oid(UInt32Builder::with_capacity(capacity)),
+            Type::FLOAT4 => ColumnBuilder::Float4(Float32Builder::with_capacity(capacity)),
+            Type::FLOAT8 => ColumnBuilder::Float8(Float64Builder::with_capacity(capacity)),
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN | Type::JSON => {
+                ColumnBuilder::Text(Decode::Binary, StringBuilder::with_capacity(capacity, 0))
+            }
+            Type::JSONB => {
+                ColumnBuilder::Text(Decode::Jsonb, StringBuilder::with_capacity(capacity, 0))
+            }
+            Type::BYTEA => ColumnBuilder::Bytea(BinaryBuilder::with_capacity(capacity, 0)),
+            Type::UUID => ColumnBuilder::Uuid(FixedSizeBinaryBuilder::with_capacity(capacity, 16)),
+            Type::DATE => ColumnBuilder::Date(Date32Builder::with_capacity(capacity)),
+            Type::TIME => ColumnBuilder::Time(Time64MicrosecondBuilder::with_capacity(capacity)),
+            Type::TIMESTAMP => {
+                ColumnBuilder::Timestamp(TimestampMicrosecondBuilder::with_capacity(capacity))
+            }
+            Type::TIMESTAMPTZ => ColumnBuilder::TimestampTz(
+                TimestampMicrosecondBuilder::with_capacity(capacity).with_timezone(UTC),
+            ),
+            _ => match ty.kind() {
+                Kind::Enum(_) => {
+                    ColumnBuilder::Text(Decode::Binary, StringBuilder::with_capacity(capacity, 0))
+                }
+                Kind::Domain(base) => ColumnBuilder::new(base, capacity)?,
+                _ => return Err(format!("cannot convert type {} to an Arrow type", ty).into()),
+            },
+        };
+        Ok(builder)
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnBuilder::Bool(_) => DataType::Boolean,
+            ColumnBuilder::Char(_) => DataType::Int8,
+            ColumnBuilder::Int2(_) => DataType::Int16,
+            ColumnBuilder::Int4(_) => DataType::Int32,
+            ColumnBuilder::Int8(_) => DataType::Int64,
+            ColumnBuilder::Oid(_) => DataType::UInt32,
+            ColumnBuilder::Float4(_) => DataType::Float32,
+            ColumnBuilder::Float8(_) => DataType::Float64,
+            ColumnBuilder::Text(_, _) => DataType::Utf8,
+            ColumnBuilder::Bytea(_) => DataType::Binary,
+            ColumnBuilder::Uuid(_) => DataType::FixedSizeBinary(16),
+            ColumnBuilder::Date(_) => DataType::Date32,
+            ColumnBuilder::Time(_) => DataType::Time64(TimeUnit::Microsecond),
+            ColumnBuilder::Timestamp(_) => DataType::Timestamp(TimeUnit::Microsecond, None),
+            ColumnBuilder::TimestampTz(_) => {
+                DataType::Timestamp(TimeUnit::Microsecond, Some(UTC.into()))
+            }
+        }
+    }
+
+    fn append(&mut self, raw: Option<&[u8]>) -> Result<(), BoxError> {
+        let raw = match raw {
+            Some(raw) => raw,
+            None => {
+                self.append_null();
+                return Ok(());
+            }
+        };
+
+        match self {
+            ColumnBuilder::Bool(builder) => builder.append_value(types::bool_from_sql(raw)?),
+            ColumnBuilder::Char(builder) => builder.append_value(types::char_from_sql(raw)?),
+            ColumnBuilder::Int2(builder) => builder.append_value(types::int2_from_sql(raw)?),
+            ColumnBuilder::Int4(builder) => builder.append_value(types::int4_from_sql(raw)?),
+            ColumnBuilder::Int8(builder) => builder.append_value(types::int8_from_sql(raw)?),
+            ColumnBuilder::Oid(builder) => builder.append_value(types::oid_from_sql(raw)?),
+            ColumnBuilder::Float4(builder) => builder.append_value(types::float4_from_sql(raw)?),
+            ColumnBuilder::Float8(builder) => builder.append_value(types::float8_from_sql(raw)?),
+            ColumnBuilder::Text(decode, builder) => {
+                let value = match decode {
+                    Decode::Binary => types::text_from_sql(raw)?,
+                    Decode::Text => str::from_utf8(raw)?,
+                    Decode::Jsonb => match raw.split_first() {
+                        Some((1, value)) => str::from_utf8(value)?,
+                        _ => return Err("unsupported JSONB encoding version".into()),
+                    },
+                };
+                builder.append_value(value);
+            }
+            ColumnBuilder::Bytea(builder) => builder.append_value(types::bytea_from_sql(raw)),
+            ColumnBuilder::Uuid(builder) => builder.append_value(types::uuid_from_sql(raw)?)?,
+            ColumnBuilder::Date(builder) => match types::date_from_sql(raw)? {
+                i32::MAX | i32::MIN => return Err("cannot convert an infinite date".into()),
+                days => builder.append_value(
+                    days.checked_add(EPOCH_DAYS)
+                        .ok_or("date out of range of Date32")?,
+                ),
+            },
+            ColumnBuilder::Time(builder) => builder.append_value(types::time_from_sql(raw)?),
+            ColumnBuilder::Timestamp(builder) | ColumnBuilder::TimestampTz(builder) => {
+                match types::timestamp_from_sql(raw)? {
+                    i64::MAX | i64::MIN => {
+                        return Err("cannot convert an infinite timestamp".into())
+                    }
+                    micros => builder.append_value(
+                        micros
+                            .checked_add(EPOCH_MICROS)
+                            .ok_or("timestamp out of range of Timestamp")?,
+                    ),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            ColumnBuilder::Bool(builder) => builder.append_null(),
+            ColumnBuilder::Char(builder) => builder.append_null(),
+            ColumnBuilder::Int2(builder) => builder.append_null(),
+            ColumnBuilder::Int4(builder) => builder.append_null(),
+            ColumnBuilder::Int8(builder) => builder.append_null(),
+            ColumnBuilder::Oid(builder) => builder.append_null(),
+            ColumnBuilder::Float4(builder) => builder.append_null(),
+            ColumnBuilder::Float8(builder) => builder.append_null(),
+            ColumnBuilder::Text(_, builder) => builder.append_null(),
+            ColumnBuilder::Bytea(builder) => builder.append_null(),
+            ColumnBuilder::Uuid(builder) => builder.append_null(),
+            ColumnBuilder::Date(builder) => builder.append_null(),
+            ColumnBuilder::Time(builder) => builder.append_null(),
+            ColumnBuilder::Timestamp(builder) | ColumnBuilder::TimestampTz(builder) => {
+                builder.append_null()
+            }
+        }
+    }
+
+    fn finish(&mut self) -> arrow_array::ArrayRef {
+        match self {
+            ColumnBuilder::Bool(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Char(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Int2(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Int4(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Int8(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Oid(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Float4(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Float8(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Text(_, builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Bytea(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Uuid(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Date(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Time(builder) => ArrayBuilder::finish(builder),
+            ColumnBuilder::Timestamp(builder) | ColumnBuilder::TimestampTz(builder) => {
+                ArrayBuilder::finish(builder)
+            }
+        }
+    }
+}
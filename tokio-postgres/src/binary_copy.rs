@@ -1,23 +1,80 @@
 //! Utilities for working with the PostgreSQL binary copy format.
 
+use crate::escape::escape_identifier;
+use crate::row::data_row;
+use crate::statement::Column;
 use crate::types::{FromSql, IsNull, ToSql, Type, WrongType};
-use crate::{slice_iter, CopyInSink, CopyOutStream, Error};
+use crate::{
+    slice_iter, Client, CopyInSink, CopyOutStream, Error, FromRow, IntoParams, Row, Statement,
+};
 use byteorder::{BigEndian, ByteOrder};
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use futures_util::{ready, SinkExt, Stream};
+use futures_util::{pin_mut, ready, SinkExt, Stream};
 use pin_project_lite::pin_project;
 use postgres_types::BorrowToSql;
 use std::convert::TryFrom;
 use std::io;
 use std::io::Cursor;
+use std::marker::PhantomData;
 use std::ops::Range;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+#[cfg(feature = "derive")]
+pub use tokio_postgres_derive::ToCopyRow;
+
 const MAGIC: &[u8] = b"PGCOPY\n\xff\r\n\0";
 const HEADER_LEN: usize = MAGIC.len() + 4 + 4;
 
+/// A trait for types which can be written as a row of a binary copy.
+///
+/// With the `derive` feature, it can be derived for structs with named fields. Each field is written to the column with
+/// the same name, in the order the fields are declared, and the type of the column is inferred from the field's type:
+/// `bool`, `i8`, `i16`, `i32`, `i64`, `u32`, `f32`, `f64` and strings are written as `BOOL`, `CHAR`, `INT2`, `INT4`,
+/// `INT8`, `OID`, `FLOAT4`, `FLOAT8` and `TEXT`, bytes as `BYTEA` and vectors and slices as arrays of their elements,
+/// looking through references, `Box` and `Option`. The field can be changed with the following attributes:
+///
+/// * `#[to_copy_row(type = "...")]` writes the field as the given type, the name of one of the constants of [`Type`],
+///   such as `"TIMESTAMPTZ"` or `"JSONB"`. This is required for the types which can't be inferred.
+/// * `#[to_copy_row(rename = "...")]` writes the field to the column with the given name.
+/// * `#[to_copy_row(skip)]` leaves the field out of the row.
+///
+/// ```ignore
+/// use tokio_postgres::binary_copy::{BinaryCopyInWriter, ToCopyRow};
+///
+/// #[derive(ToCopyRow)]
+/// struct Event {
+///     id: i64,
+///     name: String,
+///     #[to_copy_row(type = "TIMESTAMPTZ")]
+///     created_at: std::time::SystemTime,
+/// }
+///
+/// # async fn run(client: &tokio_postgres::Client, events: &[Event]) -> Result<(), tokio_postgres::Error> {
+/// let sink = client
+///     .copy_in(&format!("COPY events ({}) FROM STDIN BINARY", Event::columns().join(", ")))
+///     .await?;
+/// let writer = BinaryCopyInWriter::new(sink, &Event::types());
+/// futures_util::pin_mut!(writer);
+/// for event in events {
+///     writer.as_mut().write_row(event).await?;
+/// }
+/// writer.finish().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait ToCopyRow {
+    /// Returns the names of the columns, in the order their values are written.
+    fn columns() -> &'static [&'static str];
+
+    /// Returns the types of the columns.
+    fn types() -> Vec<Type>;
+
+    /// Returns the values of the row, in the order of the columns.
+    fn values(&self) -> Vec<&(dyn ToSql + Sync)>;
+}
+
 pin_project! {
     /// A type which serializes rows into the PostgreSQL binary copy format.
     ///
@@ -54,6 +111,18 @@ impl BinaryCopyInWriter {
         self.write_raw(slice_iter(values)).await
     }
 
+    /// Writes a single row from a [`ToCopyRow`] type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values of the row does not match the number expected.
+    pub async fn write_row<T>(self: Pin<&mut Self>, row: &T) -> Result<(), Error>
+    where
+        T: ?Sized + ToCopyRow,
+    {
+        self.write(&row.values()).await
+    }
+
     /// A maximally-flexible version of `write`.
     ///
     /// # Panics
@@ -111,6 +180,48 @@ impl BinaryCopyInWriter {
     }
 }
 
+pub(crate) async fn copy_insert<P, I>(
+    client: &Client,
+    table: &str,
+    columns: &[&str],
+    rows: I,
+) -> Result<u64, Error>
+where
+    P: IntoParams,
+    I: IntoIterator<Item = P>,
+{
+    let table = escape_identifier(table)?;
+    let columns = columns
+        .iter()
+        .map(|c| escape_identifier(c))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(", ");
+
+    // the statement is only described, to look up the types of the columns
+    let statement = client
+        .prepare(&format!("SELECT {} FROM {}", columns, table))
+        .await?;
+    let types = statement
+        .columns()
+        .iter()
+        .map(|c| c.type_().clone())
+        .collect::<Vec<_>>();
+
+    let sink = client
+        .copy_in(&format!("COPY {} ({}) FROM STDIN BINARY", table, columns))
+        .await?;
+    let writer = BinaryCopyInWriter::new(sink, &types);
+    pin_mut!(writer);
+    for row in rows {
+        let row = row.to_params();
+        if row.len() != types.len() {
+            return Err(Error::parameters(row.len(), types.len()));
+        }
+        writer.as_mut().write(&row).await?;
+    }
+    writer.finish().await
+}
+
 struct Header {
     has_oids: bool,
 }
@@ -134,6 +245,49 @@ impl BinaryCopyOutStream {
             header: None,
         }
     }
+
+    /// Converts the stream into a stream of `R`, with the columns named `names` in the order of their types.
+    ///
+    /// The rows are converted with `R`'s [`FromRow`] implementation, as if they had been returned by a query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of names does not match the number of types of the stream.
+    pub fn map_as<R>(self, names: &[&str]) -> BinaryCopyOutStreamAs<R>
+    where
+        R: FromRow,
+    {
+        assert!(
+            names.len() == self.types.len(),
+            "expected {} column names but got {}",
+            self.types.len(),
+            names.len(),
+        );
+
+        let columns = names
+            .iter()
+            .zip(self.types.iter())
+            .map(|(name, ty)| Column {
+                name: name.to_string(),
+                table_oid: None,
+                column_id: None,
+                r#type: ty.clone(),
+            })
+            .collect();
+
+        BinaryCopyOutStreamAs {
+            stream: self,
+            statement: Statement::detached(String::new(), vec![], columns),
+            _p: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl BinaryCopyOutStream {
+    pub(crate) fn types(&self) -> &[Type] {
+        &self.types
+    }
 }
 
 impl Stream for BinaryCopyOutStream {
@@ -223,6 +377,59 @@ fn check_remaining(buf: &Cursor<Bytes>, len: usize) -> Result<(), Error> {
     }
 }
 
+pin_project! {
+    /// A stream of rows of a binary copy converted to `R`, returned by [`BinaryCopyOutStream::map_as`].
+    pub struct BinaryCopyOutStreamAs<R> {
+        #[pin]
+        stream: BinaryCopyOutStream,
+        statement: Statement,
+        _p: PhantomData<fn() -> R>,
+    }
+}
+
+impl<R> Stream for BinaryCopyOutStreamAs<R>
+where
+    R: FromRow,
+{
+    type Item = Result<R, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let statement = this.statement;
+        let row = ready!(this.stream.poll_next(cx));
+        Poll::Ready(row.map(|row| {
+            row.and_then(|row| row.to_row(statement))
+                .and_then(|row| R::from_row(&row))
+        }))
+    }
+}
+
+pub(crate) async fn copy_out_as<R>(
+    client: &Client,
+    query: &str,
+) -> Result<BinaryCopyOutStreamAs<R>, Error>
+where
+    R: FromRow,
+{
+    // the query is only described, to look up the names and types of its columns
+    let statement = client.prepare(query).await?;
+    let names = statement
+        .columns()
+        .iter()
+        .map(|c| c.name())
+        .collect::<Vec<_>>();
+    let types = statement
+        .columns()
+        .iter()
+        .map(|c| c.type_().clone())
+        .collect::<Vec<_>>();
+
+    let stream = client
+        .copy_out(&format!("COPY ({}) TO STDOUT (FORMAT binary)", query))
+        .await?;
+    Ok(BinaryCopyOutStream::new(stream, &types).map_as(&names))
+}
+
 /// A row of data parsed from a binary copy out stream.
 pub struct BinaryCopyOutRow {
     buf: Bytes,
@@ -270,4 +477,27 @@ impl BinaryCopyOutRow {
             Err(e) => panic!("error retrieving column {}: {}", idx, e),
         }
     }
+
+    #[cfg(feature = "arrow")]
+    pub(crate) fn col_buffer(&self, idx: usize) -> Option<&[u8]> {
+        self.ranges[idx].clone().map(|range| &self.buf[range])
+    }
+
+    fn to_row(&self, statement: &Statement) -> Result<Row, Error> {
+        let mut values = BytesMut::new();
+        for range in &self.ranges {
+            match range {
+                Some(range) => {
+                    values.put_i32(range.len() as i32);
+                    values.put_slice(&self.buf[range.clone()]);
+                }
+                None => values.put_i32(-1),
+            }
+        }
+        Row::new(
+            statement.clone(),
+            None,
+            data_row(self.ranges.len(), &values)?,
+        )
+    }
 }
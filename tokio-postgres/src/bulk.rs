@@ -0,0 +1,203 @@
+//! Multi-row `INSERT` statements.
+//!
+//! Inserting rows one `INSERT` at a time costs a round trip and a statement execution per row. A [`BulkInsert`]
+//! instead generates `INSERT ... VALUES ($1, $2), ($3, $4), ...` statements inserting many rows each, split so that
+//! no statement exceeds the number of parameters which can be bound to a single statement.
+//!
+//! ```no_run
+//! use tokio_postgres::bulk::BulkInsert;
+//!
+//! # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! let users = vec![("alice", 30), ("bob", 25)];
+//!
+//! let inserted = BulkInsert::new("users", &["name", "age"])
+//!     .suffix("ON CONFLICT DO NOTHING")
+//!     .execute(client, &users)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! When the rows are split over several statements, they are executed one after the other, so use a transaction if
+//! they must be inserted all together or not at all. For very large numbers of rows, `COPY` is faster still.
+use crate::escape::escape_identifier;
+use crate::types::ToSql;
+use crate::{Error, GenericClient, IntoParams};
+use std::fmt::Write;
+
+/// The most parameters which can be bound to a single statement, as the count is sent as a 16-bit signed integer.
+const MAX_PARAMS: usize = i16::MAX as usize;
+
+/// Generates multi-row `INSERT` statements.
+#[derive(Debug, Clone)]
+pub struct BulkInsert {
+    schema: Option<String>,
+    table: String,
+    columns: Vec<String>,
+    suffix: Option<String>,
+    max_params: usize,
+}
+
+impl BulkInsert {
+    /// Creates a new bulk insert into the columns `columns` of the table `table`.
+    ///
+    /// The table and column names are quoted, so they are matched exactly, case included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `columns` is empty.
+    pub fn new(table: &str, columns: &[&str]) -> BulkInsert {
+        assert!(!columns.is_empty(), "a bulk insert requires a column");
+
+        BulkInsert {
+            schema: None,
+            table: table.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            suffix: None,
+            max_params: MAX_PARAMS,
+        }
+    }
+
+    /// Sets the schema of the table.
+    ///
+    /// Defaults to none, which looks the table up in the `search_path`.
+    pub fn schema(mut self, schema: &str) -> BulkInsert {
+        self.schema = Some(schema.to_string());
+        self
+    }
+
+    /// Sets SQL text appended to each statement, such as an `ON CONFLICT` clause.
+    ///
+    /// The text must not contain `$n` placeholders.
+    pub fn suffix(mut self, suffix: &str) -> BulkInsert {
+        self.suffix = Some(suffix.to_string());
+        self
+    }
+
+    /// Sets the maximum number of parameters of each statement.
+    ///
+    /// Each statement inserts as many rows as fit in this number of parameters, but always at least one. Defaults to
+    /// 32767, the most which can be bound to a statement.
+    pub fn max_params(mut self, max_params: usize) -> BulkInsert {
+        self.max_params = max_params.min(MAX_PARAMS);
+        self
+    }
+
+    /// Returns the number of rows inserted by each statement, except perhaps the last.
+    pub fn rows_per_statement(&self) -> usize {
+        (self.max_params / self.columns.len()).max(1)
+    }
+
+    /// Generates the statements inserting `rows`, along with their parameters.
+    ///
+    /// Returns an error if a row doesn't have one value per column, or if a name contains a nul character.
+    pub fn statements<'a, P, I>(&self, rows: I) -> Result<Vec<InsertStatement<'a>>, Error>
+    where
+        P: 'a + ?Sized + IntoParams,
+        I: IntoIterator<Item = &'a P>,
+    {
+        let rows_per_statement = self.rows_per_statement();
+        let mut statements = vec![];
+        let mut params = vec![];
+        let mut count = 0;
+
+        for row in rows {
+            let row = row.to_params();
+            if row.len() != self.columns.len() {
+                return Err(Error::parameters(row.len(), self.columns.len()));
+            }
+            params.extend(row);
+            count += 1;
+
+            if count == rows_per_statement {
+                statements.push(InsertStatement {
+                    sql: self.sql(count)?,
+                    params: params.split_off(0),
+                });
+                count = 0;
+            }
+        }
+        if count > 0 {
+            statements.push(InsertStatement {
+                sql: self.sql(count)?,
+                params,
+            });
+        }
+
+        Ok(statements)
+    }
+
+    /// Inserts `rows`, returning the number of rows inserted.
+    pub async fn execute<'a, C, P, I>(&self, client: &C, rows: I) -> Result<u64, Error>
+    where
+        C: GenericClient + Sync,
+        P: 'a + ?Sized + IntoParams,
+        I: IntoIterator<Item = &'a P>,
+    {
+        let mut inserted = 0;
+        for statement in self.statements(rows)? {
+            inserted += client.execute(&statement.sql, &statement.params).await?;
+        }
+        Ok(inserted)
+    }
+
+    fn sql(&self, rows: usize) -> Result<String, Error> {
+        let mut sql = String::from("INSERT INTO ");
+        if let Some(schema) = &self.schema {
+            sql.push_str(&escape_identifier(schema)?);
+            sql.push('.');
+        }
+        sql.push_str(&escape_identifier(&self.table)?);
+
+        sql.push_str(" (");
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            sql.push_str(&escape_identifier(column)?);
+        }
+        sql.push_str(") VALUES ");
+
+        let mut param = 0;
+        for row in 0..rows {
+            if row > 0 {
+                sql.push_str(", ");
+            }
+            sql.push('(');
+            for i in 0..self.columns.len() {
+                if i > 0 {
+                    sql.push_str(", ");
+                }
+                param += 1;
+                let _ = write!(sql, "${}", param);
+            }
+            sql.push(')');
+        }
+
+        if let Some(suffix) = &self.suffix {
+            sql.push(' ');
+            sql.push_str(suffix);
+        }
+
+        Ok(sql)
+    }
+}
+
+/// One of the statements of a [`BulkInsert`], along with its parameters.
+#[derive(Debug)]
+pub struct InsertStatement<'a> {
+    sql: String,
+    params: Vec<&'a (dyn ToSql + Sync)>,
+}
+
+impl<'a> InsertStatement<'a> {
+    /// Returns the SQL text of the statement.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the parameters of the statement.
+    pub fn params(&self) -> &[&'a (dyn ToSql + Sync)] {
+        &self.params
+    }
+}
@@ -0,0 +1,431 @@
+//! A client-side cache of query results.
+//!
+//! Queries which run often but whose results change rarely can be answered from memory with [`Client::cached`]. The
+//! results are keyed by the text of the statement and the values of its parameters, so that repeated executions with
+//! the same parameters do not reach the server until the entry expires. When the cache outgrows its limits, the least
+//! recently used entries are evicted.
+//!
+//! Queries passed as text are prepared through the client's statement cache, which has to be enabled with
+//! [`Config::statement_cache_capacity`](crate::Config::statement_cache_capacity) for results served from the cache
+//! to skip the server entirely.
+//!
+//! Entries can be tagged, and dropped as soon as the data they were computed from changes. Tags match the table names
+//! and channels of the [`Invalidation`]s reported by an [`Invalidator`], so the two can be wired up directly.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! # #[cfg(feature = "runtime")]
+//! use tokio_postgres::invalidate::Invalidator;
+//! use tokio_postgres::NoTls;
+//!
+//! # #[cfg(feature = "runtime")]
+//! # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! let config = "host=localhost user=postgres".parse()?;
+//! let cache = client.result_cache().clone();
+//! let invalidator = Invalidator::new(&config, NoTls)
+//!     .table("users")
+//!     .on_invalidation(move |invalidation| cache.invalidate(&invalidation));
+//! tokio::spawn(invalidator.run());
+//!
+//! let names = client
+//!     .cached(Duration::from_secs(60))
+//!     .tag("users")
+//!     .query_scalar::<String, _>("SELECT name FROM users WHERE active = $1", &[&true])
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Invalidation`]: crate::invalidate::Invalidation
+//! [`Invalidator`]: crate::invalidate::Invalidator
+#[cfg(feature = "runtime")]
+use crate::invalidate::Invalidation;
+use crate::types::{IsNull, ToSql, Type};
+use crate::{Client, Error, FromRow, Row, Statement, ToStatement};
+use bytes::{BufMut, BytesMut};
+use parking_lot::Mutex;
+use postgres_types::FromSqlOwned;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Statistics of a [`ResultCache`], as returned by [`ResultCache::stats`].
+#[derive(Debug, Copy, Clone)]
+pub struct CacheStats {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+    invalidations: u64,
+    entries: usize,
+    bytes: usize,
+}
+
+impl CacheStats {
+    /// Returns the number of queries answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of queries which were sent to the server because their result was not cached.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the number of entries evicted to stay within the limits of the cache.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
+    }
+
+    /// Returns the number of entries dropped because their time to live had passed.
+    pub fn expirations(&self) -> u64 {
+        self.expirations
+    }
+
+    /// Returns the number of entries dropped by an invalidation.
+    pub fn invalidations(&self) -> u64 {
+        self.invalidations
+    }
+
+    /// Returns the number of entries currently in the cache.
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Returns the size of the encoded rows currently in the cache, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    sql: String,
+    types: Vec<Type>,
+    params: Vec<u8>,
+}
+
+struct Entry {
+    rows: Arc<Vec<Row>>,
+    size: usize,
+    expires: Instant,
+    last_used: u64,
+    tags: Vec<String>,
+}
+
+struct State {
+    entries: HashMap<Key, Entry>,
+    max_entries: usize,
+    max_bytes: usize,
+    bytes: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expirations: u64,
+    invalidations: u64,
+}
+
+impl State {
+    fn get(&mut self, key: &Key) -> Option<Arc<Vec<Row>>> {
+        let now = Instant::now();
+        match self.entries.get_mut(key) {
+            Some(entry) if entry.expires > now => {
+                self.clock += 1;
+                entry.last_used = self.clock;
+                self.hits += 1;
+                return Some(entry.rows.clone());
+            }
+            Some(_) => {
+                self.remove(key);
+                self.expirations += 1;
+            }
+            None => {}
+        }
+        self.misses += 1;
+        None
+    }
+
+    fn insert(&mut self, key: Key, rows: Arc<Vec<Row>>, ttl: Duration, tags: Vec<String>) {
+        let size = rows.iter().map(Row::body_len).sum::<usize>();
+        self.remove(&key);
+        if self.max_entries == 0 || size > self.max_bytes {
+            return;
+        }
+
+        let now = Instant::now();
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.expires > now);
+        self.expirations += (before - self.entries.len()) as u64;
+        self.bytes = self.entries.values().map(|entry| entry.size).sum();
+
+        while self.entries.len() >= self.max_entries || self.bytes + size > self.max_bytes {
+            self.evict();
+        }
+
+        self.clock += 1;
+        self.bytes += size;
+        self.entries.insert(
+            key,
+            Entry {
+                rows,
+                size,
+                expires: now + ttl,
+                last_used: self.clock,
+                tags,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.size;
+        }
+    }
+
+    fn evict(&mut self) {
+        let key = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = key {
+            self.remove(&key);
+            self.evictions += 1;
+        }
+    }
+
+    fn invalidate_where<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, entry| !entry.tags.iter().any(|tag| f(tag)));
+        self.invalidations += (before - self.entries.len()) as u64;
+        self.bytes = self.entries.values().map(|entry| entry.size).sum();
+    }
+
+    fn clear(&mut self) {
+        self.invalidations += self.entries.len() as u64;
+        self.entries.clear();
+        self.bytes = 0;
+    }
+}
+
+/// A cache of query results, shared by the clones of the handle.
+///
+/// Every [`Client`] owns a cache, which is available from [`Client::result_cache`] and used by [`Client::cached`].
+/// Clients connected to the same database may share a cache with [`Client::set_result_cache`].
+#[derive(Clone)]
+pub struct ResultCache(Arc<Mutex<State>>);
+
+impl fmt::Debug for ResultCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.0.lock();
+        f.debug_struct("ResultCache")
+            .field("entries", &state.entries.len())
+            .field("bytes", &state.bytes)
+            .field("max_entries", &state.max_entries)
+            .field("max_bytes", &state.max_bytes)
+            .finish()
+    }
+}
+
+impl Default for ResultCache {
+    fn default() -> ResultCache {
+        ResultCache::new()
+    }
+}
+
+impl ResultCache {
+    /// Creates a new, empty cache.
+    pub fn new() -> ResultCache {
+        ResultCache(Arc::new(Mutex::new(State {
+            entries: HashMap::new(),
+            max_entries: 1024,
+            max_bytes: 16 * 1024 * 1024,
+            bytes: 0,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            expirations: 0,
+            invalidations: 0,
+        })))
+    }
+
+    /// Sets the maximum number of results kept in the cache.
+    ///
+    /// Defaults to 1024.
+    pub fn set_max_entries(&self, max_entries: usize) {
+        let mut state = self.0.lock();
+        state.max_entries = max_entries;
+        while state.entries.len() > max_entries {
+            state.evict();
+        }
+    }
+
+    /// Sets the maximum total size of the encoded rows kept in the cache, in bytes.
+    ///
+    /// Results larger than this are not cached. Defaults to 16 MiB.
+    pub fn set_max_bytes(&self, max_bytes: usize) {
+        let mut state = self.0.lock();
+        state.max_bytes = max_bytes;
+        while state.bytes > max_bytes {
+            state.evict();
+        }
+    }
+
+    /// Returns the statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        let state = self.0.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            expirations: state.expirations,
+            invalidations: state.invalidations,
+            entries: state.entries.len(),
+            bytes: state.bytes,
+        }
+    }
+
+    /// Drops the entries with the tag `tag`.
+    pub fn invalidate_tag(&self, tag: &str) {
+        self.0.lock().invalidate_where(|t| t == tag);
+    }
+
+    /// Drops the entries affected by an invalidation reported by an
+    /// [`Invalidator`](crate::invalidate::Invalidator).
+    ///
+    /// A modification of a table drops the entries tagged with its name, either bare or qualified with its schema.
+    /// A notification drops the entries tagged with its channel. After a reconnect, notifications may have been
+    /// missed, so every entry is dropped.
+    #[cfg(feature = "runtime")]
+    pub fn invalidate(&self, invalidation: &Invalidation) {
+        let mut state = self.0.lock();
+        match invalidation {
+            Invalidation::Table { schema, table, .. } => {
+                let qualified = format!("{}.{}", schema, table);
+                state.invalidate_where(|tag| tag == table || tag == qualified)
+            }
+            Invalidation::Notification(notification) => {
+                state.invalidate_where(|tag| tag == notification.channel())
+            }
+            Invalidation::Reconnected => state.clear(),
+        }
+    }
+
+    /// Drops every entry.
+    pub fn clear(&self) {
+        self.0.lock().clear();
+    }
+}
+
+/// Runs queries through the result cache of a client, as returned by [`Client::cached`].
+#[derive(Debug)]
+pub struct Cached<'a> {
+    client: &'a Client,
+    ttl: Duration,
+    tags: Vec<String>,
+}
+
+impl<'a> Cached<'a> {
+    pub(crate) fn new(client: &'a Client, ttl: Duration) -> Cached<'a> {
+        Cached {
+            client,
+            ttl,
+            tags: vec![],
+        }
+    }
+
+    /// Tags the results cached by the query, so that they can be dropped with [`ResultCache::invalidate_tag`] or
+    /// [`ResultCache::invalidate`].
+    pub fn tag(mut self, tag: &str) -> Cached<'a> {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Like [`Client::query_as`], but answered from the cache if the statement was run with the same parameters
+    /// before and its result has not expired.
+    pub async fn query_as<R, T>(
+        self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<R>, Error>
+    where
+        R: FromRow,
+        T: ?Sized + ToStatement,
+    {
+        let rows = self.rows(statement, params).await?;
+        rows.iter().map(R::from_row).collect()
+    }
+
+    /// Like [`Client::query_scalar`], but answered from the cache if the statement was run with the same parameters
+    /// before and its result has not expired.
+    pub async fn query_scalar<R, T>(
+        self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<R>, Error>
+    where
+        R: FromSqlOwned,
+        T: ?Sized + ToStatement,
+    {
+        let rows = self.rows(statement, params).await?;
+        rows.iter().map(|row| row.try_get(0)).collect()
+    }
+
+    async fn rows<T>(
+        self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Arc<Vec<Row>>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let cache = self.client.result_cache();
+        let inner = self.client.inner();
+
+        let statement = statement.__convert().into_statement(inner).await?;
+        let key = key(&statement, params)?;
+        if let Some(rows) = cache.0.lock().get(&key) {
+            return Ok(rows);
+        }
+
+        let rows = Arc::new(self.client.query(&statement, params).await?);
+        cache
+            .0
+            .lock()
+            .insert(key, rows.clone(), self.ttl, self.tags);
+        Ok(rows)
+    }
+}
+
+fn key(statement: &Statement, params: &[&(dyn ToSql + Sync)]) -> Result<Key, Error> {
+    if params.len() != statement.params().len() {
+        return Err(Error::parameters(params.len(), statement.params().len()));
+    }
+
+    let mut buf = BytesMut::new();
+    for (idx, (param, ty)) in params.iter().zip(statement.params()).enumerate() {
+        let start = buf.len();
+        buf.put_i32(0);
+        let len = match param
+            .to_sql_checked(ty, &mut buf)
+            .map_err(|e| Error::to_sql(e, idx))?
+        {
+            IsNull::Yes => -1,
+            IsNull::No => (buf.len() - start - 4) as i32,
+        };
+        buf[start..start + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    Ok(Key {
+        sql: statement.sql().to_string(),
+        types: statement.params().to_vec(),
+        params: buf.to_vec(),
+    })
+}
@@ -1,8 +1,14 @@
-use crate::client::SocketConfig;
+use crate::client::{Addr, SocketConfig};
 use crate::config::SslMode;
-use crate::tls::MakeTlsConnect;
+use crate::tls::private::ForcePrivateApi;
+use crate::tls::{MakeTlsConnect, TlsConnect};
 use crate::{cancel_query_raw, connect_socket, Error, Socket};
-use std::io;
+use bytes::BytesMut;
+use postgres_protocol::message::frontend;
+use std::io::{self, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
 
 pub(crate) async fn cancel_query<T>(
     config: Option<SocketConfig>,
@@ -14,15 +20,7 @@ pub(crate) async fn cancel_query<T>(
 where
     T: MakeTlsConnect<Socket>,
 {
-    let config = match config {
-        Some(config) => config,
-        None => {
-            return Err(Error::connect(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "unknown host",
-            )))
-        }
-    };
+    let config = socket_config(config)?;
 
     let tls = tls
         .make_tls_connect(config.hostname.as_deref().unwrap_or(""))
@@ -41,3 +39,68 @@ where
     cancel_query_raw::cancel_query_raw(socket, ssl_mode, tls, has_hostname, process_id, secret_key)
         .await
 }
+
+/// Sends the cancel request over a blocking std socket, without involving a runtime.
+///
+/// Returns `None` if the request would have to be sent over TLS or through a proxy, which can only be done
+/// asynchronously.
+pub(crate) fn cancel_query_blocking<T>(
+    config: Option<SocketConfig>,
+    ssl_mode: SslMode,
+    tls: &mut T,
+    process_id: i32,
+    secret_key: i32,
+) -> Option<Result<(), Error>>
+where
+    T: MakeTlsConnect<Socket>,
+{
+    let config = match socket_config(config) {
+        Ok(config) => config,
+        Err(e) => return Some(Err(e)),
+    };
+
+    match ssl_mode {
+        SslMode::Disable => {}
+        SslMode::Prefer => {
+            let tls = match tls.make_tls_connect(config.hostname.as_deref().unwrap_or("")) {
+                Ok(tls) => tls,
+                Err(e) => return Some(Err(Error::tls(e.into()))),
+            };
+            if tls.can_connect(ForcePrivateApi) {
+                return None;
+            }
+        }
+        SslMode::Require => return None,
+    }
+
+    let mut buf = BytesMut::new();
+    frontend::cancel_request(process_id, secret_key, &mut buf);
+
+    Some(match config.addr {
+        Addr::Tcp(ip) => {
+            let addr = SocketAddr::new(ip, config.port);
+            let stream = match config.connect_timeout {
+                Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+            stream.map_err(Error::connect).and_then(|mut stream| {
+                stream.write_all(&buf).map_err(Error::io)?;
+                stream.shutdown(Shutdown::Write).map_err(Error::io)
+            })
+        }
+        #[cfg(unix)]
+        Addr::Unix(dir) => UnixStream::connect(dir.join(format!(".s.PGSQL.{}", config.port)))
+            .map_err(Error::connect)
+            .and_then(|mut stream| {
+                stream.write_all(&buf).map_err(Error::io)?;
+                stream.shutdown(Shutdown::Write).map_err(Error::io)
+            }),
+        #[cfg(feature = "socks")]
+        Addr::Proxy { .. } => return None,
+    })
+}
+
+fn socket_config(config: Option<SocketConfig>) -> Result<SocketConfig, Error> {
+    config
+        .ok_or_else(|| Error::connect(io::Error::new(io::ErrorKind::InvalidInput, "unknown host")))
+}
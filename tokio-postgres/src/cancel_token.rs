@@ -44,6 +44,21 @@ impl CancelToken {
         .await
     }
 
+    #[doc(hidden)]
+    #[cfg(feature = "runtime")]
+    pub fn __private_api_cancel_query_blocking<T>(&self, tls: &mut T) -> Option<Result<(), Error>>
+    where
+        T: MakeTlsConnect<Socket>,
+    {
+        cancel_query::cancel_query_blocking(
+            self.socket_config.clone(),
+            self.ssl_mode,
+            tls,
+            self.process_id,
+            self.secret_key,
+        )
+    }
+
     /// Like `cancel_query`, but uses a stream which is already connected to the server rather than opening a new
     /// connection itself.
     pub async fn cancel_query_raw<S, T>(&self, stream: S, tls: T) -> Result<(), Error>
@@ -1,26 +1,44 @@
+use crate::binary_copy::BinaryCopyOutStreamAs;
+use crate::cache::{Cached, ResultCache};
 use crate::codec::{BackendMessages, FrontendMessage};
 use crate::config::SslMode;
 use crate::connection::{Request, RequestMessages};
+use crate::connection_events::{ConnectionEvents, EventSender};
 use crate::copy_out::CopyOutStream;
+use crate::dry_run::{DryRunCounters, DryRunStats};
+use crate::error::DbError;
+use crate::escape::escape_literal;
 use crate::from_row::FromRow;
 #[cfg(feature = "runtime")]
 use crate::keepalive::KeepaliveConfig;
+#[cfg(feature = "with-serde_json-1")]
+use crate::notifications::NotificationsAs;
 use crate::query::RowStream;
 use crate::simple_query::SimpleQueryStream;
+#[cfg(feature = "socks")]
+use crate::socks::Proxy;
+#[cfg(feature = "sqlcommenter")]
+use crate::sqlcommenter::SqlCommenter;
+use crate::statement::StatementCache;
+use crate::statement_stats::{StatsGuard, StatsRegistry};
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
-use crate::tls::TlsConnect;
-use crate::types::{Oid, ToSql, Type};
+use crate::tls::{TlsConnect, TlsInfo};
+use crate::types::{Oid, PgLsn, ToSql, Type};
 #[cfg(feature = "runtime")]
 use crate::Socket;
 use crate::{
-    copy_in, copy_out, prepare, query, simple_query, slice_iter, CancelToken, CopyInSink, Error,
-    Row, SimpleQueryMessage, Statement, ToStatement, Transaction, TransactionBuilder,
+    binary_copy, copy_in, copy_out, notifications, prepare, query, simple_query, slice_iter,
+    CancelToken, Config, CopyInSink, Error, IntoParams, NamedQuery, Notifications,
+    PreparedTransaction, QueryOptions, Row, SimpleQueryMessage, Statement, StatementStats,
+    ToParams, ToStatement, Transaction, TransactionBuilder,
 };
 use bytes::{Buf, BytesMut};
 use fallible_iterator::FallibleIterator;
-use futures_channel::mpsc;
-use futures_util::{future, pin_mut, ready, stream::BoxStream, StreamExt, TryStreamExt};
+use futures_channel::{mpsc, oneshot};
+use futures_util::future::{self, BoxFuture};
+use futures_util::Future;
+use futures_util::{pin_mut, ready, stream::BoxStream, StreamExt, TryStreamExt};
 use parking_lot::Mutex;
 use postgres_protocol::message::{backend::Message, frontend};
 use postgres_types::{BorrowToSql, FromSqlOwned};
@@ -30,22 +48,35 @@ use std::fmt;
 use std::net::IpAddr;
 #[cfg(feature = "runtime")]
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
-#[cfg(feature = "runtime")]
 use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::watch;
 
 pub struct Responses {
     receiver: mpsc::Receiver<BackendMessages>,
     cur: BackendMessages,
+    events: Arc<EventSender>,
+    dry_run: Option<Arc<DryRunCounters>>,
 }
 
 impl Responses {
     pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Result<Message, Error>> {
         loop {
             match self.cur.next().map_err(Error::parse)? {
-                Some(Message::ErrorResponse(body)) => return Poll::Ready(Err(Error::db(body))),
+                Some(Message::ErrorResponse(body)) => {
+                    let error = Error::db(body);
+                    self.events.send_for_error(&error);
+                    return Poll::Ready(Err(error));
+                }
+                Some(Message::CommandComplete(body)) => {
+                    if let Some(dry_run) = &self.dry_run {
+                        dry_run.record(body.tag().map_err(Error::parse)?);
+                    }
+                    return Poll::Ready(Ok(Message::CommandComplete(body)));
+                }
                 Some(message) => return Poll::Ready(Ok(message)),
                 None => {}
             }
@@ -62,6 +93,9 @@ impl Responses {
     }
 }
 
+/// The number of statements prepared again on another connection which a client keeps.
+const REPREPARED_CAPACITY: usize = 256;
+
 /// A cache of type info and prepared statements for fetching type info
 /// (corresponding to the queries in the [prepare](prepare) module).
 #[derive(Default)]
@@ -88,20 +122,69 @@ pub struct InnerClient {
 
     /// A buffer to use when writing out postgres commands.
     buffer: Mutex<BytesMut>,
+
+    /// Statements prepared by other connections and prepared again on this one, keyed by their query and parameter
+    /// types. The least recently used ones are evicted beyond `REPREPARED_CAPACITY`.
+    reprepared: Mutex<StatementCache>,
+
+    /// Statements prepared for queries passed as text, if enabled.
+    statement_cache: Mutex<StatementCache>,
+
+    stats: Option<Arc<StatsRegistry>>,
+
+    events: Arc<EventSender>,
+
+    /// The number of requests sent to the connection that it has not finished responding to.
+    pending_requests: Arc<AtomicUsize>,
+
+    response_buffer_size: usize,
+
+    /// The counters of the dry run in progress, if any.
+    dry_run: Mutex<Option<Arc<DryRunCounters>>>,
+
+    #[cfg(feature = "sqlcommenter")]
+    sql_commenter: Option<SqlCommenter>,
+
+    /// The backend of the current session, used to cancel its queries.
+    backend: Mutex<Backend>,
+
+    /// Incremented whenever the connection is replaced by a new session, which invalidates the statements prepared
+    /// on the previous one.
+    session: AtomicU64,
+
+    /// The session in which the open transaction was begun, if any. Requests are only sent on that session.
+    transaction_session: Mutex<Option<u64>>,
+}
+
+struct Backend {
+    #[cfg(feature = "runtime")]
+    socket_config: Option<SocketConfig>,
+    process_id: i32,
+    secret_key: i32,
+    tls_info: Option<TlsInfo>,
 }
 
 impl InnerClient {
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, messages)))]
     pub fn send(&self, messages: RequestMessages) -> Result<Responses, Error> {
-        let (sender, receiver) = mpsc::channel(1);
-        let request = Request { messages, sender };
-        self.sender
-            .unbounded_send(request)
-            .map_err(|_| Error::closed())?;
+        let (sender, receiver) = mpsc::channel(self.response_buffer_size);
+        let request = Request {
+            messages,
+            sender,
+            session: *self.transaction_session.lock(),
+        };
+        // counted before sending so that the connection can't finish the request before it is counted
+        self.pending_requests.fetch_add(1, Ordering::Relaxed);
+        if self.sender.unbounded_send(request).is_err() {
+            self.pending_requests.fetch_sub(1, Ordering::Relaxed);
+            return Err(Error::closed());
+        }
 
         Ok(Responses {
             receiver,
             cur: BackendMessages::empty(),
+            events: self.events.clone(),
+            dry_run: self.dry_run.lock().clone(),
         })
     }
 
@@ -109,6 +192,63 @@ impl InnerClient {
         self.cached_typeinfo.lock().typeinfo.clone()
     }
 
+    pub fn reprepared(&self, statement: &Statement) -> Option<Statement> {
+        self.reprepared
+            .lock()
+            .get(statement.sql(), statement.params())
+    }
+
+    pub fn set_reprepared(&self, statement: &Statement, reprepared: &Statement) {
+        let evicted =
+            self.reprepared
+                .lock()
+                .insert(statement.sql(), statement.params(), reprepared.clone());
+        drop(evicted);
+    }
+
+    /// Returns the cached statement prepared for a query, if the statement cache is enabled.
+    ///
+    /// Statements prepared on a previous session are dropped from the cache.
+    pub fn cached_statement(self: &Arc<Self>, query: &str, types: &[Type]) -> Option<Statement> {
+        let statement = self.statement_cache.lock().get(query, types)?;
+        if statement.is_prepared_on(self) {
+            Some(statement)
+        } else {
+            None
+        }
+    }
+
+    pub fn statement_cache_enabled(&self) -> bool {
+        self.statement_cache.lock().is_enabled()
+    }
+
+    pub fn statement_cache_len(&self) -> usize {
+        self.statement_cache.lock().len()
+    }
+
+    pub fn cache_statement(&self, query: &str, types: &[Type], statement: &Statement) {
+        let evicted = self
+            .statement_cache
+            .lock()
+            .insert(query, types, statement.clone());
+        drop(evicted);
+    }
+
+    pub fn clear_statement_cache(&self) {
+        let statements = self.statement_cache.lock().clear();
+        drop(statements);
+    }
+
+    /// Starts tracking an execution of a statement, if statistics are enabled.
+    pub fn track(&self, statement: &Statement) -> Option<StatsGuard> {
+        self.stats.as_ref().map(|stats| stats.start(statement))
+    }
+
+    #[cfg(feature = "sqlcommenter")]
+    pub(crate) fn sql_commenter(&self) -> Option<&SqlCommenter> {
+        self.sql_commenter.as_ref()
+    }
+
     pub fn set_typeinfo(&self, statement: &Statement) {
         self.cached_typeinfo.lock().typeinfo = Some(statement.clone());
     }
@@ -141,6 +281,46 @@ impl InnerClient {
         self.cached_typeinfo.lock().types.clear();
     }
 
+    /// Returns the current session of the client.
+    pub fn session(&self) -> u64 {
+        self.session.load(Ordering::SeqCst)
+    }
+
+    /// Restricts the requests of the client to `session` while a transaction is open, or lifts the restriction.
+    pub(crate) fn set_transaction_session(&self, session: Option<u64>) {
+        *self.transaction_session.lock() = session;
+    }
+
+    /// Switches the client over to the session of `new`, whose connection has replaced the one of this client.
+    ///
+    /// Statements prepared on the previous session will be prepared again when they are next used.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn resume(&self, new: &InnerClient) {
+        {
+            let new = new.backend.lock();
+            let mut backend = self.backend.lock();
+            backend.socket_config = new.socket_config.clone();
+            backend.process_id = new.process_id;
+            backend.secret_key = new.secret_key;
+            backend.tls_info = new.tls_info.clone();
+        }
+
+        {
+            let mut cached_typeinfo = self.cached_typeinfo.lock();
+            cached_typeinfo.typeinfo = None;
+            cached_typeinfo.typeinfo_composite = None;
+            cached_typeinfo.typeinfo_enum = None;
+        }
+        let reprepared = self.reprepared.lock().clear();
+        drop(reprepared);
+        self.session.fetch_add(1, Ordering::SeqCst);
+        self.events.send(crate::ConnectionEvent::Resumed);
+    }
+
+    pub(crate) fn events(&self) -> &EventSender {
+        &self.events
+    }
+
     /// Call the given function with a buffer to be used when writing out
     /// postgres commands.
     pub fn with_buf<F, R>(&self, f: F) -> R
@@ -171,6 +351,11 @@ pub(crate) enum Addr {
     Tcp(IpAddr),
     #[cfg(unix)]
     Unix(PathBuf),
+    #[cfg(feature = "socks")]
+    Proxy {
+        proxy: Proxy,
+        host: String,
+    },
 }
 
 /// An asynchronous PostgreSQL client.
@@ -179,31 +364,59 @@ pub(crate) enum Addr {
 /// through this client object.
 pub struct Client {
     inner: Arc<InnerClient>,
-    #[cfg(feature = "runtime")]
-    socket_config: Option<SocketConfig>,
     ssl_mode: SslMode,
-    process_id: i32,
-    secret_key: i32,
+    result_cache: ResultCache,
+    /// Completes when the connection has been dropped.
+    closed: oneshot::Receiver<()>,
 }
 
 impl Client {
     pub(crate) fn new(
         sender: mpsc::UnboundedSender<Request>,
-        ssl_mode: SslMode,
+        config: &Config,
         process_id: i32,
         secret_key: i32,
+        events: Arc<EventSender>,
+        pending_requests: Arc<AtomicUsize>,
+        closed: oneshot::Receiver<()>,
     ) -> Client {
         Client {
             inner: Arc::new(InnerClient {
                 sender,
                 cached_typeinfo: Default::default(),
                 buffer: Default::default(),
+                reprepared: Mutex::new(StatementCache::new(REPREPARED_CAPACITY)),
+                statement_cache: Mutex::new(StatementCache::new(config.statement_cache_capacity)),
+                stats: if config.statement_stats {
+                    Some(Default::default())
+                } else {
+                    None
+                },
+                events,
+                pending_requests,
+                response_buffer_size: config.response_buffer_size,
+                dry_run: Mutex::new(None),
+                #[cfg(feature = "sqlcommenter")]
+                sql_commenter: if config.sql_commenter {
+                    Some(SqlCommenter {
+                        application: config.application_name.clone(),
+                    })
+                } else {
+                    None
+                },
+                backend: Mutex::new(Backend {
+                    #[cfg(feature = "runtime")]
+                    socket_config: None,
+                    process_id,
+                    secret_key,
+                    tls_info: None,
+                }),
+                session: AtomicU64::new(0),
+                transaction_session: Mutex::new(None),
             }),
-            #[cfg(feature = "runtime")]
-            socket_config: None,
-            ssl_mode,
-            process_id,
-            secret_key,
+            ssl_mode: config.ssl_mode,
+            result_cache: ResultCache::new(),
+            closed,
         }
     }
 
@@ -211,15 +424,20 @@ impl Client {
         &self.inner
     }
 
+    pub(crate) fn set_tls_info(&mut self, tls_info: Option<TlsInfo>) {
+        self.inner.backend.lock().tls_info = tls_info;
+    }
+
     #[cfg(feature = "runtime")]
     pub(crate) fn set_socket_config(&mut self, socket_config: SocketConfig) {
-        self.socket_config = Some(socket_config);
+        self.inner.backend.lock().socket_config = Some(socket_config);
     }
 
     /// Creates a new prepared statement.
     ///
     /// Prepared statements can be executed repeatedly, and may contain query parameters (indicated by `$1`, `$2`, etc),
-    /// which are set when executed. Prepared statements can only be used with the connection that created them.
+    /// which are set when executed. Prepared statements live on the connection that created them, and are prepared again
+    /// when used with another connection.
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub async fn prepare(&self, query: &str) -> Result<Statement, Error> {
         prepare::prepare(&self.inner, query, &[]).await
@@ -238,6 +456,14 @@ impl Client {
         prepare::prepare(&self.inner, query, parameter_types).await
     }
 
+    #[doc(hidden)]
+    pub async fn __private_api_to_statement<T>(&self, statement: &T) -> Result<Statement, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        statement.__convert().into_statement(&self.inner).await
+    }
+
     /// Executes a statement, returning a vector of the resulting rows.
     ///
     /// A statement may contain parameters, specified by `$n`, where `n` is the index of the parameter of the list
@@ -302,6 +528,112 @@ impl Client {
         rows.into_iter().map(|r| r.try_get(0)).collect()
     }
 
+    /// Like [`Client::query`], but returns the results as described by `options`.
+    ///
+    /// This allows the values of types without a binary output function to be fetched in the text format, and a
+    /// timeout to be set for the query.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_with_options<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.with_timeout(options, async {
+            self.query_raw_with_formats(statement, slice_iter(params), options)
+                .await?
+                .try_collect()
+                .await
+        })
+        .await
+    }
+
+    /// Like [`Client::query_raw`], but returns the results as described by `options`.
+    ///
+    /// A timeout set in `options` applies until the stream is returned, not to reading the rows from it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_raw_with_options<T, P, I>(
+        &self,
+        statement: &T,
+        params: I,
+        options: &QueryOptions,
+    ) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.with_timeout(
+            options,
+            self.query_raw_with_formats(statement, params, options),
+        )
+        .await
+    }
+
+    async fn query_raw_with_formats<T, P, I>(
+        &self,
+        statement: &T,
+        params: I,
+        options: &QueryOptions,
+    ) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let statement = statement.__convert().into_statement(&self.inner).await?;
+        let formats = options.resolve(statement.columns().len())?;
+        query::query_with_formats(&self.inner, statement, params, formats).await
+    }
+
+    /// Like [`Client::execute`], but runs the statement as described by `options`.
+    ///
+    /// This allows a timeout to be set for the statement.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_with_options<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.with_timeout(options, self.execute(statement, params))
+            .await
+    }
+
+    /// Runs `f`, cancelling the query in progress on the connection if it exceeds the timeout of `options`.
+    #[cfg_attr(not(feature = "runtime"), allow(unused_variables))]
+    async fn with_timeout<F, R>(&self, options: &QueryOptions, f: F) -> Result<R, Error>
+    where
+        F: Future<Output = Result<R, Error>>,
+    {
+        pin_mut!(f);
+        #[cfg(feature = "runtime")]
+        if let Some(timeout) = options.get_timeout() {
+            return match tokio::time::timeout(timeout, &mut f).await {
+                Ok(result) => result,
+                Err(_) => {
+                    let mut token = self.cancel_token();
+                    token.ssl_mode = SslMode::Disable;
+                    // the server answers the cancelled query with an error, which has to be waited for so that the
+                    // cancellation can't hit a later query instead
+                    if token.cancel_query(crate::NoTls).await.is_ok() {
+                        let _ = f.await;
+                    }
+                    Err(Error::__private_api_timeout())
+                }
+            };
+        }
+        f.await
+    }
+
     /// Executes a statement which returns a single row, returning it.
     ///
     /// Returns an error if the query does not return exactly one row.
@@ -363,6 +695,64 @@ impl Client {
         FromRow::from_row(&row)
     }
 
+    /// Executes an `INSERT ... RETURNING` statement which inserts exactly one row, converting the returned row to `R`.
+    ///
+    /// Returns an error if the statement doesn't return exactly one row, which includes a statement without a
+    /// `RETURNING` clause. The statement has already run by then, so any rows it inserted stay inserted; run it in a
+    /// transaction which is rolled back on error where that matters.
+    ///
+    /// ```no_run
+    /// # use tokio_postgres::{Error, FromRow, Row};
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// impl FromRow for User {
+    ///     fn from_row(row: &Row) -> Result<User, Error> {
+    ///         Ok(User {
+    ///             id: row.try_get("id")?,
+    ///             name: row.try_get("name")?,
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// # async fn run(client: &tokio_postgres::Client) -> Result<(), Error> {
+    /// let user: User = client
+    ///     .insert_returning("INSERT INTO users (name) VALUES ($1) RETURNING id, name", &[&"Ferris"])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn insert_returning<R: FromRow, T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<R, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.query_one_as(statement, params).await
+    }
+
+    /// Executes an `UPDATE ... RETURNING` statement which updates exactly one row, converting the returned row to `R`.
+    ///
+    /// Returns an error if the statement doesn't return exactly one row, for example because no row matched its
+    /// `WHERE` clause. The statement has already run by then, so if it matched several rows, they all stay updated;
+    /// run it in a transaction which is rolled back on error where that matters.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn update_returning<R: FromRow, T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<R, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.query_one_as(statement, params).await
+    }
+
     /// Like [`Client::query_one_scalar`] but returns one scalar
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub async fn query_one_scalar<R: FromSqlOwned, T>(
@@ -499,6 +889,27 @@ impl Client {
         query::query(&self.inner, statement, params).await
     }
 
+    // Used by `postgres::RowIter` to fetch the rows in chunks of at most `fetch_size` rows. No other requests are sent
+    // over the connection until the stream has been exhausted or dropped, which the blocking client guarantees by
+    // borrowing itself mutably for the lifetime of the iterator.
+    #[doc(hidden)]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn __private_api_query_raw_chunked<T, P, I>(
+        &self,
+        statement: &T,
+        params: I,
+        fetch_size: i32,
+    ) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let statement = statement.__convert().into_statement(&self.inner).await?;
+        query::query_chunked(&self.inner, statement, params, fetch_size).await
+    }
+
     /// Returns a stream of rows
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub async fn stream<T>(
@@ -514,7 +925,30 @@ impl Client {
         Ok(stream)
     }
 
-    /// Returns a stream of `T`s
+    /// Like [`Client::query_as`], but returns a stream of `T`s rather than collecting them into a vector.
+    ///
+    /// Each row is converted as it is received, so large result sets can be processed without buffering them all in
+    /// memory.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn async_main(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// # #[derive(Debug)]
+    /// # struct User;
+    /// # impl tokio_postgres::FromRow for User {
+    /// #     fn from_row(_: &tokio_postgres::Row) -> Result<User, tokio_postgres::Error> { Ok(User) }
+    /// # }
+    /// use futures_util::TryStreamExt;
+    ///
+    /// let mut users = client.stream_as::<User, _>("SELECT id, name FROM users", &[]).await?;
+    /// while let Some(user) = users.try_next().await? {
+    ///     println!("{:?}", user);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "query_stream_as")]
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub async fn stream_as<R: FromRow, T>(
         &self,
@@ -531,6 +965,37 @@ impl Client {
             .boxed())
     }
 
+    /// Like [`Client::query_scalar`], but returns a stream of the values of the first column rather than collecting
+    /// them into a vector.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn async_main(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// use futures_util::TryStreamExt;
+    ///
+    /// let mut ids = client.stream_scalar::<i64, _>("SELECT id FROM events", &[]).await?;
+    /// while let Some(id) = ids.try_next().await? {
+    ///     println!("{}", id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "query_scalar_stream")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn stream_scalar<R: FromSqlOwned, T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<BoxStream<'static, Result<R, Error>>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        let statement = statement.__convert().into_statement(&self.inner).await?;
+        let stream = query::query(&self.inner, statement, slice_iter(params)).await?;
+        Ok(stream.map(|x| x.and_then(|x| x.try_get(0))).boxed())
+    }
+
     /// Executes a statement, returning the number of rows modified.
     ///
     /// A statement may contain parameters, specified by `$n`, where `n` is the index of the parameter of the list
@@ -576,6 +1041,122 @@ impl Client {
         query::execute(self.inner(), statement, params).await
     }
 
+    /// Like [`Client::query`], but takes the parameters as any [`IntoParams`], such as a tuple.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_with<T, P>(&self, statement: &T, params: P) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query(statement, &params).await
+    }
+
+    /// Like [`Client::query_one`], but takes the parameters as any [`IntoParams`], such as a tuple.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_one_with<T, P>(&self, statement: &T, params: P) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query_one(statement, &params).await
+    }
+
+    /// Like [`Client::query_opt`], but takes the parameters as any [`IntoParams`], such as a tuple.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_opt_with<T, P>(&self, statement: &T, params: P) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.query_opt(statement, &params).await
+    }
+
+    /// Like [`Client::execute`], but takes the parameters as any [`IntoParams`], such as a tuple.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_with<T, P>(&self, statement: &T, params: P) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        let params = params.to_params();
+        self.execute(statement, &params).await
+    }
+
+    /// Executes a statement once for each set of parameters, returning the number of rows modified by each execution.
+    ///
+    /// All executions are sent to the server at once and answered in a single round trip, which makes this much
+    /// faster than calling [`Client::execute`] in a loop when inserting or updating many rows. They run in a single
+    /// implicit transaction (or as part of the current one), so if one of them fails none of them take effect.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// let users = [("alice", 30), ("bob", 25)];
+    /// let rows = client
+    ///     .execute_many(
+    ///         "INSERT INTO users (name, age) VALUES ($1, $2)",
+    ///         users.iter().map(|(name, age)| (name, age)),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(rows, [1, 1]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_many<T, P, I>(&self, statement: &T, params: I) -> Result<Vec<u64>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        let statement = statement.__convert().into_statement(&self.inner).await?;
+        query::execute_many(self.inner(), statement, params).await
+    }
+
+    /// Like [`Client::query`], but with parameters referenced by name, as `:name`, and looked up in `params`.
+    ///
+    /// See [`NamedQuery`] for the syntax of the query.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// #[derive(ToParams)]
+    /// struct Filter {
+    ///     name: String,
+    ///     min_age: i32,
+    /// }
+    ///
+    /// let rows = client
+    ///     .query_named("SELECT * FROM users WHERE name = :name AND age >= :min_age", &filter)
+    ///     .await?;
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_named<P>(&self, query: &str, params: &P) -> Result<Vec<Row>, Error>
+    where
+        P: ?Sized + ToParams,
+    {
+        let query = NamedQuery::new(query);
+        let params = query.bind(params)?;
+        self.query(query.sql(), &params).await
+    }
+
+    /// Like [`Client::execute`], but with parameters referenced by name, as `:name`, and looked up in `params`.
+    ///
+    /// See [`NamedQuery`] for the syntax of the query.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_named<P>(&self, query: &str, params: &P) -> Result<u64, Error>
+    where
+        P: ?Sized + ToParams,
+    {
+        let query = NamedQuery::new(query);
+        let params = query.bind(params)?;
+        self.execute(query.sql(), &params).await
+    }
+
     /// Executes a `COPY FROM STDIN` statement, returning a sink used to write the copy data.
     ///
     /// PostgreSQL does not support parameters in `COPY` statements, so this method does not take any. The copy *must*
@@ -602,6 +1183,67 @@ impl Client {
         copy_out::copy_out(self.inner(), statement).await
     }
 
+    /// Inserts rows into the columns `columns` of the table `table` with a binary `COPY`, returning the number of rows
+    /// inserted.
+    ///
+    /// This is much faster than `INSERT` statements when loading many rows. The types of the columns are looked up
+    /// first, and each row is then encoded as it is taken from `rows`, so they don't all have to be held in memory. The
+    /// table and column names are quoted, so they are matched exactly, case included.
+    ///
+    /// If a row can't be encoded or doesn't have one value per column, the copy is aborted and no rows are inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// let rows = (0..100_000).map(|i| (i, format!("user {}", i)));
+    /// let inserted = client.copy_insert("users", &["id", "name"], rows).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(rows)))]
+    pub async fn copy_insert<P, I>(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: I,
+    ) -> Result<u64, Error>
+    where
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        binary_copy::copy_insert(self, table, columns, rows).await
+    }
+
+    /// Runs a query with a binary `COPY`, returning a stream of its rows converted to `R`.
+    ///
+    /// This is faster than [`Client::query_raw`] when exporting many rows. The query is described first, to look up
+    /// the names and types of its columns, and then wrapped in `COPY (...) TO STDOUT`. PostgreSQL does not support
+    /// parameters in `COPY` statements, so this method does not take any.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i32,
+    ///     name: String,
+    /// }
+    ///
+    /// let users = client.copy_out_as::<User>("SELECT id, name FROM users").await?;
+    /// pin_mut!(users);
+    /// while let Some(user) = users.try_next().await? {
+    ///     // ...
+    /// }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub async fn copy_out_as<R>(&self, query: &str) -> Result<BinaryCopyOutStreamAs<R>, Error>
+    where
+        R: FromRow,
+    {
+        binary_copy::copy_out_as(self, query).await
+    }
+
     /// Executes a sequence of SQL statements using the simple query protocol, returning the resulting rows.
     ///
     /// Statements should be separated by semicolons. If an error occurs, execution of the sequence will stop at that
@@ -670,6 +1312,7 @@ impl Client {
         // `batch_execute()`, but before `Responses` is asynchronously polled to
         // completion. In that case `Transaction` won't be created and thus
         // won't be rolled back.
+        let session = self.inner().session();
         {
             let mut cleaner = RollbackIfNotDone {
                 client: self,
@@ -679,7 +1322,52 @@ impl Client {
             cleaner.done = true;
         }
 
-        Ok(Transaction::new(self))
+        Ok(Transaction::new(self, session))
+    }
+
+    /// Runs `f` in a transaction which is always rolled back, returning its result and statistics about the statements
+    /// it ran.
+    ///
+    /// This can be used to preview the effect of a migration or a bulk update. Note that some effects can't be rolled
+    /// back, such as advancing sequences.
+    ///
+    /// ```no_run
+    /// # async fn run(client: &mut tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// let (_, stats) = client
+    ///     .dry_run(|tx| {
+    ///         Box::pin(async move {
+    ///             tx.execute("UPDATE users SET active = false WHERE last_login < now() - interval '1 year'", &[])
+    ///                 .await
+    ///         })
+    ///     })
+    ///     .await?;
+    /// println!("would deactivate {} users", stats.rows_affected());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn dry_run<F, T>(&mut self, f: F) -> Result<(T, DryRunStats), Error>
+    where
+        F: for<'t> FnOnce(&'t Transaction<'t>) -> BoxFuture<'t, Result<T, Error>>,
+    {
+        struct Reset<'a>(&'a InnerClient);
+
+        impl Drop for Reset<'_> {
+            fn drop(&mut self) {
+                *self.0.dry_run.lock() = None;
+            }
+        }
+
+        let inner = self.inner.clone();
+        let transaction = self.transaction().await?;
+
+        let counters = Arc::new(DryRunCounters::default());
+        *inner.dry_run.lock() = Some(counters.clone());
+        let reset = Reset(&inner);
+        let result = f(&transaction).await;
+        drop(reset);
+
+        transaction.rollback().await?;
+        Ok((result?, counters.stats()))
     }
 
     /// Returns a builder for a transaction with custom settings.
@@ -690,15 +1378,56 @@ impl Client {
         TransactionBuilder::new(self)
     }
 
+    /// Commits a transaction prepared for two-phase commit with
+    /// [`Transaction::prepare_transaction`](crate::Transaction::prepare_transaction).
+    ///
+    /// The transaction can have been prepared by any session in the current database, including one that has since
+    /// ended.
+    pub async fn commit_prepared(&self, gid: &str) -> Result<(), Error> {
+        let query = format!("COMMIT PREPARED {}", escape_literal(gid)?);
+        self.batch_execute(&query).await
+    }
+
+    /// Rolls back a transaction prepared for two-phase commit with
+    /// [`Transaction::prepare_transaction`](crate::Transaction::prepare_transaction).
+    pub async fn rollback_prepared(&self, gid: &str) -> Result<(), Error> {
+        let query = format!("ROLLBACK PREPARED {}", escape_literal(gid)?);
+        self.batch_execute(&query).await
+    }
+
+    /// Returns the transactions which are prepared for two-phase commit, and waiting to be committed or rolled back.
+    ///
+    /// This lists the transactions of every database, oldest first. After a crash of the coordinator of a distributed
+    /// transaction, it can be used to find the in-doubt transactions and resolve them with
+    /// [`Client::commit_prepared`] or [`Client::rollback_prepared`].
+    pub async fn prepared_transactions(&self) -> Result<Vec<PreparedTransaction>, Error> {
+        let rows = self
+            .query(
+                "SELECT gid, prepared, owner, database FROM pg_catalog.pg_prepared_xacts ORDER BY prepared",
+                &[],
+            )
+            .await?;
+        Ok(rows.iter().map(PreparedTransaction::from_row).collect())
+    }
+
+    /// Returns details of the TLS session of the connection, or `None` if it is not encrypted.
+    ///
+    /// How much is known about the session depends on the TLS implementation, see
+    /// [`TlsStream::tls_info`](crate::tls::TlsStream::tls_info).
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        self.inner.backend.lock().tls_info.clone()
+    }
+
     /// Constructs a cancellation token that can later be used to request cancellation of a query running on the
     /// connection associated with this client.
     pub fn cancel_token(&self) -> CancelToken {
+        let backend = self.inner.backend.lock();
         CancelToken {
             #[cfg(feature = "runtime")]
-            socket_config: self.socket_config.clone(),
+            socket_config: backend.socket_config.clone(),
             ssl_mode: self.ssl_mode,
-            process_id: self.process_id,
-            secret_key: self.secret_key,
+            process_id: backend.process_id,
+            secret_key: backend.secret_key,
         }
     }
 
@@ -737,6 +1466,255 @@ impl Client {
         self.inner().clear_type_cache();
     }
 
+    /// Closes the statements kept by the statement cache.
+    ///
+    /// See [`Config::statement_cache_capacity`](crate::Config::statement_cache_capacity).
+    pub fn clear_statement_cache(&self) {
+        self.inner().clear_statement_cache();
+    }
+
+    /// Returns the number of statements kept by the statement cache.
+    ///
+    /// See [`Config::statement_cache_capacity`](crate::Config::statement_cache_capacity).
+    pub fn statement_cache_len(&self) -> usize {
+        self.inner().statement_cache_len()
+    }
+
+    /// Returns a handle running queries through the client's result cache, whose results are kept for `ttl`.
+    ///
+    /// See the [`cache`](crate::cache) module for details.
+    pub fn cached(&self, ttl: Duration) -> Cached<'_> {
+        Cached::new(self, ttl)
+    }
+
+    /// Returns the cache used by [`Client::cached`].
+    pub fn result_cache(&self) -> &ResultCache {
+        &self.result_cache
+    }
+
+    /// Replaces the cache used by [`Client::cached`], for example to share one between several clients.
+    pub fn set_result_cache(&mut self, cache: ResultCache) {
+        self.result_cache = cache;
+    }
+
+    /// Returns execution statistics of the statements run by the client, grouped by their query text.
+    ///
+    /// Statistics are only collected if enabled with [`Config::statement_stats`](crate::Config::statement_stats),
+    /// otherwise this returns an empty list. Only statements executed with the extended query protocol (for example
+    /// `query`, `query_raw` and `execute`) are tracked; `simple_query`, `batch_execute`, portals and copies are not.
+    pub fn statement_stats(&self) -> Vec<StatementStats> {
+        match &self.inner.stats {
+            Some(stats) => stats.snapshot(),
+            None => vec![],
+        }
+    }
+
+    /// Discards the execution statistics collected so far.
+    pub fn reset_statement_stats(&self) {
+        if let Some(stats) = &self.inner.stats {
+            stats.reset();
+        }
+    }
+
+    /// Returns a stream of the events of the connection which are characteristic of a failover.
+    ///
+    /// Errors returned to the client are classified with [`ConnectionEvent::from_error`](crate::ConnectionEvent::from_error),
+    /// and the connection reports when the server was promoted or the connection terminated. Each call returns a new
+    /// stream receiving the events from then on. After a [`ConnectionEvent::Terminated`](crate::ConnectionEvent::Terminated)
+    /// event, the client should be replaced with a new connection: with multiple hosts and `target_session_attrs` set to
+    /// `read-write`, connecting again skips hosts which have been demoted.
+    pub fn connection_events(&self) -> ConnectionEvents {
+        self.inner.events.subscribe()
+    }
+
+    /// Returns a receiver which observes the value of a runtime parameter reported by the server.
+    ///
+    /// The server reports the values of parameters such as `server_version`, `TimeZone`, `is_superuser` and
+    /// `in_hot_standby` when connecting, and again whenever they change, for example because of a `SET` command. The
+    /// receiver sees the changes as they are processed by the connection. Its value is `None` if the server hasn't
+    /// reported the parameter.
+    pub fn parameter_watch(&self, name: &str) -> watch::Receiver<Option<String>> {
+        self.inner.events.watch_parameter(name)
+    }
+
+    /// Registers a callback called with each notice sent by the server, such as the output of `RAISE NOTICE`.
+    ///
+    /// The callback is called by the connection as it processes the notice, so it should not block. Notices are still
+    /// returned by [`Connection::poll_message`](crate::Connection::poll_message), or logged when the connection is
+    /// simply spawned. A new callback replaces the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn f(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// client.on_notice(|notice| println!("{}: {}", notice.severity(), notice.message()));
+    /// client.batch_execute("DO $$ BEGIN RAISE NOTICE 'hello'; END $$").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_notice<F>(&self, f: F)
+    where
+        F: Fn(DbError) + Send + Sync + 'static,
+    {
+        self.inner.events.set_notice_callback(Arc::new(f));
+    }
+
+    /// Registers a callback called with the name and new value of each runtime parameter the server reports a change
+    /// of, for example because of a `SET` command.
+    ///
+    /// The callback is called by the connection as it processes the change, so it should not block. The values
+    /// reported when connecting are not passed to the callback; use [`Client::parameter_watch`] to observe the current
+    /// value of a parameter. A new callback replaces the previous one.
+    pub fn on_parameter_change<F>(&self, f: F)
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.inner.events.set_parameter_callback(Arc::new(f));
+    }
+
+    /// Listens on a notification channel, returning a stream of its notifications.
+    ///
+    /// This executes `LISTEN` on the channel, after which the connection routes the notifications it receives on the
+    /// channel to the stream, without the [`Connection`](crate::Connection) having to be polled for messages. Several
+    /// streams can listen on the same channel, each receiving every notification. Once all of them have been dropped,
+    /// `UNLISTEN` is sent for the channel.
+    ///
+    /// Notifications are buffered until they are read from the stream. The channel name is quoted, so it is matched
+    /// exactly, case included. A connection resumed with [`Config::connect_resuming`](crate::Config::connect_resuming)
+    /// is no longer listening on the channel, although the stream doesn't end.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn f(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// let mut notifications = client.listen("jobs").await?;
+    /// while let Some(notification) = notifications.next().await {
+    ///     println!("new job: {}", notification.payload());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn listen(&self, channel: &str) -> Result<Notifications, Error> {
+        notifications::listen(&self.inner, channel).await
+    }
+
+    /// Like [`Client::listen`], but deserializes the payloads of the notifications from JSON.
+    ///
+    /// A payload which can't be deserialized into a `T` is returned as an error, after which the stream continues
+    /// with the next notification.
+    ///
+    /// Requires the `with-serde_json-1` Cargo feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// # use serde_1 as serde;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// # #[serde(crate = "serde_1")]
+    /// struct Job {
+    ///     id: i64,
+    ///     kind: String,
+    /// }
+    ///
+    /// # async fn f(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// let mut jobs = client.listen_as::<Job>("jobs").await?;
+    /// while let Some(job) = jobs.next().await {
+    ///     let job = job?;
+    ///     println!("job {}: {}", job.id, job.kind);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "with-serde_json-1")]
+    pub async fn listen_as<T>(&self, channel: &str) -> Result<NotificationsAs<T>, Error>
+    where
+        T: serde_1::de::DeserializeOwned,
+    {
+        self.listen(channel).await.map(NotificationsAs::new)
+    }
+
+    /// Returns the current write-ahead log insert location of the server.
+    ///
+    /// To read your own writes from a replica, capture the location on the primary after committing a write and pass
+    /// it to [`Client::wait_for_lsn`] on the replica before reading.
+    pub async fn current_wal_lsn(&self) -> Result<PgLsn, Error> {
+        self.query_one("SELECT pg_current_wal_insert_lsn()", &[])
+            .await?
+            .try_get(0)
+    }
+
+    /// Waits until the server has replayed the write-ahead log up to `lsn`.
+    ///
+    /// On a standby, `pg_last_wal_replay_lsn()` is polled until it reaches `lsn`, and an error is returned if that
+    /// doesn't happen within `timeout`. A primary has all of its own writes, so this returns immediately.
+    #[cfg(feature = "runtime")]
+    pub async fn wait_for_lsn(&self, lsn: PgLsn, timeout: Duration) -> Result<(), Error> {
+        let wait = async {
+            let stmt = self
+                .prepare_typed(
+                    "SELECT NOT pg_is_in_recovery() OR coalesce(pg_last_wal_replay_lsn() >= $1, false)",
+                    &[Type::PG_LSN],
+                )
+                .await?;
+
+            let mut delay = Duration::from_millis(1);
+            loop {
+                if self.query_one(&stmt, &[&lsn]).await?.try_get(0)? {
+                    return Ok(());
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(Duration::from_millis(50));
+            }
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| Error::__private_api_timeout())?
+    }
+
+    /// Returns the number of requests sent to the connection that it has not finished responding to.
+    ///
+    /// Requests are processed in order, so a growing number indicates that requests are queued behind slow ones, or
+    /// that the results of an earlier request are not being consumed. Pings sent by the connection while idle are not
+    /// counted.
+    pub fn pending_requests(&self) -> usize {
+        self.inner.pending_requests.load(Ordering::Relaxed)
+    }
+
+    /// Checks that the connection is still usable by sending a `Sync` message and waiting for the server to respond.
+    ///
+    /// Unlike [`Client::is_closed`], this detects connections whose socket is still open but which no longer lead to a
+    /// responsive server. The server answers without running a query, so the check is cheap, but it is queued behind
+    /// the requests already sent. An error is returned if the connection has failed, or if the server has not
+    /// responded within `timeout`.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    #[cfg(feature = "runtime")]
+    pub async fn is_valid(&self, timeout: Duration) -> Result<(), Error> {
+        let ping = async {
+            let buf = self.inner.with_buf(|buf| {
+                frontend::sync(buf);
+                buf.split().freeze()
+            });
+            let mut responses = self
+                .inner
+                .send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+            match responses.next().await? {
+                Message::ReadyForQuery(_) => Ok(()),
+                _ => Err(Error::unexpected_message()),
+            }
+        };
+
+        tokio::time::timeout(timeout, ping)
+            .await
+            .map_err(|_| Error::__private_api_timeout())?
+    }
+
     /// Determines if the connection to the server has already closed.
     ///
     /// In that case, all future queries will fail.
@@ -744,6 +1722,29 @@ impl Client {
         self.inner.sender.is_closed()
     }
 
+    /// Gracefully closes the connection.
+    ///
+    /// No new requests can be made once this is called. The connection finishes processing the requests already sent,
+    /// including the results of any streams still held, then sends a `Terminate` message to the server and closes the
+    /// socket. The returned future resolves once the connection has completed, even if the `Connection` is still
+    /// held, or once it has been dropped after failing. It only makes progress while the `Connection` is being polled.
+    ///
+    /// Errors of the connection are reported by the `Connection` itself, not by this method.
+    pub async fn close(mut self) {
+        self.inner.sender.close_channel();
+        let _ = (&mut self.closed).await;
+    }
+
+    /// Like [`Client::close`], but returns an error if the connection has not closed within `timeout`.
+    ///
+    /// The connection keeps draining the outstanding requests in the background after the timeout expires.
+    #[cfg(feature = "runtime")]
+    pub async fn close_timeout(self, timeout: Duration) -> Result<(), Error> {
+        tokio::time::timeout(timeout, self.close())
+            .await
+            .map_err(|_| Error::__private_api_timeout())
+    }
+
     #[doc(hidden)]
     pub fn __private_api_close(&mut self) {
         self.inner.sender.close_channel()
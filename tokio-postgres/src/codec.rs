@@ -1,5 +1,6 @@
 use bytes::{Buf, Bytes, BytesMut};
 use fallible_iterator::FallibleIterator;
+use postgres_protocol::connection::{decode_frame, Frame};
 use postgres_protocol::message::backend;
 use postgres_protocol::message::frontend::CopyData;
 use std::io;
@@ -55,44 +56,17 @@ impl Decoder for PostgresCodec {
     type Error = io::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BackendMessage>, io::Error> {
-        let mut idx = 0;
-        let mut request_complete = false;
-
-        while let Some(header) = backend::Header::parse(&src[idx..])? {
-            let len = header.len() as usize + 1;
-            if src[idx..].len() < len {
-                break;
-            }
-
-            match header.tag() {
-                backend::NOTICE_RESPONSE_TAG
-                | backend::NOTIFICATION_RESPONSE_TAG
-                | backend::PARAMETER_STATUS_TAG => {
-                    if idx == 0 {
-                        let message = backend::Message::parse(src)?.unwrap();
-                        return Ok(Some(BackendMessage::Async(message)));
-                    } else {
-                        break;
-                    }
-                }
-                _ => {}
-            }
-
-            idx += len;
-
-            if header.tag() == backend::READY_FOR_QUERY_TAG {
-                request_complete = true;
-                break;
-            }
-        }
-
-        if idx == 0 {
-            Ok(None)
-        } else {
-            Ok(Some(BackendMessage::Normal {
-                messages: BackendMessages(src.split_to(idx)),
+        let message = match decode_frame(src)? {
+            Some(Frame::Messages {
+                messages,
                 request_complete,
-            }))
-        }
+            }) => BackendMessage::Normal {
+                messages: BackendMessages(messages),
+                request_complete,
+            },
+            Some(Frame::Async(message)) => BackendMessage::Async(message),
+            None => return Ok(None),
+        };
+        Ok(Some(message))
     }
 }
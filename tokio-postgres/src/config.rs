@@ -2,26 +2,37 @@
 
 #[cfg(feature = "runtime")]
 use crate::connect::connect;
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+use crate::connect::connect_with;
 use crate::connect_raw::connect_raw;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::keepalive::KeepaliveConfig;
 #[cfg(feature = "runtime")]
+use crate::resolve::{Resolve, Resolver};
+use crate::service;
+#[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
 use crate::tls::TlsConnect;
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+use crate::uring::UringStream;
+#[cfg(feature = "runtime")]
+use crate::ResumingConnection;
 #[cfg(feature = "runtime")]
 use crate::Socket;
-use crate::{Client, Connection, Error};
+use crate::{Client, Connection, Error, OverflowPolicy};
 use std::borrow::Cow;
+use std::collections::HashSet;
 #[cfg(unix)]
 use std::ffi::OsStr;
 use std::net::IpAddr;
-use std::ops::Deref;
 #[cfg(unix)]
 use std::os::unix::ffi::OsStrExt;
-#[cfg(unix)]
+#[cfg(any(unix, feature = "runtime"))]
 use std::path::{Path, PathBuf};
 use std::str;
 use std::str::FromStr;
+#[cfg(feature = "runtime")]
+use std::sync::Arc;
 use std::time::Duration;
 use std::{error, fmt, iter, mem};
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -36,6 +47,12 @@ pub enum TargetSessionAttrs {
     ReadWrite,
     /// The session allow only reads.
     ReadOnly,
+    /// The server must not be in hot standby mode.
+    Primary,
+    /// The server must be in hot standby mode.
+    Standby,
+    /// A server in hot standby mode is preferred, but any server is used if none of the hosts is one.
+    PreferStandby,
 }
 
 /// TLS configuration.
@@ -119,6 +136,12 @@ pub enum Host {
 ///         * If both `host` and `hostaddr` are specified, the value for `hostaddr` gives the server network address.
 ///             The value for `host` is ignored unless the authentication method requires it,
 ///             in which case it will be used as the host name.
+///     Multiple hostaddrs can be specified, separated by commas, in which case each is paired with the host at the same
+///     position. An empty hostaddr causes the name of its host to be looked up, so that hosts with and without a
+///     known address can be mixed.
+/// * `passfile` - The path to the password file, which is read when no password is given. Defaults to the file named
+///     by the `PGPASSFILE` environment variable, or `~/.pgpass` (`%APPDATA%\postgresql\pgpass.conf` on Windows).
+///     See [`Config::passfile`] for details.
 /// * `port` - The port to connect to. Multiple ports can be specified, separated by commas. The number of ports must be
 ///     either 1, in which case it will be used for all hosts, or the same as the number of hosts. Defaults to 5432 if
 ///     omitted or the empty string.
@@ -136,11 +159,21 @@ pub enum Host {
 /// * `keepalives_retries` - The maximum number of TCP keepalive probes that will be sent before dropping a connection.
 ///     This option is ignored when connecting with Unix sockets.
 /// * `target_session_attrs` - Specifies requirements of the session. If set to `read-write`, the client will check that
-///     the `transaction_read_write` session parameter is set to `on`. This can be used to connect to the primary server
-///     in a database cluster as opposed to the secondary read-only mirrors. Defaults to `all`.
+///     the `transaction_read_only` session parameter is set to `off`, and if set to `read-only`, that it is set to
+///     `on`. If set to `primary` or `standby`, the client will check whether the server is in hot standby mode, and if
+///     set to `prefer-standby`, it connects to the first host in hot standby mode, falling back to any host if none
+///     is. This can be used to connect to the primary server in a database cluster as opposed to the secondary
+///     read-only mirrors, which also finds the current primary after a failover. Defaults to `any`.
 /// * `channel_binding` - Controls usage of channel binding in the authentication process. If set to `disable`, channel
 ///     binding will not be used. If set to `prefer`, channel binding will be used if available, but not used otherwise.
 ///     If set to `require`, the authentication process will fail if channel binding is not used. Defaults to `prefer`.
+/// * `service` - The name of a service, whose parameters are read from the service file. Parameters given in the
+///     connection string take precedence over those of the service. The service is looked up in the per-user service
+///     file, and then in the `pg_service.conf` file of the directory named by the `PGSYSCONFDIR` environment variable.
+///     A service file consists of sections headed by a `[name]` line, each of which holds `key=value` lines with the
+///     parameters of the service of that name.
+/// * `servicefile` - The path to the per-user service file. Defaults to the file named by the `PGSERVICEFILE`
+///     environment variable, or `~/.pg_service.conf` (`%APPDATA%\postgresql\.pg_service.conf` on Windows).
 /// * `load_balance_hosts` - Controls the order in which the client tries to connect to the available hosts and
 ///     addresses. Once a connection attempt is successful no other hosts and addresses will be tried. This parameter
 ///     is typically used in combination with multiple host names or a DNS record that returns multiple IPs. If set to
@@ -166,6 +199,10 @@ pub enum Host {
 /// host=host1,host2,host3 port=1234,,5678 user=postgres target_session_attrs=read-write
 /// ```
 ///
+/// ```not_rust
+/// service=reporting dbname=sales
+/// ```
+///
 /// # Url
 ///
 /// This format resembles a URL with a scheme of either `postgres://` or `postgresql://`. All components are optional,
@@ -197,9 +234,11 @@ pub struct Config {
     pub(crate) dbname: Option<String>,
     pub(crate) options: Option<String>,
     pub(crate) application_name: Option<String>,
+    pub(crate) runtime_params: Vec<(String, String)>,
     pub(crate) ssl_mode: SslMode,
     pub(crate) host: Vec<Host>,
-    pub(crate) hostaddr: Vec<IpAddr>,
+    pub(crate) hostaddr: Vec<Option<IpAddr>>,
+    hostaddr_ips: Vec<IpAddr>,
     pub(crate) port: Vec<u16>,
     pub(crate) connect_timeout: Option<Duration>,
     pub(crate) tcp_user_timeout: Option<Duration>,
@@ -209,6 +248,23 @@ pub struct Config {
     pub(crate) target_session_attrs: TargetSessionAttrs,
     pub(crate) channel_binding: ChannelBinding,
     pub(crate) load_balance_hosts: LoadBalanceHosts,
+    pub(crate) statement_stats: bool,
+    pub(crate) response_buffer_size: usize,
+    pub(crate) statement_cache_capacity: usize,
+    pub(crate) notification_buffer_size: Option<usize>,
+    pub(crate) notification_overflow: OverflowPolicy,
+    #[cfg(feature = "runtime")]
+    pub(crate) idle_ping_interval: Option<Duration>,
+    #[cfg(feature = "runtime")]
+    pub(crate) resolver: Option<Resolver>,
+    #[cfg(feature = "runtime")]
+    pub(crate) passfile: Option<PathBuf>,
+    #[cfg(feature = "sqlcommenter")]
+    pub(crate) sql_commenter: bool,
+    #[cfg(feature = "socks")]
+    pub(crate) proxy: Option<(String, u16)>,
+    #[cfg(feature = "socks")]
+    pub(crate) proxy_credentials: Option<(String, String)>,
 }
 
 impl Default for Config {
@@ -226,9 +282,11 @@ impl Config {
             dbname: None,
             options: None,
             application_name: None,
+            runtime_params: vec![],
             ssl_mode: SslMode::Prefer,
             host: vec![],
             hostaddr: vec![],
+            hostaddr_ips: vec![],
             port: vec![],
             connect_timeout: None,
             tcp_user_timeout: None,
@@ -242,6 +300,23 @@ impl Config {
             target_session_attrs: TargetSessionAttrs::Any,
             channel_binding: ChannelBinding::Prefer,
             load_balance_hosts: LoadBalanceHosts::Disable,
+            statement_stats: false,
+            response_buffer_size: 1,
+            statement_cache_capacity: 0,
+            notification_buffer_size: None,
+            notification_overflow: OverflowPolicy::DropOldest,
+            #[cfg(feature = "runtime")]
+            idle_ping_interval: None,
+            #[cfg(feature = "runtime")]
+            resolver: None,
+            #[cfg(feature = "runtime")]
+            passfile: None,
+            #[cfg(feature = "sqlcommenter")]
+            sql_commenter: false,
+            #[cfg(feature = "socks")]
+            proxy: None,
+            #[cfg(feature = "socks")]
+            proxy_credentials: None,
         }
     }
 
@@ -312,6 +387,36 @@ impl Config {
         self.application_name.as_deref()
     }
 
+    /// Sets the value of an additional runtime parameter, which is sent to the server when connecting.
+    ///
+    /// This sets parameters such as `search_path` or `default_transaction_read_only` for the whole session, without
+    /// having to quote them into `options`. Setting a parameter again replaces its value. The parameters which are set
+    /// with their own methods, such as `user`, `dbname`, `options` and `application_name`, are rejected when
+    /// connecting, as are `client_encoding` and `replication`. Unknown parameters are rejected by the server.
+    pub fn runtime_param(&mut self, name: &str, value: &str) -> &mut Config {
+        match self.runtime_params.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self
+                .runtime_params
+                .push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Gets the value of an additional runtime parameter, if it has been set with the `runtime_param` method.
+    pub fn get_runtime_param(&self, name: &str) -> Option<&str> {
+        self.runtime_params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| &**v)
+    }
+
+    /// Gets the names and values of the additional runtime parameters set with the `runtime_param` method, in the
+    /// order they were first set.
+    pub fn get_runtime_params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.runtime_params.iter().map(|(n, v)| (&**n, &**v))
+    }
+
     /// Sets the SSL configuration.
     ///
     /// Defaults to `prefer`.
@@ -348,8 +453,16 @@ impl Config {
     }
 
     /// Gets the hostaddrs that have been added to the configuration with `hostaddr`.
+    ///
+    /// The empty hostaddrs added with `hostaddr_lookup` are skipped, so the hostaddrs don't necessarily line up with
+    /// the hosts. Use `get_hostaddr_entries` to get both.
     pub fn get_hostaddrs(&self) -> &[IpAddr] {
-        self.hostaddr.deref()
+        &self.hostaddr_ips
+    }
+
+    /// Gets the hostaddrs that have been added to the configuration with `hostaddr` and `hostaddr_lookup`, in order.
+    pub fn get_hostaddr_entries(&self) -> &[Option<IpAddr>] {
+        &self.hostaddr
     }
 
     /// Adds a Unix socket host to the configuration.
@@ -369,7 +482,16 @@ impl Config {
     /// Multiple hostaddrs can be specified by calling this method multiple times, and each will be tried in order.
     /// There must be either no hostaddrs, or the same number of hostaddrs as hosts.
     pub fn hostaddr(&mut self, hostaddr: IpAddr) -> &mut Config {
-        self.hostaddr.push(hostaddr);
+        self.hostaddr.push(Some(hostaddr));
+        self.hostaddr_ips.push(hostaddr);
+        self
+    }
+
+    /// Adds an empty hostaddr to the configuration, so that the name of the host at the same position is looked up.
+    ///
+    /// This allows hosts whose address is known to be mixed with hosts which must be looked up.
+    pub fn hostaddr_lookup(&mut self) -> &mut Config {
+        self.hostaddr.push(None);
         self
     }
 
@@ -522,6 +644,220 @@ impl Config {
         self.load_balance_hosts
     }
 
+    /// Sets whether the client collects execution statistics of its statements.
+    ///
+    /// The statistics are available from [`Client::statement_stats`](crate::Client::statement_stats). This is a
+    /// client-side setting and cannot be set in a connection string. Timing relies on `std::time::Instant`, so it is
+    /// not supported on `wasm32-unknown-unknown`.
+    ///
+    /// Defaults to `false`.
+    pub fn statement_stats(&mut self, statement_stats: bool) -> &mut Config {
+        self.statement_stats = statement_stats;
+        self
+    }
+
+    /// Gets whether the client collects execution statistics of its statements.
+    pub fn get_statement_stats(&self) -> bool {
+        self.statement_stats
+    }
+
+    /// Sets the number of batches of response messages buffered for each request.
+    ///
+    /// The connection stops reading from the socket when the buffer of the request at the head of the queue is full,
+    /// until its results are consumed. A larger buffer lets the connection read ahead of a slow consumer, which can
+    /// improve throughput for large result sets at the cost of memory. This is a client-side setting and cannot be set
+    /// in a connection string.
+    ///
+    /// Defaults to 1.
+    pub fn response_buffer_size(&mut self, response_buffer_size: usize) -> &mut Config {
+        self.response_buffer_size = response_buffer_size;
+        self
+    }
+
+    /// Gets the number of batches of response messages buffered for each request.
+    pub fn get_response_buffer_size(&self) -> usize {
+        self.response_buffer_size
+    }
+
+    /// Sets the number of statements the client keeps prepared for queries passed as text.
+    ///
+    /// Methods taking a query string, or a [`TypedQuery`](crate::TypedQuery), normally prepare it again each time they
+    /// are called. With a capacity above 0, the client keeps the statements it prepares for them, keyed by their text
+    /// and parameter types, and reuses them for later calls, closing the least recently used statement once the
+    /// capacity is reached. The cache can be emptied with
+    /// [`Client::clear_statement_cache`](crate::Client::clear_statement_cache), for example after schema changes which
+    /// affect the result types of cached statements. This is a client-side setting and cannot be set in a connection
+    /// string.
+    ///
+    /// Defaults to 0, which disables the cache.
+    pub fn statement_cache_capacity(&mut self, statement_cache_capacity: usize) -> &mut Config {
+        self.statement_cache_capacity = statement_cache_capacity;
+        self
+    }
+
+    /// Gets the number of statements the client keeps prepared for queries passed as text.
+    pub fn get_statement_cache_capacity(&self) -> usize {
+        self.statement_cache_capacity
+    }
+
+    /// Sets the number of notifications buffered by each stream of notifications of the connection.
+    ///
+    /// This bounds the streams returned by [`Client::listen`](crate::Client::listen) and
+    /// [`Connection::split_messages`](crate::Connection::split_messages), whose notifications are otherwise buffered
+    /// without limit when they are not read as fast as they arrive. When a buffer is full, the
+    /// [`notification_overflow`](Config::notification_overflow) policy applies. This is a client-side setting and
+    /// cannot be set in a connection string.
+    ///
+    /// Defaults to no limit.
+    pub fn notification_buffer_size(&mut self, notification_buffer_size: usize) -> &mut Config {
+        self.notification_buffer_size = Some(notification_buffer_size);
+        self
+    }
+
+    /// Gets the number of notifications buffered by each stream of notifications of the connection, if limited.
+    pub fn get_notification_buffer_size(&self) -> Option<usize> {
+        self.notification_buffer_size
+    }
+
+    /// Sets what happens to a notification when the buffer of a stream of notifications is full.
+    ///
+    /// The number of notifications discarded by a stream is available from its `dropped` method. This is a
+    /// client-side setting and cannot be set in a connection string.
+    ///
+    /// Defaults to [`OverflowPolicy::DropOldest`].
+    pub fn notification_overflow(&mut self, notification_overflow: OverflowPolicy) -> &mut Config {
+        self.notification_overflow = notification_overflow;
+        self
+    }
+
+    /// Gets what happens to a notification when the buffer of a stream of notifications is full.
+    pub fn get_notification_overflow(&self) -> OverflowPolicy {
+        self.notification_overflow
+    }
+
+    /// Sets the time after which an idle connection sends a round trip to the server.
+    ///
+    /// The connection sends a `Sync` message, which the server answers without doing any work, so that NAT mappings,
+    /// firewalls and load balancers see traffic on connections that would otherwise sit idle. Unlike TCP keepalives,
+    /// this also works through proxies which terminate the TCP connection. This is a client-side setting and cannot
+    /// be set in a connection string.
+    ///
+    /// Requires the `runtime` Cargo feature. Defaults to no pings.
+    #[cfg(feature = "runtime")]
+    pub fn idle_ping_interval(&mut self, idle_ping_interval: Duration) -> &mut Config {
+        self.idle_ping_interval = Some(idle_ping_interval);
+        self
+    }
+
+    /// Gets the time after which an idle connection sends a round trip to the server, if one has been set with the
+    /// `idle_ping_interval` method.
+    #[cfg(feature = "runtime")]
+    pub fn get_idle_ping_interval(&self) -> Option<Duration> {
+        self.idle_ping_interval
+    }
+
+    /// Sets the path to the password file.
+    ///
+    /// When no password has been set, the password for each connection attempt is looked up in this file, as libpq
+    /// does. Each line of the file has the format `hostname:port:database:username:password`, where any of the first
+    /// four fields can be `*` to match anything, and `:` and `\` characters are escaped with a `\`. The password of
+    /// the first matching line is used. The host name is matched against the name of the host, or its hostaddr if it
+    /// has no name, and Unix socket hosts match `localhost`. On Unix, the file is ignored if it can be accessed by
+    /// the group or others.
+    ///
+    /// Requires the `runtime` Cargo feature. Defaults to the file named by the `PGPASSFILE` environment variable, or
+    /// `~/.pgpass` (`%APPDATA%\postgresql\pgpass.conf` on Windows).
+    #[cfg(feature = "runtime")]
+    pub fn passfile<T>(&mut self, passfile: T) -> &mut Config
+    where
+        T: AsRef<Path>,
+    {
+        self.passfile = Some(passfile.as_ref().to_path_buf());
+        self
+    }
+
+    /// Gets the path to the password file, if one has been set with the `passfile` method.
+    #[cfg(feature = "runtime")]
+    pub fn get_passfile(&self) -> Option<&Path> {
+        self.passfile.as_deref()
+    }
+
+    /// Sets the resolver used to look up the addresses of TCP hosts.
+    ///
+    /// The resolver is used instead of the system resolver for hosts without a `hostaddr`, unless connecting through a
+    /// proxy. This is a client-side setting and cannot be set in a connection string.
+    ///
+    /// Requires the `runtime` Cargo feature. Defaults to [`SystemResolver`](crate::resolve::SystemResolver).
+    #[cfg(feature = "runtime")]
+    pub fn resolver<R>(&mut self, resolver: R) -> &mut Config
+    where
+        R: Resolve + 'static,
+    {
+        self.resolver = Some(Resolver(Arc::new(resolver)));
+        self
+    }
+
+    /// Gets the resolver used to look up the addresses of TCP hosts, if one has been set with the `resolver` method.
+    #[cfg(feature = "runtime")]
+    pub fn get_resolver(&self) -> Option<&dyn Resolve> {
+        self.resolver.as_ref().map(|resolver| &*resolver.0)
+    }
+
+    /// Sets whether the client appends [sqlcommenter](crate::sqlcommenter) comments to the statements it prepares.
+    ///
+    /// The comments carry the tags of the current [`Context`](crate::sqlcommenter::Context), and the `application`
+    /// tag if `application_name` is set. This is a client-side setting and cannot be set in a connection string.
+    ///
+    /// Requires the `sqlcommenter` Cargo feature. Defaults to `false`.
+    #[cfg(feature = "sqlcommenter")]
+    pub fn sql_commenter(&mut self, sql_commenter: bool) -> &mut Config {
+        self.sql_commenter = sql_commenter;
+        self
+    }
+
+    /// Gets whether the client appends sqlcommenter comments to the statements it prepares.
+    #[cfg(feature = "sqlcommenter")]
+    pub fn get_sql_commenter(&self) -> bool {
+        self.sql_commenter
+    }
+
+    /// Sets a SOCKS5 proxy through which TCP connections to the hosts are tunneled.
+    ///
+    /// Host names are resolved by the proxy rather than locally, and hostaddrs are passed to it as they are. Unix
+    /// socket hosts are connected to directly. Query cancellation goes through the proxy as well. This is a
+    /// client-side setting and cannot be set in a connection string.
+    ///
+    /// Requires the `socks` Cargo feature. Defaults to no proxy.
+    #[cfg(feature = "socks")]
+    pub fn proxy(&mut self, host: &str, port: u16) -> &mut Config {
+        self.proxy = Some((host.to_string(), port));
+        self
+    }
+
+    /// Gets the host and port of the SOCKS5 proxy, if one has been set with the `proxy` method.
+    #[cfg(feature = "socks")]
+    pub fn get_proxy(&self) -> Option<(&str, u16)> {
+        self.proxy.as_ref().map(|(host, port)| (&**host, *port))
+    }
+
+    /// Sets the username and password to authenticate with the SOCKS5 proxy.
+    ///
+    /// Requires the `socks` Cargo feature. Defaults to no authentication.
+    #[cfg(feature = "socks")]
+    pub fn proxy_credentials(&mut self, username: &str, password: &str) -> &mut Config {
+        self.proxy_credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Gets the username used to authenticate with the SOCKS5 proxy, if one has been set with the
+    /// `proxy_credentials` method.
+    #[cfg(feature = "socks")]
+    pub fn get_proxy_username(&self) -> Option<&str> {
+        self.proxy_credentials
+            .as_ref()
+            .map(|(username, _)| &**username)
+    }
+
     fn param(&mut self, key: &str, value: &str) -> Result<(), Error> {
         match key {
             "user" => {
@@ -555,12 +891,20 @@ impl Config {
             }
             "hostaddr" => {
                 for hostaddr in value.split(',') {
+                    if hostaddr.is_empty() {
+                        self.hostaddr_lookup();
+                        continue;
+                    }
                     let addr = hostaddr
                         .parse()
                         .map_err(|_| Error::config_parse(Box::new(InvalidValue("hostaddr"))))?;
                     self.hostaddr(addr);
                 }
             }
+            #[cfg(feature = "runtime")]
+            "passfile" => {
+                self.passfile(value);
+            }
             "port" => {
                 for port in value.split(',') {
                     let port = if port.is_empty() {
@@ -625,6 +969,9 @@ impl Config {
                     "any" => TargetSessionAttrs::Any,
                     "read-write" => TargetSessionAttrs::ReadWrite,
                     "read-only" => TargetSessionAttrs::ReadOnly,
+                    "primary" => TargetSessionAttrs::Primary,
+                    "standby" => TargetSessionAttrs::Standby,
+                    "prefer-standby" => TargetSessionAttrs::PreferStandby,
                     _ => {
                         return Err(Error::config_parse(Box::new(InvalidValue(
                             "target_session_attrs",
@@ -668,6 +1015,37 @@ impl Config {
         Ok(())
     }
 
+    fn given_param(
+        &mut self,
+        given: &mut GivenParams,
+        key: &str,
+        value: &str,
+    ) -> Result<(), Error> {
+        match key {
+            "service" => given.service = Some(value.to_string()),
+            "servicefile" => given.servicefile = Some(value.to_string()),
+            key => self.param(key, value)?,
+        }
+        given.keys.insert(key.to_string());
+
+        Ok(())
+    }
+
+    fn apply_service(&mut self, given: GivenParams) -> Result<(), Error> {
+        let name = match &given.service {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        for (key, value) in service::parameters(name, given.servicefile.as_deref())? {
+            if !given.keys.contains(&key) {
+                self.param(&key, &value)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Opens a connection to a PostgreSQL database.
     ///
     /// Requires the `runtime` Cargo feature (enabled by default).
@@ -679,6 +1057,38 @@ impl Config {
         connect(tls, self).await
     }
 
+    /// Opens a connection to a PostgreSQL database whose socket is driven by io_uring.
+    ///
+    /// The connection has to be run on a tokio-uring runtime. See the [`uring`](crate::uring) module for details.
+    ///
+    /// Requires the `tokio-uring` Cargo feature, and is only available on Linux.
+    #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+    pub async fn connect_uring<T>(
+        &self,
+        tls: T,
+    ) -> Result<(Client, Connection<UringStream, T::Stream>), Error>
+    where
+        T: MakeTlsConnect<UringStream>,
+    {
+        connect_with(tls, self, Socket::into_uring).await
+    }
+
+    /// Opens a connection to a PostgreSQL database which is re-established whenever it is lost.
+    ///
+    /// See [`ResumingConnection`] for details.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    #[cfg(feature = "runtime")]
+    pub async fn connect_resuming<T>(
+        &self,
+        tls: T,
+    ) -> Result<(Client, ResumingConnection<T>), Error>
+    where
+        T: MakeTlsConnect<Socket> + Clone,
+    {
+        ResumingConnection::connect(self, tls).await
+    }
+
     /// Connects to a PostgreSQL database over an arbitrary stream.
     ///
     /// All of the settings other than `user`, `password`, `dbname`, `options`, and `application_name` name are ignored.
@@ -723,6 +1133,7 @@ impl fmt::Debug for Config {
             .field("dbname", &self.dbname)
             .field("options", &self.options)
             .field("application_name", &self.application_name)
+            .field("runtime_params", &self.runtime_params)
             .field("ssl_mode", &self.ssl_mode)
             .field("host", &self.host)
             .field("hostaddr", &self.hostaddr)
@@ -742,7 +1153,37 @@ impl fmt::Debug for Config {
         config_dbg
             .field("target_session_attrs", &self.target_session_attrs)
             .field("channel_binding", &self.channel_binding)
-            .finish()
+            .field("statement_stats", &self.statement_stats)
+            .field("response_buffer_size", &self.response_buffer_size)
+            .field("statement_cache_capacity", &self.statement_cache_capacity)
+            .field("notification_buffer_size", &self.notification_buffer_size)
+            .field("notification_overflow", &self.notification_overflow);
+
+        #[cfg(feature = "runtime")]
+        {
+            config_dbg = config_dbg
+                .field("idle_ping_interval", &self.idle_ping_interval)
+                .field("resolver", &self.resolver)
+                .field("passfile", &self.passfile);
+        }
+
+        #[cfg(feature = "sqlcommenter")]
+        {
+            config_dbg = config_dbg.field("sql_commenter", &self.sql_commenter);
+        }
+
+        #[cfg(feature = "socks")]
+        {
+            config_dbg = config_dbg.field("proxy", &self.proxy).field(
+                "proxy_credentials",
+                &self
+                    .proxy_credentials
+                    .as_ref()
+                    .map(|(username, _)| (username, Redaction {})),
+            );
+        }
+
+        config_dbg.finish()
     }
 }
 
@@ -768,6 +1209,14 @@ impl fmt::Display for InvalidValue {
 
 impl error::Error for InvalidValue {}
 
+// The parameters given in a connection string, which take precedence over those of its service.
+#[derive(Default)]
+struct GivenParams {
+    keys: HashSet<String>,
+    service: Option<String>,
+    servicefile: Option<String>,
+}
+
 struct Parser<'a> {
     s: &'a str,
     it: iter::Peekable<str::CharIndices<'a>>,
@@ -781,11 +1230,13 @@ impl<'a> Parser<'a> {
         };
 
         let mut config = Config::new();
+        let mut given = GivenParams::default();
 
         while let Some((key, value)) = parser.parameter()? {
-            config.param(key, &value)?;
+            config.given_param(&mut given, key, &value)?;
         }
 
+        config.apply_service(given)?;
         Ok(config)
     }
 
@@ -930,6 +1381,7 @@ impl<'a> Parser<'a> {
 struct UrlParser<'a> {
     s: &'a str,
     config: Config,
+    given: GivenParams,
 }
 
 impl<'a> UrlParser<'a> {
@@ -942,6 +1394,7 @@ impl<'a> UrlParser<'a> {
         let mut parser = UrlParser {
             s,
             config: Config::new(),
+            given: GivenParams::default(),
         };
 
         parser.parse_credentials()?;
@@ -949,6 +1402,7 @@ impl<'a> UrlParser<'a> {
         parser.parse_path()?;
         parser.parse_params()?;
 
+        parser.config.apply_service(parser.given)?;
         Ok(Some(parser.config))
     }
 
@@ -991,10 +1445,12 @@ impl<'a> UrlParser<'a> {
         let mut it = creds.splitn(2, ':');
         let user = self.decode(it.next().unwrap())?;
         self.config.user(&user);
+        self.given.keys.insert("user".to_string());
 
         if let Some(password) = it.next() {
             let password = Cow::from(percent_encoding::percent_decode(password.as_bytes()));
             self.config.password(password);
+            self.given.keys.insert("password".to_string());
         }
 
         Ok(())
@@ -1035,7 +1491,7 @@ impl<'a> UrlParser<'a> {
 
             self.host_param(host)?;
             let port = self.decode(port.unwrap_or("5432"))?;
-            self.config.param("port", &port)?;
+            self.config.given_param(&mut self.given, "port", &port)?;
         }
 
         Ok(())
@@ -1054,6 +1510,7 @@ impl<'a> UrlParser<'a> {
 
         if !dbname.is_empty() {
             self.config.dbname(&self.decode(dbname)?);
+            self.given.keys.insert("dbname".to_string());
         }
 
         Ok(())
@@ -1084,7 +1541,7 @@ impl<'a> UrlParser<'a> {
                 self.host_param(value)?;
             } else {
                 let value = self.decode(value)?;
-                self.config.param(&key, &value)?;
+                self.config.given_param(&mut self.given, &key, &value)?;
             }
         }
 
@@ -1093,6 +1550,7 @@ impl<'a> UrlParser<'a> {
 
     #[cfg(unix)]
     fn host_param(&mut self, s: &str) -> Result<(), Error> {
+        self.given.keys.insert("host".to_string());
         let decoded = Cow::from(percent_encoding::percent_decode(s.as_bytes()));
         if decoded.first() == Some(&b'/') {
             self.config.host_path(OsStr::from_bytes(&decoded));
@@ -1107,7 +1565,7 @@ impl<'a> UrlParser<'a> {
     #[cfg(not(unix))]
     fn host_param(&mut self, s: &str) -> Result<(), Error> {
         let s = self.decode(s)?;
-        self.config.param("host", &s)
+        self.config.given_param(&mut self.given, "host", &s)
     }
 
     fn decode(&self, s: &'a str) -> Result<Cow<'a, str>, Error> {
@@ -1148,6 +1606,27 @@ mod tests {
         assert_eq!(1, 1);
     }
 
+    #[test]
+    fn test_mixed_hostaddr_parsing() {
+        let s = "host=host1,host2,host3 hostaddr=127.0.0.1,,127.0.0.3";
+        let config = s.parse::<Config>().unwrap();
+        assert_eq!(
+            [
+                Some("127.0.0.1".parse::<IpAddr>().unwrap()),
+                None,
+                Some("127.0.0.3".parse::<IpAddr>().unwrap()),
+            ],
+            config.get_hostaddr_entries(),
+        );
+        assert_eq!(
+            [
+                "127.0.0.1".parse::<IpAddr>().unwrap(),
+                "127.0.0.3".parse::<IpAddr>().unwrap()
+            ],
+            config.get_hostaddrs(),
+        );
+    }
+
     #[test]
     fn test_invalid_hostaddr_parsing() {
         let s = "user=pass_user dbname=postgres host=host1 hostaddr=127.0.0 port=26257";
@@ -2,22 +2,43 @@ use crate::client::{Addr, SocketConfig};
 use crate::config::{Host, LoadBalanceHosts, TargetSessionAttrs};
 use crate::connect_raw::connect_raw;
 use crate::connect_socket::connect_socket;
+use crate::pgpass;
+use crate::resolve::{Resolve, SystemResolver};
+#[cfg(feature = "socks")]
+use crate::socks::Proxy;
 use crate::tls::MakeTlsConnect;
 use crate::{Client, Config, Connection, Error, SimpleQueryMessage, Socket};
 use futures_util::{future, pin_mut, Future, FutureExt, Stream};
 use rand::seq::SliceRandom;
+use std::borrow::Cow;
 use std::task::Poll;
 use std::{cmp, io};
-use tokio::net;
+use tokio::io::{AsyncRead, AsyncWrite};
 
 pub async fn connect<T>(
-    mut tls: T,
+    tls: T,
     config: &Config,
 ) -> Result<(Client, Connection<Socket, T::Stream>), Error>
 where
     T: MakeTlsConnect<Socket>,
 {
-    if config.host.is_empty() && config.hostaddr.is_empty() {
+    connect_with(tls, config, Ok).await
+}
+
+/// Like `connect`, but converts each socket with `wrap` before starting up the connection on it.
+pub(crate) async fn connect_with<S, T, F>(
+    mut tls: T,
+    config: &Config,
+    wrap: F,
+) -> Result<(Client, Connection<S, T::Stream>), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: MakeTlsConnect<S>,
+    F: Fn(Socket) -> io::Result<S>,
+{
+    if config.host.is_empty()
+        && (config.hostaddr.is_empty() || config.hostaddr.iter().any(Option::is_none))
+    {
         return Err(Error::config("both host and hostaddr are missing".into()));
     }
 
@@ -42,6 +63,44 @@ where
         return Err(Error::config("invalid number of ports".into()));
     }
 
+    // like libpq, a standby is looked for among all of the hosts before falling back to any of them
+    if config.target_session_attrs == TargetSessionAttrs::PreferStandby {
+        if let Ok(connected) = connect_hosts(
+            &mut tls,
+            config,
+            &wrap,
+            num_hosts,
+            TargetSessionAttrs::Standby,
+        )
+        .await
+        {
+            return Ok(connected);
+        }
+        return connect_hosts(&mut tls, config, &wrap, num_hosts, TargetSessionAttrs::Any).await;
+    }
+
+    connect_hosts(
+        &mut tls,
+        config,
+        &wrap,
+        num_hosts,
+        config.target_session_attrs,
+    )
+    .await
+}
+
+async fn connect_hosts<S, T, F>(
+    tls: &mut T,
+    config: &Config,
+    wrap: &F,
+    num_hosts: usize,
+    target_session_attrs: TargetSessionAttrs,
+) -> Result<(Client, Connection<S, T::Stream>), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: MakeTlsConnect<S>,
+    F: Fn(Socket) -> io::Result<S>,
+{
     let mut indices = (0..num_hosts).collect::<Vec<_>>();
     if config.load_balance_hosts == LoadBalanceHosts::Random {
         indices.shuffle(&mut rand::thread_rng());
@@ -50,7 +109,7 @@ where
     let mut error = None;
     for i in indices {
         let host = config.host.get(i);
-        let hostaddr = config.hostaddr.get(i);
+        let hostaddr = config.hostaddr.get(i).copied().flatten();
         let port = config
             .port
             .get(i)
@@ -67,14 +126,49 @@ where
             None => None,
         };
 
-        // Try to use the value of hostaddr to establish the TCP connection,
+        // Try to use the value of hostaddr to establish the TCP connection without a lookup,
         // fallback to host if hostaddr is not present.
-        let addr = match hostaddr {
-            Some(ipaddr) => Host::Tcp(ipaddr.to_string()),
-            None => host.cloned().unwrap(),
+        let result = match hostaddr {
+            #[cfg(feature = "socks")]
+            Some(ipaddr) if config.proxy.is_some() => {
+                connect_host(
+                    Host::Tcp(ipaddr.to_string()),
+                    hostname,
+                    port,
+                    tls,
+                    config,
+                    wrap,
+                    target_session_attrs,
+                )
+                .await
+            }
+            Some(ipaddr) => {
+                connect_once(
+                    Addr::Tcp(ipaddr),
+                    hostname.as_deref(),
+                    port,
+                    tls,
+                    config,
+                    wrap,
+                    target_session_attrs,
+                )
+                .await
+            }
+            None => {
+                connect_host(
+                    host.cloned().unwrap(),
+                    hostname,
+                    port,
+                    tls,
+                    config,
+                    wrap,
+                    target_session_attrs,
+                )
+                .await
+            }
         };
 
-        match connect_host(addr, hostname, port, &mut tls, config).await {
+        match result {
             Ok((client, connection)) => return Ok((client, connection)),
             Err(e) => error = Some(e),
         }
@@ -83,22 +177,49 @@ where
     Err(error.unwrap())
 }
 
-async fn connect_host<T>(
+async fn connect_host<S, T, F>(
     host: Host,
     hostname: Option<String>,
     port: u16,
     tls: &mut T,
     config: &Config,
-) -> Result<(Client, Connection<Socket, T::Stream>), Error>
+    wrap: &F,
+    target_session_attrs: TargetSessionAttrs,
+) -> Result<(Client, Connection<S, T::Stream>), Error>
 where
-    T: MakeTlsConnect<Socket>,
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: MakeTlsConnect<S>,
+    F: Fn(Socket) -> io::Result<S>,
 {
     match host {
+        #[cfg(feature = "socks")]
+        Host::Tcp(host) if config.proxy.is_some() => {
+            let (proxy_host, proxy_port) = config.proxy.clone().unwrap();
+            let proxy = Proxy {
+                host: proxy_host,
+                port: proxy_port,
+                credentials: config.proxy_credentials.clone(),
+            };
+            connect_once(
+                Addr::Proxy { proxy, host },
+                hostname.as_deref(),
+                port,
+                tls,
+                config,
+                wrap,
+                target_session_attrs,
+            )
+            .await
+        }
         Host::Tcp(host) => {
-            let mut addrs = net::lookup_host((&*host, port))
+            let resolver = match &config.resolver {
+                Some(resolver) => &*resolver.0,
+                None => &SystemResolver as &dyn Resolve,
+            };
+            let mut addrs = resolver
+                .resolve(&host, port)
                 .await
-                .map_err(Error::connect)?
-                .collect::<Vec<_>>();
+                .map_err(Error::connect)?;
 
             if config.load_balance_hosts == LoadBalanceHosts::Random {
                 addrs.shuffle(&mut rand::thread_rng());
@@ -106,8 +227,16 @@ where
 
             let mut last_err = None;
             for addr in addrs {
-                match connect_once(Addr::Tcp(addr.ip()), hostname.as_deref(), port, tls, config)
-                    .await
+                match connect_once(
+                    Addr::Tcp(addr.ip()),
+                    hostname.as_deref(),
+                    port,
+                    tls,
+                    config,
+                    wrap,
+                    target_session_attrs,
+                )
+                .await
                 {
                     Ok(stream) => return Ok(stream),
                     Err(e) => {
@@ -126,20 +255,33 @@ where
         }
         #[cfg(unix)]
         Host::Unix(path) => {
-            connect_once(Addr::Unix(path), hostname.as_deref(), port, tls, config).await
+            connect_once(
+                Addr::Unix(path),
+                hostname.as_deref(),
+                port,
+                tls,
+                config,
+                wrap,
+                target_session_attrs,
+            )
+            .await
         }
     }
 }
 
-async fn connect_once<T>(
+async fn connect_once<S, T, F>(
     addr: Addr,
     hostname: Option<&str>,
     port: u16,
     tls: &mut T,
     config: &Config,
-) -> Result<(Client, Connection<Socket, T::Stream>), Error>
+    wrap: &F,
+    target_session_attrs: TargetSessionAttrs,
+) -> Result<(Client, Connection<S, T::Stream>), Error>
 where
-    T: MakeTlsConnect<Socket>,
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: MakeTlsConnect<S>,
+    F: Fn(Socket) -> io::Result<S>,
 {
     let socket = connect_socket(
         &addr,
@@ -153,62 +295,24 @@ where
         },
     )
     .await?;
+    let socket = wrap(socket).map_err(Error::connect)?;
 
     let tls = tls
         .make_tls_connect(hostname.unwrap_or(""))
         .map_err(|e| Error::tls(e.into()))?;
     let has_hostname = hostname.is_some();
-    let (mut client, mut connection) = connect_raw(socket, tls, has_hostname, config).await?;
-
-    if config.target_session_attrs != TargetSessionAttrs::Any {
-        let rows = client.simple_query_raw("SHOW transaction_read_only");
-        pin_mut!(rows);
-
-        let rows = future::poll_fn(|cx| {
-            if connection.poll_unpin(cx)?.is_ready() {
-                return Poll::Ready(Err(Error::closed()));
-            }
 
-            rows.as_mut().poll(cx)
-        })
-        .await?;
-        pin_mut!(rows);
-
-        loop {
-            let next = future::poll_fn(|cx| {
-                if connection.poll_unpin(cx)?.is_ready() {
-                    return Poll::Ready(Some(Err(Error::closed())));
-                }
-
-                rows.as_mut().poll_next(cx)
-            });
-
-            match next.await.transpose()? {
-                Some(SimpleQueryMessage::Row(row)) => {
-                    let read_only_result = row.try_get(0)?;
-                    if read_only_result == Some("on")
-                        && config.target_session_attrs == TargetSessionAttrs::ReadWrite
-                    {
-                        return Err(Error::connect(io::Error::new(
-                            io::ErrorKind::PermissionDenied,
-                            "database does not allow writes",
-                        )));
-                    } else if read_only_result == Some("off")
-                        && config.target_session_attrs == TargetSessionAttrs::ReadOnly
-                    {
-                        return Err(Error::connect(io::Error::new(
-                            io::ErrorKind::PermissionDenied,
-                            "database is not read only",
-                        )));
-                    } else {
-                        break;
-                    }
-                }
-                Some(_) => {}
-                None => return Err(Error::unexpected_message()),
-            }
+    let raw_config = match passfile_password(&addr, hostname, port, config) {
+        Some(password) => {
+            let mut config = config.clone();
+            config.password = Some(password);
+            Cow::Owned(config)
         }
-    }
+        None => Cow::Borrowed(config),
+    };
+    let (mut client, mut connection) = connect_raw(socket, tls, has_hostname, &raw_config).await?;
+
+    check_session(&client, &mut connection, target_session_attrs).await?;
 
     client.set_socket_config(SocketConfig {
         addr,
@@ -225,3 +329,94 @@ where
 
     Ok((client, connection))
 }
+
+fn passfile_password(
+    addr: &Addr,
+    hostname: Option<&str>,
+    port: u16,
+    config: &Config,
+) -> Option<Vec<u8>> {
+    if config.password.is_some() {
+        return None;
+    }
+
+    let host = match (hostname, addr) {
+        (Some(hostname), _) => hostname.to_string(),
+        (None, Addr::Tcp(ip)) => ip.to_string(),
+        _ => "localhost".to_string(),
+    };
+    let user = config.user.clone().unwrap_or_else(whoami::username);
+
+    pgpass::password(config, &host, port, &user)
+}
+
+async fn check_session<S, T>(
+    client: &Client,
+    connection: &mut Connection<S, T>,
+    target_session_attrs: TargetSessionAttrs,
+) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let (query, expected, message) = match target_session_attrs {
+        TargetSessionAttrs::ReadWrite => (
+            "SHOW transaction_read_only",
+            "off",
+            "database does not allow writes",
+        ),
+        TargetSessionAttrs::ReadOnly => (
+            "SHOW transaction_read_only",
+            "on",
+            "database is not read only",
+        ),
+        TargetSessionAttrs::Primary => (
+            "SELECT pg_is_in_recovery()",
+            "f",
+            "database is in hot standby mode",
+        ),
+        TargetSessionAttrs::Standby => (
+            "SELECT pg_is_in_recovery()",
+            "t",
+            "database is not in hot standby mode",
+        ),
+        TargetSessionAttrs::Any | TargetSessionAttrs::PreferStandby => return Ok(()),
+    };
+
+    let rows = client.simple_query_raw(query);
+    pin_mut!(rows);
+
+    let rows = future::poll_fn(|cx| {
+        if connection.poll_unpin(cx)?.is_ready() {
+            return Poll::Ready(Err(Error::closed()));
+        }
+
+        rows.as_mut().poll(cx)
+    })
+    .await?;
+    pin_mut!(rows);
+
+    loop {
+        let next = future::poll_fn(|cx| {
+            if connection.poll_unpin(cx)?.is_ready() {
+                return Poll::Ready(Some(Err(Error::closed())));
+            }
+
+            rows.as_mut().poll_next(cx)
+        });
+
+        match next.await.transpose()? {
+            Some(SimpleQueryMessage::Row(row)) => {
+                if row.try_get(0)? == Some(expected) {
+                    return Ok(());
+                }
+                return Err(Error::connect(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    message,
+                )));
+            }
+            Some(_) => {}
+            None => return Err(Error::unexpected_message()),
+        }
+    }
+}
@@ -1,22 +1,23 @@
 use crate::codec::{BackendMessage, BackendMessages, FrontendMessage, PostgresCodec};
 use crate::config::{self, Config};
 use crate::connect_tls::connect_tls;
+use crate::connection_events::EventSender;
 use crate::maybe_tls_stream::MaybeTlsStream;
+use crate::notifications::Channels;
 use crate::tls::{TlsConnect, TlsStream};
 use crate::{Client, Connection, Error};
 use bytes::BytesMut;
 use fallible_iterator::FallibleIterator;
-use futures_channel::mpsc;
+use futures_channel::{mpsc, oneshot};
 use futures_util::{ready, Sink, SinkExt, Stream, TryStreamExt};
-use postgres_protocol::authentication;
-use postgres_protocol::authentication::sasl;
-use postgres_protocol::authentication::sasl::ScramSha256;
-use postgres_protocol::message::backend::{AuthenticationSaslBody, Message};
-use postgres_protocol::message::frontend;
+use postgres_protocol::connection::{Startup, StartupError, Step};
+use postgres_protocol::message::backend::Message;
 use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
 use std::io;
 use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::codec::Framed;
@@ -90,6 +91,7 @@ where
     T: TlsConnect<S>,
 {
     let stream = connect_tls(stream, config.ssl_mode, tls, has_hostname).await?;
+    let tls_info = stream.tls_info();
 
     let mut stream = StartupStream {
         inner: Framed::new(stream, PostgresCodec),
@@ -102,13 +104,39 @@ where
         .as_deref()
         .map_or_else(|| Cow::Owned(whoami::username()), Cow::Borrowed);
 
-    startup(&mut stream, config, &user).await?;
-    authenticate(&mut stream, config, &user).await?;
-    let (process_id, secret_key, parameters) = read_info(&mut stream).await?;
+    let (process_id, secret_key, parameters) = startup(&mut stream, config, &user).await?;
 
     let (sender, receiver) = mpsc::unbounded();
-    let client = Client::new(sender, config.ssl_mode, process_id, secret_key);
-    let connection = Connection::new(stream.inner, stream.delayed, parameters, receiver);
+    let events = Arc::new(EventSender::new(Channels::new(
+        config.notification_buffer_size,
+        config.notification_overflow,
+    )));
+    let pending_requests = Arc::new(AtomicUsize::new(0));
+    let (closed_sender, closed) = oneshot::channel();
+    let mut client = Client::new(
+        sender,
+        config,
+        process_id,
+        secret_key,
+        events.clone(),
+        pending_requests.clone(),
+        closed,
+    );
+    client.set_tls_info(tls_info);
+    #[allow(unused_mut)]
+    let mut connection = Connection::new(
+        stream.inner,
+        stream.delayed,
+        parameters,
+        receiver,
+        events,
+        pending_requests,
+        closed_sender,
+    );
+    #[cfg(feature = "runtime")]
+    if let Some(interval) = config.idle_ping_interval {
+        connection.set_idle_ping_interval(interval);
+    }
 
     Ok((client, connection))
 }
@@ -117,13 +145,12 @@ async fn startup<S, T>(
     stream: &mut StartupStream<S, T>,
     config: &Config,
     user: &str,
-) -> Result<(), Error>
+) -> Result<(i32, i32, HashMap<String, String>), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin,
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: TlsStream + Unpin,
 {
     let mut params = vec![("client_encoding", "UTF8")];
-    params.push(("user", user));
     if let Some(dbname) = &config.dbname {
         params.push(("database", &**dbname));
     }
@@ -133,223 +160,69 @@ where
     if let Some(application_name) = &config.application_name {
         params.push(("application_name", &**application_name));
     }
-
-    let mut buf = BytesMut::new();
-    frontend::startup_message(params, &mut buf).map_err(Error::encode)?;
-
-    stream
-        .send(FrontendMessage::Raw(buf.freeze()))
-        .await
-        .map_err(Error::io)
-}
-
-async fn authenticate<S, T>(
-    stream: &mut StartupStream<S, T>,
-    config: &Config,
-    user: &str,
-) -> Result<(), Error>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-    T: TlsStream + Unpin,
-{
-    match stream.try_next().await.map_err(Error::io)? {
-        Some(Message::AuthenticationOk) => {
-            can_skip_channel_binding(config)?;
-            return Ok(());
-        }
-        Some(Message::AuthenticationCleartextPassword) => {
-            can_skip_channel_binding(config)?;
-
-            let pass = config
-                .password
-                .as_ref()
-                .ok_or_else(|| Error::config("password missing".into()))?;
-
-            authenticate_password(stream, pass).await?;
-        }
-        Some(Message::AuthenticationMd5Password(body)) => {
-            can_skip_channel_binding(config)?;
-
-            let pass = config
-                .password
-                .as_ref()
-                .ok_or_else(|| Error::config("password missing".into()))?;
-
-            let output = authentication::md5_hash(user.as_bytes(), pass, body.salt());
-            authenticate_password(stream, output.as_bytes()).await?;
-        }
-        Some(Message::AuthenticationSasl(body)) => {
-            authenticate_sasl(stream, body, config).await?;
-        }
-        Some(Message::AuthenticationKerberosV5)
-        | Some(Message::AuthenticationScmCredential)
-        | Some(Message::AuthenticationGss)
-        | Some(Message::AuthenticationSspi) => {
-            return Err(Error::authentication(
-                "unsupported authentication method".into(),
-            ))
+    for (name, value) in &config.runtime_params {
+        match &**name {
+            "" | "user" | "database" | "options" | "application_name" | "client_encoding"
+            | "replication" => {
+                return Err(Error::config(
+                    format!("invalid runtime parameter `{}`", name).into(),
+                ));
+            }
+            _ => params.push((name, value)),
         }
-        Some(Message::ErrorResponse(body)) => return Err(Error::db(body)),
-        Some(_) => return Err(Error::unexpected_message()),
-        None => return Err(Error::closed()),
     }
 
-    match stream.try_next().await.map_err(Error::io)? {
-        Some(Message::AuthenticationOk) => Ok(()),
-        Some(Message::ErrorResponse(body)) => Err(Error::db(body)),
-        Some(_) => Err(Error::unexpected_message()),
-        None => Err(Error::closed()),
+    let mut startup = Startup::new(user)
+        .require_channel_binding(config.channel_binding == config::ChannelBinding::Require);
+    if let Some(password) = &config.password {
+        startup = startup.password(password);
     }
-}
-
-fn can_skip_channel_binding(config: &Config) -> Result<(), Error> {
-    match config.channel_binding {
-        config::ChannelBinding::Disable | config::ChannelBinding::Prefer => Ok(()),
-        config::ChannelBinding::Require => Err(Error::authentication(
-            "server did not use channel binding".into(),
-        )),
+    if config.channel_binding != config::ChannelBinding::Disable {
+        if let Some(data) = stream
+            .inner
+            .get_ref()
+            .channel_binding()
+            .tls_server_end_point
+        {
+            startup = startup.tls_server_end_point(data);
+        }
     }
-}
 
-async fn authenticate_password<S, T>(
-    stream: &mut StartupStream<S, T>,
-    password: &[u8],
-) -> Result<(), Error>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-    T: AsyncRead + AsyncWrite + Unpin,
-{
     let mut buf = BytesMut::new();
-    frontend::password_message(password, &mut buf).map_err(Error::encode)?;
-
-    stream
-        .send(FrontendMessage::Raw(buf.freeze()))
-        .await
-        .map_err(Error::io)
-}
-
-async fn authenticate_sasl<S, T>(
-    stream: &mut StartupStream<S, T>,
-    body: AuthenticationSaslBody,
-    config: &Config,
-) -> Result<(), Error>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-    T: TlsStream + Unpin,
-{
-    let password = config
-        .password
-        .as_ref()
-        .ok_or_else(|| Error::config("password missing".into()))?;
+    startup.start(params, &mut buf).map_err(startup_error)?;
 
-    let mut has_scram = false;
-    let mut has_scram_plus = false;
-    let mut mechanisms = body.mechanisms();
-    while let Some(mechanism) = mechanisms.next().map_err(Error::parse)? {
-        match mechanism {
-            sasl::SCRAM_SHA_256 => has_scram = true,
-            sasl::SCRAM_SHA_256_PLUS => has_scram_plus = true,
-            _ => {}
+    loop {
+        if !buf.is_empty() {
+            stream
+                .send(FrontendMessage::Raw(buf.split().freeze()))
+                .await
+                .map_err(Error::io)?;
         }
-    }
 
-    let channel_binding = stream
-        .inner
-        .get_ref()
-        .channel_binding()
-        .tls_server_end_point
-        .filter(|_| config.channel_binding != config::ChannelBinding::Disable)
-        .map(sasl::ChannelBinding::tls_server_end_point);
-
-    let (channel_binding, mechanism) = if has_scram_plus {
-        match channel_binding {
-            Some(channel_binding) => (channel_binding, sasl::SCRAM_SHA_256_PLUS),
-            None => (sasl::ChannelBinding::unsupported(), sasl::SCRAM_SHA_256),
-        }
-    } else if has_scram {
-        match channel_binding {
-            Some(_) => (sasl::ChannelBinding::unrequested(), sasl::SCRAM_SHA_256),
-            None => (sasl::ChannelBinding::unsupported(), sasl::SCRAM_SHA_256),
+        let message = match stream.try_next().await.map_err(Error::io)? {
+            Some(message) => message,
+            None => return Err(Error::closed()),
+        };
+        match startup.handle(message, &mut buf).map_err(startup_error)? {
+            Step::Continue => {}
+            Step::Notice(body) => stream
+                .delayed
+                .push_back(BackendMessage::Async(Message::NoticeResponse(body))),
+            Step::Ready => break,
         }
-    } else {
-        return Err(Error::authentication("unsupported SASL mechanism".into()));
-    };
-
-    if mechanism != sasl::SCRAM_SHA_256_PLUS {
-        can_skip_channel_binding(config)?;
     }
 
-    let mut scram = ScramSha256::new(password, channel_binding);
-
-    let mut buf = BytesMut::new();
-    frontend::sasl_initial_response(mechanism, scram.message(), &mut buf).map_err(Error::encode)?;
-    stream
-        .send(FrontendMessage::Raw(buf.freeze()))
-        .await
-        .map_err(Error::io)?;
-
-    let body = match stream.try_next().await.map_err(Error::io)? {
-        Some(Message::AuthenticationSaslContinue(body)) => body,
-        Some(Message::ErrorResponse(body)) => return Err(Error::db(body)),
-        Some(_) => return Err(Error::unexpected_message()),
-        None => return Err(Error::closed()),
-    };
-
-    scram
-        .update(body.data())
-        .map_err(|e| Error::authentication(e.into()))?;
-
-    let mut buf = BytesMut::new();
-    frontend::sasl_response(scram.message(), &mut buf).map_err(Error::encode)?;
-    stream
-        .send(FrontendMessage::Raw(buf.freeze()))
-        .await
-        .map_err(Error::io)?;
-
-    let body = match stream.try_next().await.map_err(Error::io)? {
-        Some(Message::AuthenticationSaslFinal(body)) => body,
-        Some(Message::ErrorResponse(body)) => return Err(Error::db(body)),
-        Some(_) => return Err(Error::unexpected_message()),
-        None => return Err(Error::closed()),
-    };
-
-    scram
-        .finish(body.data())
-        .map_err(|e| Error::authentication(e.into()))?;
-
-    Ok(())
+    let parameters = startup.parameters().iter().cloned().collect();
+    Ok((startup.process_id(), startup.secret_key(), parameters))
 }
 
-async fn read_info<S, T>(
-    stream: &mut StartupStream<S, T>,
-) -> Result<(i32, i32, HashMap<String, String>), Error>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-    T: AsyncRead + AsyncWrite + Unpin,
-{
-    let mut process_id = 0;
-    let mut secret_key = 0;
-    let mut parameters = HashMap::new();
-
-    loop {
-        match stream.try_next().await.map_err(Error::io)? {
-            Some(Message::BackendKeyData(body)) => {
-                process_id = body.process_id();
-                secret_key = body.secret_key();
-            }
-            Some(Message::ParameterStatus(body)) => {
-                parameters.insert(
-                    body.name().map_err(Error::parse)?.to_string(),
-                    body.value().map_err(Error::parse)?.to_string(),
-                );
-            }
-            Some(msg @ Message::NoticeResponse(_)) => {
-                stream.delayed.push_back(BackendMessage::Async(msg))
-            }
-            Some(Message::ReadyForQuery(_)) => return Ok((process_id, secret_key, parameters)),
-            Some(Message::ErrorResponse(body)) => return Err(Error::db(body)),
-            Some(_) => return Err(Error::unexpected_message()),
-            None => return Err(Error::closed()),
-        }
+fn startup_error(e: StartupError) -> Error {
+    match e {
+        StartupError::Db(body) => Error::db(body),
+        StartupError::PasswordMissing => Error::config("password missing".into()),
+        StartupError::Authentication(e) => Error::authentication(e),
+        StartupError::Parse(e) => Error::parse(e),
+        StartupError::Encode(e) => Error::encode(e),
+        _ => Error::unexpected_message(),
     }
 }
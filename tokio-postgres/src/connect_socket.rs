@@ -1,5 +1,7 @@
 use crate::client::Addr;
 use crate::keepalive::KeepaliveConfig;
+#[cfg(feature = "socks")]
+use crate::socks;
 use crate::{Error, Socket};
 use socket2::{SockRef, TcpKeepalive};
 use std::future::Future;
@@ -23,24 +25,13 @@ pub(crate) async fn connect_socket(
         Addr::Tcp(ip) => {
             let stream =
                 connect_with_timeout(TcpStream::connect((*ip, port)), connect_timeout).await?;
-
-            stream.set_nodelay(true).map_err(Error::connect)?;
-
-            let sock_ref = SockRef::from(&stream);
-            #[cfg(target_os = "linux")]
-            {
-                sock_ref
-                    .set_tcp_user_timeout(tcp_user_timeout)
-                    .map_err(Error::connect)?;
-            }
-
-            if let Some(keepalive_config) = keepalive_config {
-                sock_ref
-                    .set_tcp_keepalive(&TcpKeepalive::from(keepalive_config))
-                    .map_err(Error::connect)?;
-            }
-
-            Ok(Socket::new_tcp(stream))
+            configure_tcp(stream, tcp_user_timeout, keepalive_config)
+        }
+        #[cfg(feature = "socks")]
+        Addr::Proxy { proxy, host } => {
+            let stream =
+                connect_with_timeout(socks::connect(proxy, host, port), connect_timeout).await?;
+            configure_tcp(stream, tcp_user_timeout, keepalive_config)
         }
         #[cfg(unix)]
         Addr::Unix(dir) => {
@@ -51,6 +42,32 @@ pub(crate) async fn connect_socket(
     }
 }
 
+fn configure_tcp(
+    stream: TcpStream,
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))] tcp_user_timeout: Option<
+        Duration,
+    >,
+    keepalive_config: Option<&KeepaliveConfig>,
+) -> Result<Socket, Error> {
+    stream.set_nodelay(true).map_err(Error::connect)?;
+
+    let sock_ref = SockRef::from(&stream);
+    #[cfg(target_os = "linux")]
+    {
+        sock_ref
+            .set_tcp_user_timeout(tcp_user_timeout)
+            .map_err(Error::connect)?;
+    }
+
+    if let Some(keepalive_config) = keepalive_config {
+        sock_ref
+            .set_tcp_keepalive(&TcpKeepalive::from(keepalive_config))
+            .map_err(Error::connect)?;
+    }
+
+    Ok(Socket::new_tcp(stream))
+}
+
 async fn connect_with_timeout<F, T>(connect: F, timeout: Option<Duration>) -> Result<T, Error>
 where
     F: Future<Output = io::Result<T>>,
@@ -1,33 +1,47 @@
 use crate::codec::{BackendMessage, BackendMessages, FrontendMessage, PostgresCodec};
+use crate::connection_events::{ConnectionEvent, EventSender};
 use crate::copy_in::CopyInReceiver;
 use crate::error::DbError;
 use crate::maybe_tls_stream::MaybeTlsStream;
+use crate::notifications::Queue;
 use crate::{info, trace, AsyncMessage, Error, Notification};
 use bytes::BytesMut;
 use fallible_iterator::FallibleIterator;
-use futures_channel::mpsc;
-use futures_util::{ready, stream::FusedStream, Sink, Stream, StreamExt};
+use futures_channel::{mpsc, oneshot};
+use futures_util::stream::{self, FusedStream};
+use futures_util::{ready, Sink, Stream, StreamExt};
 use postgres_protocol::message::backend::Message;
 use postgres_protocol::message::frontend;
 use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+#[cfg(feature = "runtime")]
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "runtime")]
+use tokio::time::{self, Instant, Sleep};
 use tokio_util::codec::Framed;
 
 pub enum RequestMessages {
     Single(FrontendMessage),
     CopyIn(CopyInReceiver),
+    Fetch(mpsc::UnboundedReceiver<FrontendMessage>),
 }
 
 pub struct Request {
     pub messages: RequestMessages,
     pub sender: mpsc::Sender<BackendMessages>,
+    /// The session the request must be sent on, if it belongs to a transaction.
+    pub session: Option<u64>,
 }
 
 pub struct Response {
     sender: mpsc::Sender<BackendMessages>,
+    // idle pings are not requests of the client, so they don't count towards its pending requests
+    ping: bool,
 }
 
 #[derive(PartialEq, Debug)]
@@ -53,6 +67,15 @@ pub struct Connection<S, T> {
     pending_responses: VecDeque<BackendMessage>,
     responses: VecDeque<Response>,
     state: State,
+    events: Arc<EventSender>,
+    pending_requests: Arc<AtomicUsize>,
+    /// Incremented whenever the connection is resumed, in step with the session of the client.
+    session: u64,
+    #[cfg(feature = "runtime")]
+    idle_ping: Option<IdlePing>,
+    /// Dropped once the connection has completed, or along with the connection, which signals
+    /// [`Client::close`](crate::Client::close). A failed connection keeps it, as it may still be resumed.
+    closed: Option<oneshot::Sender<()>>,
 }
 
 impl<S, T> Connection<S, T>
@@ -65,7 +88,13 @@ where
         pending_responses: VecDeque<BackendMessage>,
         parameters: HashMap<String, String>,
         receiver: mpsc::UnboundedReceiver<Request>,
+        events: Arc<EventSender>,
+        pending_requests: Arc<AtomicUsize>,
+        closed: oneshot::Sender<()>,
     ) -> Connection<S, T> {
+        for (name, value) in &parameters {
+            events.set_parameter(name, value);
+        }
         Connection {
             stream,
             parameters,
@@ -74,9 +103,85 @@ where
             pending_responses,
             responses: VecDeque::new(),
             state: State::Active,
+            events,
+            pending_requests,
+            session: 0,
+            closed: Some(closed),
+            #[cfg(feature = "runtime")]
+            idle_ping: None,
+        }
+    }
+
+    /// Replaces the lost connection to the server with `new`, keeping the requests queued by the client.
+    ///
+    /// Requests which were already sent on the lost connection fail as their responses are dropped.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn resume(&mut self, new: Connection<S, T>) {
+        self.stream = new.stream;
+        for (name, value) in &new.parameters {
+            self.events.set_parameter(name, value);
+        }
+        self.parameters = new.parameters;
+        self.pending_responses = new.pending_responses;
+        self.pending_request = None;
+        let requests = self.responses.iter().filter(|r| !r.ping).count();
+        self.pending_requests.fetch_sub(requests, Ordering::Relaxed);
+        self.responses.clear();
+        self.session += 1;
+        self.state = State::Active;
+        if let Some(idle_ping) = &mut self.idle_ping {
+            idle_ping.busy = true;
         }
     }
 
+    #[cfg(feature = "runtime")]
+    pub(crate) fn set_idle_ping_interval(&mut self, interval: Duration) {
+        self.idle_ping = Some(IdlePing {
+            interval,
+            sleep: Box::pin(time::sleep(interval)),
+            busy: false,
+        });
+    }
+
+    /// Queues a `Sync` message if the connection has been idle for the configured interval.
+    #[cfg(feature = "runtime")]
+    fn poll_idle_ping(&mut self, cx: &mut Context<'_>) -> bool {
+        let idle_ping = match &mut self.idle_ping {
+            Some(idle_ping) => idle_ping,
+            None => return false,
+        };
+
+        if self.state != State::Active
+            || !self.responses.is_empty()
+            || self.pending_request.is_some()
+        {
+            idle_ping.busy = true;
+            return false;
+        }
+
+        if idle_ping.busy {
+            idle_ping.busy = false;
+            let deadline = Instant::now() + idle_ping.interval;
+            idle_ping.sleep.as_mut().reset(deadline);
+        }
+
+        if idle_ping.sleep.as_mut().poll(cx).is_pending() {
+            return false;
+        }
+
+        trace!("poll_idle_ping: sending ping");
+        idle_ping.busy = true;
+        // nobody is waiting for the response, which is discarded like that of a request whose receiver has hung up
+        let (sender, _) = mpsc::channel(1);
+        self.responses.push_back(Response { sender, ping: true });
+        let mut request = BytesMut::new();
+        frontend::sync(&mut request);
+        self.pending_request = Some(RequestMessages::Single(FrontendMessage::Raw(
+            request.freeze(),
+        )));
+        true
+    }
+
     fn poll_response(
         &mut self,
         cx: &mut Context<'_>,
@@ -110,6 +215,7 @@ where
             let (mut messages, request_complete) = match message {
                 BackendMessage::Async(Message::NoticeResponse(body)) => {
                     let error = DbError::parse(&mut body.fields()).map_err(Error::parse)?;
+                    self.events.notice(&error);
                     return Ok(Some(AsyncMessage::Notice(error)));
                 }
                 BackendMessage::Async(Message::NotificationResponse(body)) => {
@@ -118,13 +224,23 @@ where
                         channel: body.channel().map_err(Error::parse)?.to_string(),
                         payload: body.message().map_err(Error::parse)?.to_string(),
                     };
+                    if !self.events.channels.notify(&notification) {
+                        return Err(Error::__private_api_overflow());
+                    }
                     return Ok(Some(AsyncMessage::Notification(notification)));
                 }
                 BackendMessage::Async(Message::ParameterStatus(body)) => {
-                    self.parameters.insert(
-                        body.name().map_err(Error::parse)?.to_string(),
-                        body.value().map_err(Error::parse)?.to_string(),
-                    );
+                    let name = body.name().map_err(Error::parse)?;
+                    let value = body.value().map_err(Error::parse)?;
+                    if name == "in_hot_standby"
+                        && value == "off"
+                        && self.parameter(name) == Some("on")
+                    {
+                        self.events.send(ConnectionEvent::Promoted);
+                    }
+                    self.events.set_parameter(name, value);
+                    self.events.parameter_changed(name, value);
+                    self.parameters.insert(name.to_string(), value.to_string());
                     continue;
                 }
                 BackendMessage::Async(_) => unreachable!(),
@@ -145,15 +261,11 @@ where
             match response.sender.poll_ready(cx) {
                 Poll::Ready(Ok(())) => {
                     let _ = response.sender.start_send(messages);
-                    if !request_complete {
-                        self.responses.push_front(response);
-                    }
+                    self.complete_response(response, request_complete);
                 }
                 Poll::Ready(Err(_)) => {
                     // we need to keep paging through the rest of the messages even if the receiver's hung up
-                    if !request_complete {
-                        self.responses.push_front(response);
-                    }
+                    self.complete_response(response, request_complete);
                 }
                 Poll::Pending => {
                     self.responses.push_front(response);
@@ -168,6 +280,16 @@ where
         }
     }
 
+    fn complete_response(&mut self, response: Response, request_complete: bool) {
+        if request_complete {
+            if !response.ping {
+                self.pending_requests.fetch_sub(1, Ordering::Relaxed);
+            }
+        } else {
+            self.responses.push_front(response);
+        }
+    }
+
     fn poll_request(&mut self, cx: &mut Context<'_>) -> Poll<Option<RequestMessages>> {
         if let Some(messages) = self.pending_request.take() {
             trace!("retrying pending request");
@@ -178,16 +300,30 @@ where
             return Poll::Ready(None);
         }
 
-        match self.receiver.poll_next_unpin(cx) {
-            Poll::Ready(Some(request)) => {
-                trace!("polled new request");
-                self.responses.push_back(Response {
-                    sender: request.sender,
-                });
-                Poll::Ready(Some(request.messages))
+        loop {
+            match self.receiver.poll_next_unpin(cx) {
+                Poll::Ready(Some(request)) => {
+                    // the transaction of the request was lost along with its session, so rather than running
+                    // outside of it, the request fails as its response is dropped
+                    if request
+                        .session
+                        .is_some_and(|session| session != self.session)
+                    {
+                        trace!("dropping request of a previous session");
+                        self.pending_requests.fetch_sub(1, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    trace!("polled new request");
+                    self.responses.push_back(Response {
+                        sender: request.sender,
+                        ping: false,
+                    });
+                    return Poll::Ready(Some(request.messages));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
             }
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
         }
     }
 
@@ -257,6 +393,24 @@ where
                         .map_err(Error::io)?;
                     self.pending_request = Some(RequestMessages::CopyIn(receiver));
                 }
+                RequestMessages::Fetch(mut receiver) => {
+                    let message = match receiver.poll_next_unpin(cx) {
+                        Poll::Ready(Some(message)) => message,
+                        Poll::Ready(None) => {
+                            trace!("poll_write: finished fetch request");
+                            continue;
+                        }
+                        Poll::Pending => {
+                            trace!("poll_write: waiting on fetch stream");
+                            self.pending_request = Some(RequestMessages::Fetch(receiver));
+                            return Ok(true);
+                        }
+                    };
+                    Pin::new(&mut self.stream)
+                        .start_send(message)
+                        .map_err(Error::io)?;
+                    self.pending_request = Some(RequestMessages::Fetch(receiver));
+                }
             }
         }
     }
@@ -307,9 +461,64 @@ where
     pub fn poll_message(
         &mut self,
         cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<AsyncMessage, Error>>> {
+        let message = self.poll_message_inner(cx);
+        match message {
+            Poll::Ready(Some(Err(_))) => self.events.send(ConnectionEvent::Terminated),
+            Poll::Ready(None) => self.closed = None,
+            _ => {}
+        }
+        message
+    }
+
+    /// Returns a stream of asynchronous messages from the server, which also drives the connection.
+    ///
+    /// This is a convenience wrapper around [`poll_message`](Connection::poll_message). The stream ends when the
+    /// connection closes, and should not be polled again after it yields an error.
+    pub fn messages(&mut self) -> impl Stream<Item = Result<AsyncMessage, Error>> + '_ {
+        stream::poll_fn(move |cx| self.poll_message(cx))
+    }
+
+    /// Separates the asynchronous messages from the connection.
+    ///
+    /// The returned future drives the connection like the `Connection` itself, and can be spawned off onto an
+    /// executor, while the messages it receives are forwarded to the returned [`AsyncMessages`] stream. Messages are
+    /// discarded once the stream has been dropped.
+    ///
+    /// Notifications and notices are buffered up to the
+    /// [`notification_buffer_size`](crate::Config::notification_buffer_size) of the connection's configuration, after
+    /// which its [`notification_overflow`](crate::Config::notification_overflow) policy applies.
+    pub fn split_messages(mut self) -> (impl Future<Output = Result<(), Error>>, AsyncMessages) {
+        let queue = Arc::new(self.events.channels.queue());
+        let messages = AsyncMessages {
+            queue: queue.clone(),
+        };
+        let future = async move {
+            let _close = CloseOnDrop(queue.clone());
+            let mut messages = self.messages();
+            while let Some(message) = messages.next().await.transpose()? {
+                if queue.is_closed() {
+                    if let AsyncMessage::Notice(notice) = message {
+                        info!("{}: {}", notice.severity(), notice.message());
+                        let _ = notice;
+                    }
+                } else if !queue.push(message) {
+                    return Err(Error::__private_api_overflow());
+                }
+            }
+            Ok(())
+        };
+        (future, messages)
+    }
+
+    fn poll_message_inner(
+        &mut self,
+        cx: &mut Context<'_>,
     ) -> Poll<Option<Result<AsyncMessage, Error>>> {
         let message = self.poll_read(cx)?;
         let want_flush = self.poll_write(cx)?;
+        #[cfg(feature = "runtime")]
+        let want_flush = want_flush | (self.poll_idle_ping(cx) && self.poll_write(cx)?);
         if want_flush {
             self.poll_flush(cx)?;
         }
@@ -324,6 +533,52 @@ where
     }
 }
 
+#[cfg(feature = "runtime")]
+struct IdlePing {
+    interval: Duration,
+    sleep: Pin<Box<Sleep>>,
+    /// Set while the connection has outstanding requests, so that the timer is restarted once it becomes idle.
+    busy: bool,
+}
+
+/// A stream of asynchronous messages separated from a [`Connection`].
+///
+/// Returned by [`Connection::split_messages`]. The stream ends when the connection closes.
+#[must_use = "streams do nothing unless polled"]
+pub struct AsyncMessages {
+    queue: Arc<Queue<AsyncMessage>>,
+}
+
+impl AsyncMessages {
+    /// Returns the number of messages which were discarded because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+}
+
+impl Stream for AsyncMessages {
+    type Item = AsyncMessage;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<AsyncMessage>> {
+        self.queue.poll_next(cx)
+    }
+}
+
+impl Drop for AsyncMessages {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+/// Ends the stream of a queue when the future feeding it completes or is dropped.
+struct CloseOnDrop<T>(Arc<Queue<T>>);
+
+impl<T> Drop for CloseOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
 impl<S, T> Future for Connection<S, T>
 where
     S: AsyncRead + AsyncWrite + Unpin,
@@ -0,0 +1,197 @@
+use crate::error::{DbError, Severity, SqlState};
+use crate::notifications::Channels;
+use crate::Error;
+use futures_channel::mpsc;
+use futures_util::{Stream, StreamExt};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::watch;
+
+/// A change of the server's role or of the state of the connection, as typically caused by a failover.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConnectionEvent {
+    /// The server was promoted from a standby to a primary.
+    ///
+    /// This is detected from the `in_hot_standby` parameter, which is only reported by Postgres 14 and newer.
+    Promoted,
+    /// The server rejected a write because it is read-only, which usually means that it was demoted to a standby.
+    ///
+    /// Writes in a transaction explicitly started as `READ ONLY` are rejected in the same way.
+    Demoted,
+    /// The connection was terminated, for example because the server shut down or the network failed.
+    Terminated,
+    /// The connection was lost and has been replaced by a new one, with a new session.
+    ///
+    /// This is only sent for clients connected with [`Config::connect_resuming`](crate::Config::connect_resuming), which
+    /// do not send [`Terminated`](ConnectionEvent::Terminated) when the connection is lost. Session state such as
+    /// settings, temporary tables and `LISTEN` registrations has to be restored by the application.
+    Resumed,
+}
+
+impl ConnectionEvent {
+    /// Classifies an error returned by a query, returning the event it indicates, if any.
+    pub fn from_error(error: &Error) -> Option<ConnectionEvent> {
+        if error.is_closed() {
+            return Some(ConnectionEvent::Terminated);
+        }
+
+        let db_error = error.as_db_error()?;
+        match db_error.code() {
+            &SqlState::READ_ONLY_SQL_TRANSACTION => Some(ConnectionEvent::Demoted),
+            &SqlState::ADMIN_SHUTDOWN
+            | &SqlState::CRASH_SHUTDOWN
+            | &SqlState::CANNOT_CONNECT_NOW => Some(ConnectionEvent::Terminated),
+            _ => match db_error.parsed_severity() {
+                Some(Severity::Fatal | Severity::Panic) => Some(ConnectionEvent::Terminated),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A stream of [`ConnectionEvent`]s, returned by [`Client::connection_events`](crate::Client::connection_events).
+///
+/// The stream ends once the connection has terminated.
+#[must_use = "streams do nothing unless polled"]
+pub struct ConnectionEvents {
+    receiver: mpsc::UnboundedReceiver<ConnectionEvent>,
+}
+
+impl Stream for ConnectionEvents {
+    type Item = ConnectionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ConnectionEvent>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+type NoticeCallback = Arc<dyn Fn(DbError) + Send + Sync>;
+type ParameterCallback = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// The sending side of the event streams, shared by a client and its connection.
+#[derive(Default)]
+pub(crate) struct EventSender {
+    senders: Mutex<Vec<mpsc::UnboundedSender<ConnectionEvent>>>,
+    terminated: AtomicBool,
+    /// Set while a lost connection is going to be replaced, in which case `Terminated` events are not sent.
+    resuming: AtomicBool,
+    /// The current values of the runtime parameters reported by the server.
+    parameters: Mutex<HashMap<String, watch::Sender<Option<String>>>>,
+    /// The channels listened on by the client, and the size of the buffers of notifications.
+    pub(crate) channels: Channels,
+    /// The callback registered with [`Client::on_notice`](crate::Client::on_notice).
+    notice_callback: Mutex<Option<NoticeCallback>>,
+    /// The callback registered with [`Client::on_parameter_change`](crate::Client::on_parameter_change).
+    parameter_callback: Mutex<Option<ParameterCallback>>,
+}
+
+impl EventSender {
+    pub(crate) fn new(channels: Channels) -> EventSender {
+        EventSender {
+            channels,
+            ..EventSender::default()
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> ConnectionEvents {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut senders = self.senders.lock();
+        if self.terminated.load(Ordering::SeqCst) {
+            let _ = sender.unbounded_send(ConnectionEvent::Terminated);
+        } else {
+            senders.push(sender);
+        }
+        ConnectionEvents { receiver }
+    }
+
+    pub(crate) fn send(&self, event: ConnectionEvent) {
+        let mut senders = self.senders.lock();
+        if event == ConnectionEvent::Terminated {
+            if self.resuming.load(Ordering::SeqCst) {
+                return;
+            }
+            if self.terminated.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            for sender in senders.drain(..) {
+                let _ = sender.unbounded_send(event);
+            }
+            self.channels.close();
+        } else {
+            senders.retain(|sender| sender.unbounded_send(event).is_ok());
+        }
+    }
+
+    /// Sets whether lost connections are going to be replaced.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn set_resuming(&self, resuming: bool) {
+        self.resuming.store(resuming, Ordering::SeqCst);
+    }
+
+    /// Records the value of a runtime parameter reported by the server.
+    pub(crate) fn set_parameter(&self, name: &str, value: &str) {
+        let mut parameters = self.parameters.lock();
+        match parameters.get(name) {
+            Some(sender) => {
+                sender.send_if_modified(|v| {
+                    if v.as_deref() == Some(value) {
+                        false
+                    } else {
+                        *v = Some(value.to_string());
+                        true
+                    }
+                });
+            }
+            None => {
+                let (sender, _) = watch::channel(Some(value.to_string()));
+                parameters.insert(name.to_string(), sender);
+            }
+        }
+    }
+
+    pub(crate) fn set_notice_callback(&self, callback: NoticeCallback) {
+        *self.notice_callback.lock() = Some(callback);
+    }
+
+    pub(crate) fn set_parameter_callback(&self, callback: ParameterCallback) {
+        *self.parameter_callback.lock() = Some(callback);
+    }
+
+    /// Passes a notice to the notice callback, if any.
+    pub(crate) fn notice(&self, notice: &DbError) {
+        // the callback is called without holding the lock, so that it can replace itself
+        let callback = self.notice_callback.lock().clone();
+        if let Some(callback) = callback {
+            callback(notice.clone());
+        }
+    }
+
+    /// Passes a change of a runtime parameter to the parameter callback, if any.
+    pub(crate) fn parameter_changed(&self, name: &str, value: &str) {
+        let callback = self.parameter_callback.lock().clone();
+        if let Some(callback) = callback {
+            callback(name, value);
+        }
+    }
+
+    /// Returns a receiver of the values of a runtime parameter.
+    pub(crate) fn watch_parameter(&self, name: &str) -> watch::Receiver<Option<String>> {
+        self.parameters
+            .lock()
+            .entry(name.to_string())
+            .or_insert_with(|| watch::channel(None).0)
+            .subscribe()
+    }
+
+    /// Sends the event indicated by an error returned to the client, if any.
+    pub(crate) fn send_for_error(&self, error: &Error) {
+        if let Some(event) = ConnectionEvent::from_error(error) {
+            self.send(event);
+        }
+    }
+}
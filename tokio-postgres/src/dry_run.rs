@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Statistics about the statements run by [`Client::dry_run`](crate::Client::dry_run).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DryRunStats {
+    statements: u64,
+    rows_affected: u64,
+}
+
+impl DryRunStats {
+    /// Returns the number of statements which completed.
+    pub fn statements(&self) -> u64 {
+        self.statements
+    }
+
+    /// Returns the number of rows inserted, updated, deleted, merged or copied by the statements.
+    pub fn rows_affected(&self) -> u64 {
+        self.rows_affected
+    }
+}
+
+/// The counters of a dry run in progress, updated from the command tags of the completed statements.
+#[derive(Default)]
+pub(crate) struct DryRunCounters {
+    statements: AtomicU64,
+    rows_affected: AtomicU64,
+}
+
+impl DryRunCounters {
+    pub(crate) fn record(&self, tag: &str) {
+        self.statements.fetch_add(1, Ordering::Relaxed);
+
+        let mut words = tag.split(' ');
+        if let Some("INSERT" | "UPDATE" | "DELETE" | "MERGE" | "COPY") = words.next() {
+            let rows = words.next_back().and_then(|n| n.parse().ok()).unwrap_or(0);
+            self.rows_affected.fetch_add(rows, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> DryRunStats {
+        DryRunStats {
+            statements: self.statements.load(Ordering::Relaxed),
+            rows_affected: self.rows_affected.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::types::{ToSql, Type};
+use crate::{Client, Error, Row, Statement, Transaction};
+use async_trait::async_trait;
+
+/// An object-safe trait allowing abstraction over connections and transactions.
+///
+/// Unlike [`GenericClient`](crate::GenericClient), this trait can be used as a trait object, so code written against
+/// `&dyn DynClient` can be handed a [`Client`], a [`Transaction`], or a test double such as
+/// [`MockClient`](crate::test::MockClient). In exchange, queries are always passed as query strings, and statements
+/// returned by [`DynClient::prepare`] are only used to inspect their parameters and columns.
+///
+/// The trait is not sealed, so it can be implemented outside of this crate.
+#[async_trait]
+pub trait DynClient: Send + Sync {
+    /// Like `Client::execute`.
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error>;
+
+    /// Like `Client::query`.
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error>;
+
+    /// Like `Client::query_one`.
+    async fn query_one(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error> {
+        let mut rows = self.query(query, params).await?;
+        if rows.len() != 1 {
+            return Err(Error::row_count());
+        }
+        Ok(rows.remove(0))
+    }
+
+    /// Like `Client::query_opt`.
+    async fn query_opt(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        let mut rows = self.query(query, params).await?;
+        if rows.len() > 1 {
+            return Err(Error::row_count());
+        }
+        Ok(rows.pop())
+    }
+
+    /// Like `Client::prepare`.
+    async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        self.prepare_typed(query, &[]).await
+    }
+
+    /// Like `Client::prepare_typed`.
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error>;
+
+    /// Like `Client::batch_execute`.
+    async fn batch_execute(&self, query: &str) -> Result<(), Error>;
+
+    /// Like `Client::transaction`.
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error>;
+}
+
+/// An object-safe transaction, returned by [`DynClient::transaction`].
+#[async_trait]
+pub trait DynTransaction: DynClient {
+    /// Like `Transaction::commit`.
+    async fn commit(self: Box<Self>) -> Result<(), Error>;
+
+    /// Like `Transaction::rollback`.
+    async fn rollback(self: Box<Self>) -> Result<(), Error>;
+}
+
+#[async_trait]
+impl DynClient for Client {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        self.execute(query, params).await
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        self.query(query, params).await
+    }
+
+    async fn query_one(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error> {
+        self.query_one(query, params).await
+    }
+
+    async fn query_opt(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        self.query_opt(query, params).await
+    }
+
+    async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        self.prepare(query).await
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        self.prepare_typed(query, parameter_types).await
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        self.batch_execute(query).await
+    }
+
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error> {
+        Ok(Box::new(self.transaction().await?))
+    }
+}
+
+#[async_trait]
+impl DynClient for Transaction<'_> {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        self.execute(query, params).await
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        self.query(query, params).await
+    }
+
+    async fn query_one(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, Error> {
+        self.query_one(query, params).await
+    }
+
+    async fn query_opt(
+        &self,
+        query: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        self.query_opt(query, params).await
+    }
+
+    async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        self.prepare(query).await
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        self.prepare_typed(query, parameter_types).await
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        self.batch_execute(query).await
+    }
+
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error> {
+        Ok(Box::new(self.transaction().await?))
+    }
+}
+
+#[async_trait]
+impl DynTransaction for Transaction<'_> {
+    async fn commit(self: Box<Self>) -> Result<(), Error> {
+        (*self).commit().await
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), Error> {
+        (*self).rollback().await
+    }
+}
@@ -1,5 +1,7 @@
 //! Errors.
 
+use crate::row::RowIndex;
+use crate::Row;
 use fallible_iterator::FallibleIterator;
 use postgres_protocol::message::backend::{ErrorFields, ErrorResponseBody};
 use std::error::Error as StdError;
@@ -49,7 +51,7 @@ impl fmt::Display for Severity {
 }
 
 impl Severity {
-    fn from_str(s: &str) -> Option<Severity> {
+    pub(crate) fn from_str(s: &str) -> Option<Severity> {
         match s {
             "PANIC" => Some(Severity::Panic),
             "FATAL" => Some(Severity::Fatal),
@@ -322,6 +324,96 @@ impl fmt::Display for DbError {
 
 impl StdError for DbError {}
 
+/// A builder for [`DbError`]s which don't come from a database, such as canned errors in tests.
+///
+/// The built error can be turned into an [`Error`] with [`Error::from_db_error`].
+#[derive(Debug, Clone)]
+pub struct DbErrorBuilder(DbError);
+
+impl DbErrorBuilder {
+    /// Creates a new builder for an error with `ERROR` severity and the given code and message.
+    pub fn new(code: SqlState, message: &str) -> DbErrorBuilder {
+        DbErrorBuilder(DbError {
+            severity: Severity::Error.to_string().into_boxed_str(),
+            parsed_severity: Some(Severity::Error),
+            code,
+            message: message.into(),
+            detail: None,
+            hint: None,
+            position: None,
+            where_: None,
+            schema: None,
+            table: None,
+            column: None,
+            datatype: None,
+            constraint: None,
+            file: None,
+            line: None,
+            routine: None,
+        })
+    }
+
+    /// Sets the severity of the error.
+    pub fn severity(mut self, severity: Severity) -> DbErrorBuilder {
+        self.0.severity = severity.to_string().into_boxed_str();
+        self.0.parsed_severity = Some(severity);
+        self
+    }
+
+    /// Sets the detail message of the error.
+    pub fn detail(mut self, detail: &str) -> DbErrorBuilder {
+        self.0.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the hint of the error.
+    pub fn hint(mut self, hint: &str) -> DbErrorBuilder {
+        self.0.hint = Some(hint.into());
+        self
+    }
+
+    /// Sets the position of the error.
+    pub fn position(mut self, position: ErrorPosition) -> DbErrorBuilder {
+        self.0.position = Some(position);
+        self
+    }
+
+    /// Sets the name of the schema associated with the error.
+    pub fn schema(mut self, schema: &str) -> DbErrorBuilder {
+        self.0.schema = Some(schema.into());
+        self
+    }
+
+    /// Sets the name of the table associated with the error.
+    pub fn table(mut self, table: &str) -> DbErrorBuilder {
+        self.0.table = Some(table.into());
+        self
+    }
+
+    /// Sets the name of the column associated with the error.
+    pub fn column(mut self, column: &str) -> DbErrorBuilder {
+        self.0.column = Some(column.into());
+        self
+    }
+
+    /// Sets the name of the data type associated with the error.
+    pub fn datatype(mut self, datatype: &str) -> DbErrorBuilder {
+        self.0.datatype = Some(datatype.into());
+        self
+    }
+
+    /// Sets the name of the constraint associated with the error.
+    pub fn constraint(mut self, constraint: &str) -> DbErrorBuilder {
+        self.0.constraint = Some(constraint.into());
+        self
+    }
+
+    /// Builds the error.
+    pub fn build(self) -> DbError {
+        self.0
+    }
+}
+
 /// Represents the position of an error in a query.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum ErrorPosition {
@@ -338,6 +430,7 @@ pub enum ErrorPosition {
 
 /// An error communicating with the Postgres server.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Kind {
     /// An IO Error occurred.
     Io(io::Error),
@@ -346,6 +439,8 @@ pub enum Kind {
     /// An error occurred during the TLS handshake.
     Tls(Box<dyn StdError + Sync + Send>),
     /// An error occurred while converting Rust data to bytes to form a request.
+    ///
+    /// The index is zero-based, so it refers to the `$n` placeholder with `n = idx + 1`.
     ToSql(usize, Box<dyn StdError + Sync + Send>),
     /// An error occurred while converting bytes received from postgres to Rust data.
     FromSql(usize, Box<dyn StdError + Sync + Send>),
@@ -374,10 +469,47 @@ pub enum Kind {
     RowCount,
     /// A timeout while waiting for the server.
     Timeout,
+    /// The applied migrations don't match the embedded ones.
+    #[cfg(feature = "migrate")]
+    Migration(Box<dyn StdError + Sync + Send>),
+    /// A pagination cursor was invalid.
+    Cursor(Box<dyn StdError + Sync + Send>),
+    /// The operation was canceled by the client before it completed.
+    Canceled,
+    /// The payload of a notification could not be deserialized.
+    Payload(Box<dyn StdError + Sync + Send>),
+    /// A buffer of notifications overflowed with the [`OverflowPolicy::Error`](crate::OverflowPolicy::Error) policy.
+    Overflow,
+}
+
+/// The field of a [`FromRow`](crate::FromRow) type which could not be read, as returned by [`Error::field`].
+#[derive(Debug, Clone)]
+pub struct FieldContext {
+    type_name: &'static str,
+    field: &'static str,
+    column: String,
+}
+
+impl FieldContext {
+    /// Returns the name of the type the row was read into.
+    pub fn type_name(&self) -> &str {
+        self.type_name
+    }
+
+    /// Returns the name of the field, which is its index for tuple structs.
+    pub fn field(&self) -> &str {
+        self.field
+    }
+
+    /// Returns the name of the column the field was read from.
+    pub fn column(&self) -> &str {
+        &self.column
+    }
 }
 
 struct ErrorInner {
     kind: Kind,
+    field: Option<Box<FieldContext>>,
     #[cfg(feature = "tracing-error")]
     span_trace: Option<tracing_error::SpanTrace>,
 }
@@ -389,6 +521,9 @@ impl fmt::Debug for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut ds = fmt.debug_struct("Error");
         ds.field("kind", &self.0.kind);
+        if let Some(field) = &self.0.field {
+            ds.field("field", field);
+        }
 
         #[cfg(feature = "tracing-error")]
         ds.field("span_trace:", &self.0.span_trace);
@@ -399,11 +534,21 @@ impl fmt::Debug for Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(field) = &self.0.field {
+            write!(
+                f,
+                "error reading field `{}::{}` from column `{}`: ",
+                field.type_name, field.field, field.column
+            )?;
+        }
+
         match &self.0.kind {
             Kind::Io(err) => write!(f, "error communicating with the server: {err}")?,
             Kind::UnexpectedMessage => f.write_str("unexpected message from server")?,
             Kind::Tls(err) => write!(f, "error performing TLS handshake: {err}")?,
-            Kind::ToSql(idx, err) => write!(f, "error serializing parameter {idx}: {err}")?,
+            Kind::ToSql(idx, err) => {
+                write!(f, "error serializing parameter {idx} (${}): {err}", idx + 1)?
+            }
             Kind::FromSql(idx, err) => write!(f, "error deserializing column {idx}: {err}")?,
             Kind::Column(column) => write!(f, "invalid column `{column}`")?,
             Kind::Parameters(real, expected) => {
@@ -420,6 +565,12 @@ impl fmt::Display for Error {
             Kind::Connect(err) => write!(f, "error connecting to server: {err}")?,
             Kind::RowCount => f.write_str("query returned an unexpected number of rows")?,
             Kind::Timeout => f.write_str("timeout waiting for server")?,
+            #[cfg(feature = "migrate")]
+            Kind::Migration(err) => write!(f, "migration error: {err}")?,
+            Kind::Cursor(err) => write!(f, "invalid cursor: {err}")?,
+            Kind::Canceled => f.write_str("operation canceled")?,
+            Kind::Payload(err) => write!(f, "error deserializing notification payload: {err}")?,
+            Kind::Overflow => f.write_str("notification buffer overflowed")?,
         };
 
         #[cfg(feature = "tracing-error")]
@@ -463,6 +614,12 @@ impl StdError for Error {
             Kind::Connect(err) => Some(err as _),
             Kind::RowCount => None,
             Kind::Timeout => None,
+            #[cfg(feature = "migrate")]
+            Kind::Migration(err) => Some(&**err as _),
+            Kind::Cursor(err) => Some(&**err as _),
+            Kind::Canceled => None,
+            Kind::Payload(err) => Some(&**err as _),
+            Kind::Overflow => None,
         }
     }
 }
@@ -489,6 +646,12 @@ impl Error {
             Kind::Connect(err) => Some(Box::new(err)),
             Kind::RowCount => None,
             Kind::Timeout => None,
+            #[cfg(feature = "migrate")]
+            Kind::Migration(err) => Some(err),
+            Kind::Cursor(err) => Some(err),
+            Kind::Canceled => None,
+            Kind::Payload(err) => Some(err),
+            Kind::Overflow => None,
         }
     }
 
@@ -502,6 +665,17 @@ impl Error {
         }
     }
 
+    /// Creates an error wrapping a `DbError`, such as one built with [`DbErrorBuilder`].
+    pub fn from_db_error(e: DbError) -> Error {
+        Error::new(Kind::Db(Box::new(e)))
+    }
+
+    /// Returns the field which could not be read, if the error occurred while reading a row into a type deriving
+    /// [`FromRow`](crate::FromRow).
+    pub fn field(&self) -> Option<&FieldContext> {
+        self.0.field.as_deref()
+    }
+
     /// Determines if the error was associated with closed connection.
     pub fn is_closed(&self) -> bool {
         matches!(self.0.kind, Kind::Closed)
@@ -517,6 +691,7 @@ impl Error {
     fn new(kind: Kind) -> Self {
         Self(Box::new(ErrorInner {
             kind,
+            field: None,
             #[cfg(feature = "tracing-error")]
             span_trace: Some(tracing_error::SpanTrace::capture()),
         }))
@@ -614,8 +789,73 @@ impl Error {
         Error::new(Kind::Connect(e))
     }
 
+    #[cfg(feature = "migrate")]
+    pub(crate) fn migration(e: Box<dyn StdError + Sync + Send>) -> Error {
+        Error::new(Kind::Migration(e))
+    }
+
+    pub(crate) fn cursor(e: Box<dyn StdError + Sync + Send>) -> Error {
+        Error::new(Kind::Cursor(e))
+    }
+
+    pub(crate) fn canceled() -> Error {
+        Error::new(Kind::Canceled)
+    }
+
+    #[cfg(feature = "with-serde_json-1")]
+    pub(crate) fn payload(e: Box<dyn StdError + Sync + Send>) -> Error {
+        Error::new(Kind::Payload(e))
+    }
+
+    #[doc(hidden)]
+    pub fn __private_api_overflow() -> Error {
+        Error::new(Kind::Overflow)
+    }
+
     #[doc(hidden)]
     pub fn __private_api_timeout() -> Error {
         Error::new(Kind::Timeout)
     }
+
+    #[doc(hidden)]
+    pub fn __private_api_encode(e: io::Error) -> Error {
+        Error::encode(e)
+    }
+
+    #[doc(hidden)]
+    pub fn __private_api_from_sql<I>(row: &Row, idx: I, e: Box<dyn StdError + Sync + Send>) -> Error
+    where
+        I: RowIndex + fmt::Display,
+    {
+        match idx.__idx(row.columns()) {
+            Some(idx) => Error::from_sql(e, idx),
+            None => Error::column(idx.to_string()),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __private_api_field<I>(
+        mut self,
+        row: &Row,
+        idx: I,
+        type_name: &'static str,
+        field: &'static str,
+    ) -> Error
+    where
+        I: RowIndex + fmt::Display,
+    {
+        // errors of nested types keep the innermost field
+        if self.0.field.is_none() {
+            let column = match idx.__idx(row.columns()) {
+                Some(idx) => row.columns()[idx].name().to_string(),
+                None => idx.to_string(),
+            };
+            self.0.field = Some(Box::new(FieldContext {
+                type_name,
+                field,
+                column,
+            }));
+        }
+        self
+    }
 }
@@ -0,0 +1,47 @@
+//! Escaping of identifiers and literals.
+//!
+//! Some statements, such as most DDL, can't take parameters, so names and values have to be embedded in their text.
+//! These functions quote them the same way as libpq's `PQescapeIdentifier` and `PQescapeLiteral`. Use parameters
+//! wherever possible instead.
+//!
+//! ```no_run
+//! use tokio_postgres::escape::escape_identifier;
+//!
+//! # async fn run(client: &tokio_postgres::Client, tenant: &str) -> Result<(), tokio_postgres::Error> {
+//! let schema = escape_identifier(&format!("tenant_{}", tenant))?;
+//! client.batch_execute(&format!("CREATE SCHEMA {}", schema)).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::Error;
+use postgres_protocol::escape;
+use std::io;
+
+/// Quotes an identifier, such as the name of a table or schema, doubling any embedded double quotes.
+///
+/// Returns an error if the identifier contains a nul character, which Postgres does not allow in text.
+pub fn escape_identifier(identifier: &str) -> Result<String, Error> {
+    check(identifier)?;
+    Ok(escape::escape_identifier(identifier))
+}
+
+/// Quotes a string literal, doubling any embedded single quotes.
+///
+/// A literal containing backslashes is written in the `E'...'` syntax, so that it is read correctly whatever the
+/// setting of `standard_conforming_strings`.
+///
+/// Returns an error if the literal contains a nul character, which Postgres does not allow in text.
+pub fn escape_literal(literal: &str) -> Result<String, Error> {
+    check(literal)?;
+    Ok(escape::escape_literal(literal))
+}
+
+fn check(s: &str) -> Result<(), Error> {
+    if s.contains('\0') {
+        return Err(Error::encode(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "string contains a nul character",
+        )));
+    }
+    Ok(())
+}
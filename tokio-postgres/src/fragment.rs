@@ -0,0 +1,133 @@
+use crate::types::ToSql;
+use crate::IntoParams;
+use std::fmt::Write;
+
+/// A piece of SQL together with the parameters it binds.
+///
+/// Fragments are built from text and bound values, and can be combined with other fragments. The placeholders of the
+/// bound values are only numbered when the final query is built, so conditionally assembled queries can't get them
+/// wrong.
+///
+/// ```no_run
+/// use tokio_postgres::Fragment;
+///
+/// # async fn run(client: &tokio_postgres::Client, name: Option<&str>, min_age: Option<i32>) -> Result<(), tokio_postgres::Error> {
+/// let mut conditions = vec![];
+/// if let Some(name) = &name {
+///     conditions.push(Fragment::new("name = ").bind(name));
+/// }
+/// if let Some(min_age) = &min_age {
+///     conditions.push(Fragment::new("age >= ").bind(min_age));
+/// }
+///
+/// let mut query = Fragment::new("SELECT * FROM users");
+/// if !conditions.is_empty() {
+///     query = query.push(" WHERE ").append(Fragment::join(conditions, " AND "));
+/// }
+///
+/// let (sql, params) = query.build();
+/// let rows = client.query(&sql, &params).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Fragment<'a> {
+    pieces: Vec<Piece>,
+    params: Vec<&'a (dyn ToSql + Sync)>,
+}
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Sql(String),
+    Param,
+}
+
+impl<'a> Fragment<'a> {
+    /// Creates a fragment from SQL text.
+    ///
+    /// The text must not contain `$n` placeholders of its own.
+    pub fn new(sql: &str) -> Fragment<'a> {
+        Fragment::default().push(sql)
+    }
+
+    /// Appends SQL text.
+    pub fn push(mut self, sql: &str) -> Fragment<'a> {
+        match self.pieces.last_mut() {
+            Some(Piece::Sql(s)) => s.push_str(sql),
+            _ if sql.is_empty() => {}
+            _ => self.pieces.push(Piece::Sql(sql.to_string())),
+        }
+        self
+    }
+
+    /// Appends a placeholder bound to a value.
+    pub fn bind(mut self, value: &'a (dyn ToSql + Sync)) -> Fragment<'a> {
+        self.pieces.push(Piece::Param);
+        self.params.push(value);
+        self
+    }
+
+    /// Appends another fragment, along with its parameters.
+    pub fn append(mut self, other: Fragment<'a>) -> Fragment<'a> {
+        for piece in other.pieces {
+            match piece {
+                Piece::Sql(sql) => self = self.push(&sql),
+                Piece::Param => self.pieces.push(Piece::Param),
+            }
+        }
+        self.params.extend(other.params);
+        self
+    }
+
+    /// Combines fragments, separating them with `separator`.
+    pub fn join<I>(fragments: I, separator: &str) -> Fragment<'a>
+    where
+        I: IntoIterator<Item = Fragment<'a>>,
+    {
+        let mut joined = Fragment::default();
+        for (i, fragment) in fragments.into_iter().enumerate() {
+            if i > 0 {
+                joined = joined.push(separator);
+            }
+            joined = joined.append(fragment);
+        }
+        joined
+    }
+
+    /// Determines if the fragment contains neither text nor parameters.
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// Returns the parameters bound by the fragment, in order.
+    pub fn params(&self) -> &[&'a (dyn ToSql + Sync)] {
+        &self.params
+    }
+
+    /// Returns the SQL text of the fragment, with its placeholders numbered from `$1`.
+    pub fn sql(&self) -> String {
+        let mut sql = String::new();
+        let mut param = 0;
+        for piece in &self.pieces {
+            match piece {
+                Piece::Sql(s) => sql.push_str(s),
+                Piece::Param => {
+                    param += 1;
+                    let _ = write!(sql, "${}", param);
+                }
+            }
+        }
+        sql
+    }
+
+    /// Returns the SQL text and the parameters of the fragment.
+    pub fn build(&self) -> (String, Vec<&'a (dyn ToSql + Sync)>) {
+        (self.sql(), self.params.clone())
+    }
+}
+
+impl IntoParams for Fragment<'_> {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.params.clone()
+    }
+}
@@ -6,9 +6,149 @@ use crate::{Error, Row};
 pub use tokio_postgres_derive::FromRow;
 
 /// A trait for types that can be created from a Postgres row.
+///
+/// With the `derive` feature, it can be derived for structs with named fields. Each field is read from the column with
+/// the same name, which can be changed with the following attributes:
+///
+/// * `#[from_row(rename = "...")]` reads the field from the column with the given name.
+/// * `#[from_row(from = "T")]` reads a `T` from the column and converts it to the field's type with `From`.
+/// * `#[from_row(try_from = "T")]` reads a `T` from the column and converts it to the field's type with `TryFrom`.
+///   A failed conversion is reported like an error decoding the column.
+/// * `#[from_row(with = "path")]` reads the field by calling the function at `path` with the row and the name of the
+///   column, as in `fn(&Row, &str) -> Result<T, Error>`, for full control over the decoding. When fields are mapped
+///   by index, the function is called with the index of the column instead.
+/// * `#[from_row(json)]` deserializes the field from a `json` or `jsonb` column with serde, without wrapping its type
+///   in [`Json`](crate::types::Json). A `NULL` column is deserialized like a JSON `null`, so an `Option` field reads
+///   it as `None`. Requires the `with-serde_json-1` Cargo feature.
+/// * `#[from_row(flatten)]` reads the field with its own `FromRow` implementation, from the same row.
+/// * `#[from_row(skip)]` doesn't read the field at all, and initializes it with `Default::default()`.
+/// * `#[from_row(flatten, prefix = "...")]` reads a flattened field from the columns whose names start with the given
+///   prefix, which is stripped before matching them to the fields of its type. This allows one struct per table to be
+///   read from the result of a join, with the columns of each table aliased with a prefix.
+/// * `#[from_row(bound = "...")]` replaces the trait bounds generated for the field's type with the given where
+///   clause predicates, such as `"T: for<'a> FromSql<'a>"`. An empty string removes them.
+/// * `#[from_row(default)]` initializes the field with `Default::default()` if the row has no column for it, so
+///   that one struct can be read from queries selecting different columns. With `#[from_row(default = "path")]`,
+///   the function at `path` is called instead.
+///
+/// The names of all fields can be converted to column names with a naming convention by putting
+/// `#[from_row(rename_all = "...")]` on the struct, where the convention is one of `"lowercase"`, `"UPPERCASE"`,
+/// `"PascalCase"`, `"camelCase"`, `"snake_case"`, `"SCREAMING_SNAKE_CASE"`, `"kebab-case"`, `"SCREAMING-KEBAB-CASE"`
+/// or `"Train-Case"`. Fields with a `rename` attribute keep their explicit name. Similarly, `#[from_row(prefix = "...")]`
+/// on the struct prepends a prefix to the names of all columns, including those of flattened fields.
+///
+/// For generic structs, the derive bounds the types of the fields by the traits needed to read them. When these
+/// guesses are wrong, `#[from_row(bound = "...")]` on the struct replaces all of them with the given where clause
+/// predicates, like serde's attribute of the same name.
+///
+/// ```ignore
+/// use tokio_postgres::FromRow;
+///
+/// #[derive(FromRow)]
+/// struct User {
+///     id: i32,
+///     #[from_row(rename = "createdAt")]
+///     created_at: String,
+///     #[from_row(rename = "userAge", from = "i32")]
+///     age: Age,
+///     #[from_row(skip)]
+///     sessions: Vec<Session>,
+/// }
+/// ```
+///
+/// A flattened field of type `Option<T>` is `None` when all the columns of `T` are `NULL`, which is how a `LEFT JOIN`
+/// without a matching row shows up:
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// struct Order {
+///     id: i32,
+///     #[from_row(flatten, prefix = "customer_")]
+///     customer: Option<Customer>,
+/// }
+/// ```
+///
+/// Errors reading a field report the name of the struct, the field and its column in their message, and through
+/// [`Error::field`](crate::Error::field).
+///
+/// For tuple structs, the fields are read from the columns in order, by index rather than by name. Skipped fields don't
+/// take up a column, and `rename`, `rename_all`, `prefix` and `flatten` are not supported. Structs with named fields
+/// are mapped the same way with `#[from_row(by_index)]`, which helps with computed columns that have no useful name.
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// struct Count(i64);
+///
+/// let count = client.query_one_as::<Count, _>("SELECT count(*) FROM users", &[]).await?;
+/// ```
+///
+/// It can also be derived for enums without fields, which are read from the first column of the row. An integer
+/// column is matched against the discriminants of the variants, and any other column is read as a string and matched
+/// against their names. The names can be changed with `#[from_row(rename = "...")]` on a variant, or
+/// `#[from_row(rename_all = "...")]` on the enum.
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// #[from_row(rename_all = "lowercase")]
+/// enum Status {
+///     Active,
+///     Suspended,
+/// }
+///
+/// let statuses = client.query_as::<Status, _>("SELECT status FROM users", &[]).await?;
+/// ```
 pub trait FromRow: Sized {
     /// Tries to perform the conversion.
     ///
     /// Will return an error if the row does not contain the expected column names.
     fn from_row(row: &Row) -> Result<Self, Error>;
+
+    /// Like `from_row`, but reads the columns whose names are the expected ones preceded by `prefix`.
+    ///
+    /// This is used by the derive to read flattened fields with a prefix. The default implementation fails unless the
+    /// prefix is empty, in which case it calls `from_row`.
+    fn from_row_prefixed(row: &Row, prefix: &str) -> Result<Self, Error> {
+        if prefix.is_empty() {
+            Self::from_row(row)
+        } else {
+            Err(Error::column(prefix.to_string()))
+        }
+    }
+
+    /// Determines if all the columns this type is read from are `NULL`, given the prefix of their names as in
+    /// `from_row_prefixed`.
+    ///
+    /// This is used by the implementation for `Option<T>`. The default implementation checks all the columns whose
+    /// names start with `prefix`, and the derive checks exactly the columns of the fields, ignoring columns missing
+    /// from the row.
+    fn is_null(row: &Row, prefix: &str) -> bool {
+        row.columns()
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.name().starts_with(prefix))
+            .all(|(idx, _)| row.col_buffer(idx).is_none())
+    }
+}
+
+/// Reads `None` if all the columns of `T` are `NULL`, as for the columns of a table on the right side of a
+/// `LEFT JOIN` without a matching row.
+impl<T> FromRow for Option<T>
+where
+    T: FromRow,
+{
+    fn from_row(row: &Row) -> Result<Option<T>, Error> {
+        Self::from_row_prefixed(row, "")
+    }
+
+    fn from_row_prefixed(row: &Row, prefix: &str) -> Result<Option<T>, Error> {
+        if T::is_null(row, prefix) {
+            Ok(None)
+        } else {
+            T::from_row_prefixed(row, prefix).map(Some)
+        }
+    }
+
+    fn is_null(row: &Row, prefix: &str) -> bool {
+        T::is_null(row, prefix)
+    }
 }
@@ -1,6 +1,10 @@
 use std::fmt;
 
+#[cfg(feature = "pool")]
+use crate::pool::PooledClient;
 use crate::query::RowStream;
+#[cfg(feature = "test-util")]
+use crate::test::TestTransaction;
 use crate::types::{BorrowToSql, ToSql, Type};
 use crate::{Client, Error, Row, Statement, ToStatement, Transaction};
 use async_trait::async_trait;
@@ -249,3 +253,179 @@ impl GenericClient for Transaction<'_> {
         self.client()
     }
 }
+
+#[cfg(feature = "test-util")]
+impl private::Sealed for TestTransaction<'_> {}
+
+#[cfg(feature = "test-util")]
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl GenericClient for TestTransaction<'_> {
+    async fn execute<T>(&self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).execute(query, params).await
+    }
+
+    async fn execute_raw<P, I, T>(&self, statement: &T, params: I) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P> + Sync + Send,
+        I::IntoIter: ExactSizeIterator,
+    {
+        (**self).execute_raw(statement, params).await
+    }
+
+    async fn query<T>(&self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).query(query, params).await
+    }
+
+    async fn query_one<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).query_one(statement, params).await
+    }
+
+    async fn query_opt<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).query_opt(statement, params).await
+    }
+
+    async fn query_raw<T, P, I>(&self, statement: &T, params: I) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P> + Sync + Send,
+        I::IntoIter: ExactSizeIterator,
+    {
+        (**self).query_raw(statement, params).await
+    }
+
+    async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        (**self).prepare(query).await
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        (**self).prepare_typed(query, parameter_types).await
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    async fn transaction<'a>(&'a mut self) -> Result<Transaction<'a>, Error> {
+        self.transaction().await
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        (**self).batch_execute(query).await
+    }
+
+    fn client(&self) -> &Client {
+        (**self).client()
+    }
+}
+
+#[cfg(feature = "pool")]
+impl private::Sealed for PooledClient {}
+
+#[cfg(feature = "pool")]
+#[async_trait]
+impl GenericClient for PooledClient {
+    async fn execute<T>(&self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).execute(query, params).await
+    }
+
+    async fn execute_raw<P, I, T>(&self, statement: &T, params: I) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P> + Sync + Send,
+        I::IntoIter: ExactSizeIterator,
+    {
+        (**self).execute_raw(statement, params).await
+    }
+
+    async fn query<T>(&self, query: &T, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).query(query, params).await
+    }
+
+    async fn query_one<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).query_one(statement, params).await
+    }
+
+    async fn query_opt<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+    {
+        (**self).query_opt(statement, params).await
+    }
+
+    async fn query_raw<T, P, I>(&self, statement: &T, params: I) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement + Sync + Send + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P> + Sync + Send,
+        I::IntoIter: ExactSizeIterator,
+    {
+        (**self).query_raw(statement, params).await
+    }
+
+    async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        (**self).prepare(query).await
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        (**self).prepare_typed(query, parameter_types).await
+    }
+
+    async fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
+        (**self).transaction().await
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        (**self).batch_execute(query).await
+    }
+
+    fn client(&self) -> &Client {
+        self
+    }
+}
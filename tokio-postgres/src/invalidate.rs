@@ -0,0 +1,243 @@
+//! Cache invalidation with `LISTEN` and `NOTIFY`.
+//!
+//! An [`Invalidator`] keeps a dedicated connection listening for notifications with a
+//! [`Listener`](crate::listener::Listener), and passes them on to a callback as [`Invalidation`]s. It can install
+//! triggers which send a notification whenever a table is modified, and it reconnects and listens again whenever the
+//! connection is lost.
+//!
+//! Notifications sent while the connection was down are lost, so the callback receives [`Invalidation::Reconnected`]
+//! after a reconnect. Caches should be cleared entirely at that point.
+//!
+//! Requires the `runtime` Cargo feature (enabled by default).
+//!
+//! ```no_run
+//! use tokio_postgres::invalidate::{Invalidation, Invalidator};
+//! use tokio_postgres::NoTls;
+//!
+//! # async fn run() -> Result<(), tokio_postgres::Error> {
+//! let config = "host=localhost user=postgres".parse()?;
+//! let invalidator = Invalidator::new(&config, NoTls)
+//!     .table("users")
+//!     .channel("settings_changed")
+//!     .on_invalidation(|invalidation| match invalidation {
+//!         Invalidation::Table { table, .. } => println!("{} changed", table),
+//!         Invalidation::Notification(n) => println!("{}: {}", n.channel(), n.payload()),
+//!         Invalidation::Reconnected => println!("clearing all caches"),
+//!         _ => {}
+//!     });
+//!
+//! tokio::spawn(invalidator.run());
+//! # Ok(())
+//! # }
+//! ```
+use crate::listener::{Event, Listener};
+use crate::tls::MakeTlsConnect;
+use crate::{Client, Config, Error, Notification, Socket};
+use futures_util::future;
+use std::fmt;
+use std::time::Duration;
+
+const TABLE_CHANNEL: &str = "tokio_postgres_table_changes";
+
+/// A modification of a table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Rows were inserted.
+    Insert,
+    /// Rows were updated.
+    Update,
+    /// Rows were deleted.
+    Delete,
+    /// The table was truncated.
+    Truncate,
+}
+
+/// An event passed to the callback of an [`Invalidator`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Invalidation {
+    /// A table registered with [`Invalidator::table`] was modified.
+    ///
+    /// Triggers fire once per statement, so a single event may cover many rows.
+    Table {
+        /// The schema of the table.
+        schema: String,
+        /// The name of the table.
+        table: String,
+        /// The kind of modification.
+        operation: Operation,
+    },
+    /// A notification was received on a channel registered with [`Invalidator::channel`].
+    Notification(Notification),
+    /// The connection was re-established after it was lost, so notifications may have been missed.
+    Reconnected,
+}
+
+/// Listens for changes and passes them on to a callback.
+pub struct Invalidator<T> {
+    config: Config,
+    tls: T,
+    tables: Vec<String>,
+    channels: Vec<String>,
+    callback: Box<dyn FnMut(Invalidation) + Send>,
+    reconnect_delay: Duration,
+}
+
+impl<T> fmt::Debug for Invalidator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Invalidator")
+            .field("config", &self.config)
+            .field("tables", &self.tables)
+            .field("channels", &self.channels)
+            .field("reconnect_delay", &self.reconnect_delay)
+            .finish()
+    }
+}
+
+impl<T> Invalidator<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    /// Creates a new invalidator connecting with `config`.
+    pub fn new(config: &Config, tls: T) -> Invalidator<T> {
+        Invalidator {
+            config: config.clone(),
+            tls,
+            tables: vec![],
+            channels: vec![],
+            callback: Box::new(|_| {}),
+            reconnect_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Watches a table for modifications.
+    ///
+    /// A statement-level trigger sending a notification is installed on the table when the invalidator starts, which
+    /// requires the privilege to create triggers on it. The name is used verbatim, so it may be schema-qualified but
+    /// must be quoted if necessary.
+    pub fn table(mut self, table: &str) -> Invalidator<T> {
+        self.tables.push(table.to_string());
+        self
+    }
+
+    /// Listens on a notification channel.
+    pub fn channel(mut self, channel: &str) -> Invalidator<T> {
+        self.channels.push(channel.to_string());
+        self
+    }
+
+    /// Sets the callback invoked for each invalidation.
+    pub fn on_invalidation<F>(mut self, callback: F) -> Invalidator<T>
+    where
+        F: FnMut(Invalidation) + Send + 'static,
+    {
+        self.callback = Box::new(callback);
+        self
+    }
+
+    /// Sets the time to wait before reconnecting after the connection was lost.
+    ///
+    /// Defaults to 1 second.
+    pub fn reconnect_delay(mut self, delay: Duration) -> Invalidator<T> {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Runs the invalidator.
+    ///
+    /// Returns an error if the first connection or the installation of the triggers fails. Afterwards the future
+    /// never completes, reconnecting whenever the connection is lost.
+    pub async fn run(self) -> Result<(), Error> {
+        if !self.tables.is_empty() {
+            let (client, connection) = self.config.connect(self.tls.clone()).await?;
+            let tables = &self.tables;
+            // the connection completes once the client is dropped
+            let install = async move { install_triggers(&client, tables).await };
+            future::try_join(install, connection).await?;
+        }
+
+        let mut listener = Listener::new(&self.config, self.tls)
+            .channels(&self.channels)
+            .reconnect_delay(self.reconnect_delay);
+        if !self.tables.is_empty() {
+            listener = listener.channel(TABLE_CHANNEL);
+        }
+        let mut callback = self.callback;
+        listener.hook = Some(Box::new(move |event| match event {
+            Event::Listening { reconnect: true } => callback(Invalidation::Reconnected),
+            Event::Listening { reconnect: false } => {}
+            Event::Notification(notification) => {
+                if let Some(invalidation) = parse(notification.clone()) {
+                    callback(invalidation);
+                }
+            }
+        }));
+        listener.run().await
+    }
+}
+
+async fn install_triggers(client: &Client, tables: &[String]) -> Result<(), Error> {
+    if tables.is_empty() {
+        return Ok(());
+    }
+
+    // The payload is the operation, followed by the byte length of the schema name, the schema name and the table
+    // name, so that names can be split reliably whatever characters they contain.
+    let mut query = format!(
+        "BEGIN;
+         SELECT pg_advisory_xact_lock(hashtext('{channel}'));
+         CREATE OR REPLACE FUNCTION {channel}() RETURNS trigger AS $$
+         BEGIN
+             PERFORM pg_notify('{channel}', TG_OP || ' ' || octet_length(TG_TABLE_SCHEMA) || ' '
+                 || TG_TABLE_SCHEMA || TG_TABLE_NAME);
+             RETURN NULL;
+         END;
+         $$ LANGUAGE plpgsql;",
+        channel = TABLE_CHANNEL,
+    );
+    for table in tables {
+        query.push_str(&format!(
+            "DROP TRIGGER IF EXISTS {channel} ON {table};
+             CREATE TRIGGER {channel} AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON {table}
+                 FOR EACH STATEMENT EXECUTE PROCEDURE {channel}();",
+            channel = TABLE_CHANNEL,
+            table = table,
+        ));
+    }
+    query.push_str("COMMIT;");
+
+    let result = client.batch_execute(&query).await;
+    if result.is_err() {
+        let _ = client.batch_execute("ROLLBACK").await;
+    }
+    result
+}
+
+fn parse(notification: Notification) -> Option<Invalidation> {
+    if notification.channel() != TABLE_CHANNEL {
+        return Some(Invalidation::Notification(notification));
+    }
+
+    let mut it = notification.payload().splitn(3, ' ');
+    let operation = match it.next()? {
+        "INSERT" => Operation::Insert,
+        "UPDATE" => Operation::Update,
+        "DELETE" => Operation::Delete,
+        "TRUNCATE" => Operation::Truncate,
+        _ => return None,
+    };
+    let len = it.next()?.parse().ok()?;
+    let rest = it.next()?;
+    if !rest.is_char_boundary(len) {
+        return None;
+    }
+    let (schema, table) = rest.split_at(len);
+
+    Some(Invalidation::Table {
+        schema: schema.to_string(),
+        table: table.to_string(),
+        operation,
+    })
+}
@@ -0,0 +1,440 @@
+//! Conversion of rows to JSON.
+//!
+//! Rows are converted to JSON objects keyed by column name, without a Rust type describing them, which makes it easy
+//! to export the results of arbitrary queries:
+//!
+//! ```no_run
+//! # async fn run<W>(client: &tokio_postgres::Client, out: &mut W) -> Result<(), tokio_postgres::Error>
+//! # where W: tokio::io::AsyncWrite + Unpin {
+//! let rows = client.query_raw("SELECT * FROM users", std::iter::empty::<i32>()).await?;
+//! tokio_postgres::json::write_ndjson(rows, out).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Values are converted as follows:
+//!
+//! | Postgres type | JSON value |
+//! |---------------|------------|
+//! | `NULL` | `null` |
+//! | `BOOL` | boolean |
+//! | `"char"`, `INT2`, `INT4`, `INT8`, `OID` | number |
+//! | `FLOAT4`, `FLOAT8` | number, or the string `"NaN"`, `"Infinity"` or `"-Infinity"` |
+//! | `NUMERIC` | string, to keep its precision |
+//! | `TEXT`, `VARCHAR`, `BPCHAR`, `NAME`, `UNKNOWN`, enums | string |
+//! | `JSON`, `JSONB` | the value itself |
+//! | `BYTEA` | string in the `\x` hex format |
+//! | `UUID`, `INET`, `CIDR`, `MACADDR` | string in the Postgres text format |
+//! | `DATE`, `TIME`, `TIMESTAMP`, `TIMESTAMPTZ` | ISO 8601 string, or `"infinity"` or `"-infinity"` |
+//! | arrays | array, nested for each dimension |
+//! | composite types | object keyed by field name |
+//! | domains | the value of the underlying type |
+//!
+//! Columns of any other type result in an error.
+//!
+//! [`Row`] and [`SimpleQueryRow`] also implement serde's `Serialize` as a map from column name to value, so a row can
+//! be embedded in a response directly or passed to `serde_json::to_value`. The values of a `SimpleQueryRow` are
+//! serialized as strings, as the server sends them.
+//!
+//! `HashMap<String, Value>` implements [`FromRow`], so rows can be read as maps of column name to value when the
+//! columns of a query are only known at runtime:
+//!
+//! ```no_run
+//! # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! use serde_json_1::Value;
+//! use std::collections::HashMap;
+//!
+//! let rows = client
+//!     .query_as::<HashMap<String, Value>, _>("SELECT * FROM users", &[])
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requires the `with-serde_json-1` Cargo feature.
+use crate::row::RowIndex;
+use crate::types::{Format, FromSql, Json, Kind, Type, WrongType};
+use crate::{Error, FromRow, Row, SimpleQueryRow};
+use fallible_iterator::FallibleIterator;
+use futures_util::{Stream, TryStreamExt};
+use postgres_protocol::types;
+use serde_1::ser::{Error as _, Serialize, SerializeMap, Serializer};
+use serde_json_1::{Map, Number, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::fmt::Write;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+type BoxError = Box<dyn error::Error + Sync + Send>;
+
+/// Converts a row to a JSON object keyed by column name.
+///
+/// If several columns have the same name, the last one wins.
+pub fn to_value(row: &Row) -> Result<Value, Error> {
+    let mut map = Map::new();
+    for (idx, column) in row.columns().iter().enumerate() {
+        map.insert(column.name().to_string(), column_value(row, idx)?);
+    }
+    Ok(Value::Object(map))
+}
+
+/// Converts the value of a column of a row to JSON.
+///
+/// Values returned in the text format are converted to strings.
+///
+/// # Panics
+///
+/// Panics if the index is out of bounds.
+pub fn column_value(row: &Row, idx: usize) -> Result<Value, Error> {
+    let ty = match row.column_format(idx) {
+        Format::Binary => row.columns()[idx].type_(),
+        Format::Text => &Type::TEXT,
+    };
+    convert(ty, row.col_buffer(idx)).map_err(|e| Error::from_sql(e, idx))
+}
+
+#[doc(hidden)]
+pub fn __private_api_from_row<T, I>(row: &Row, idx: I) -> Result<T, Error>
+where
+    Json<T>: for<'a> FromSql<'a>,
+    I: RowIndex + fmt::Display,
+{
+    match row.try_get::<_, Option<Json<T>>>(&idx)? {
+        Some(Json(value)) => Ok(value),
+        // a NULL column is read like a JSON null, so that it can be deserialized into an `Option`
+        None => Json::<T>::from_sql(&Type::JSON, b"null")
+            .map(|json| json.0)
+            .map_err(|e| Error::__private_api_from_sql(row, idx, e)),
+    }
+}
+
+/// Converts a stream of rows, such as a [`RowStream`](crate::RowStream), to a stream of JSON objects.
+pub fn to_values<S>(rows: S) -> impl Stream<Item = Result<Value, Error>>
+where
+    S: Stream<Item = Result<Row, Error>>,
+{
+    rows.and_then(|row| async move { to_value(&row) })
+}
+
+/// Writes a stream of rows as newline-delimited JSON, one object per row, returning the number of rows written.
+///
+/// Unlike [`to_value`], the objects keep the columns in the order of the query, including columns with duplicate
+/// names.
+pub async fn write_ndjson<S, W>(rows: S, writer: &mut W) -> Result<u64, Error>
+where
+    S: Stream<Item = Result<Row, Error>>,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    futures_util::pin_mut!(rows);
+
+    let mut buf = vec![];
+    let mut count = 0;
+    while let Some(row) = rows.try_next().await? {
+        buf.clear();
+        buf.push(b'{');
+        for (idx, column) in row.columns().iter().enumerate() {
+            if idx > 0 {
+                buf.push(b',');
+            }
+            let value = column_value(&row, idx)?;
+            serde_json_1::to_writer(&mut buf, column.name())
+                .and_then(|()| {
+                    buf.push(b':');
+                    serde_json_1::to_writer(&mut buf, &value)
+                })
+                .map_err(|e| Error::from_sql(Box::new(e), idx))?;
+        }
+        buf.extend_from_slice(b"}\n");
+
+        writer.write_all(&buf).await.map_err(Error::io)?;
+        count += 1;
+    }
+    writer.flush().await.map_err(Error::io)?;
+
+    Ok(count)
+}
+
+impl FromRow for HashMap<String, Value> {
+    /// Converts a row to a map of column name to JSON value.
+    ///
+    /// If several columns have the same name, the last one wins.
+    fn from_row(row: &Row) -> Result<HashMap<String, Value>, Error> {
+        let mut map = HashMap::with_capacity(row.len());
+        for (idx, column) in row.columns().iter().enumerate() {
+            map.insert(column.name().to_string(), column_value(row, idx)?);
+        }
+        Ok(map)
+    }
+}
+
+impl Serialize for Row {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (idx, column) in self.columns().iter().enumerate() {
+            let value = column_value(self, idx).map_err(S::Error::custom)?;
+            map.serialize_entry(column.name(), &value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for SimpleQueryRow {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (idx, column) in self.columns().iter().enumerate() {
+            map.serialize_entry(column.name(), &self.get(idx))?;
+        }
+        map.end()
+    }
+}
+
+fn convert(ty: &Type, raw: Option<&[u8]>) -> Result<Value, BoxError> {
+    let raw = match raw {
+        Some(raw) => raw,
+        None => return Ok(Value::Null),
+    };
+
+    let value = match *ty {
+        Type::BOOL => Value::Bool(types::bool_from_sql(raw)?),
+        Type::CHAR => Value::from(types::char_from_sql(raw)?),
+        Type::INT2 => Value::from(types::int2_from_sql(raw)?),
+        Type::INT4 => Value::from(types::int4_from_sql(raw)?),
+        Type::INT8 => Value::from(types::int8_from_sql(raw)?),
+        Type::OID => Value::from(types::oid_from_sql(raw)?),
+        Type::FLOAT4 => float(types::float4_from_sql(raw)?.into()),
+        Type::FLOAT8 => float(types::float8_from_sql(raw)?),
+        Type::NUMERIC => Value::String(numeric(raw)?),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => {
+            Value::String(types::text_from_sql(raw)?.to_string())
+        }
+        Type::JSON | Type::JSONB => Value::from_sql(ty, raw)?,
+        Type::BYTEA => {
+            let mut s = String::with_capacity(2 + raw.len() * 2);
+            s.push_str("\\x");
+            for b in raw {
+                let _ = write!(s, "{:02x}", b);
+            }
+            Value::String(s)
+        }
+        Type::UUID => {
+            let b = types::uuid_from_sql(raw)?;
+            Value::String(format!(
+                "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+                b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15],
+            ))
+        }
+        Type::INET | Type::CIDR => {
+            let inet = types::inet_from_sql(raw)?;
+            let full = if inet.addr().is_ipv4() { 32 } else { 128 };
+            if *ty == Type::INET && inet.netmask() == full {
+                Value::String(inet.addr().to_string())
+            } else {
+                Value::String(format!("{}/{}", inet.addr(), inet.netmask()))
+            }
+        }
+        Type::MACADDR => {
+            let b = types::macaddr_from_sql(raw)?;
+            Value::String(format!(
+                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                b[0], b[1], b[2], b[3], b[4], b[5],
+            ))
+        }
+        Type::DATE => match types::date_from_sql(raw)? {
+            i32::MAX => Value::from("infinity"),
+            i32::MIN => Value::from("-infinity"),
+            days => {
+                let (mut s, bc) = date(days.into());
+                if bc {
+                    s.push_str(" BC");
+                }
+                Value::String(s)
+            }
+        },
+        Type::TIME => Value::String(time(types::time_from_sql(raw)?)),
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => match types::timestamp_from_sql(raw)? {
+            i64::MAX => Value::from("infinity"),
+            i64::MIN => Value::from("-infinity"),
+            micros => {
+                let days = micros.div_euclid(MICROS_PER_DAY);
+                let (mut s, bc) = date(days);
+                s.push('T');
+                s.push_str(&time(micros.rem_euclid(MICROS_PER_DAY)));
+                if *ty == Type::TIMESTAMPTZ {
+                    s.push('Z');
+                }
+                if bc {
+                    s.push_str(" BC");
+                }
+                Value::String(s)
+            }
+        },
+        _ => match ty.kind() {
+            Kind::Enum(_) => Value::String(types::text_from_sql(raw)?.to_string()),
+            Kind::Domain(base) => convert(base, Some(raw))?,
+            Kind::Array(member) => array(member, raw)?,
+            Kind::Composite(fields) => {
+                let record = types::record_from_sql(raw)?;
+                if record.len() as usize != fields.len() {
+                    return Err(format!(
+                        "expected {} fields but got {}",
+                        fields.len(),
+                        record.len()
+                    )
+                    .into());
+                }
+                let mut map = Map::new();
+                let mut values = record.fields();
+                for field in fields {
+                    let value = values.next()?.ok_or("missing record field")?;
+                    map.insert(
+                        field.name().to_string(),
+                        convert(field.type_(), value.bytes())?,
+                    );
+                }
+                Value::Object(map)
+            }
+            _ => return Err(Box::new(WrongType::new::<Value>(ty.clone()))),
+        },
+    };
+
+    Ok(value)
+}
+
+fn float(f: f64) -> Value {
+    match Number::from_f64(f) {
+        Some(n) => Value::Number(n),
+        None if f.is_nan() => Value::from("NaN"),
+        None if f > 0. => Value::from("Infinity"),
+        None => Value::from("-Infinity"),
+    }
+}
+
+fn array(member: &Type, raw: &[u8]) -> Result<Value, BoxError> {
+    let array = types::array_from_sql(raw)?;
+    let dimensions = array
+        .dimensions()
+        .map(|d| Ok(d.len as usize))
+        .collect::<Vec<_>>()?;
+
+    let mut values = array.values().map(|v| convert(member, v));
+    if dimensions.is_empty() {
+        return Ok(Value::Array(vec![]));
+    }
+    nest(&dimensions, &mut values)
+}
+
+fn nest<I>(dimensions: &[usize], values: &mut I) -> Result<Value, BoxError>
+where
+    I: FallibleIterator<Item = Value, Error = BoxError>,
+{
+    let mut out = Vec::with_capacity(dimensions[0]);
+    for _ in 0..dimensions[0] {
+        let value = if dimensions.len() == 1 {
+            values.next()?.ok_or("missing array element")?
+        } else {
+            nest(&dimensions[1..], values)?
+        };
+        out.push(value);
+    }
+    Ok(Value::Array(out))
+}
+
+fn numeric(mut raw: &[u8]) -> Result<String, BoxError> {
+    use byteorder::{BigEndian, ReadBytesExt};
+
+    let ndigits = raw.read_i16::<BigEndian>()?;
+    let weight = raw.read_i16::<BigEndian>()?;
+    let sign = raw.read_u16::<BigEndian>()?;
+    let dscale = raw.read_u16::<BigEndian>()? as usize;
+    let digits = (0..ndigits)
+        .map(|_| raw.read_i16::<BigEndian>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if !raw.is_empty() {
+        return Err("invalid buffer size".into());
+    }
+
+    let mut s = match sign {
+        0x0000 => String::new(),
+        0x4000 => "-".to_string(),
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => return Err("invalid numeric sign".into()),
+    };
+    let digit = |i: i32| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    if weight < 0 {
+        s.push('0');
+    } else {
+        let _ = write!(s, "{}", digit(0));
+        for i in 1..=i32::from(weight) {
+            let _ = write!(s, "{:04}", digit(i));
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut i = i32::from(weight) + 1;
+        while fraction.len() < dscale {
+            let _ = write!(fraction, "{:04}", digit(i));
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        s.push('.');
+        s.push_str(&fraction);
+    }
+
+    Ok(s)
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Formats a number of days since 2000-01-01 as a date, returning whether it is before Christ.
+fn date(days: i64) -> (String, bool) {
+    // Howard Hinnant's days_from_civil inverse, shifted to an epoch of 0000-03-01
+    let z = days + 10_957 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    // there is no year 0 in the Postgres calendar
+    let (year, bc) = if year > 0 {
+        (year, false)
+    } else {
+        (1 - year, true)
+    };
+    (format!("{:04}-{:02}-{:02}", year, month, day), bc)
+}
+
+/// Formats a number of microseconds since midnight as a time of day.
+fn time(micros: i64) -> String {
+    let secs = micros / 1_000_000;
+    let mut s = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    let fraction = micros % 1_000_000;
+    if fraction != 0 {
+        let _ = write!(s, ".{:06}", fraction);
+        while s.ends_with('0') {
+            s.pop();
+        }
+    }
+    s
+}
@@ -90,6 +90,26 @@
 //! connection process, but these are gated by the `runtime` Cargo feature, which is enabled by default. If disabled,
 //! all dependence on the tokio runtime is removed.
 //!
+//! Without the `runtime` feature, the crate builds for `wasm32-unknown-unknown` (enable the `js` feature to source
+//! randomness from the JavaScript environment). Connections are then established by opening a stream with whatever
+//! transport the platform provides, such as a Cloudflare Workers socket or a WebSocket tunnel, and handing it to
+//! [`Config::connect_raw`]. Only tokio's `AsyncRead` and `AsyncWrite` traits are required, which are available on any
+//! target without a tokio runtime. The returned [`Connection`] is a plain future and can be driven by any executor:
+//!
+//! ```rust
+//! use tokio::io::{AsyncRead, AsyncWrite};
+//! use tokio_postgres::tls::NoTlsStream;
+//! use tokio_postgres::{Client, Config, Connection, Error, NoTls};
+//!
+//! async fn connect<S>(stream: S) -> Result<(Client, Connection<S, NoTlsStream>), Error>
+//! where
+//!     S: AsyncRead + AsyncWrite + Unpin,
+//! {
+//!     let config: Config = "user=postgres dbname=app".parse()?;
+//!     config.connect_raw(stream, NoTls).await
+//! }
+//! ```
+//!
 //! # SSL/TLS support
 //!
 //! TLS support is implemented via external libraries. `Client::connect` and `Config::connect` take a TLS implementation
@@ -104,14 +124,26 @@
 //! | Feature | Description | Extra dependencies | Default |
 //! | ------- | ----------- | ------------------ | ------- |
 //! | `runtime` | Enable convenience API for the connection process based on the `tokio` crate. | [tokio](https://crates.io/crates/tokio) 1.0 with the features `net` and `time` | yes |
+//! | `arrow` | Enable converting query results to Apache Arrow record batches in the `arrow` module. | [arrow-array](https://crates.io/crates/arrow-array) and [arrow-schema](https://crates.io/crates/arrow-schema) 54 | no |
+//! | `derive` | Enable the `FromRow`, `ToParams` and `ToCopyRow` derive macros. | - | no |
+//! | `migrate` | Enable embedded schema migrations in the `migrate` module. | [sha2](https://crates.io/crates/sha2) 0.10 | no |
+//! | `pool` | Enable the connection pool in the `pool` module. | [tokio](https://crates.io/crates/tokio) 1.0 with the feature `rt` | no |
+//! | `deadpool` | Enable the deadpool connection manager in the `manager` module. | [deadpool](https://crates.io/crates/deadpool) 0.10 | no |
+//! | `bb8` | Enable the bb8 connection manager in the `manager` module. | [bb8](https://crates.io/crates/bb8) 0.8 | no |
+//! | `socks` | Enable connecting through a SOCKS5 proxy with `Config::proxy`. | - | no |
+//! | `sqlcommenter` | Enable appending sqlcommenter comments to statements, see the `sqlcommenter` module. | [tokio](https://crates.io/crates/tokio) 1.0 with the feature `rt` | no |
+//! | `test-util` | Enable the test utilities in the `test` module. | [tokio](https://crates.io/crates/tokio) 1.0 with the feature `rt` | no |
+//! | `tokio-uring` | Enable driving connections with io_uring on Linux, see the `uring` module. | [tokio-uring](https://crates.io/crates/tokio-uring) 0.4 | no |
 //! | `array-impls` | Enables `ToSql` and `FromSql` trait impls for arrays | - | no |
 //! | `with-bit-vec-0_6` | Enable support for the `bit-vec` crate. | [bit-vec](https://crates.io/crates/bit-vec) 0.6 | no |
 //! | `with-chrono-0_4` | Enable support for the `chrono` crate. | [chrono](https://crates.io/crates/chrono) 0.4 | no |
+//! | `with-chrono-tz-0_9` | Enable support for the 0.9 version of the `chrono-tz` crate. | [chrono-tz](https://crates.io/crates/chrono-tz/0.9.0) 0.9 | no |
+//! | `with-chrono-tz-0_10` | Enable support for the 0.10 version of the `chrono-tz` crate. | [chrono-tz](https://crates.io/crates/chrono-tz/0.10.0) 0.10 | no |
 //! | `with-eui48-0_4` | Enable support for the 0.4 version of the `eui48` crate. This is deprecated and will be removed. | [eui48](https://crates.io/crates/eui48) 0.4 | no |
 //! | `with-eui48-1` | Enable support for the 1.0 version of the `eui48` crate. | [eui48](https://crates.io/crates/eui48) 1.0 | no |
 //! | `with-geo-types-0_6` | Enable support for the 0.6 version of the `geo-types` crate. | [geo-types](https://crates.io/crates/geo-types/0.6.0) 0.6 | no |
 //! | `with-geo-types-0_7` | Enable support for the 0.7 version of the `geo-types` crate. | [geo-types](https://crates.io/crates/geo-types/0.7.0) 0.7 | no |
-//! | `with-serde_json-1` | Enable support for the `serde_json` crate. | [serde_json](https://crates.io/crates/serde_json) 1.0 | no |
+//! | `with-serde_json-1` | Enable support for the `serde_json` crate, the [`json`] module, `Serialize` for rows and [`Client::listen_as`]. | [serde_json](https://crates.io/crates/serde_json) 1.0 | no |
 //! | `with-uuid-0_8` | Enable support for the `uuid` crate. | [uuid](https://crates.io/crates/uuid) 0.8 | no |
 //! | `with-uuid-1` | Enable support for the `uuid` crate. | [uuid](https://crates.io/crates/uuid) 1.0 | no |
 //! | `with-time-0_2` | Enable support for the 0.2 version of the `time` crate. | [time](https://crates.io/crates/time/0.2.0) 0.2 | no |
@@ -121,30 +153,48 @@
 pub use crate::cancel_token::CancelToken;
 pub use crate::client::Client;
 pub use crate::config::Config;
-pub use crate::connection::Connection;
+pub use crate::connection::{AsyncMessages, Connection};
+pub use crate::connection_events::{ConnectionEvent, ConnectionEvents};
 pub use crate::copy_in::CopyInSink;
 pub use crate::copy_out::CopyOutStream;
+pub use crate::dry_run::DryRunStats;
+pub use crate::dyn_client::{DynClient, DynTransaction};
 use crate::error::DbError;
 pub use crate::error::Error;
+pub use crate::fragment::Fragment;
 pub use crate::from_row::FromRow;
 pub use crate::generic_client::GenericClient;
+pub use crate::named::NamedQuery;
+#[cfg(feature = "with-serde_json-1")]
+pub use crate::notifications::NotificationsAs;
+pub use crate::notifications::{Notifications, OverflowPolicy};
+pub use crate::params::{IntoParams, ParamsVec, ToParams};
 pub use crate::portal::Portal;
-pub use crate::query::RowStream;
+pub use crate::prepared_transaction::PreparedTransaction;
+pub use crate::query::{RowChunks, RowStream, RowStreamAs};
+pub use crate::query_options::QueryOptions;
+#[cfg(feature = "runtime")]
+pub use crate::resume::ResumingConnection;
 pub use crate::row::{Row, SimpleQueryRow};
 pub use crate::simple_query::SimpleQueryStream;
 #[cfg(feature = "runtime")]
 pub use crate::socket::Socket;
 pub use crate::statement::{Column, Statement};
+pub use crate::statement_stats::StatementStats;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
 pub use crate::tls::NoTls;
-pub use crate::to_statement::ToStatement;
+pub use crate::to_statement::{ToStatement, TypedQuery};
 pub use crate::transaction::Transaction;
 pub use crate::transaction_builder::{IsolationLevel, TransactionBuilder};
 use crate::types::ToSql;
 
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod binary_copy;
 mod bind;
+pub mod bulk;
+pub mod cache;
 #[cfg(feature = "runtime")]
 mod cancel_query;
 mod cancel_query_raw;
@@ -159,27 +209,67 @@ mod connect_raw;
 mod connect_socket;
 mod connect_tls;
 mod connection;
+mod connection_events;
 mod copy_in;
 mod copy_out;
+mod dry_run;
+mod dyn_client;
 pub mod error;
+pub mod escape;
+mod fragment;
 mod from_row;
 mod generic_client;
+#[cfg(feature = "runtime")]
+pub mod invalidate;
+#[cfg(feature = "with-serde_json-1")]
+pub mod json;
 #[cfg(not(target_arch = "wasm32"))]
 mod keepalive;
+#[cfg(feature = "runtime")]
+pub mod listener;
+#[cfg(any(feature = "deadpool", feature = "bb8"))]
+pub mod manager;
 mod maybe_tls_stream;
+#[cfg(feature = "migrate")]
+pub mod migrate;
+pub mod multiplex;
+mod named;
+mod notifications;
+pub mod paginate;
+mod params;
+#[cfg(feature = "runtime")]
+mod pgpass;
+#[cfg(feature = "pool")]
+pub mod pool;
 mod portal;
 mod prepare;
+mod prepared_transaction;
 mod query;
+mod query_options;
+#[cfg(feature = "runtime")]
+pub mod resolve;
+#[cfg(feature = "runtime")]
+mod resume;
 pub mod row;
+mod service;
 mod simple_query;
 #[cfg(feature = "runtime")]
 mod socket;
+#[cfg(feature = "socks")]
+mod socks;
+#[cfg(feature = "sqlcommenter")]
+pub mod sqlcommenter;
 mod statement;
+mod statement_stats;
+#[cfg(feature = "test-util")]
+pub mod test;
 pub mod tls;
 mod to_statement;
 mod transaction;
 mod transaction_builder;
 pub mod types;
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+pub mod uring;
 
 /// A convenience function which parses a connection string and connects to the database.
 ///
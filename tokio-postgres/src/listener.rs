@@ -0,0 +1,257 @@
+//! Listening on many notification channels with `LISTEN`.
+//!
+//! A [`Listener`] keeps a dedicated connection listening on a set of channels, and routes the notifications it
+//! receives to [`Subscription`]s by channel name. A subscription matches either a single channel, or every channel
+//! starting with a prefix when its pattern ends with `*`. The listener reconnects and listens again whenever the
+//! connection is lost, although notifications sent while it was down are lost.
+//!
+//! Each subscription buffers a bounded number of notifications, so that a slow consumer cannot exhaust memory. What
+//! happens when the buffer is full is determined by its [`OverflowPolicy`].
+//!
+//! Requires the `runtime` Cargo feature (enabled by default).
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use tokio_postgres::listener::{Listener, OverflowPolicy};
+//! use tokio_postgres::NoTls;
+//!
+//! # async fn run() -> Result<(), tokio_postgres::Error> {
+//! let config = "host=localhost user=postgres".parse()?;
+//! let mut listener = Listener::new(&config, NoTls)
+//!     .channel("orders.created")
+//!     .channel("orders.shipped")
+//!     .channel("users.created");
+//! let mut orders = listener.subscribe("orders.*", 1000, OverflowPolicy::DropOldest);
+//! let mut everything = listener.subscribe("*", 100, OverflowPolicy::DropNewest);
+//! tokio::spawn(listener.run());
+//!
+//! while let Some(notification) = orders.next().await {
+//!     println!("{}: {}", notification.channel(), notification.payload());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::escape::escape_identifier;
+use crate::notifications::Queue;
+use crate::tls::MakeTlsConnect;
+use crate::{info, AsyncMessage, Config, Error, Notification, Socket};
+use futures_channel::mpsc;
+use futures_util::{future, Stream, StreamExt};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+pub use crate::notifications::OverflowPolicy;
+
+/// Listens on notification channels and routes notifications to subscriptions.
+pub struct Listener<T> {
+    config: Config,
+    tls: T,
+    channels: Vec<String>,
+    routes: Vec<Route>,
+    reconnect_delay: Duration,
+    pub(crate) hook: Option<Hook>,
+}
+
+pub(crate) type Hook = Box<dyn FnMut(Event<'_>) + Send>;
+
+/// An event passed to the hook of a [`Listener`], through which other listening subsystems are built on it.
+pub(crate) enum Event<'a> {
+    /// The listener has started listening on a connection, which is a new one after a reconnect.
+    Listening { reconnect: bool },
+    /// A notification was received.
+    Notification(&'a Notification),
+}
+
+impl<T> fmt::Debug for Listener<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listener")
+            .field("config", &self.config)
+            .field("channels", &self.channels)
+            .field("reconnect_delay", &self.reconnect_delay)
+            .finish()
+    }
+}
+
+impl<T> Listener<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    /// Creates a new listener connecting with `config`.
+    pub fn new(config: &Config, tls: T) -> Listener<T> {
+        Listener {
+            config: config.clone(),
+            tls,
+            channels: vec![],
+            routes: vec![],
+            reconnect_delay: Duration::from_secs(1),
+            hook: None,
+        }
+    }
+
+    /// Listens on a notification channel.
+    pub fn channel(mut self, channel: &str) -> Listener<T> {
+        self.channels.push(channel.to_string());
+        self
+    }
+
+    /// Listens on several notification channels.
+    pub fn channels<I, S>(mut self, channels: I) -> Listener<T>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.channels
+            .extend(channels.into_iter().map(|c| c.as_ref().to_string()));
+        self
+    }
+
+    /// Sets the time to wait before reconnecting after the connection was lost.
+    ///
+    /// Defaults to 1 second.
+    pub fn reconnect_delay(mut self, delay: Duration) -> Listener<T> {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Subscribes to the notifications of the channels matching `pattern`.
+    ///
+    /// The pattern is either the name of a channel, or a prefix followed by `*`, which matches every channel starting
+    /// with the prefix. A notification is passed to every subscription it matches. Up to `capacity` notifications
+    /// are buffered, after which `policy` applies. With [`OverflowPolicy::Error`], the subscription ends once it
+    /// overflows.
+    ///
+    /// Only the channels registered with [`Listener::channel`] are listened on, whatever the pattern.
+    pub fn subscribe(
+        &mut self,
+        pattern: &str,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Subscription {
+        let queue = Arc::new(Queue::new(capacity, policy));
+        self.routes.push(Route {
+            pattern: pattern.to_string(),
+            queue: queue.clone(),
+        });
+        Subscription { queue }
+    }
+
+    /// Runs the listener.
+    ///
+    /// Returns an error if the first connection fails. Afterwards the future never completes, reconnecting whenever
+    /// the connection is lost. The subscriptions end when the future is dropped.
+    pub async fn run(mut self) -> Result<(), Error> {
+        let mut listening = false;
+        loop {
+            match self.session(&mut listening).await {
+                Err(e) if !listening => return Err(e),
+                Err(e) => info!("listener failed: {}", e),
+                Ok(()) => {}
+            }
+            tokio::time::sleep(self.reconnect_delay).await;
+        }
+    }
+
+    // `listening` is set once the first session has started listening, after which errors lead to a reconnect.
+    async fn session(&mut self, listening: &mut bool) -> Result<(), Error> {
+        let reconnect = *listening;
+        let (client, mut connection) = self.config.connect(self.tls.clone()).await?;
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        let messages = async move {
+            while let Some(message) = future::poll_fn(|cx| connection.poll_message(cx)).await {
+                if let AsyncMessage::Notification(notification) = message? {
+                    let _ = sender.unbounded_send(notification);
+                }
+            }
+            Ok(())
+        };
+
+        let channels = &self.channels;
+        let routes = &self.routes;
+        let hook = &mut self.hook;
+        let listen = async move {
+            let mut query = String::new();
+            for channel in channels {
+                query.push_str(&format!("LISTEN {};", escape_identifier(channel)?));
+            }
+            client.batch_execute(&query).await?;
+            *listening = true;
+
+            if let Some(hook) = hook.as_mut() {
+                hook(Event::Listening { reconnect });
+            }
+            while let Some(notification) = receiver.next().await {
+                if let Some(hook) = hook.as_mut() {
+                    hook(Event::Notification(&notification));
+                }
+                for route in routes {
+                    if route.matches(notification.channel())
+                        && !route.queue.push(notification.clone())
+                    {
+                        route.queue.close();
+                    }
+                }
+            }
+            Ok(())
+        };
+
+        future::try_join(messages, listen).await.map(|_| ())
+    }
+}
+
+struct Route {
+    pattern: String,
+    queue: Arc<Queue<Notification>>,
+}
+
+impl Route {
+    fn matches(&self, channel: &str) -> bool {
+        match self.pattern.strip_suffix('*') {
+            Some(prefix) => channel.starts_with(prefix),
+            None => self.pattern == channel,
+        }
+    }
+}
+
+impl Drop for Route {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+/// A stream of the notifications matching a pattern, returned by [`Listener::subscribe`].
+///
+/// The stream ends once the listener has been dropped.
+#[must_use = "streams do nothing unless polled"]
+pub struct Subscription {
+    queue: Arc<Queue<Notification>>,
+}
+
+impl Subscription {
+    /// Returns the number of notifications which were discarded because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("capacity", &self.queue.capacity())
+            .field("policy", &self.queue.policy())
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Notification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Notification>> {
+        self.queue.poll_next(cx)
+    }
+}
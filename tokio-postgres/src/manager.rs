@@ -0,0 +1,230 @@
+//! Connection managers for third-party pools.
+//!
+//! [`DeadpoolManager`] and [`Bb8Manager`] let the [deadpool](https://crates.io/crates/deadpool) and
+//! [bb8](https://crates.io/crates/bb8) pools manage connections of this crate. They open connections with a [`Config`]
+//! and check them with [`Client::is_valid`] before they are handed out again. Whether the statement cache of a
+//! connection is kept for its next user is configured with [`StatementCacheRecycling`]. For a pool without further
+//! dependencies, see the [`pool`](crate::pool) module instead.
+//!
+//! Requires the `deadpool` or `bb8` Cargo feature, respectively.
+//!
+//! ```no_run
+//! # #[cfg(feature = "deadpool")]
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use deadpool::managed::Pool;
+//! use tokio_postgres::manager::DeadpoolManager;
+//! use tokio_postgres::NoTls;
+//!
+//! let config = "host=localhost user=postgres".parse()?;
+//! let pool: Pool<DeadpoolManager<NoTls>> = Pool::builder(DeadpoolManager::new(&config, NoTls))
+//!     .max_size(16)
+//!     .build()?;
+//!
+//! let client = pool.get().await?;
+//! let names: Vec<String> = client.query_scalar("SELECT name FROM users", &[]).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::tls::{MakeTlsConnect, TlsConnect};
+use crate::{info, Client, Config, Error, Socket};
+use async_trait::async_trait;
+use std::fmt;
+use std::time::Duration;
+
+/// What happens to the statement cache of a connection when it is recycled.
+///
+/// See [`Config::statement_cache_capacity`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatementCacheRecycling {
+    /// The cached statements stay prepared for the next user of the connection.
+    Keep,
+    /// The cached statements are closed, so that the next user of the connection starts with an empty cache.
+    Clear,
+}
+
+struct Manager<T> {
+    config: Config,
+    tls: T,
+    health_check_timeout: Duration,
+    statement_cache: StatementCacheRecycling,
+}
+
+impl<T> Manager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    fn new(config: &Config, tls: T) -> Manager<T> {
+        Manager {
+            config: config.clone(),
+            tls,
+            health_check_timeout: Duration::from_secs(5),
+            statement_cache: StatementCacheRecycling::Keep,
+        }
+    }
+
+    async fn connect(&self) -> Result<Client, Error> {
+        let (client, connection) = self.config.connect(self.tls.clone()).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                info!("pooled connection failed: {}", e);
+            }
+        });
+        Ok(client)
+    }
+
+    async fn check(&self, client: &Client) -> Result<(), Error> {
+        if client.is_closed() {
+            return Err(Error::closed());
+        }
+        client.is_valid(self.health_check_timeout).await?;
+        if self.statement_cache == StatementCacheRecycling::Clear
+            && client.statement_cache_len() > 0
+        {
+            client.clear_statement_cache();
+        }
+        Ok(())
+    }
+}
+
+impl<T> fmt::Debug for Manager<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Manager")
+            .field("config", &self.config)
+            .field("health_check_timeout", &self.health_check_timeout)
+            .field("statement_cache", &self.statement_cache)
+            .finish()
+    }
+}
+
+/// A [`deadpool::managed::Manager`] of connections.
+///
+/// Requires the `deadpool` Cargo feature.
+#[cfg(feature = "deadpool")]
+#[derive(Debug)]
+pub struct DeadpoolManager<T>(Manager<T>);
+
+#[cfg(feature = "deadpool")]
+impl<T> DeadpoolManager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    /// Creates a new manager opening connections with `config`.
+    pub fn new(config: &Config, tls: T) -> DeadpoolManager<T> {
+        DeadpoolManager(Manager::new(config, tls))
+    }
+
+    /// Sets the time a connection has to pass its health check before it is recycled, after which it is closed.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn health_check_timeout(mut self, health_check_timeout: Duration) -> DeadpoolManager<T> {
+        self.0.health_check_timeout = health_check_timeout;
+        self
+    }
+
+    /// Sets what happens to the statement cache of a connection when it is recycled.
+    ///
+    /// Defaults to [`StatementCacheRecycling::Keep`].
+    pub fn statement_cache_recycling(
+        mut self,
+        statement_cache: StatementCacheRecycling,
+    ) -> DeadpoolManager<T> {
+        self.0.statement_cache = statement_cache;
+        self
+    }
+}
+
+#[cfg(feature = "deadpool")]
+#[async_trait]
+impl<T> deadpool::managed::Manager for DeadpoolManager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    type Type = Client;
+    type Error = Error;
+
+    async fn create(&self) -> Result<Client, Error> {
+        self.0.connect().await
+    }
+
+    async fn recycle(
+        &self,
+        client: &mut Client,
+        _: &deadpool::managed::Metrics,
+    ) -> deadpool::managed::RecycleResult<Error> {
+        self.0.check(client).await.map_err(Into::into)
+    }
+}
+
+/// A [`bb8::ManageConnection`] of connections.
+///
+/// Requires the `bb8` Cargo feature.
+#[cfg(feature = "bb8")]
+#[derive(Debug)]
+pub struct Bb8Manager<T>(Manager<T>);
+
+#[cfg(feature = "bb8")]
+impl<T> Bb8Manager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    /// Creates a new manager opening connections with `config`.
+    pub fn new(config: &Config, tls: T) -> Bb8Manager<T> {
+        Bb8Manager(Manager::new(config, tls))
+    }
+
+    /// Sets the time a connection has to pass its health check, after which it is closed.
+    ///
+    /// Whether connections are checked before being handed out is configured on the bb8 pool. Defaults to 5 seconds.
+    pub fn health_check_timeout(mut self, health_check_timeout: Duration) -> Bb8Manager<T> {
+        self.0.health_check_timeout = health_check_timeout;
+        self
+    }
+
+    /// Sets what happens to the statement cache of a connection when it is checked.
+    ///
+    /// bb8 has no hook for connections returned to the pool, so the cache is cleared when the connection is checked
+    /// before being handed out again. Defaults to [`StatementCacheRecycling::Keep`].
+    pub fn statement_cache_recycling(
+        mut self,
+        statement_cache: StatementCacheRecycling,
+    ) -> Bb8Manager<T> {
+        self.0.statement_cache = statement_cache;
+        self
+    }
+}
+
+#[cfg(feature = "bb8")]
+#[async_trait]
+impl<T> bb8::ManageConnection for Bb8Manager<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    type Connection = Client;
+    type Error = Error;
+
+    async fn connect(&self) -> Result<Client, Error> {
+        self.0.connect().await
+    }
+
+    async fn is_valid(&self, client: &mut Client) -> Result<(), Error> {
+        self.0.check(client).await
+    }
+
+    fn has_broken(&self, client: &mut Client) -> bool {
+        client.is_closed()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::tls::{ChannelBinding, TlsStream};
+use crate::tls::{ChannelBinding, TlsInfo, TlsStream};
 use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -57,6 +57,19 @@ where
     }
 }
 
+impl<S, T> MaybeTlsStream<S, T>
+where
+    T: TlsStream,
+{
+    /// Returns details of the TLS session, if the stream is encrypted.
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        match self {
+            MaybeTlsStream::Raw(_) => None,
+            MaybeTlsStream::Tls(s) => Some(s.tls_info()),
+        }
+    }
+}
+
 impl<S, T> TlsStream for MaybeTlsStream<S, T>
 where
     S: AsyncRead + AsyncWrite + Unpin,
@@ -0,0 +1,249 @@
+//! Embedded schema migrations.
+//!
+//! Migrations are SQL scripts identified by an increasing version number. A [`Migrator`] applies the ones which have
+//! not been applied to a database yet, each in its own transaction, and records them in a version-tracking table along
+//! with a checksum of their contents. Migrations which were already applied must not be changed afterwards.
+//!
+//! The [`embed_migrations!`] macro embeds the scripts of a directory into the binary at compile time. Files must be
+//! named `V{version}__{name}.sql`, and the path is relative to the crate's `Cargo.toml`, defaulting to `migrations`.
+//!
+//! Requires the `migrate` Cargo feature.
+//!
+//! ```ignore
+//! use tokio_postgres::migrate::embed_migrations;
+//!
+//! # async fn run(client: &mut tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! let migrator = embed_migrations!("migrations");
+//! let applied = migrator.run(client).await?;
+//! println!("applied {} migrations", applied.len());
+//! # Ok(())
+//! # }
+//! ```
+use crate::{Client, Error};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Embeds the migrations of a directory, evaluating to a [`Migrator`].
+pub use tokio_postgres_derive::embed_migrations;
+
+const DEFAULT_TABLE: &str = "_tokio_postgres_migrations";
+
+/// A single migration script.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    version: i64,
+    name: Cow<'static, str>,
+    sql: Cow<'static, str>,
+}
+
+impl Migration {
+    /// Creates a new migration.
+    pub fn new<N, S>(version: i64, name: N, sql: S) -> Migration
+    where
+        N: Into<Cow<'static, str>>,
+        S: Into<Cow<'static, str>>,
+    {
+        Migration {
+            version,
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+
+    /// Returns the version of the migration.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Returns the name of the migration.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the SQL script of the migration.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the hex-encoded SHA-256 checksum of the migration's script.
+    pub fn checksum(&self) -> String {
+        let digest = Sha256::digest(self.sql.as_bytes());
+        let mut checksum = String::with_capacity(digest.len() * 2);
+        for byte in digest {
+            let _ = write!(checksum, "{:02x}", byte);
+        }
+        checksum
+    }
+}
+
+/// Applies migrations to a database.
+#[derive(Debug, Clone)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    table: String,
+}
+
+impl Migrator {
+    /// Creates a new `Migrator` applying the provided migrations.
+    pub fn new<I>(migrations: I) -> Migrator
+    where
+        I: IntoIterator<Item = Migration>,
+    {
+        let mut migrations = migrations.into_iter().collect::<Vec<_>>();
+        migrations.sort_by_key(|m| m.version);
+
+        Migrator {
+            migrations,
+            table: DEFAULT_TABLE.to_string(),
+        }
+    }
+
+    /// Sets the name of the table used to track applied migrations.
+    ///
+    /// The name is used verbatim, so it may be schema-qualified but must be quoted if necessary. Defaults to
+    /// `_tokio_postgres_migrations`.
+    pub fn table(mut self, table: &str) -> Migrator {
+        self.table = table.to_string();
+        self
+    }
+
+    /// Returns the migrations, ordered by version.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Applies all pending migrations, returning the ones which were applied.
+    ///
+    /// A session-level advisory lock is held while the migrations are applied, so concurrent runs against the same
+    /// database wait for each other rather than applying a migration twice. Each migration is applied in its own
+    /// transaction together with its entry in the tracking table.
+    ///
+    /// Returns an error without applying anything if a previously applied migration has been changed or is no longer
+    /// known, or if a pending migration has a lower version than one which was already applied.
+    pub async fn run(&self, client: &mut Client) -> Result<Vec<&Migration>, Error> {
+        client
+            .execute("SELECT pg_advisory_lock(hashtext($1))", &[&self.table])
+            .await?;
+        let result = self.run_locked(client).await;
+        let unlock = client
+            .execute("SELECT pg_advisory_unlock(hashtext($1))", &[&self.table])
+            .await;
+
+        let applied = result?;
+        unlock?;
+        Ok(applied)
+    }
+
+    async fn run_locked(&self, client: &mut Client) -> Result<Vec<&Migration>, Error> {
+        client
+            .batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    version BIGINT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    checksum TEXT NOT NULL,
+                    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+                )",
+                self.table
+            ))
+            .await?;
+
+        let pending = self.pending(client).await?;
+
+        let insert = format!(
+            "INSERT INTO {} (version, name, checksum) VALUES ($1, $2, $3)",
+            self.table
+        );
+        for migration in &pending {
+            let transaction = client.transaction().await?;
+            transaction.batch_execute(migration.sql()).await?;
+            transaction
+                .execute(
+                    &insert,
+                    &[&migration.version, &migration.name(), &migration.checksum()],
+                )
+                .await?;
+            transaction.commit().await?;
+        }
+
+        Ok(pending)
+    }
+
+    /// Returns the migrations which would be applied by [`Migrator::run`], without applying them.
+    ///
+    /// The applied migrations are validated the same way as by `run`.
+    pub async fn dry_run(&self, client: &Client) -> Result<Vec<&Migration>, Error> {
+        let exists = client
+            .query_one("SELECT to_regclass($1) IS NOT NULL", &[&self.table])
+            .await?
+            .try_get::<_, bool>(0)?;
+        if !exists {
+            return Ok(self.migrations.iter().collect());
+        }
+
+        self.pending(client).await
+    }
+
+    async fn pending(&self, client: &Client) -> Result<Vec<&Migration>, Error> {
+        let rows = client
+            .query(
+                &format!("SELECT version, checksum FROM {}", self.table),
+                &[],
+            )
+            .await?;
+        let mut applied = HashMap::new();
+        for row in rows {
+            applied.insert(row.try_get::<_, i64>(0)?, row.try_get::<_, String>(1)?);
+        }
+
+        let mut known = HashMap::new();
+        for migration in &self.migrations {
+            if known.insert(migration.version, migration).is_some() {
+                return Err(Error::migration(
+                    format!("duplicate migration version {}", migration.version).into(),
+                ));
+            }
+        }
+
+        for (version, checksum) in &applied {
+            match known.get(version) {
+                Some(migration) if migration.checksum() == *checksum => {}
+                Some(migration) => {
+                    return Err(Error::migration(
+                        format!(
+                            "migration V{}__{} was changed after it was applied",
+                            version, migration.name
+                        )
+                        .into(),
+                    ))
+                }
+                None => {
+                    return Err(Error::migration(
+                        format!("applied migration version {} is missing", version).into(),
+                    ))
+                }
+            }
+        }
+
+        let latest = applied.keys().max().copied();
+        let pending = self
+            .migrations
+            .iter()
+            .filter(|m| !applied.contains_key(&m.version))
+            .collect::<Vec<_>>();
+        if let (Some(latest), Some(first)) = (latest, pending.first()) {
+            if first.version < latest {
+                return Err(Error::migration(
+                    format!(
+                        "migration V{}__{} is older than the latest applied version {}",
+                        first.version, first.name, latest
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        Ok(pending)
+    }
+}
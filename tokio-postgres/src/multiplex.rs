@@ -0,0 +1,401 @@
+//! Sharing a single connection among many logical clients.
+//!
+//! A [`Client`] can already be used concurrently, pipelining the requests of all of its users. A [`Multiplexer`] adds
+//! fairness and isolation on top: every [`MuxHandle`] has its own queue, requests are sent round-robin across the
+//! handles with pending work so that one busy handle can't starve the others, and a handle's requests can be canceled
+//! without affecting other handles.
+//!
+//! Handles can also be given a [`Priority`]. Waiting requests of higher priority handles are sent first, so that
+//! latency-sensitive work such as health checks isn't held up by a burst of background jobs. To bound how long lower
+//! priority requests wait while higher priority ones keep arriving, a request is promoted by one priority for every
+//! [aging interval](Multiplexer::aging) it has waited. Priorities only order the requests waiting for a slot: requests
+//! already sent over the connection are answered by the server in the order they were sent, and requests made through
+//! the [`Client`] directly bypass the multiplexer.
+//!
+//! This is useful where the server's connection limit is too tight for a pool. As all handles share one session,
+//! they must not change session state, and transactions are not available.
+//!
+//! ```no_run
+//! use tokio_postgres::multiplex::Multiplexer;
+//!
+//! # async fn run(client: tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! let multiplexer = Multiplexer::new(client, 8);
+//!
+//! let handle = multiplexer.handle();
+//! tokio::spawn(async move {
+//!     let rows = handle.query("SELECT id FROM users", &[]).await?;
+//!     Ok::<_, tokio_postgres::Error>(rows.len())
+//! });
+//! # Ok(())
+//! # }
+//! ```
+use crate::types::ToSql;
+use crate::{Client, Error, Row, Statement, ToStatement};
+use futures_channel::oneshot;
+use futures_util::future::{AbortHandle, Abortable};
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The priority of the requests of a [`MuxHandle`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Served before all other requests.
+    High,
+    /// The default priority.
+    #[default]
+    Normal,
+    /// Served after the requests of the other priorities.
+    Low,
+}
+
+/// Shares a [`Client`] among many [`MuxHandle`]s.
+#[derive(Clone)]
+pub struct Multiplexer {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for Multiplexer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.shared.state.lock();
+        f.debug_struct("Multiplexer")
+            .field("client", &self.shared.client)
+            .field("in_flight", &state.in_flight)
+            .field("max_in_flight", &state.max_in_flight)
+            .field("aging", &state.aging)
+            .finish()
+    }
+}
+
+struct Shared {
+    client: Client,
+    state: Mutex<State>,
+}
+
+struct State {
+    in_flight: usize,
+    max_in_flight: usize,
+    aging: Duration,
+    next_id: u64,
+    /// The requests waiting for a slot, along with the time they started waiting, per handle.
+    queues: HashMap<u64, VecDeque<(Instant, oneshot::Sender<()>)>>,
+    /// The handles with waiting requests, in the order they are served, per priority.
+    ready: [VecDeque<u64>; 3],
+}
+
+impl Multiplexer {
+    /// Creates a new multiplexer.
+    ///
+    /// At most `max_in_flight` requests are pipelined on the connection at once; the others wait in their handle's
+    /// queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is zero.
+    pub fn new(client: Client, max_in_flight: usize) -> Multiplexer {
+        assert!(max_in_flight > 0, "max_in_flight must be positive");
+
+        Multiplexer {
+            shared: Arc::new(Shared {
+                client,
+                state: Mutex::new(State {
+                    in_flight: 0,
+                    max_in_flight,
+                    aging: Duration::from_secs(1),
+                    next_id: 0,
+                    queues: HashMap::new(),
+                    ready: Default::default(),
+                }),
+            }),
+        }
+    }
+
+    /// Sets the time after which a waiting request is served as if it had the next higher priority.
+    ///
+    /// A request which has waited for twice the interval is served like a request of [`Priority::High`], so that
+    /// requests of lower priority wait for at most about two intervals once a slot frees up. Defaults to 1 second.
+    pub fn aging(self, aging: Duration) -> Multiplexer {
+        self.shared.state.lock().aging = aging;
+        self
+    }
+
+    /// Creates a new logical client with its own queue.
+    pub fn handle(&self) -> MuxHandle {
+        self.handle_with_priority(Priority::Normal)
+    }
+
+    /// Creates a new logical client with its own queue, whose requests are served with the given priority.
+    pub fn handle_with_priority(&self, priority: Priority) -> MuxHandle {
+        let mut state = self.shared.state.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.queues.insert(id, VecDeque::new());
+
+        MuxHandle {
+            shared: self.shared.clone(),
+            id,
+            priority,
+            aborts: Mutex::new(HashMap::new()),
+            next_op: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the underlying client.
+    pub fn client(&self) -> &Client {
+        &self.shared.client
+    }
+}
+
+impl Shared {
+    async fn acquire(self: &Arc<Self>, id: u64, priority: Priority) -> Result<Slot, Error> {
+        let waiter = {
+            let mut state = self.state.lock();
+            if state.in_flight < state.max_in_flight && state.ready.iter().all(|r| r.is_empty()) {
+                state.in_flight += 1;
+                None
+            } else {
+                let state = &mut *state;
+                let (sender, receiver) = oneshot::channel();
+                let queue = state.queues.entry(id).or_default();
+                if queue.is_empty() {
+                    state.ready[priority as usize].push_back(id);
+                }
+                queue.push_back((Instant::now(), sender));
+                Some(Waiter {
+                    shared: self.clone(),
+                    receiver: Some(receiver),
+                })
+            }
+        };
+
+        if let Some(mut waiter) = waiter {
+            let receiver = waiter.receiver.as_mut().unwrap();
+            receiver.await.map_err(|_| Error::canceled())?;
+            waiter.receiver = None;
+        }
+
+        Ok(Slot {
+            shared: self.clone(),
+        })
+    }
+
+    /// Hands a free slot to the next waiting request, or returns it if there is none.
+    fn release(&self) {
+        let mut state = self.state.lock();
+        while let Some(sender) = state.next_waiter() {
+            // the request may have been dropped while waiting
+            if sender.send(()).is_ok() {
+                return;
+            }
+        }
+        state.in_flight -= 1;
+    }
+}
+
+impl State {
+    /// Removes the next request to be served from its queue.
+    ///
+    /// The handles of a priority are served round-robin. The first request of each priority is promoted by one
+    /// priority for every aging interval it has waited, and promoted requests are served before requests which
+    /// originally had the priority they were promoted to.
+    fn next_waiter(&mut self) -> Option<oneshot::Sender<()>> {
+        let now = Instant::now();
+        let mut next = None;
+        for (priority, ready) in self.ready.iter_mut().enumerate() {
+            let waited = loop {
+                let id = match ready.front() {
+                    Some(id) => *id,
+                    None => break None,
+                };
+                match self.queues.get(&id).and_then(|queue| queue.front()) {
+                    Some((since, _)) => break Some(now.saturating_duration_since(*since)),
+                    // the handle was dropped
+                    None => {
+                        ready.pop_front();
+                    }
+                }
+            };
+            let waited = match waited {
+                Some(waited) => waited,
+                None => continue,
+            };
+
+            let promotion = if self.aging.is_zero() {
+                priority
+            } else {
+                (waited.as_nanos() / self.aging.as_nanos()) as usize
+            };
+            let effective = priority.saturating_sub(promotion);
+            match next {
+                Some((best, _)) if best < effective => {}
+                _ => next = Some((effective, priority)),
+            }
+        }
+
+        let (_, priority) = next?;
+        let ready = &mut self.ready[priority];
+        let id = ready.pop_front()?;
+        let queue = self.queues.get_mut(&id)?;
+        let (_, sender) = queue.pop_front()?;
+        if !queue.is_empty() {
+            ready.push_back(id);
+        }
+        Some(sender)
+    }
+}
+
+/// A request waiting for a slot.
+struct Waiter {
+    shared: Arc<Shared>,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        // if the request is dropped after it was handed a slot but before it could take it, pass the slot on
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if let Ok(Some(())) = receiver.try_recv() {
+                self.shared.release();
+            }
+        }
+    }
+}
+
+/// A slot for a request on the connection, freed when dropped.
+struct Slot {
+    shared: Arc<Shared>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.shared.release();
+    }
+}
+
+/// A logical client sharing the connection of a [`Multiplexer`].
+pub struct MuxHandle {
+    shared: Arc<Shared>,
+    id: u64,
+    priority: Priority,
+    aborts: Mutex<HashMap<u64, AbortHandle>>,
+    next_op: AtomicU64,
+}
+
+impl fmt::Debug for MuxHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MuxHandle")
+            .field("id", &self.id)
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+impl Drop for MuxHandle {
+    fn drop(&mut self) {
+        self.cancel();
+        self.shared.state.lock().queues.remove(&self.id);
+    }
+}
+
+impl MuxHandle {
+    async fn run<'a, F, T>(&'a self, f: impl FnOnce(&'a Client) -> F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        let op = self.next_op.fetch_add(1, Ordering::Relaxed);
+        let (abort, registration) = AbortHandle::new_pair();
+        self.aborts.lock().insert(op, abort.clone());
+
+        let operation = async {
+            let _slot = Abortable::new(self.shared.acquire(self.id, self.priority), registration)
+                .await
+                .unwrap_or_else(|_| Err(Error::canceled()))?;
+            // once sent, a request keeps its slot until the server has answered, even if it was canceled, as the
+            // server is still busy with it until then
+            let result = f(&self.shared.client).await;
+            if abort.is_aborted() {
+                Err(Error::canceled())
+            } else {
+                result
+            }
+        };
+        let result = operation.await;
+
+        self.aborts.lock().remove(&op);
+        result
+    }
+
+    /// Returns the priority of the handle's requests.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Cancels all requests of this handle which are waiting or in progress.
+    ///
+    /// The canceled requests return an error. Waiting requests fail immediately. Requests which were already sent to
+    /// the server still run to completion there, and fail once the server has answered, so that they occupy their slot
+    /// until then. Their results are discarded. Requests of other handles are not affected.
+    pub fn cancel(&self) {
+        for (_, abort) in self.aborts.lock().drain() {
+            abort.abort();
+        }
+    }
+
+    /// Like [`Client::prepare`].
+    pub async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        self.run(|client| client.prepare(query)).await
+    }
+
+    /// Like [`Client::execute`].
+    pub async fn execute<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + Sync + fmt::Debug,
+    {
+        self.run(|client| client.execute(statement, params)).await
+    }
+
+    /// Like [`Client::query`].
+    pub async fn query<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + Sync + fmt::Debug,
+    {
+        self.run(|client| client.query(statement, params)).await
+    }
+
+    /// Like [`Client::query_one`].
+    pub async fn query_one<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + Sync + fmt::Debug,
+    {
+        self.run(|client| client.query_one(statement, params)).await
+    }
+
+    /// Like [`Client::query_opt`].
+    pub async fn query_opt<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + Sync + fmt::Debug,
+    {
+        self.run(|client| client.query_opt(statement, params)).await
+    }
+}
@@ -0,0 +1,180 @@
+use crate::types::ToSql;
+use crate::{Error, ToParams};
+use std::fmt::Write;
+
+/// A query whose parameters are referenced by name, as `:name`, rather than by position.
+///
+/// The names are replaced with `$n` placeholders when the query is created, numbered in the order they first appear,
+/// and the values are looked up by name when binding a [`ToParams`] type, such as a struct deriving it or a map. A
+/// name used several times refers to the same parameter.
+///
+/// ```no_run
+/// use tokio_postgres::types::ToSql;
+/// use tokio_postgres::NamedQuery;
+/// use std::collections::HashMap;
+///
+/// # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+/// let query = NamedQuery::new("SELECT * FROM users WHERE name = :name OR nickname = :name AND age > :age");
+/// assert_eq!(query.sql(), "SELECT * FROM users WHERE name = $1 OR nickname = $1 AND age > $2");
+///
+/// let mut params = HashMap::new();
+/// params.insert("name", &"Ferris" as &(dyn ToSql + Sync));
+/// params.insert("age", &30i32);
+/// let rows = client.query(query.sql(), &query.bind(&params)?).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Names are only recognized outside of string literals, quoted identifiers and comments, and `::` casts are left
+/// alone. A name directly after a colon in an array slice, as in `values[1:n]`, is taken as a parameter, so such
+/// slices need a space after the colon.
+#[derive(Debug, Clone)]
+pub struct NamedQuery {
+    sql: String,
+    names: Vec<String>,
+}
+
+impl NamedQuery {
+    /// Creates a query from SQL text with named parameters.
+    pub fn new(sql: &str) -> NamedQuery {
+        let bytes = sql.as_bytes();
+        let mut out = String::with_capacity(sql.len());
+        let mut names: Vec<String> = vec![];
+        let mut start = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\'' => {
+                    let escapes = i > 0
+                        && matches!(bytes[i - 1], b'E' | b'e')
+                        && (i < 2 || !is_ident(bytes[i - 2]));
+                    i = skip_quoted(bytes, i, b'\'', escapes);
+                }
+                b'"' => i = skip_quoted(bytes, i, b'"', false),
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    i = bytes[i..]
+                        .iter()
+                        .position(|&b| b == b'\n')
+                        .map_or(bytes.len(), |p| i + p + 1);
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i),
+                b'$' if i == 0 || !is_ident(bytes[i - 1]) => i = skip_dollar_quoted(sql, i),
+                b':' if bytes.get(i + 1) == Some(&b':') => i += 2,
+                b':' if matches!(bytes.get(i + 1), Some(b) if b.is_ascii_alphabetic() || *b == b'_') =>
+                {
+                    let end = bytes[i + 1..]
+                        .iter()
+                        .position(|b| !b.is_ascii_alphanumeric() && *b != b'_')
+                        .map_or(bytes.len(), |p| i + 1 + p);
+                    let name = &sql[i + 1..end];
+                    let idx = match names.iter().position(|n| n == name) {
+                        Some(idx) => idx,
+                        None => {
+                            names.push(name.to_string());
+                            names.len() - 1
+                        }
+                    };
+
+                    out.push_str(&sql[start..i]);
+                    let _ = write!(out, "${}", idx + 1);
+                    start = end;
+                    i = end;
+                }
+                _ => i += 1,
+            }
+        }
+        out.push_str(&sql[start..]);
+
+        NamedQuery { sql: out, names }
+    }
+
+    /// Returns the SQL text of the query, with the names replaced by `$n` placeholders.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the names of the parameters, in the order of their placeholders.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Looks up the values of the parameters in `params`, returning them in the order of their placeholders.
+    ///
+    /// Returns an error if a parameter is missing.
+    pub fn bind<'a, P>(&self, params: &'a P) -> Result<Vec<&'a (dyn ToSql + Sync)>, Error>
+    where
+        P: ?Sized + ToParams,
+    {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                params.param(name).ok_or_else(|| {
+                    Error::to_sql(format!("missing parameter `{}`", name).into(), idx)
+                })
+            })
+            .collect()
+    }
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+// returns the index after the closing quote, doubled quotes being read as two quoted strings
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if escapes && bytes[i] == b'\\' {
+            i += 2;
+        } else if bytes[i] == quote {
+            return i + 1;
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"/*" => {
+                depth += 1;
+                i += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+// skips a `$tag$ ... $tag$` string, or just the `$` if it doesn't start one, as in `$1`
+fn skip_dollar_quoted(sql: &str, start: usize) -> usize {
+    let bytes = sql.as_bytes();
+    let tag_len = bytes[start + 1..]
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80));
+    let tag_end = match tag_len {
+        Some(len) if bytes[start + 1 + len] == b'$' => start + 1 + len,
+        _ => return start + 1,
+    };
+    if matches!(bytes.get(start + 1), Some(b) if b.is_ascii_digit()) {
+        return start + 1;
+    }
+
+    let delimiter = &sql[start..=tag_end];
+    sql[tag_end + 1..]
+        .find(delimiter)
+        .map_or(bytes.len(), |p| tag_end + 1 + p + delimiter.len())
+}
@@ -0,0 +1,314 @@
+use crate::client::InnerClient;
+use crate::escape::escape_identifier;
+use crate::{simple_query, Error, Notification};
+use futures_util::task::AtomicWaker;
+use futures_util::Stream;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+#[cfg(feature = "with-serde_json-1")]
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+
+/// What a buffer of notifications does with a new notification when it is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered notification to make room for the new one.
+    DropOldest,
+    /// Discard the new notification.
+    DropNewest,
+    /// Discard the new notification and fail with an error.
+    ///
+    /// The connection fails for the buffers of [`Client::listen`](crate::Client::listen) and
+    /// [`Connection::split_messages`](crate::Connection::split_messages), while a
+    /// [`Subscription`](crate::listener::Subscription) of a [`Listener`](crate::listener::Listener) ends.
+    Error,
+}
+
+/// A bounded buffer between a producer and a single consumer, which applies an [`OverflowPolicy`] when it is full.
+pub(crate) struct Queue<T> {
+    state: Mutex<QueueState<T>>,
+    waker: AtomicWaker,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+}
+
+struct QueueState<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+impl<T> Queue<T> {
+    pub(crate) fn new(capacity: usize, policy: OverflowPolicy) -> Queue<T> {
+        Queue {
+            state: Mutex::new(QueueState {
+                items: VecDeque::new(),
+                closed: false,
+            }),
+            waker: AtomicWaker::new(),
+            capacity,
+            policy,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Buffers an item, returning `false` if it overflowed the buffer with the `Error` policy.
+    pub(crate) fn push(&self, item: T) -> bool {
+        let mut state = self.state.lock();
+        if state.closed {
+            return true;
+        }
+        if state.items.len() >= self.capacity {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            match self.policy {
+                OverflowPolicy::DropOldest => {
+                    state.items.pop_front();
+                }
+                OverflowPolicy::DropNewest => return true,
+                OverflowPolicy::Error => return false,
+            }
+        }
+        if self.capacity > 0 {
+            state.items.push_back(item);
+        }
+        drop(state);
+        self.waker.wake();
+        true
+    }
+
+    /// Ends the stream of items once the buffered ones have been consumed.
+    pub(crate) fn close(&self) {
+        self.state.lock().closed = true;
+        self.waker.wake();
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state.lock().closed
+    }
+
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+    pub(crate) fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Returns the number of items which were discarded because the buffer was full.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.waker.register(cx.waker());
+        let mut state = self.state.lock();
+        match state.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if state.closed => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// The buffers of the streams returned by [`Client::listen`](crate::Client::listen), keyed by channel.
+pub(crate) struct Channels {
+    queues: Mutex<HashMap<String, Vec<Arc<Queue<Notification>>>>>,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+impl Default for Channels {
+    fn default() -> Channels {
+        Channels::new(None, OverflowPolicy::DropOldest)
+    }
+}
+
+impl Channels {
+    pub(crate) fn new(capacity: Option<usize>, policy: OverflowPolicy) -> Channels {
+        Channels {
+            queues: Mutex::new(HashMap::new()),
+            capacity: capacity.unwrap_or(usize::MAX),
+            policy,
+        }
+    }
+
+    /// Creates a buffer with the configured capacity and policy.
+    pub(crate) fn queue<T>(&self) -> Queue<T> {
+        Queue::new(self.capacity, self.policy)
+    }
+
+    /// Passes a notification to the streams listening on its channel.
+    ///
+    /// Returns `false` if the buffer of a stream overflowed with the `Error` policy.
+    pub(crate) fn notify(&self, notification: &Notification) -> bool {
+        let mut ok = true;
+        if let Some(queues) = self.queues.lock().get(notification.channel()) {
+            for queue in queues {
+                ok &= queue.push(notification.clone());
+            }
+        }
+        ok
+    }
+
+    /// Ends all of the streams.
+    pub(crate) fn close(&self) {
+        for (_, queues) in self.queues.lock().drain() {
+            for queue in queues {
+                queue.close();
+            }
+        }
+    }
+}
+
+impl Drop for Channels {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+pub(crate) async fn listen(
+    client: &Arc<InnerClient>,
+    channel: &str,
+) -> Result<Notifications, Error> {
+    let query = format!("LISTEN {}", escape_identifier(channel)?);
+    let notifications = Notifications {
+        queue: Arc::new(client.events().channels.queue()),
+        channel: channel.to_string(),
+        client: Arc::downgrade(client),
+    };
+
+    // the statement is sent while holding the lock so that it is ordered with the `UNLISTEN` of a dropped stream
+    let responses = {
+        let mut queues = client.events().channels.queues.lock();
+        queues
+            .entry(channel.to_string())
+            .or_default()
+            .push(notifications.queue.clone());
+        simple_query::send(client, &query)?
+    };
+    simple_query::complete(responses).await?;
+
+    Ok(notifications)
+}
+
+/// A stream of the notifications of a channel, returned by [`Client::listen`](crate::Client::listen).
+///
+/// The connection stops listening on the channel once every stream listening on it has been dropped. The stream ends
+/// when the connection terminates.
+///
+/// Notifications are buffered until they are read from the stream, up to the
+/// [`notification_buffer_size`](crate::Config::notification_buffer_size) of the connection's configuration, after which
+/// its [`notification_overflow`](crate::Config::notification_overflow) policy applies.
+#[must_use = "streams do nothing unless polled"]
+pub struct Notifications {
+    queue: Arc<Queue<Notification>>,
+    channel: String,
+    client: Weak<InnerClient>,
+}
+
+impl Notifications {
+    /// Returns the name of the channel.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Returns the number of notifications which were discarded because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+}
+
+impl fmt::Debug for Notifications {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Notifications")
+            .field("channel", &self.channel)
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+impl Stream for Notifications {
+    type Item = Notification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Notification>> {
+        self.queue.poll_next(cx)
+    }
+}
+
+/// A stream of the notifications of a channel with JSON payloads, returned by
+/// [`Client::listen_as`](crate::Client::listen_as).
+///
+/// Requires the `with-serde_json-1` Cargo feature.
+#[cfg(feature = "with-serde_json-1")]
+#[must_use = "streams do nothing unless polled"]
+pub struct NotificationsAs<T> {
+    notifications: Notifications,
+    _p: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "with-serde_json-1")]
+impl<T> NotificationsAs<T> {
+    pub(crate) fn new(notifications: Notifications) -> NotificationsAs<T> {
+        NotificationsAs {
+            notifications,
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns the name of the channel.
+    pub fn channel(&self) -> &str {
+        self.notifications.channel()
+    }
+
+    /// Returns the number of notifications which were discarded because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.notifications.dropped()
+    }
+}
+
+#[cfg(feature = "with-serde_json-1")]
+impl<T> Stream for NotificationsAs<T>
+where
+    T: serde_1::de::DeserializeOwned,
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T, Error>>> {
+        Pin::new(&mut self.notifications)
+            .poll_next(cx)
+            .map(|notification| {
+                notification.map(|notification| {
+                    serde_json_1::from_str(notification.payload())
+                        .map_err(|e| Error::payload(Box::new(e)))
+                })
+            })
+    }
+}
+
+impl Drop for Notifications {
+    fn drop(&mut self) {
+        self.queue.close();
+
+        let client = match self.client.upgrade() {
+            Some(client) => client,
+            None => return,
+        };
+        let mut channels = client.events().channels.queues.lock();
+        if let Some(queues) = channels.get_mut(&self.channel) {
+            queues.retain(|queue| !Arc::ptr_eq(queue, &self.queue));
+            if queues.is_empty() {
+                channels.remove(&self.channel);
+                if let Ok(channel) = escape_identifier(&self.channel) {
+                    let _ = simple_query::send(&client, &format!("UNLISTEN {}", channel));
+                }
+            }
+        }
+    }
+}
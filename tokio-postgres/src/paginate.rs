@@ -0,0 +1,300 @@
+//! Keyset pagination.
+//!
+//! Keyset pagination pages through the results of a query by remembering the ordering key of the last row of a page
+//! and asking for the rows after it, rather than skipping a number of rows with `OFFSET`. Pages stay consistent when
+//! rows are inserted or deleted in between requests, and later pages are as cheap to fetch as the first one when the
+//! key is indexed.
+//!
+//! ```no_run
+//! use tokio_postgres::paginate::{Cursor, Paginator};
+//! use tokio_postgres::{Error, FromRow, Row};
+//!
+//! struct User {
+//!     id: i32,
+//!     name: String,
+//! }
+//!
+//! impl FromRow for User {
+//!     fn from_row(row: &Row) -> Result<User, Error> {
+//!         Ok(User {
+//!             id: row.try_get("id")?,
+//!             name: row.try_get("name")?,
+//!         })
+//!     }
+//! }
+//!
+//! # async fn run(client: &tokio_postgres::Client) -> Result<(), Error> {
+//! let paginator = Paginator::new("SELECT id, name FROM users WHERE active", &["name", "id"], 50);
+//!
+//! let page = paginator.fetch::<User, _>(client, &[], None).await?;
+//! if let Some(cursor) = page.next_cursor() {
+//!     // hand the token to the caller, and parse it again when they ask for the next page
+//!     let cursor = Cursor::from_token(&cursor.to_token())?;
+//!     let page = paginator.fetch::<User, _>(client, &[], Some(&cursor)).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::escape::escape_identifier;
+use crate::types::{IsNull, ToSql, Type, WrongType};
+use crate::{Error, FromRow, GenericClient, Row};
+use bytes::{BufMut, BytesMut};
+use std::error;
+use std::fmt::Write;
+
+/// Generates keyset pagination queries for a base query.
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    query: String,
+    keys: Vec<String>,
+    page_size: usize,
+    descending: bool,
+}
+
+impl Paginator {
+    /// Creates a new paginator.
+    ///
+    /// `keys` are the names of the columns of `query` which the results are ordered by. Together, they must uniquely
+    /// identify a row and they must not be `NULL`, otherwise rows may be skipped or repeated. The base query must not
+    /// have an `ORDER BY` or `LIMIT` clause of its own, and its parameters must use the placeholders `$1` to `$n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` is empty or `page_size` is zero.
+    pub fn new(query: &str, keys: &[&str], page_size: usize) -> Paginator {
+        assert!(!keys.is_empty(), "keyset pagination requires a key");
+        assert!(page_size > 0, "page size must be positive");
+
+        Paginator {
+            query: query.to_string(),
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            page_size,
+            descending: false,
+        }
+    }
+
+    /// Sets whether the rows are returned in descending order of their keys.
+    ///
+    /// Defaults to `false`.
+    pub fn descending(mut self, descending: bool) -> Paginator {
+        self.descending = descending;
+        self
+    }
+
+    // The cursor's values are bound after the `params` parameters of the base query. One row more than the page size
+    // is requested to determine if there is a next page.
+    fn query(&self, params: usize, after_cursor: bool) -> Result<String, Error> {
+        let mut query = format!("SELECT * FROM ({}) AS keyset_page", self.query);
+
+        if after_cursor {
+            query.push_str(" WHERE (");
+            self.push_keys(&mut query, "")?;
+            query.push_str(if self.descending { ") < (" } else { ") > (" });
+            for i in 0..self.keys.len() {
+                if i > 0 {
+                    query.push_str(", ");
+                }
+                let _ = write!(query, "${}", params + i + 1);
+            }
+            query.push(')');
+        }
+
+        query.push_str(" ORDER BY ");
+        self.push_keys(&mut query, if self.descending { " DESC" } else { " ASC" })?;
+        let _ = write!(query, " LIMIT {}", self.page_size + 1);
+
+        Ok(query)
+    }
+
+    fn push_keys(&self, query: &mut String, suffix: &str) -> Result<(), Error> {
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                query.push_str(", ");
+            }
+            let _ = write!(query, "{}{}", escape_identifier(key)?, suffix);
+        }
+        Ok(())
+    }
+
+    /// Fetches the page after `cursor`, or the first page if it is `None`.
+    pub async fn fetch<T, C>(
+        &self,
+        client: &C,
+        params: &[&(dyn ToSql + Sync)],
+        cursor: Option<&Cursor>,
+    ) -> Result<KeysetPage<T>, Error>
+    where
+        T: FromRow,
+        C: GenericClient + Sync,
+    {
+        let mut all_params = params.to_vec();
+        if let Some(cursor) = cursor {
+            if cursor.values.len() != self.keys.len() {
+                return Err(Error::cursor(
+                    format!(
+                        "expected {} key values but got {}",
+                        self.keys.len(),
+                        cursor.values.len()
+                    )
+                    .into(),
+                ));
+            }
+            all_params.extend(cursor.values.iter().map(|v| v as &(dyn ToSql + Sync)));
+        }
+
+        let query = self.query(params.len(), cursor.is_some())?;
+        let mut rows = client.query(&query, &all_params).await?;
+
+        let next = if rows.len() > self.page_size {
+            rows.truncate(self.page_size);
+            Some(self.cursor(&rows[self.page_size - 1])?)
+        } else {
+            None
+        };
+
+        let items = rows
+            .iter()
+            .map(T::from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(KeysetPage { items, next })
+    }
+
+    /// Returns the cursor pointing after a row returned by the paginator's query.
+    pub fn cursor(&self, row: &Row) -> Result<Cursor, Error> {
+        let values = self
+            .keys
+            .iter()
+            .map(|key| {
+                let idx = row
+                    .columns()
+                    .iter()
+                    .position(|c| c.name() == key)
+                    .ok_or_else(|| Error::column(key.clone()))?;
+                let value = row
+                    .col_buffer(idx)
+                    .ok_or_else(|| Error::cursor(format!("key column `{}` is NULL", key).into()))?;
+                Ok(KeyValue {
+                    ty: row.columns()[idx].type_().clone(),
+                    value: value.to_vec(),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Cursor { values })
+    }
+}
+
+/// A page of results.
+#[derive(Debug)]
+pub struct KeysetPage<T> {
+    items: Vec<T>,
+    next: Option<Cursor>,
+}
+
+impl<T> KeysetPage<T> {
+    /// Returns the items of the page.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Consumes the page, returning its items.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Returns the cursor of the next page, or `None` if this is the last page.
+    pub fn next_cursor(&self) -> Option<&Cursor> {
+        self.next.as_ref()
+    }
+}
+
+/// The position after the last row of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    values: Vec<KeyValue>,
+}
+
+impl Cursor {
+    /// Encodes the cursor as an opaque, URL-safe token.
+    pub fn to_token(&self) -> String {
+        let mut token = String::new();
+        for (i, value) in self.values.iter().enumerate() {
+            if i > 0 {
+                token.push('.');
+            }
+            let _ = write!(token, "{}-", value.ty.oid());
+            for byte in &value.value {
+                let _ = write!(token, "{:02x}", byte);
+            }
+        }
+        token
+    }
+
+    /// Decodes a cursor from a token created by [`Cursor::to_token`].
+    ///
+    /// Tokens are usually handed out to untrusted clients, so they are validated: their values are sent as parameters
+    /// of the types they were read as, and only types built into Postgres are supported.
+    pub fn from_token(token: &str) -> Result<Cursor, Error> {
+        let values = token
+            .split('.')
+            .map(|value| {
+                let (oid, hex) = value
+                    .split_once('-')
+                    .ok_or_else(|| Error::cursor("malformed token".into()))?;
+                let ty = oid
+                    .parse()
+                    .ok()
+                    .and_then(Type::from_oid)
+                    .ok_or_else(|| Error::cursor("unsupported key type".into()))?;
+                if hex.len() % 2 != 0 {
+                    return Err(Error::cursor("malformed token".into()));
+                }
+                let value = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|b| u8::from_str_radix(b, 16).ok())
+                            .ok_or_else(|| Error::cursor("malformed token".into()))
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(KeyValue { ty, value })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Cursor { values })
+    }
+}
+
+/// A value of a key column, in the binary format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct KeyValue {
+    ty: Type,
+    value: Vec<u8>,
+}
+
+impl ToSql for KeyValue {
+    fn to_sql(
+        &self,
+        _: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn error::Error + Sync + Send>> {
+        out.put_slice(&self.value);
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    fn to_sql_checked(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn error::Error + Sync + Send>> {
+        if *ty != self.ty {
+            return Err(Box::new(WrongType::new::<KeyValue>(ty.clone())));
+        }
+        self.to_sql(ty, out)
+    }
+}
@@ -0,0 +1,259 @@
+use crate::types::ToSql;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+
+#[cfg(feature = "derive")]
+pub use tokio_postgres_derive::ToParams;
+
+/// A list of query parameters.
+///
+/// This is implemented for tuples of up to 16 values or references to values, so that parameters of different types
+/// can be passed without casting each of them to a trait object. It is also implemented for the slices of trait objects
+/// taken by methods such as [`Client::query`](crate::Client::query).
+///
+/// ```no_run
+/// # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+/// let name = String::from("Ferris");
+/// let rows = client
+///     .query_with("SELECT * FROM users WHERE name = $1 AND age > $2", (&name, 30i32))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait IntoParams {
+    /// Returns the parameters, in order.
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)>;
+}
+
+/// A trait for types whose fields are query parameters.
+///
+/// With the `derive` feature, it can be derived for structs with named fields, along with [`IntoParams`], which passes
+/// the fields in the order they are declared. The name of a parameter can be changed with
+/// `#[to_params(rename = "...")]`, and a field is left out with `#[to_params(skip)]`.
+///
+/// ```ignore
+/// use tokio_postgres::ToParams;
+///
+/// #[derive(ToParams)]
+/// struct NewUser {
+///     name: String,
+///     #[to_params(rename = "years")]
+///     age: i32,
+///     #[to_params(skip)]
+///     password_confirmation: String,
+/// }
+///
+/// # async fn run(client: &tokio_postgres::Client, user: &NewUser) -> Result<(), tokio_postgres::Error> {
+/// client
+///     .execute_with("INSERT INTO users (name, age) VALUES ($1, $2)", user)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Its fields can also be bound to a [`NamedQuery`](crate::NamedQuery) by name, which is how
+/// [`Client::execute_named`](crate::Client::execute_named) works. It is also implemented for maps from names to
+/// parameters.
+pub trait ToParams {
+    /// Returns the names of the parameters, in order.
+    fn param_names() -> &'static [&'static str]
+    where
+        Self: Sized;
+
+    /// Returns the parameter with the given name.
+    fn param(&self, name: &str) -> Option<&(dyn ToSql + Sync)>;
+}
+
+/// The names of the entries of a map are only known at runtime, so `param_names` returns an empty slice.
+impl<K, S> ToParams for HashMap<K, &(dyn ToSql + Sync), S>
+where
+    K: Borrow<str> + Hash + Eq,
+    S: BuildHasher,
+{
+    fn param_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn param(&self, name: &str) -> Option<&(dyn ToSql + Sync)> {
+        self.get(name).copied()
+    }
+}
+
+/// The names of the entries of a map are only known at runtime, so `param_names` returns an empty slice.
+impl<K> ToParams for BTreeMap<K, &(dyn ToSql + Sync)>
+where
+    K: Borrow<str> + Ord,
+{
+    fn param_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn param(&self, name: &str) -> Option<&(dyn ToSql + Sync)> {
+        self.get(name).copied()
+    }
+}
+
+/// An owned, growable list of query parameters.
+///
+/// Unlike a slice of references, a `ParamsVec` owns its values, so it can be built up dynamically, returned from
+/// functions, and held across await points or moved into spawned tasks.
+///
+/// ```no_run
+/// use tokio_postgres::ParamsVec;
+///
+/// # async fn run(client: &tokio_postgres::Client, name: Option<String>) -> Result<(), tokio_postgres::Error> {
+/// let mut sql = String::from("SELECT * FROM users WHERE true");
+/// let mut params = ParamsVec::new();
+/// if let Some(name) = name {
+///     params.push(name);
+///     sql.push_str(&format!(" AND name = ${}", params.len()));
+/// }
+/// let rows = client.query_with(&sql, &params).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ParamsVec(Vec<Box<dyn ToSql + Send + Sync>>);
+
+impl ParamsVec {
+    /// Creates an empty list of parameters.
+    pub fn new() -> ParamsVec {
+        ParamsVec(Vec::new())
+    }
+
+    /// Creates an empty list of parameters with space for at least `capacity` values.
+    pub fn with_capacity(capacity: usize) -> ParamsVec {
+        ParamsVec(Vec::with_capacity(capacity))
+    }
+
+    /// Appends a parameter to the end of the list.
+    pub fn push<T>(&mut self, value: T)
+    where
+        T: ToSql + Send + Sync + 'static,
+    {
+        self.0.push(Box::new(value));
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Determines if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the parameters, suitable for [`Client::query_raw`](crate::Client::query_raw).
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &(dyn ToSql + Sync)> {
+        self.0.iter().map(|p| &**p as _)
+    }
+
+    /// Consumes the list, returning the boxed parameters.
+    pub fn into_inner(self) -> Vec<Box<dyn ToSql + Send + Sync>> {
+        self.0
+    }
+}
+
+impl From<Vec<Box<dyn ToSql + Send + Sync>>> for ParamsVec {
+    fn from(params: Vec<Box<dyn ToSql + Send + Sync>>) -> ParamsVec {
+        ParamsVec(params)
+    }
+}
+
+impl<T> Extend<T> for ParamsVec
+where
+    T: ToSql + Send + Sync + 'static,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.0.extend(
+            iter.into_iter()
+                .map(|p| Box::new(p) as Box<dyn ToSql + Send + Sync>),
+        );
+    }
+}
+
+impl IntoParams for ParamsVec {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.iter().collect()
+    }
+}
+
+impl IntoParams for [Box<dyn ToSql + Send + Sync>] {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.iter().map(|p| &**p as _).collect()
+    }
+}
+
+impl IntoParams for Vec<Box<dyn ToSql + Send + Sync>> {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self[..].to_params()
+    }
+}
+
+impl<T> IntoParams for &T
+where
+    T: ?Sized + IntoParams,
+{
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        T::to_params(self)
+    }
+}
+
+impl IntoParams for [&(dyn ToSql + Sync)] {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.to_vec()
+    }
+}
+
+impl<const N: usize> IntoParams for [&(dyn ToSql + Sync); N] {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.to_vec()
+    }
+}
+
+impl IntoParams for Vec<&(dyn ToSql + Sync)> {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.clone()
+    }
+}
+
+impl IntoParams for () {
+    fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        vec![]
+    }
+}
+
+macro_rules! tuple_impl {
+    ($($t:ident $idx:tt),+) => {
+        impl<$($t),+> IntoParams for ($($t,)+)
+        where
+            $($t: ToSql + Sync,)+
+        {
+            fn to_params(&self) -> Vec<&(dyn ToSql + Sync)> {
+                vec![$(&self.$idx as &(dyn ToSql + Sync)),+]
+            }
+        }
+    };
+}
+
+tuple_impl!(T0 0);
+tuple_impl!(T0 0, T1 1);
+tuple_impl!(T0 0, T1 1, T2 2);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13, T14 14);
+tuple_impl!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11, T12 12, T13 13, T14 14, T15 15);
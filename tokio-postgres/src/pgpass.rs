@@ -0,0 +1,123 @@
+//! Password lookup in a libpq-compatible password file.
+//!
+//! Each line of the file has the format `hostname:port:database:username:password`. Any of the first four fields can
+//! be `*`, which matches anything, and `:` and `\` characters in a field are escaped with a `\`. The password of the
+//! first matching line is used. Lines that are empty or start with `#` are ignored.
+
+use crate::Config;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::{env, fs, mem};
+
+/// Looks up the password for a connection in the password file.
+///
+/// `host` is the name of the host, or its address if only a hostaddr was given, and is `localhost` for Unix sockets.
+pub(crate) fn password(config: &Config, host: &str, port: u16, user: &str) -> Option<Vec<u8>> {
+    let path = path(config)?;
+
+    let metadata = fs::metadata(&path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        if metadata.permissions().mode() & 0o077 != 0 {
+            crate::info!(
+                "password file {} has group or world access; permissions should be u=rw (0600) or less",
+                path.display()
+            );
+            return None;
+        }
+    }
+
+    let contents = fs::read_to_string(&path).ok()?;
+    let port = port.to_string();
+    let dbname = config.dbname.as_deref().unwrap_or(user);
+
+    contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .find_map(|line| {
+            let fields = split(line)?;
+            let matches = [host, &port, dbname, user]
+                .iter()
+                .zip(&fields)
+                .all(|(value, field)| field.wildcard || field.value == *value);
+            if matches {
+                Some(fields[4].value.clone().into_bytes())
+            } else {
+                None
+            }
+        })
+}
+
+fn path(config: &Config) -> Option<PathBuf> {
+    if let Some(passfile) = &config.passfile {
+        return Some(passfile.clone());
+    }
+    if let Some(passfile) = env::var_os("PGPASSFILE") {
+        return Some(PathBuf::from(passfile));
+    }
+
+    #[cfg(windows)]
+    {
+        let appdata = env::var_os("APPDATA")?;
+        Some(
+            PathBuf::from(appdata)
+                .join("postgresql")
+                .join("pgpass.conf"),
+        )
+    }
+
+    #[cfg(not(windows))]
+    {
+        let home = env::var_os("HOME")?;
+        Some(PathBuf::from(home).join(".pgpass"))
+    }
+}
+
+struct Field {
+    value: String,
+    wildcard: bool,
+}
+
+// splits a line into its five fields, the last of which is the rest of the line
+fn split(line: &str) -> Option<Vec<Field>> {
+    let mut fields = Vec::with_capacity(5);
+    let mut value = String::new();
+    let mut raw = String::new();
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                raw.push(c);
+                if let Some(c) = chars.next() {
+                    raw.push(c);
+                    value.push(c);
+                }
+            }
+            ':' if fields.len() < 4 => {
+                fields.push(Field {
+                    wildcard: raw == "*",
+                    value: mem::take(&mut value),
+                });
+                raw.clear();
+            }
+            c => {
+                raw.push(c);
+                value.push(c);
+            }
+        }
+    }
+
+    if fields.len() < 4 {
+        return None;
+    }
+    fields.push(Field {
+        value,
+        wildcard: false,
+    });
+    Some(fields)
+}
@@ -0,0 +1,468 @@
+//! A pool of connections.
+//!
+//! A [`Pool`] keeps connections open and hands them out as [`PooledClient`]s, which dereference to a [`Client`] and
+//! return their connection to the pool when dropped. At most `max_size` connections are open at once, and callers
+//! wait for one to be returned once they all are in use.
+//!
+//! Connections are checked before being handed out again, and closed once they exceed their maximum lifetime. A
+//! background task closes the connections which have been idle for too long, and opens new ones to keep at least
+//! `min_size` connections open.
+//!
+//! The state of a session, such as the settings changed with `SET`, carries over to the next user of its connection.
+//! Settings which every connection should have are best set with [`Config::runtime_param`].
+//!
+//! Requires the `pool` Cargo feature.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use tokio_postgres::pool::Pool;
+//! use tokio_postgres::NoTls;
+//!
+//! # async fn run() -> Result<(), tokio_postgres::Error> {
+//! let config = "host=localhost user=postgres".parse()?;
+//! let pool = Pool::builder(&config, NoTls)
+//!     .max_size(16)
+//!     .acquire_timeout(Some(Duration::from_secs(5)))
+//!     .build();
+//!
+//! let client = pool.get().await?;
+//! let names: Vec<String> = client.query_scalar("SELECT name FROM users", &[]).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::tls::{MakeTlsConnect, TlsConnect};
+use crate::{info, Client, Config, Error, Socket};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A builder for a [`Pool`].
+pub struct PoolBuilder<T> {
+    config: Config,
+    tls: T,
+    options: Options,
+}
+
+#[derive(Debug, Clone)]
+struct Options {
+    min_size: usize,
+    max_size: usize,
+    acquire_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    health_check: bool,
+    health_check_timeout: Duration,
+    reap_interval: Duration,
+}
+
+impl<T> fmt::Debug for PoolBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolBuilder")
+            .field("config", &self.config)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl<T> PoolBuilder<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    /// Sets the number of connections the pool keeps open, even while they are idle.
+    ///
+    /// Defaults to 0.
+    pub fn min_size(mut self, min_size: usize) -> PoolBuilder<T> {
+        self.options.min_size = min_size;
+        self
+    }
+
+    /// Sets the maximum number of connections open at once.
+    ///
+    /// Defaults to 10.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is 0.
+    pub fn max_size(mut self, max_size: usize) -> PoolBuilder<T> {
+        assert!(max_size > 0, "max_size must be positive");
+        self.options.max_size = max_size;
+        self
+    }
+
+    /// Sets the time [`Pool::get`] waits for a connection, including the time it takes to open one, before failing
+    /// with a timeout error.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn acquire_timeout(mut self, acquire_timeout: Option<Duration>) -> PoolBuilder<T> {
+        self.options.acquire_timeout = acquire_timeout;
+        self
+    }
+
+    /// Sets the time after which a connection is closed instead of being handed out again.
+    ///
+    /// Defaults to 30 minutes.
+    pub fn max_lifetime(mut self, max_lifetime: Option<Duration>) -> PoolBuilder<T> {
+        self.options.max_lifetime = max_lifetime;
+        self
+    }
+
+    /// Sets the time after which an idle connection is closed, unless that would leave fewer than `min_size`
+    /// connections open.
+    ///
+    /// Defaults to 10 minutes.
+    pub fn idle_timeout(mut self, idle_timeout: Option<Duration>) -> PoolBuilder<T> {
+        self.options.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets whether idle connections are checked with [`Client::is_valid`] before being handed out.
+    ///
+    /// Connections which have closed are never handed out, but without the check a connection whose server has
+    /// become unreachable is only noticed by the first query run on it.
+    ///
+    /// Defaults to `true`.
+    pub fn health_check(mut self, health_check: bool) -> PoolBuilder<T> {
+        self.options.health_check = health_check;
+        self
+    }
+
+    /// Sets the time a connection has to pass its health check, after which it is closed.
+    ///
+    /// Defaults to 5 seconds.
+    pub fn health_check_timeout(mut self, health_check_timeout: Duration) -> PoolBuilder<T> {
+        self.options.health_check_timeout = health_check_timeout;
+        self
+    }
+
+    /// Sets the interval at which the background task closes idle and expired connections, and opens the connections
+    /// missing to reach `min_size`.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn reap_interval(mut self, reap_interval: Duration) -> PoolBuilder<T> {
+        self.options.reap_interval = reap_interval;
+        self
+    }
+
+    /// Creates the pool.
+    ///
+    /// No connection is opened until the pool is used or the background task runs. The background task ends once
+    /// every handle to the pool has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn build(self) -> Pool<T> {
+        let inner = Arc::new(Inner {
+            permits: Arc::new(Semaphore::new(self.options.max_size)),
+            shared: Arc::new(Shared {
+                idle: Mutex::new(VecDeque::new()),
+                slots: Arc::new(()),
+                options: self.options,
+            }),
+            config: self.config,
+            tls: self.tls,
+        });
+        tokio::spawn(maintain(Arc::downgrade(&inner)));
+        Pool { inner }
+    }
+}
+
+/// A pool of connections.
+///
+/// Handles to the pool are cheap to clone and share the same connections.
+pub struct Pool<T> {
+    inner: Arc<Inner<T>>,
+}
+
+struct Inner<T> {
+    config: Config,
+    tls: T,
+    permits: Arc<Semaphore>,
+    shared: Arc<Shared>,
+}
+
+// the state needed to return a connection to the pool, which doesn't depend on how connections are made
+struct Shared {
+    idle: Mutex<VecDeque<Idle>>,
+    // every open connection holds a clone, so that connections dropped anywhere, even by a cancelled future, are no
+    // longer counted
+    slots: Arc<()>,
+    options: Options,
+}
+
+struct Conn {
+    client: Client,
+    created: Instant,
+    _slot: Arc<()>,
+}
+
+struct Idle {
+    conn: Conn,
+    since: Instant,
+}
+
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Pool<T> {
+        Pool {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("config", &self.inner.config)
+            .field("options", &self.inner.shared.options)
+            .field("size", &self.size())
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+impl<T> Pool<T> {
+    /// Returns the number of open connections, whether they are idle or in use.
+    pub fn size(&self) -> usize {
+        self.inner.shared.size()
+    }
+
+    /// Returns the number of idle connections.
+    pub fn idle(&self) -> usize {
+        self.inner.shared.idle.lock().len()
+    }
+}
+
+impl<T> Pool<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + Sync + 'static,
+    T::Stream: Send,
+    T::TlsConnect: Send,
+    <T::TlsConnect as TlsConnect<Socket>>::Future: Send,
+{
+    /// Returns a builder for a pool connecting with `config`.
+    pub fn builder(config: &Config, tls: T) -> PoolBuilder<T> {
+        PoolBuilder {
+            config: config.clone(),
+            tls,
+            options: Options {
+                min_size: 0,
+                max_size: 10,
+                acquire_timeout: Some(Duration::from_secs(30)),
+                max_lifetime: Some(Duration::from_secs(30 * 60)),
+                idle_timeout: Some(Duration::from_secs(10 * 60)),
+                health_check: true,
+                health_check_timeout: Duration::from_secs(5),
+                reap_interval: Duration::from_secs(30),
+            },
+        }
+    }
+
+    /// Creates a pool connecting with `config`, with the default settings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn new(config: &Config, tls: T) -> Pool<T> {
+        Pool::builder(config, tls).build()
+    }
+
+    /// Returns a connection from the pool, opening a new one if none is idle.
+    ///
+    /// Waits for a connection to be returned if `max_size` connections are already in use, and fails with a timeout
+    /// error if none is available within the `acquire_timeout`.
+    pub async fn get(&self) -> Result<PooledClient, Error> {
+        match self.inner.shared.options.acquire_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.acquire())
+                .await
+                .map_err(|_| Error::__private_api_timeout())?,
+            None => self.acquire().await,
+        }
+    }
+
+    async fn acquire(&self) -> Result<PooledClient, Error> {
+        let permit = self
+            .inner
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("pool semaphore closed");
+
+        loop {
+            let idle = self.inner.shared.idle.lock().pop_back();
+            let conn = match idle {
+                Some(idle) => idle.conn,
+                None => break,
+            };
+            if self.inner.shared.is_usable(&conn).await {
+                return Ok(PooledClient::new(conn, &self.inner.shared, permit));
+            }
+        }
+
+        let conn = self.inner.connect().await?;
+        Ok(PooledClient::new(conn, &self.inner.shared, permit))
+    }
+}
+
+impl<T> Inner<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    async fn connect(&self) -> Result<Conn, Error> {
+        let (client, connection) = self.config.connect(self.tls.clone()).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                info!("pooled connection failed: {}", e);
+            }
+        });
+        Ok(Conn {
+            client,
+            created: Instant::now(),
+            _slot: self.shared.slots.clone(),
+        })
+    }
+
+    // opens the connections missing to reach `min_size`, without waiting for connections in use
+    async fn replenish(&self) {
+        while self.shared.size() < self.shared.options.min_size {
+            let _permit = match self.permits.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            match self.connect().await {
+                Ok(conn) => self.shared.idle.lock().push_front(Idle {
+                    conn,
+                    since: Instant::now(),
+                }),
+                Err(e) => {
+                    info!("failed to open pooled connection: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn size(&self) -> usize {
+        Arc::strong_count(&self.slots) - 1
+    }
+
+    fn is_expired(&self, conn: &Conn) -> bool {
+        conn.client.is_closed()
+            || self
+                .options
+                .max_lifetime
+                .is_some_and(|lifetime| conn.created.elapsed() >= lifetime)
+    }
+
+    async fn is_usable(&self, conn: &Conn) -> bool {
+        !self.is_expired(conn)
+            && (!self.options.health_check
+                || conn
+                    .client
+                    .is_valid(self.options.health_check_timeout)
+                    .await
+                    .is_ok())
+    }
+
+    // closes the expired connections, and the connections idle for too long in excess of `min_size`
+    fn reap(&self) {
+        let mut idle = self.idle.lock();
+        idle.retain(|idle| !self.is_expired(&idle.conn));
+
+        // the least recently used connections are at the front
+        if let Some(timeout) = self.options.idle_timeout {
+            while self.size() > self.options.min_size
+                && idle
+                    .front()
+                    .is_some_and(|idle| idle.since.elapsed() >= timeout)
+            {
+                idle.pop_front();
+            }
+        }
+    }
+}
+
+async fn maintain<T>(inner: Weak<Inner<T>>)
+where
+    T: MakeTlsConnect<Socket> + Clone,
+    T::Stream: Send + 'static,
+{
+    loop {
+        let interval = match inner.upgrade() {
+            Some(inner) => inner.shared.options.reap_interval,
+            None => return,
+        };
+        tokio::time::sleep(interval).await;
+
+        let inner = match inner.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        inner.shared.reap();
+        inner.replenish().await;
+    }
+}
+
+/// A connection borrowed from a [`Pool`].
+///
+/// It dereferences to a [`Client`], and returns the connection to the pool when dropped, unless it has closed or
+/// exceeded its maximum lifetime. It also implements [`GenericClient`](crate::GenericClient), so that code abstracting
+/// over clients and transactions can own a pooled connection.
+pub struct PooledClient {
+    conn: Option<Conn>,
+    shared: Arc<Shared>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl PooledClient {
+    fn new(conn: Conn, shared: &Arc<Shared>, permit: OwnedSemaphorePermit) -> PooledClient {
+        PooledClient {
+            conn: Some(conn),
+            shared: shared.clone(),
+            _permit: permit,
+        }
+    }
+}
+
+impl fmt::Debug for PooledClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledClient").finish()
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.conn.as_ref().unwrap().client
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.conn.as_mut().unwrap().client
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        let conn = self.conn.take().unwrap();
+        if !self.shared.is_expired(&conn) {
+            // the permit is only released afterwards, so that the next caller finds the connection
+            self.shared.idle.lock().push_back(Idle {
+                conn,
+                since: Instant::now(),
+            });
+        }
+    }
+}
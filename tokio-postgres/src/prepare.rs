@@ -63,7 +63,61 @@ pub async fn prepare(
     types: &[Type],
 ) -> Result<Statement, Error> {
     let name = format!("s{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
-    let buf = encode(client, &name, query, types)?;
+    prepare_named(client, name, query, types).await
+}
+
+/// Like `prepare`, but reuses the statement prepared for the same query and types if the statement cache is enabled.
+pub async fn prepare_cached(
+    client: &Arc<InnerClient>,
+    query: &str,
+    types: &[Type],
+) -> Result<Statement, Error> {
+    if !client.statement_cache_enabled() {
+        return prepare(client, query, types).await;
+    }
+
+    // the comment depends on the context of each call, so commented statements can't be shared
+    #[cfg(feature = "sqlcommenter")]
+    if client
+        .sql_commenter()
+        .and_then(|c| c.comment(query))
+        .is_some()
+    {
+        return prepare(client, query, types).await;
+    }
+
+    if let Some(statement) = client.cached_statement(query, types) {
+        return Ok(statement);
+    }
+
+    let statement = prepare(client, query, types).await?;
+    client.cache_statement(query, types, &statement);
+    Ok(statement)
+}
+
+async fn prepare_named(
+    client: &Arc<InnerClient>,
+    name: String,
+    query: &str,
+    types: &[Type],
+) -> Result<Statement, Error> {
+    #[cfg(feature = "sqlcommenter")]
+    if let Some(commented) = client.sql_commenter().and_then(|c| c.comment(query)) {
+        return prepare_text(client, name, query, &commented, types).await;
+    }
+
+    prepare_text(client, name, query, query, types).await
+}
+
+// `text` is sent to the server, while `query` is what the statement is identified by.
+async fn prepare_text(
+    client: &Arc<InnerClient>,
+    name: String,
+    query: &str,
+    text: &str,
+    types: &[Type],
+) -> Result<Statement, Error> {
+    let buf = encode(client, &name, text, types)?;
     let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
 
     match responses.next().await? {
@@ -104,7 +158,31 @@ pub async fn prepare(
         }
     }
 
-    Ok(Statement::new(client, name, parameters, columns))
+    Ok(Statement::new(
+        client,
+        name,
+        query.to_string(),
+        parameters,
+        columns,
+    ))
+}
+
+/// Prepares a statement of another connection on this one, reusing the result for later uses of the same query.
+pub async fn reprepare(
+    client: &Arc<InnerClient>,
+    statement: &Statement,
+) -> Result<Statement, Error> {
+    if let Some(reprepared) = client.reprepared(statement) {
+        return Ok(reprepared);
+    }
+
+    debug!(
+        "preparing statement {} on a new connection",
+        statement.name()
+    );
+    let reprepared = prepare(client, statement.sql(), statement.params()).await?;
+    client.set_reprepared(statement, &reprepared);
+    Ok(reprepared)
 }
 
 fn prepare_rec<'a>(
@@ -112,7 +190,11 @@ fn prepare_rec<'a>(
     query: &'a str,
     types: &'a [Type],
 ) -> Pin<Box<dyn Future<Output = Result<Statement, Error>> + 'a + Send>> {
-    Box::pin(prepare(client, query, types))
+    // internal queries are never commented
+    Box::pin(async move {
+        let name = format!("s{}", NEXT_ID.fetch_add(1, Ordering::SeqCst));
+        prepare_text(client, name, query, query, types).await
+    })
 }
 
 fn encode(client: &InnerClient, name: &str, query: &str, types: &[Type]) -> Result<Bytes, Error> {
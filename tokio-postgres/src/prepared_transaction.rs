@@ -0,0 +1,44 @@
+use crate::Row;
+use std::time::SystemTime;
+
+/// A transaction prepared for two-phase commit, as returned by
+/// [`Client::prepared_transactions`](crate::Client::prepared_transactions).
+#[derive(Debug, Clone)]
+pub struct PreparedTransaction {
+    gid: String,
+    prepared: SystemTime,
+    owner: String,
+    database: String,
+}
+
+impl PreparedTransaction {
+    pub(crate) fn from_row(row: &Row) -> PreparedTransaction {
+        PreparedTransaction {
+            gid: row.get(0),
+            prepared: row.get(1),
+            owner: row.get(2),
+            database: row.get(3),
+        }
+    }
+
+    /// Returns the global identifier the transaction was prepared with.
+    pub fn gid(&self) -> &str {
+        &self.gid
+    }
+
+    /// Returns the time at which the transaction was prepared.
+    pub fn prepared(&self) -> SystemTime {
+        self.prepared
+    }
+
+    /// Returns the name of the user that prepared the transaction.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Returns the name of the database the transaction was prepared in, which is the one it must be committed or
+    /// rolled back from.
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+}
@@ -1,16 +1,22 @@
 use crate::client::{InnerClient, Responses};
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
-use crate::types::{BorrowToSql, IsNull};
-use crate::{debug, Error, Portal, Row, Statement};
+use crate::statement_stats::StatsGuard;
+use crate::types::{BorrowToSql, Format, IsNull};
+#[cfg(feature = "arrow")]
+use crate::Column;
+use crate::{debug, Error, FromRow, IntoParams, Portal, Row, Statement};
 use bytes::{Bytes, BytesMut};
+use futures_channel::mpsc;
 use futures_util::{ready, Stream};
 use pin_project_lite::pin_project;
 use postgres_protocol::message::backend::{CommandCompleteBody, Message};
 use postgres_protocol::message::frontend;
 use std::fmt;
-use std::marker::PhantomPinned;
+use std::marker::{PhantomData, PhantomPinned};
+use std::mem;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 struct BorrowToSqlParamsDebug<'a, T>(&'a [T]);
@@ -53,6 +59,21 @@ pub async fn query<P, I>(
     statement: Statement,
     params: I,
 ) -> Result<RowStream, Error>
+where
+    P: BorrowToSql,
+    I: IntoIterator<Item = P>,
+    I::IntoIter: ExactSizeIterator,
+{
+    query_with_formats(client, statement, params, None).await
+}
+
+/// Like `query`, but requests the columns of the result in `formats`, or all in the binary format if `None`.
+pub async fn query_with_formats<P, I>(
+    client: &InnerClient,
+    statement: Statement,
+    params: I,
+    formats: Option<Arc<[Format]>>,
+) -> Result<RowStream, Error>
 where
     P: BorrowToSql,
     I: IntoIterator<Item = P>,
@@ -65,15 +86,86 @@ where
             statement.name(),
             BorrowToSqlParamsDebug(params.as_slice()),
         );
-        encode(client, &statement, params)?
+        encode_with_formats(client, &statement, params, formats.as_deref())?
     } else {
-        encode(client, &statement, params)?
+        encode_with_formats(client, &statement, params, formats.as_deref())?
     };
-    let responses = start(client, buf).await?;
+    let mut stats = client.track(&statement);
+    let responses = start(client, buf)
+        .await
+        .map_err(|e| failed(&mut stats, e))?;
     Ok(RowStream {
         statement,
+        formats,
         responses,
         rows_affected: None,
+        fetch: None,
+        stats,
+        _p: PhantomPinned,
+    })
+}
+
+pub async fn query_chunked<P, I>(
+    client: &InnerClient,
+    statement: Statement,
+    params: I,
+    fetch_size: i32,
+) -> Result<RowStream, Error>
+where
+    P: BorrowToSql,
+    I: IntoIterator<Item = P>,
+    I::IntoIter: ExactSizeIterator,
+{
+    if fetch_size <= 0 {
+        return query(client, statement, params).await;
+    }
+
+    debug!(
+        "executing statement {} in chunks of {} rows",
+        statement.name(),
+        fetch_size,
+    );
+
+    // The portal only survives until the next Sync, so the request is kept open with Flush messages and only synced
+    // once the portal has been drained (or the stream is dropped).
+    let buf = client.with_buf(|buf| {
+        encode_bind(&statement, params, "", buf)?;
+        frontend::execute("", fetch_size, buf).map_err(Error::encode)?;
+        frontend::flush(buf);
+        Ok(buf.split().freeze())
+    })?;
+
+    let (sender, receiver) = mpsc::unbounded();
+    sender
+        .unbounded_send(FrontendMessage::Raw(buf))
+        .map_err(|_| Error::closed())?;
+    let mut fetch = Fetch {
+        sender: Some(sender),
+        fetch_size,
+        fetched: 0,
+    };
+
+    let mut stats = client.track(&statement);
+    let mut responses = client.send(RequestMessages::Fetch(receiver))?;
+    match responses.next().await {
+        Ok(Message::BindComplete) => {}
+        Ok(_) => {
+            fetch.sync();
+            return Err(failed(&mut stats, Error::unexpected_message()));
+        }
+        Err(e) => {
+            fetch.sync();
+            return Err(failed(&mut stats, e));
+        }
+    }
+
+    Ok(RowStream {
+        statement,
+        formats: None,
+        responses,
+        rows_affected: None,
+        fetch: Some(fetch),
+        stats,
         _p: PhantomPinned,
     })
 }
@@ -93,8 +185,11 @@ pub async fn query_portal(
 
     Ok(RowStream {
         statement: portal.statement().clone(),
+        formats: None,
         responses,
         rows_affected: None,
+        fetch: None,
+        stats: None,
         _p: PhantomPinned,
     })
 }
@@ -133,6 +228,20 @@ where
     } else {
         encode(client, &statement, params)?
     };
+
+    let mut stats = client.track(&statement);
+    match execute_inner(client, buf).await {
+        Ok(rows) => {
+            if let Some(stats) = &mut stats {
+                stats.add_rows(rows);
+            }
+            Ok(rows)
+        }
+        Err(e) => Err(failed(&mut stats, e)),
+    }
+}
+
+async fn execute_inner(client: &InnerClient, buf: Bytes) -> Result<u64, Error> {
     let mut responses = start(client, buf).await?;
 
     let mut rows = 0;
@@ -149,6 +258,76 @@ where
     }
 }
 
+pub async fn execute_many<P, I>(
+    client: &InnerClient,
+    statement: Statement,
+    params: I,
+) -> Result<Vec<u64>, Error>
+where
+    P: IntoParams,
+    I: IntoIterator<Item = P>,
+{
+    // every Bind/Execute pair is written ahead of a single Sync, so the executions run in one implicit transaction
+    // and cost a single round trip
+    let mut count = 0;
+    let buf = client.with_buf(|buf| {
+        for params in params {
+            encode_bind(&statement, params.to_params(), "", buf)?;
+            frontend::execute("", 0, buf).map_err(Error::encode)?;
+            count += 1;
+        }
+        frontend::sync(buf);
+        Ok(buf.split().freeze())
+    })?;
+    if count == 0 {
+        return Ok(vec![]);
+    }
+
+    debug!(
+        "executing statement {} with {} parameter sets",
+        statement.name(),
+        count,
+    );
+
+    let mut stats = client.track(&statement);
+    match execute_many_inner(client, buf, count).await {
+        Ok(rows) => {
+            if let Some(stats) = &mut stats {
+                stats.add_rows(rows.iter().sum());
+            }
+            Ok(rows)
+        }
+        Err(e) => Err(failed(&mut stats, e)),
+    }
+}
+
+async fn execute_many_inner(
+    client: &InnerClient,
+    buf: Bytes,
+    count: usize,
+) -> Result<Vec<u64>, Error> {
+    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+
+    let mut rows = Vec::with_capacity(count);
+    loop {
+        match responses.next().await? {
+            Message::BindComplete | Message::DataRow(_) => {}
+            Message::CommandComplete(body) => rows.push(extract_row_affected(&body)?),
+            Message::EmptyQueryResponse => rows.push(0),
+            Message::ReadyForQuery(_) if rows.len() == count => return Ok(rows),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+/// Marks a tracked execution as failed.
+fn failed(stats: &mut Option<StatsGuard>, e: Error) -> Error {
+    if let Some(stats) = stats {
+        stats.set_error();
+    }
+    e
+}
+
 async fn start(client: &InnerClient, buf: Bytes) -> Result<Responses, Error> {
     let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
 
@@ -161,13 +340,27 @@ async fn start(client: &InnerClient, buf: Bytes) -> Result<Responses, Error> {
 }
 
 pub fn encode<P, I>(client: &InnerClient, statement: &Statement, params: I) -> Result<Bytes, Error>
+where
+    P: BorrowToSql,
+    I: IntoIterator<Item = P>,
+    I::IntoIter: ExactSizeIterator,
+{
+    encode_with_formats(client, statement, params, None)
+}
+
+fn encode_with_formats<P, I>(
+    client: &InnerClient,
+    statement: &Statement,
+    params: I,
+    formats: Option<&[Format]>,
+) -> Result<Bytes, Error>
 where
     P: BorrowToSql,
     I: IntoIterator<Item = P>,
     I::IntoIter: ExactSizeIterator,
 {
     client.with_buf(|buf| {
-        encode_bind(statement, params, "", buf)?;
+        encode_bind_with_formats(statement, params, "", formats, buf)?;
         frontend::execute("", 0, buf).map_err(Error::encode)?;
         frontend::sync(buf);
         Ok(buf.split().freeze())
@@ -180,6 +373,21 @@ pub fn encode_bind<P, I>(
     portal: &str,
     buf: &mut BytesMut,
 ) -> Result<(), Error>
+where
+    P: BorrowToSql,
+    I: IntoIterator<Item = P>,
+    I::IntoIter: ExactSizeIterator,
+{
+    encode_bind_with_formats(statement, params, portal, None, buf)
+}
+
+fn encode_bind_with_formats<P, I>(
+    statement: &Statement,
+    params: I,
+    portal: &str,
+    formats: Option<&[Format]>,
+    buf: &mut BytesMut,
+) -> Result<(), Error>
 where
     P: BorrowToSql,
     I: IntoIterator<Item = P>,
@@ -199,6 +407,12 @@ where
 
     let params = params.into_iter();
 
+    // a single format code applies to every column
+    let result_formats = match formats {
+        Some(formats) => formats.iter().map(|f| *f as i16).collect(),
+        None => vec![1],
+    };
+
     let mut error_idx = 0;
     let r = frontend::bind(
         portal,
@@ -213,7 +427,7 @@ where
                 Err(e)
             }
         },
-        Some(1),
+        result_formats,
         buf,
     );
     match r {
@@ -223,12 +437,55 @@ where
     }
 }
 
+/// The sending half of a chunked query, used to request more rows from the unnamed portal.
+struct Fetch {
+    sender: Option<mpsc::UnboundedSender<FrontendMessage>>,
+    fetch_size: i32,
+    /// Rows returned by previous executions, since a `CommandComplete` only counts the rows of the last one.
+    fetched: u64,
+}
+
+impl Fetch {
+    fn execute(&mut self) -> Result<(), Error> {
+        let sender = match &self.sender {
+            Some(sender) => sender,
+            None => return Err(Error::unexpected_message()),
+        };
+
+        self.fetched += self.fetch_size as u64;
+        let mut buf = BytesMut::new();
+        frontend::execute("", self.fetch_size, &mut buf).map_err(Error::encode)?;
+        frontend::flush(&mut buf);
+        sender
+            .unbounded_send(FrontendMessage::Raw(buf.freeze()))
+            .map_err(|_| Error::closed())
+    }
+
+    /// Ends the request, closing the portal. Does nothing if the request already ended.
+    fn sync(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let mut buf = BytesMut::new();
+            frontend::sync(&mut buf);
+            let _ = sender.unbounded_send(FrontendMessage::Raw(buf.freeze()));
+        }
+    }
+}
+
+impl Drop for Fetch {
+    fn drop(&mut self) {
+        self.sync();
+    }
+}
+
 pin_project! {
     /// A stream of table rows.
     pub struct RowStream {
         statement: Statement,
+        formats: Option<Arc<[Format]>>,
         responses: Responses,
         rows_affected: Option<u64>,
+        fetch: Option<Fetch>,
+        stats: Option<StatsGuard>,
         #[pin]
         _p: PhantomPinned,
     }
@@ -240,16 +497,53 @@ impl Stream for RowStream {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
         loop {
-            match ready!(this.responses.poll_next(cx)?) {
+            let message = match ready!(this.responses.poll_next(cx)) {
+                Ok(message) => message,
+                Err(e) => {
+                    if let Some(fetch) = this.fetch {
+                        fetch.sync();
+                    }
+                    return Poll::Ready(Some(Err(failed(this.stats, e))));
+                }
+            };
+
+            match message {
                 Message::DataRow(body) => {
-                    return Poll::Ready(Some(Ok(Row::new(this.statement.clone(), body)?)))
+                    if let Some(stats) = this.stats {
+                        stats.add_rows(1);
+                    }
+                    return Poll::Ready(Some(Ok(Row::new(
+                        this.statement.clone(),
+                        this.formats.clone(),
+                        body,
+                    )?)));
                 }
                 Message::CommandComplete(body) => {
-                    *this.rows_affected = Some(extract_row_affected(&body)?);
+                    let mut rows = extract_row_affected(&body)?;
+                    if let Some(fetch) = this.fetch {
+                        rows += fetch.fetched;
+                        fetch.sync();
+                    }
+                    *this.rows_affected = Some(rows);
+                }
+                Message::EmptyQueryResponse => {
+                    if let Some(fetch) = this.fetch {
+                        fetch.sync();
+                    }
+                }
+                Message::PortalSuspended => {
+                    if let Some(fetch) = this.fetch {
+                        fetch.execute()?;
+                    }
+                }
+                Message::ReadyForQuery(_) => {
+                    // the execution is recorded when the guard is dropped
+                    *this.stats = None;
+                    return Poll::Ready(None);
+                }
+                _ => {
+                    return Poll::Ready(Some(Err(failed(this.stats, Error::unexpected_message()))))
                 }
-                Message::EmptyQueryResponse | Message::PortalSuspended => {}
-                Message::ReadyForQuery(_) => return Poll::Ready(None),
-                _ => return Poll::Ready(Some(Err(Error::unexpected_message()))),
             }
         }
     }
@@ -262,4 +556,138 @@ impl RowStream {
     pub fn rows_affected(&self) -> Option<u64> {
         self.rows_affected
     }
+
+    #[cfg(feature = "arrow")]
+    pub(crate) fn columns(&self) -> &[Column] {
+        self.statement.columns()
+    }
+
+    #[cfg(feature = "arrow")]
+    pub(crate) fn column_format(&self, idx: usize) -> Format {
+        match &self.formats {
+            Some(formats) => formats[idx],
+            None => Format::Binary,
+        }
+    }
+
+    /// Returns a stream of batches of up to `size` rows.
+    ///
+    /// Every batch but the last one has exactly `size` rows. If an error occurs, the rows received before it are
+    /// returned as a batch first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn chunks(self, size: usize) -> RowChunks {
+        assert!(size > 0, "chunk size must be positive");
+
+        RowChunks {
+            rows: self,
+            size,
+            chunk: vec![],
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Returns a stream of the rows converted to `R`.
+    pub fn map_as<R>(self) -> RowStreamAs<R>
+    where
+        R: FromRow,
+    {
+        RowStreamAs {
+            rows: self,
+            _p: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    /// A stream of batches of table rows, returned by [`RowStream::chunks`].
+    pub struct RowChunks {
+        #[pin]
+        rows: RowStream,
+        size: usize,
+        chunk: Vec<Row>,
+        error: Option<Error>,
+        done: bool,
+    }
+}
+
+impl Stream for RowChunks {
+    type Item = Result<Vec<Row>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if let Some(e) = this.error.take() {
+            return Poll::Ready(Some(Err(e)));
+        }
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match ready!(this.rows.as_mut().poll_next(cx)) {
+                Some(Ok(row)) => {
+                    this.chunk.push(row);
+                    if this.chunk.len() == *this.size {
+                        return Poll::Ready(Some(Ok(mem::take(this.chunk))));
+                    }
+                }
+                Some(Err(e)) => {
+                    if this.chunk.is_empty() {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    *this.error = Some(e);
+                    return Poll::Ready(Some(Ok(mem::take(this.chunk))));
+                }
+                None => {
+                    *this.done = true;
+                    if this.chunk.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Ok(mem::take(this.chunk))));
+                }
+            }
+        }
+    }
+}
+
+impl RowChunks {
+    /// Returns the number of rows affected by the query.
+    ///
+    /// This function will return `None` until the stream has been exhausted.
+    pub fn rows_affected(&self) -> Option<u64> {
+        self.rows.rows_affected()
+    }
+}
+
+pin_project! {
+    /// A stream of table rows converted to `R`, returned by [`RowStream::map_as`].
+    pub struct RowStreamAs<R> {
+        #[pin]
+        rows: RowStream,
+        _p: PhantomData<fn() -> R>,
+    }
+}
+
+impl<R> Stream for RowStreamAs<R>
+where
+    R: FromRow,
+{
+    type Item = Result<R, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let row = ready!(self.project().rows.poll_next(cx));
+        Poll::Ready(row.map(|row| row.and_then(|row| R::from_row(&row))))
+    }
+}
+
+impl<R> RowStreamAs<R> {
+    /// Returns the number of rows affected by the query.
+    ///
+    /// This function will return `None` until the stream has been exhausted.
+    pub fn rows_affected(&self) -> Option<u64> {
+        self.rows.rows_affected()
+    }
 }
@@ -0,0 +1,135 @@
+use crate::types::Format;
+use crate::Error;
+use std::sync::Arc;
+#[cfg(feature = "runtime")]
+use std::time::Duration;
+
+/// Options controlling how a query is run and how its results are returned, used with [`Client::query_with_options`].
+///
+/// By default every column of the result is returned in the binary format, in which values are decoded with their
+/// `FromSql` implementations. Some types, notably those of extensions, only implement the text format, and fail the
+/// whole query when asked for their binary representation. Such columns can instead be requested in the text format,
+/// and read with [`Row::get_text`] or as a `&str` or `String`, while the other columns stay in the binary format.
+///
+/// ```no_run
+/// use tokio_postgres::types::Format;
+/// use tokio_postgres::QueryOptions;
+///
+/// # async fn f(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+/// let options = QueryOptions::new().column_format(1, Format::Text);
+/// let rows = client
+///     .query_with_options("SELECT id, shape FROM shapes", &[], &options)
+///     .await?;
+/// for row in rows {
+///     let id: i32 = row.get(0);
+///     let shape: &str = row.get(1);
+///     println!("{}: {}", id, shape);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client::query_with_options`]: crate::Client::query_with_options
+/// [`Row::get_text`]: crate::Row::get_text
+#[derive(Debug, Clone)]
+pub struct QueryOptions {
+    result_format: Format,
+    column_formats: Vec<Option<Format>>,
+    #[cfg(feature = "runtime")]
+    timeout: Option<Duration>,
+}
+
+impl Default for QueryOptions {
+    fn default() -> QueryOptions {
+        QueryOptions::new()
+    }
+}
+
+impl QueryOptions {
+    /// Creates options returning every column in the binary format.
+    pub fn new() -> QueryOptions {
+        QueryOptions {
+            result_format: Format::Binary,
+            column_formats: vec![],
+            #[cfg(feature = "runtime")]
+            timeout: None,
+        }
+    }
+
+    /// Sets the format of the columns without a format of their own.
+    ///
+    /// Defaults to `Format::Binary`.
+    pub fn result_format(mut self, format: Format) -> QueryOptions {
+        self.result_format = format;
+        self
+    }
+
+    /// Sets the format of the column at index `idx`.
+    pub fn column_format(mut self, idx: usize, format: Format) -> QueryOptions {
+        if self.column_formats.len() <= idx {
+            self.column_formats.resize(idx + 1, None);
+        }
+        self.column_formats[idx] = Some(format);
+        self
+    }
+
+    /// Sets the formats of the leading columns of the result, in order.
+    ///
+    /// The remaining columns use the format set with [`QueryOptions::result_format`].
+    pub fn column_formats(mut self, formats: &[Format]) -> QueryOptions {
+        for (idx, format) in formats.iter().enumerate() {
+            self = self.column_format(idx, *format);
+        }
+        self
+    }
+
+    /// Sets the time the query may take before it is cancelled.
+    ///
+    /// Once the timeout expires, a cancel request is sent to the server and the query fails with a timeout error. As
+    /// with [`CancelToken`](crate::CancelToken), the cancellation is racy and applies to the connection, so a query
+    /// run concurrently on the same client may be the one cancelled, and a cancelled query aborts the transaction it
+    /// runs in. The cancel request is sent without TLS, like libpq's `PQcancel` does; use a `CancelToken` with a TLS
+    /// connector instead if that is not acceptable.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    #[cfg(feature = "runtime")]
+    pub fn timeout(mut self, timeout: Duration) -> QueryOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the time the query may take before it is cancelled, if one has been set.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    #[cfg(feature = "runtime")]
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the format of the column at index `idx`.
+    pub fn get_column_format(&self, idx: usize) -> Format {
+        self.column_formats
+            .get(idx)
+            .copied()
+            .flatten()
+            .unwrap_or(self.result_format)
+    }
+
+    /// Resolves the formats of a result with `columns` columns, or `None` if they are all binary.
+    ///
+    /// Fails if a format was set for a column past the end of the result.
+    pub(crate) fn resolve(&self, columns: usize) -> Result<Option<Arc<[Format]>>, Error> {
+        if self.column_formats.len() > columns {
+            return Err(Error::column((self.column_formats.len() - 1).to_string()));
+        }
+
+        let formats = (0..columns)
+            .map(|idx| self.get_column_format(idx))
+            .collect::<Arc<[_]>>();
+        if formats.iter().all(|f| matches!(f, Format::Binary)) {
+            Ok(None)
+        } else {
+            Ok(Some(formats))
+        }
+    }
+}
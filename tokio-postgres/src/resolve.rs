@@ -0,0 +1,92 @@
+//! Host name resolution.
+//!
+//! When connecting, the names of TCP hosts are normally looked up with the system resolver. A [`Resolve`]
+//! implementation set with [`Config::resolver`](crate::Config::resolver) replaces that lookup, for example to use an
+//! asynchronous DNS client, to cache lookups, or to resolve internal names differently than the system does.
+//!
+//! The resolver is not used for hosts which have a `hostaddr`, for Unix socket hosts, or when connecting through a
+//! SOCKS5 proxy, which resolves host names itself.
+//!
+//! Requires the `runtime` Cargo feature (enabled by default).
+//!
+//! ```no_run
+//! use futures_util::future::{self, BoxFuture};
+//! use std::io;
+//! use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+//! use tokio_postgres::resolve::{Resolve, SystemResolver};
+//! use tokio_postgres::Config;
+//!
+//! // resolves `db.internal` to a fixed address, and everything else as usual
+//! struct Internal;
+//!
+//! impl Resolve for Internal {
+//!     fn resolve<'a>(
+//!         &'a self,
+//!         host: &'a str,
+//!         port: u16,
+//!     ) -> BoxFuture<'a, io::Result<Vec<SocketAddr>>> {
+//!         if host == "db.internal" {
+//!             let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+//!             return Box::pin(future::ready(Ok(vec![SocketAddr::new(ip, port)])));
+//!         }
+//!         SystemResolver.resolve(host, port)
+//!     }
+//! }
+//!
+//! let mut config = "host=db.internal user=postgres".parse::<Config>().unwrap();
+//! config.resolver(Internal);
+//! ```
+
+use futures_util::future::BoxFuture;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net;
+
+/// A resolver of host names.
+pub trait Resolve: Send + Sync {
+    /// Looks up the addresses of a host.
+    ///
+    /// The addresses are tried in the order returned, unless hosts are load balanced randomly. Returning no addresses
+    /// fails the connection attempt to the host.
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<SocketAddr>>>;
+}
+
+/// The resolver used by default, which looks up host names with the system resolver.
+///
+/// Custom resolvers can delegate to it for the names they don't handle themselves.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<SocketAddr>>> {
+        Box::pin(async move { Ok(net::lookup_host((host, port)).await?.collect()) })
+    }
+}
+
+/// A shared resolver, compared by identity so that `Config` stays comparable.
+#[derive(Clone)]
+pub(crate) struct Resolver(pub(crate) Arc<dyn Resolve>);
+
+impl PartialEq for Resolver {
+    fn eq(&self, other: &Resolver) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for Resolver {}
+
+impl fmt::Debug for Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Resolver")
+    }
+}
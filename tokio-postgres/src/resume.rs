@@ -0,0 +1,124 @@
+use crate::client::InnerClient;
+use crate::connect::connect;
+use crate::tls::MakeTlsConnect;
+use crate::{info, Client, Config, Connection, Error, Socket};
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// A connection which is re-established whenever it is lost, while its [`Client`] stays usable.
+///
+/// This is returned along with a client by [`Config::connect_resuming`]. Like a [`Connection`], it performs the
+/// actual IO with the server, and [`ResumingConnection::run`] should be spawned off onto an executor.
+///
+/// When the connection is lost, the queries which were in flight fail, and queries made afterwards wait until a new
+/// connection has been established. The new connection has a new session: statements are prepared again the first
+/// time they are used, and [`ConnectionEvent::Resumed`](crate::ConnectionEvent::Resumed) is sent to the client's
+/// [connection events](Client::connection_events) so that other session state can be restored. A transaction which
+/// was open is lost along with its session: its remaining statements and its commit fail rather than running outside
+/// of it.
+///
+/// Requires the `runtime` Cargo feature (enabled by default).
+///
+/// ```no_run
+/// use tokio_postgres::{Config, NoTls};
+///
+/// # async fn run() -> Result<(), tokio_postgres::Error> {
+/// let config = "host=localhost user=postgres".parse::<Config>()?;
+/// let (client, connection) = config.connect_resuming(NoTls).await?;
+/// tokio::spawn(connection.run());
+///
+/// // `client` keeps working when the connection is re-established
+/// client.batch_execute("SELECT 1").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ResumingConnection<T>
+where
+    T: MakeTlsConnect<Socket>,
+{
+    config: Config,
+    tls: T,
+    client: Weak<InnerClient>,
+    connection: Connection<Socket, T::Stream>,
+    reconnect_delay: Duration,
+}
+
+impl<T> fmt::Debug for ResumingConnection<T>
+where
+    T: MakeTlsConnect<Socket>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResumingConnection")
+            .field("config", &self.config)
+            .field("reconnect_delay", &self.reconnect_delay)
+            .finish()
+    }
+}
+
+impl<T> ResumingConnection<T>
+where
+    T: MakeTlsConnect<Socket> + Clone,
+{
+    pub(crate) async fn connect(
+        config: &Config,
+        tls: T,
+    ) -> Result<(Client, ResumingConnection<T>), Error> {
+        let (client, connection) = connect(tls.clone(), config).await?;
+        client.inner().events().set_resuming(true);
+        let connection = ResumingConnection {
+            config: config.clone(),
+            tls,
+            client: Arc::downgrade(client.inner()),
+            connection,
+            reconnect_delay: Duration::from_secs(1),
+        };
+        Ok((client, connection))
+    }
+
+    /// Sets the delay before each attempt to reconnect.
+    ///
+    /// Defaults to 1 second.
+    pub fn reconnect_delay(mut self, delay: Duration) -> ResumingConnection<T> {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Runs the connection, reconnecting whenever it is lost.
+    ///
+    /// The returned future resolves once the client has been dropped or closed and all outstanding work has
+    /// completed. If the client has been dropped when the connection is lost, the error is returned rather than
+    /// reconnecting.
+    pub async fn run(mut self) -> Result<(), Error> {
+        loop {
+            let error = match (&mut self.connection).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            info!("connection lost, reconnecting: {}", &error);
+
+            loop {
+                if self.client.strong_count() == 0 {
+                    return Err(error);
+                }
+
+                tokio::time::sleep(self.reconnect_delay).await;
+                let (client, connection) = match connect(self.tls.clone(), &self.config).await {
+                    Ok(connected) => connected,
+                    Err(e) => {
+                        info!("failed to reconnect: {}", e);
+                        continue;
+                    }
+                };
+
+                let inner = match self.client.upgrade() {
+                    Some(inner) => inner,
+                    None => return Err(error),
+                };
+                self.connection.resume(connection);
+                inner.resume(client.inner());
+                break;
+            }
+        }
+    }
+}
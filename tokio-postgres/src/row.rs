@@ -3,13 +3,17 @@
 use crate::row::sealed::{AsName, Sealed};
 use crate::simple_query::SimpleColumn;
 use crate::statement::Column;
-use crate::types::{FromSql, Type, WrongType};
+use crate::types::{Format, FromSql, IsNull, ToSql, Type, WasNull, WrongType};
 use crate::{Error, Statement};
+use bytes::{BufMut, BytesMut};
 use fallible_iterator::FallibleIterator;
-use postgres_protocol::message::backend::DataRowBody;
+use postgres_protocol::message::backend::{DataRowBody, Message};
+use postgres_protocol::types;
+use std::borrow::Cow;
+use std::error;
 use std::fmt;
 use std::ops::Range;
-use std::str;
+use std::str::{self, FromStr};
 use std::sync::Arc;
 
 mod sealed {
@@ -97,6 +101,7 @@ where
 /// A row of data returned from the database by a query.
 pub struct Row {
     statement: Statement,
+    formats: Option<Arc<[Format]>>,
     body: DataRowBody,
     ranges: Vec<Option<Range<usize>>>,
 }
@@ -110,10 +115,15 @@ impl fmt::Debug for Row {
 }
 
 impl Row {
-    pub(crate) fn new(statement: Statement, body: DataRowBody) -> Result<Row, Error> {
+    pub(crate) fn new(
+        statement: Statement,
+        formats: Option<Arc<[Format]>>,
+        body: DataRowBody,
+    ) -> Result<Row, Error> {
         let ranges = body.ranges().collect().map_err(Error::parse)?;
         Ok(Row {
             statement,
+            formats,
             body,
             ranges,
         })
@@ -134,9 +144,30 @@ impl Row {
         self.columns().len()
     }
 
+    /// Returns the size of the encoded values of the row.
+    pub(crate) fn body_len(&self) -> usize {
+        self.body.buffer().len()
+    }
+
+    /// Returns the format in which the value of the column at index `idx` was returned.
+    ///
+    /// This is `Format::Binary` unless the text format was requested with a [`QueryOptions`](crate::QueryOptions).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn column_format(&self, idx: usize) -> Format {
+        assert!(idx < self.len(), "column index {} out of bounds", idx);
+        match &self.formats {
+            Some(formats) => formats[idx],
+            None => Format::Binary,
+        }
+    }
+
     /// Deserializes a value from the row.
     ///
-    /// The value can be specified either by its numeric index in the row, or by its column name.
+    /// The value can be specified either by its numeric index in the row, or by its column name. Values returned in
+    /// the text format can only be read as a `&str` or `String`, or with [`Row::get_text`].
     ///
     /// # Panics
     ///
@@ -162,17 +193,119 @@ impl Row {
         self.get_inner(&idx)
     }
 
+    /// Like `Row::try_get`, but decodes the value as if its column had the type `ty`.
+    ///
+    /// This allows values of types without a [`FromSql`] implementation to be read through a type with the same
+    /// binary representation, such as a column of an enum type as a `String` with `Type::TEXT`. The caller is responsible
+    /// for the representations matching.
+    pub fn try_get_by_type<'a, I, T>(&'a self, idx: I, ty: &Type) -> Result<T, Error>
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSql<'a>,
+    {
+        let idx = self.index(&idx)?;
+        self.decode(idx, ty)
+    }
+
+    /// Converts a value returned in the text format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds, if the value was returned in the binary format, or if it cannot be
+    /// converted to the specified type.
+    #[track_caller]
+    pub fn get_text<I, T>(&self, idx: I) -> T
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSqlText,
+    {
+        match self.try_get_text(&idx) {
+            Ok(ok) => ok,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `Row::get_text`, but returns a `Result` rather than panicking.
+    pub fn try_get_text<I, T>(&self, idx: I) -> Result<T, Error>
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSqlText,
+    {
+        let idx = self.index(&idx)?;
+        if let Format::Binary = self.column_format(idx) {
+            return Err(Error::from_sql(
+                Box::new(FormatMismatch(Format::Binary)),
+                idx,
+            ));
+        }
+        let value = match self.col_buffer(idx) {
+            Some(buf) => types::text_from_sql(buf)
+                .and_then(T::from_sql_text)
+                .map_err(|e| Error::from_sql(e, idx))?,
+            None => T::from_sql_text_null().map_err(|e| Error::from_sql(e, idx))?,
+        };
+        Ok(value)
+    }
+
+    /// Returns the raw value of a column in the format it was returned in, or `None` if it is `NULL`.
+    pub fn try_get_raw<I>(&self, idx: I) -> Result<Option<&[u8]>, Error>
+    where
+        I: RowIndex + fmt::Display,
+    {
+        let idx = self.index(&idx)?;
+        Ok(self.col_buffer(idx))
+    }
+
+    /// Returns an iterator over the name, type and raw value of each column of the row.
+    ///
+    /// ```
+    /// # use tokio_postgres::Row;
+    /// fn log_row(row: &Row) {
+    ///     for (name, ty, value) in row.iter() {
+    ///         match value {
+    ///             Some(value) => println!("{} ({}): {} bytes", name, ty, value.len()),
+    ///             None => println!("{} ({}): NULL", name, ty),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            row: self,
+            idx: 0..self.len(),
+        }
+    }
+
+    fn index<I>(&self, idx: &I) -> Result<usize, Error>
+    where
+        I: RowIndex + fmt::Display,
+    {
+        idx.__idx(self.columns())
+            .ok_or_else(|| Error::column(idx.to_string()))
+    }
+
     fn get_inner<'a, I, T>(&'a self, idx: &I) -> Result<T, Error>
     where
         I: RowIndex + fmt::Display,
         T: FromSql<'a>,
     {
-        let idx = match idx.__idx(self.columns()) {
-            Some(idx) => idx,
-            None => return Err(Error::column(idx.to_string())),
+        let idx = self.index(idx)?;
+        self.decode(idx, self.columns()[idx].type_())
+    }
+
+    fn decode<'a, T>(&'a self, idx: usize, ty: &Type) -> Result<T, Error>
+    where
+        T: FromSql<'a>,
+    {
+        // values in the text format are decoded like a text column, which only string types accept
+        let ty = match self.column_format(idx) {
+            Format::Binary => ty,
+            Format::Text if T::accepts(&Type::TEXT) => &Type::TEXT,
+            Format::Text => {
+                return Err(Error::from_sql(Box::new(FormatMismatch(Format::Text)), idx));
+            }
         };
 
-        let ty = self.columns()[idx].type_();
         if !T::accepts(ty) {
             return Err(Error::from_sql(
                 Box::new(WrongType::new::<T>(ty.clone())),
@@ -184,12 +317,180 @@ impl Row {
     }
 
     /// Get the raw bytes for the column at the given index.
-    fn col_buffer(&self, idx: usize) -> Option<&[u8]> {
+    pub(crate) fn col_buffer(&self, idx: usize) -> Option<&[u8]> {
         let range = self.ranges[idx].to_owned()?;
         Some(&self.body.buffer()[range])
     }
 }
 
+/// An error reading a column of a [`Row`] which was returned in a different format than the method expects.
+#[derive(Debug)]
+struct FormatMismatch(Format);
+
+impl fmt::Display for FormatMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Format::Text => f.write_str(
+                "value was returned in the text format and can only be read as a string or with `Row::get_text`",
+            ),
+            Format::Binary => f.write_str("value was returned in the binary format"),
+        }
+    }
+}
+
+impl error::Error for FormatMismatch {}
+
+impl<'a> IntoIterator for &'a Row {
+    type Item = (&'a str, &'a Type, Option<&'a [u8]>);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the columns of a [`Row`], returned by [`Row::iter`].
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    row: &'a Row,
+    idx: Range<usize>,
+}
+
+impl<'a> Iter<'a> {
+    fn item(&self, idx: usize) -> (&'a str, &'a Type, Option<&'a [u8]>) {
+        let column = &self.row.columns()[idx];
+        (column.name(), column.type_(), self.row.col_buffer(idx))
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a Type, Option<&'a [u8]>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.idx.next().map(|idx| self.item(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.idx.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.idx.next_back().map(|idx| self.item(idx))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// A builder for [`Row`]s which don't come from a database, such as canned results in tests.
+///
+/// Values are encoded in the binary format with their `ToSql` implementations, as the server would send them.
+///
+/// ```
+/// use tokio_postgres::row::RowBuilder;
+/// use tokio_postgres::types::Type;
+///
+/// # fn main() -> Result<(), tokio_postgres::Error> {
+/// let row = RowBuilder::new()
+///     .column("id", Type::INT4, &1i32)
+///     .column("name", Type::TEXT, &"Ferris")
+///     .build()?;
+///
+/// assert_eq!(row.get::<_, &str>("name"), "Ferris");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct RowBuilder {
+    columns: Vec<Column>,
+    values: BytesMut,
+    error: Option<Error>,
+}
+
+impl RowBuilder {
+    /// Creates a new builder for a row without columns.
+    pub fn new() -> RowBuilder {
+        RowBuilder::default()
+    }
+
+    /// Appends a column with the given name, type and value.
+    ///
+    /// An error encoding the value is reported by [`RowBuilder::build`].
+    pub fn column(mut self, name: &str, ty: Type, value: &dyn ToSql) -> RowBuilder {
+        if self.error.is_none() {
+            let idx = self.columns.len();
+            let base = self.values.len();
+            self.values.put_i32(0);
+            match value.to_sql_checked(&ty, &mut self.values) {
+                Ok(IsNull::No) => {
+                    let len = (self.values.len() - base - 4) as i32;
+                    self.values[base..base + 4].copy_from_slice(&len.to_be_bytes());
+                }
+                Ok(IsNull::Yes) => {
+                    self.values.truncate(base);
+                    self.values.put_i32(-1);
+                }
+                Err(e) => self.error = Some(Error::to_sql(e, idx)),
+            }
+        }
+
+        self.push_column(name, ty);
+        self
+    }
+
+    /// Appends a column with a value which is already encoded in the binary format.
+    #[cfg(feature = "test-util")]
+    pub(crate) fn raw_column(mut self, name: &str, ty: Type, value: Option<&[u8]>) -> RowBuilder {
+        match value {
+            Some(value) => {
+                self.values.put_i32(value.len() as i32);
+                self.values.put_slice(value);
+            }
+            None => self.values.put_i32(-1),
+        }
+
+        self.push_column(name, ty);
+        self
+    }
+
+    fn push_column(&mut self, name: &str, ty: Type) {
+        self.columns.push(Column {
+            name: name.to_string(),
+            table_oid: None,
+            column_id: None,
+            r#type: ty,
+        });
+    }
+
+    /// Builds the row.
+    pub fn build(self) -> Result<Row, Error> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let body = data_row(self.columns.len(), &self.values)?;
+        Row::new(
+            Statement::detached(String::new(), vec![], self.columns),
+            None,
+            body,
+        )
+    }
+}
+
+/// Creates the body of a `DataRow` message from the values of its columns, each prefixed with its length.
+pub(crate) fn data_row(columns: usize, values: &[u8]) -> Result<DataRowBody, Error> {
+    let mut buf = BytesMut::with_capacity(values.len() + 7);
+    buf.put_u8(b'D');
+    buf.put_i32((values.len() + 6) as i32);
+    buf.put_i16(columns as i16);
+    buf.put_slice(values);
+    match Message::parse(&mut buf).map_err(Error::parse)? {
+        Some(Message::DataRow(body)) => Ok(body),
+        _ => Err(Error::unexpected_message()),
+    }
+}
+
 impl AsName for SimpleColumn {
     fn as_name(&self) -> &str {
         self.name()
@@ -259,6 +560,65 @@ impl SimpleQueryRow {
         self.get_inner(&idx)
     }
 
+    /// Parses a value from the row with its `FromStr` implementation, returning `None` if it is `NULL`.
+    ///
+    /// Note that the text format of some types differs from what their `FromStr` implementation expects, such as
+    /// `t` and `f` for booleans. [`SimpleQueryRow::try_get_text`] understands these.
+    pub fn try_get_parse<I, T>(&self, idx: I) -> Result<Option<T>, Error>
+    where
+        I: RowIndex + fmt::Display,
+        T: FromStr,
+        T::Err: Into<Box<dyn error::Error + Sync + Send>>,
+    {
+        let idx = self.index(&idx)?;
+        match self.get_inner(&idx)? {
+            Some(s) => s
+                .parse()
+                .map(Some)
+                .map_err(|e: T::Err| Error::from_sql(e.into(), idx)),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts a value from the row from the Postgres text format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or if the value cannot be converted to the specified type.
+    #[track_caller]
+    pub fn get_text<I, T>(&self, idx: I) -> T
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSqlText,
+    {
+        match self.try_get_text(&idx) {
+            Ok(ok) => ok,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `SimpleQueryRow::get_text`, but returns a `Result` rather than panicking.
+    pub fn try_get_text<I, T>(&self, idx: I) -> Result<T, Error>
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSqlText,
+    {
+        let idx = self.index(&idx)?;
+        let value = match self.get_inner(&idx)? {
+            Some(s) => T::from_sql_text(s),
+            None => T::from_sql_text_null(),
+        };
+        value.map_err(|e| Error::from_sql(e, idx))
+    }
+
+    fn index<I>(&self, idx: &I) -> Result<usize, Error>
+    where
+        I: RowIndex + fmt::Display,
+    {
+        idx.__idx(&self.columns)
+            .ok_or_else(|| Error::column(idx.to_string()))
+    }
+
     fn get_inner<I>(&self, idx: &I) -> Result<Option<&str>, Error>
     where
         I: RowIndex + fmt::Display,
@@ -272,3 +632,105 @@ impl SimpleQueryRow {
         FromSql::from_sql_nullable(&Type::TEXT, buf).map_err(|e| Error::from_sql(e, idx))
     }
 }
+
+/// A trait for types that can be converted from the text format of Postgres values, in which the results of simple
+/// queries are returned.
+pub trait FromSqlText: Sized {
+    /// Creates a new value of this type from its text format.
+    fn from_sql_text(s: &str) -> Result<Self, Box<dyn error::Error + Sync + Send>>;
+
+    /// Creates a new value of this type from a `NULL` value.
+    ///
+    /// The default implementation returns an error.
+    fn from_sql_text_null() -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        Err(Box::new(WasNull))
+    }
+}
+
+impl<T> FromSqlText for Option<T>
+where
+    T: FromSqlText,
+{
+    fn from_sql_text(s: &str) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        T::from_sql_text(s).map(Some)
+    }
+
+    fn from_sql_text_null() -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        Ok(None)
+    }
+}
+
+impl FromSqlText for bool {
+    fn from_sql_text(s: &str) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        match s {
+            "t" | "true" => Ok(true),
+            "f" | "false" => Ok(false),
+            _ => Err(format!("invalid boolean `{}`", s).into()),
+        }
+    }
+}
+
+impl FromSqlText for String {
+    fn from_sql_text(s: &str) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        Ok(s.to_string())
+    }
+}
+
+impl FromSqlText for Vec<u8> {
+    fn from_sql_text(s: &str) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        let hex = s
+            .strip_prefix("\\x")
+            .ok_or("bytea value is not in the hex format")?;
+        if hex.len() % 2 != 0 {
+            return Err("invalid bytea hex length".into());
+        }
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| Ok((nibble(pair[0])? << 4) | nibble(pair[1])?))
+            .collect()
+    }
+}
+
+fn nibble(c: u8) -> Result<u8, Box<dyn error::Error + Sync + Send>> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err("invalid bytea hex digit".into()),
+    }
+}
+
+macro_rules! from_str_impl {
+    ($($t:ty),*) => {
+        $(
+            impl FromSqlText for $t {
+                fn from_sql_text(s: &str) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+                    s.parse().map_err(Into::into)
+                }
+            }
+        )*
+    };
+}
+
+// Rust's float parsing also accepts the `NaN`, `Infinity` and `-Infinity` written by Postgres.
+from_str_impl!(i16, i32, i64, u32, f32, f64);
+
+#[doc(hidden)]
+pub fn __private_api_prefixed(prefix: &str, name: &'static str) -> Cow<'static, str> {
+    if prefix.is_empty() {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("{}{}", prefix, name))
+    }
+}
+
+#[doc(hidden)]
+pub fn __private_api_is_null<I>(row: &Row, idx: I) -> bool
+where
+    I: RowIndex,
+{
+    match idx.__idx(row.columns()) {
+        Some(idx) => row.col_buffer(idx).is_none(),
+        None => true,
+    }
+}
@@ -0,0 +1,125 @@
+//! Lookup of connection services in libpq-compatible service files.
+//!
+//! A service file consists of sections headed by a `[name]` line, each of which holds `key=value` lines with the
+//! connection parameters of the service of that name. Lines that are empty or start with `#` are ignored.
+
+use crate::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// Looks up the parameters of a service.
+///
+/// The service is looked up in `servicefile` if given, and otherwise in the file named by the `PGSERVICEFILE`
+/// environment variable or `~/.pg_service.conf`. If it isn't found there, the `pg_service.conf` file of the directory
+/// named by the `PGSYSCONFDIR` environment variable is searched next.
+pub(crate) fn parameters(
+    name: &str,
+    servicefile: Option<&str>,
+) -> Result<Vec<(String, String)>, Error> {
+    let user_file = match servicefile {
+        Some(servicefile) => {
+            let path = Path::new(servicefile);
+            if !path.is_file() {
+                return Err(Error::config_parse(
+                    format!("service file `{}` not found", path.display()).into(),
+                ));
+            }
+            Some(path.to_path_buf())
+        }
+        None => user_file(),
+    };
+    let sys_file =
+        env::var_os("PGSYSCONFDIR").map(|dir| PathBuf::from(dir).join("pg_service.conf"));
+
+    for path in user_file.iter().chain(&sys_file) {
+        if !path.is_file() {
+            continue;
+        }
+        if let Some(parameters) = search(path, name)? {
+            return Ok(parameters);
+        }
+    }
+
+    Err(Error::config_parse(
+        format!("definition of service `{}` not found", name).into(),
+    ))
+}
+
+fn user_file() -> Option<PathBuf> {
+    if let Some(servicefile) = env::var_os("PGSERVICEFILE") {
+        return Some(PathBuf::from(servicefile));
+    }
+
+    #[cfg(windows)]
+    {
+        let appdata = env::var_os("APPDATA")?;
+        Some(
+            PathBuf::from(appdata)
+                .join("postgresql")
+                .join(".pg_service.conf"),
+        )
+    }
+
+    #[cfg(not(windows))]
+    {
+        let home = env::var_os("HOME")?;
+        Some(PathBuf::from(home).join(".pg_service.conf"))
+    }
+}
+
+fn search(path: &Path, name: &str) -> Result<Option<Vec<(String, String)>>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        Error::config_parse(Box::new(io::Error::new(
+            e.kind(),
+            format!("error reading service file `{}`: {}", path.display(), e),
+        )))
+    })?;
+
+    let mut parameters = None;
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(section) = line.strip_prefix('[') {
+            if parameters.is_some() {
+                break;
+            }
+            if section.strip_suffix(']') == Some(name) {
+                parameters = Some(vec![]);
+            }
+            continue;
+        }
+
+        if let Some(parameters) = &mut parameters {
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => {
+                    return Err(Error::config_parse(
+                        format!(
+                            "syntax error in service file `{}`, line {}",
+                            path.display(),
+                            i + 1
+                        )
+                        .into(),
+                    ))
+                }
+            };
+            if key == "service" || key == "servicefile" {
+                return Err(Error::config_parse(
+                    format!(
+                        "nested service specifications not supported in service file `{}`, line {}",
+                        path.display(),
+                        i + 1
+                    )
+                    .into(),
+                ));
+            }
+            parameters.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    Ok(parameters)
+}
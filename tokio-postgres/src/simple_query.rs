@@ -47,9 +47,18 @@ pub async fn simple_query(client: &InnerClient, query: &str) -> Result<SimpleQue
 pub async fn batch_execute(client: &InnerClient, query: &str) -> Result<(), Error> {
     debug!("executing statement batch: {}", query);
 
+    let responses = send(client, query)?;
+    complete(responses).await
+}
+
+/// Sends a query, without waiting for its responses.
+pub(crate) fn send(client: &InnerClient, query: &str) -> Result<Responses, Error> {
     let buf = encode(client, query)?;
-    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+    client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))
+}
 
+/// Waits for the responses of a query sent with [`send`], discarding any rows.
+pub(crate) async fn complete(mut responses: Responses) -> Result<(), Error> {
     loop {
         match responses.next().await? {
             Message::ReadyForQuery(_) => return Ok(()),
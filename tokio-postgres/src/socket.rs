@@ -28,6 +28,27 @@ impl Socket {
     pub(crate) fn new_unix(stream: UnixStream) -> Socket {
         Socket(Inner::Unix(stream))
     }
+
+    #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+    pub(crate) fn into_uring(self) -> io::Result<crate::uring::UringStream> {
+        // io_uring waits on blocking sockets itself, while older kernels fail operations on non-blocking ones with `EAGAIN`
+        match self.0 {
+            Inner::Tcp(stream) => {
+                let stream = stream.into_std()?;
+                stream.set_nonblocking(false)?;
+                Ok(crate::uring::UringStream::new_tcp(
+                    tokio_uring::net::TcpStream::from_std(stream),
+                ))
+            }
+            Inner::Unix(stream) => {
+                let stream = stream.into_std()?;
+                stream.set_nonblocking(false)?;
+                Ok(crate::uring::UringStream::new_unix(
+                    tokio_uring::net::UnixStream::from_std(stream),
+                ))
+            }
+        }
+    }
 }
 
 impl AsyncRead for Socket {
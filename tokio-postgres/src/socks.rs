@@ -0,0 +1,160 @@
+//! A minimal SOCKS5 client, as described in RFC 1928 and RFC 1929.
+
+use std::convert::TryFrom;
+use std::io;
+use std::net::IpAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const VERSION: u8 = 5;
+const NO_AUTHENTICATION: u8 = 0;
+const USERNAME_PASSWORD: u8 = 2;
+const NO_ACCEPTABLE_METHODS: u8 = 0xff;
+const CONNECT: u8 = 1;
+const IPV4: u8 = 1;
+const DOMAIN_NAME: u8 = 3;
+const IPV6: u8 = 4;
+
+/// A SOCKS5 proxy through which TCP connections are tunneled.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Proxy {
+    pub host: String,
+    pub port: u16,
+    pub credentials: Option<(String, String)>,
+}
+
+/// Connects to `host` and `port` through the proxy.
+///
+/// Host names are resolved by the proxy, so that hosts which are only known on the other side of the proxy can be
+/// reached.
+pub(crate) async fn connect(proxy: &Proxy, host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect((&*proxy.host, proxy.port)).await?;
+    authenticate(&mut stream, proxy.credentials.as_ref()).await?;
+    request_connect(&mut stream, host, port).await?;
+    Ok(stream)
+}
+
+async fn authenticate(
+    stream: &mut TcpStream,
+    credentials: Option<&(String, String)>,
+) -> io::Result<()> {
+    match credentials {
+        Some(_) => {
+            stream
+                .write_all(&[VERSION, 2, NO_AUTHENTICATION, USERNAME_PASSWORD])
+                .await?
+        }
+        None => stream.write_all(&[VERSION, 1, NO_AUTHENTICATION]).await?,
+    }
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(invalid_data("invalid SOCKS version in proxy reply"));
+    }
+
+    match (reply[1], credentials) {
+        (NO_AUTHENTICATION, _) => Ok(()),
+        (USERNAME_PASSWORD, Some((username, password))) => {
+            let mut buf = vec![1];
+            push_len_prefixed(&mut buf, username.as_bytes(), "proxy username")?;
+            push_len_prefixed(&mut buf, password.as_bytes(), "proxy password")?;
+            stream.write_all(&buf).await?;
+
+            let mut reply = [0; 2];
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "proxy authentication failed",
+                ));
+            }
+            Ok(())
+        }
+        (NO_ACCEPTABLE_METHODS, _) | (USERNAME_PASSWORD, None) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "proxy requires an unsupported authentication method",
+        )),
+        _ => Err(invalid_data("invalid authentication method in proxy reply")),
+    }
+}
+
+async fn request_connect(stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    let mut buf = vec![VERSION, CONNECT, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            buf.push(IPV4);
+            buf.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            buf.push(IPV6);
+            buf.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            buf.push(DOMAIN_NAME);
+            push_len_prefixed(&mut buf, host.as_bytes(), "host name")?;
+        }
+    }
+    buf.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&buf).await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(invalid_data("invalid SOCKS version in proxy reply"));
+    }
+    if reply[1] != 0 {
+        return Err(reply_error(reply[1]));
+    }
+
+    // the address the proxy bound to is of no use to us, but must be consumed
+    let len = match reply[3] {
+        IPV4 => 4,
+        IPV6 => 16,
+        DOMAIN_NAME => stream.read_u8().await? as usize,
+        _ => return Err(invalid_data("invalid address type in proxy reply")),
+    };
+    let mut bound = vec![0; len + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+fn push_len_prefixed(buf: &mut Vec<u8>, value: &[u8], what: &str) -> io::Result<()> {
+    let len = u8::try_from(value.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too long for the proxy", what),
+        )
+    })?;
+    buf.push(len);
+    buf.extend_from_slice(value);
+    Ok(())
+}
+
+fn reply_error(code: u8) -> io::Error {
+    let (kind, message) = match code {
+        2 => (
+            io::ErrorKind::PermissionDenied,
+            "connection not allowed by proxy ruleset",
+        ),
+        3 => (io::ErrorKind::Other, "network unreachable from proxy"),
+        4 => (io::ErrorKind::Other, "host unreachable from proxy"),
+        5 => (
+            io::ErrorKind::ConnectionRefused,
+            "connection refused by proxied host",
+        ),
+        6 => (io::ErrorKind::TimedOut, "TTL expired at proxy"),
+        7 => (io::ErrorKind::Unsupported, "command not supported by proxy"),
+        8 => (
+            io::ErrorKind::Unsupported,
+            "address type not supported by proxy",
+        ),
+        _ => (io::ErrorKind::Other, "general proxy failure"),
+    };
+    io::Error::new(kind, message)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
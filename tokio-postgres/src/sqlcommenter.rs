@@ -0,0 +1,161 @@
+//! [sqlcommenter] support.
+//!
+//! When enabled with [`Config::sql_commenter`](crate::Config::sql_commenter), the client appends a comment to the text
+//! of the statements it prepares, carrying the tags of the [`Context`] the statement was prepared in:
+//!
+//! ```sql
+//! SELECT * FROM users WHERE id = $1 /*application='api',route='%2Fusers%2F%3Aid',traceparent='00-...-01'*/
+//! ```
+//!
+//! Database-side tooling such as `pg_stat_activity` and the server logs then shows which request issued a query.
+//!
+//! Only the text sent to the server is commented. [`Statement`](crate::Statement)s and the statistics of
+//! [`Client::statement_stats`](crate::Client::statement_stats) keep the text passed by the caller, so tags do not
+//! change how statements are identified. Note that a prepared statement carries the tags of the context it was
+//! prepared in for as long as it is executed.
+//!
+//! Requires the `sqlcommenter` Cargo feature.
+//!
+//! ```no_run
+//! use tokio_postgres::sqlcommenter::Context;
+//!
+//! # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+//! let context = Context::new()
+//!     .route("/users/:id")
+//!     .traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+//!
+//! context
+//!     .scope(async {
+//!         client.query("SELECT * FROM users WHERE id = $1", &[&1i32]).await
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [sqlcommenter]: https://google.github.io/sqlcommenter/spec/
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+
+// The characters left alone by JavaScript's `encodeURIComponent`, as required by the specification.
+const TAG: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+tokio::task_local! {
+    static CONTEXT: Context;
+}
+
+/// The tags describing the request a task is working on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    tags: BTreeMap<String, String>,
+}
+
+impl Context {
+    /// Creates a context without tags.
+    pub fn new() -> Context {
+        Context::default()
+    }
+
+    /// Returns the context of the current task, if it runs within [`Context::scope`].
+    pub fn current() -> Option<Context> {
+        CONTEXT.try_with(Context::clone).ok()
+    }
+
+    /// Sets a tag, replacing any previous value.
+    pub fn tag(mut self, key: &str, value: &str) -> Context {
+        self.tags.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the `route` tag.
+    pub fn route(self, route: &str) -> Context {
+        self.tag("route", route)
+    }
+
+    /// Sets the `controller` tag.
+    pub fn controller(self, controller: &str) -> Context {
+        self.tag("controller", controller)
+    }
+
+    /// Sets the `action` tag.
+    pub fn action(self, action: &str) -> Context {
+        self.tag("action", action)
+    }
+
+    /// Sets the `traceparent` tag, a [W3C trace context](https://www.w3.org/TR/trace-context/) header.
+    pub fn traceparent(self, traceparent: &str) -> Context {
+        self.tag("traceparent", traceparent)
+    }
+
+    /// Sets the `tracestate` tag, a [W3C trace context](https://www.w3.org/TR/trace-context/) header.
+    pub fn tracestate(self, tracestate: &str) -> Context {
+        self.tag("tracestate", tracestate)
+    }
+
+    /// Runs a future with this context as the current context.
+    pub async fn scope<F>(self, f: F) -> F::Output
+    where
+        F: Future,
+    {
+        CONTEXT.scope(self, f).await
+    }
+}
+
+/// The client side of the sqlcommenter support.
+#[derive(Debug, Clone)]
+pub(crate) struct SqlCommenter {
+    pub(crate) application: Option<String>,
+}
+
+impl SqlCommenter {
+    /// Returns the text of `query` with a comment appended, or `None` if it should be sent unchanged.
+    ///
+    /// Queries which already contain a comment are left alone, as the specification requires.
+    pub(crate) fn comment(&self, query: &str) -> Option<String> {
+        if query.contains("/*") || query.contains("--") {
+            return None;
+        }
+
+        let mut tags = Context::current().map(|c| c.tags).unwrap_or_default();
+        if let Some(application) = &self.application {
+            tags.entry("application".to_string())
+                .or_insert_with(|| application.clone());
+        }
+        if tags.is_empty() {
+            return None;
+        }
+
+        let trimmed = query.trim_end();
+        let (statement, semicolon) = match trimmed.strip_suffix(';') {
+            Some(statement) => (statement.trim_end(), ";"),
+            None => (trimmed, ""),
+        };
+
+        let mut commented = format!("{} /*", statement);
+        for (i, (key, value)) in tags.iter().enumerate() {
+            if i > 0 {
+                commented.push(',');
+            }
+            let _ = write!(commented, "{}='{}'", escape(key), escape(value));
+        }
+        commented.push_str("*/");
+        commented.push_str(semicolon);
+
+        Some(commented)
+    }
+}
+
+fn escape(s: &str) -> String {
+    utf8_percent_encode(s, TAG).to_string().replace('\'', "\\'")
+}
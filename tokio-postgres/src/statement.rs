@@ -3,12 +3,16 @@ use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
 use crate::types::Type;
 use postgres_protocol::message::frontend;
+use std::collections::HashMap;
+use std::ptr;
 use std::sync::{Arc, Weak};
 
 #[derive(Debug)]
 struct StatementInner {
     client: Weak<InnerClient>,
+    session: u64,
     name: String,
+    query: String,
     params: Vec<Type>,
     columns: Vec<Column>,
 }
@@ -28,7 +32,8 @@ impl Drop for StatementInner {
 
 /// A prepared statement.
 ///
-/// Prepared statements can only be used with the connection that created them.
+/// Prepared statements live on the connection that created them. When a statement is used with another connection,
+/// for example after reconnecting, it is prepared again from its query text the first time it is used there.
 #[derive(Clone, Debug)]
 pub struct Statement(Arc<StatementInner>);
 
@@ -36,21 +41,50 @@ impl Statement {
     pub(crate) fn new(
         inner: &Arc<InnerClient>,
         name: String,
+        query: String,
         params: Vec<Type>,
         columns: Vec<Column>,
     ) -> Statement {
         Statement(Arc::new(StatementInner {
             client: Arc::downgrade(inner),
+            session: inner.session(),
             name,
+            query,
             params,
             columns,
         }))
     }
 
-    pub(crate) fn name(&self) -> &str {
+    /// Creates a statement which isn't associated with a connection, for rows which were built locally.
+    pub(crate) fn detached(query: String, params: Vec<Type>, columns: Vec<Column>) -> Statement {
+        Statement(Arc::new(StatementInner {
+            client: Weak::new(),
+            session: 0,
+            name: String::new(),
+            query,
+            params,
+            columns,
+        }))
+    }
+
+    /// Returns the name of the statement on the connection it was prepared on.
+    ///
+    /// Statements which are prepared again on another connection get a different name there, and statements which
+    /// were not prepared on a connection have an empty name.
+    pub fn name(&self) -> &str {
         &self.0.name
     }
 
+    /// Returns the text of the query the statement was prepared from.
+    pub fn sql(&self) -> &str {
+        &self.0.query
+    }
+
+    /// Determines if the statement was prepared by `client` in its current session.
+    pub(crate) fn is_prepared_on(&self, client: &Arc<InnerClient>) -> bool {
+        ptr::eq(self.0.client.as_ptr(), Arc::as_ptr(client)) && self.0.session == client.session()
+    }
+
     /// Returns the expected types of the statement's parameters.
     pub fn params(&self) -> &[Type] {
         &self.0.params
@@ -92,3 +126,83 @@ impl Column {
         &self.r#type
     }
 }
+
+/// The statements prepared for queries passed as text, keyed by the query.
+pub(crate) struct StatementCache {
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<String, CachedStatement>,
+}
+
+struct CachedStatement {
+    types: Vec<Type>,
+    statement: Statement,
+    last_used: u64,
+}
+
+impl StatementCache {
+    pub(crate) fn new(capacity: usize) -> StatementCache {
+        StatementCache {
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn get(&mut self, query: &str, types: &[Type]) -> Option<Statement> {
+        let entry = self.entries.get_mut(query)?;
+        if entry.types != types {
+            return None;
+        }
+
+        self.clock += 1;
+        entry.last_used = self.clock;
+        Some(entry.statement.clone())
+    }
+
+    /// Adds a statement, returning the least recently used one if it had to be evicted.
+    ///
+    /// The evicted statement is returned rather than dropped, so that the lock of the cache can be released before it
+    /// closes the statement.
+    pub(crate) fn insert(
+        &mut self,
+        query: &str,
+        types: &[Type],
+        statement: Statement,
+    ) -> Option<Statement> {
+        self.clock += 1;
+        let entry = CachedStatement {
+            types: types.to_vec(),
+            statement,
+            last_used: self.clock,
+        };
+        if let Some(old) = self.entries.insert(query.to_string(), entry) {
+            return Some(old.statement);
+        }
+
+        if self.entries.len() <= self.capacity {
+            return None;
+        }
+        let lru = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(query, _)| query.clone())?;
+        self.entries.remove(&lru).map(|entry| entry.statement)
+    }
+
+    pub(crate) fn clear(&mut self) -> Vec<Statement> {
+        self.entries
+            .drain()
+            .map(|(_, entry)| entry.statement)
+            .collect()
+    }
+}
@@ -0,0 +1,155 @@
+use crate::Statement;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The number of most recent execution times kept per statement to compute percentiles.
+const SAMPLES: usize = 1024;
+
+/// Execution statistics of a statement, as returned by [`Client::statement_stats`](crate::Client::statement_stats).
+#[derive(Debug, Clone)]
+pub struct StatementStats {
+    query: String,
+    calls: u64,
+    errors: u64,
+    rows: u64,
+    total_time: Duration,
+    p95_time: Duration,
+}
+
+impl StatementStats {
+    /// Returns the text of the statement.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Returns the number of times the statement was executed, including failed executions.
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Returns the number of executions which failed.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Returns the total number of rows returned or affected by the statement.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Returns the total time spent executing the statement.
+    pub fn total_time(&self) -> Duration {
+        self.total_time
+    }
+
+    /// Returns the 95th percentile of the time spent executing the statement, over its most recent executions.
+    pub fn p95_time(&self) -> Duration {
+        self.p95_time
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    calls: u64,
+    errors: u64,
+    rows: u64,
+    total_time: Duration,
+    samples: Vec<Duration>,
+}
+
+#[derive(Default)]
+pub struct StatsRegistry {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl StatsRegistry {
+    pub fn start(self: &Arc<Self>, statement: &Statement) -> StatsGuard {
+        StatsGuard {
+            registry: self.clone(),
+            statement: statement.clone(),
+            start: Instant::now(),
+            rows: 0,
+            error: false,
+        }
+    }
+
+    fn record(&self, query: &str, time: Duration, rows: u64, error: bool) {
+        let mut entries = self.entries.lock();
+        let entry = match entries.get_mut(query) {
+            Some(entry) => entry,
+            None => entries.entry(query.to_string()).or_default(),
+        };
+
+        if entry.samples.len() < SAMPLES {
+            entry.samples.push(time);
+        } else {
+            entry.samples[(entry.calls % SAMPLES as u64) as usize] = time;
+        }
+        entry.calls += 1;
+        entry.rows += rows;
+        entry.total_time += time;
+        if error {
+            entry.errors += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<StatementStats> {
+        self.entries
+            .lock()
+            .iter()
+            .map(|(query, entry)| {
+                let mut samples = entry.samples.clone();
+                samples.sort_unstable();
+                let p95_time = match samples.len() {
+                    0 => Duration::ZERO,
+                    n => samples[(n * 95).div_ceil(100) - 1],
+                };
+
+                StatementStats {
+                    query: query.clone(),
+                    calls: entry.calls,
+                    errors: entry.errors,
+                    rows: entry.rows,
+                    total_time: entry.total_time,
+                    p95_time,
+                }
+            })
+            .collect()
+    }
+
+    pub fn reset(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+/// Records an execution of a statement when dropped.
+pub struct StatsGuard {
+    registry: Arc<StatsRegistry>,
+    statement: Statement,
+    start: Instant,
+    rows: u64,
+    error: bool,
+}
+
+impl StatsGuard {
+    pub fn add_rows(&mut self, rows: u64) {
+        self.rows += rows;
+    }
+
+    pub fn set_error(&mut self) {
+        self.error = true;
+    }
+}
+
+impl Drop for StatsGuard {
+    fn drop(&mut self) {
+        self.registry.record(
+            self.statement.sql(),
+            self.start.elapsed(),
+            self.rows,
+            self.error,
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::escape::escape_identifier;
+use crate::tls::MakeTlsConnect;
+use crate::{Client, Config, Error, Socket};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{process, thread};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary database which is dropped when the value is dropped.
+pub struct TestDatabase<T> {
+    admin: Config,
+    config: Config,
+    name: String,
+    tls: T,
+    cleanup: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl<T> fmt::Debug for TestDatabase<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestDatabase")
+            .field("config", &self.config)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl<T> TestDatabase<T>
+where
+    T: MakeTlsConnect<Socket> + Clone + Send + 'static,
+    T::Stream: Send + 'static,
+{
+    /// Creates a new empty database.
+    ///
+    /// `config` is used to connect to the server to create and drop the database, so its user must have the
+    /// `CREATEDB` privilege. The database it names, if any, is left untouched.
+    pub async fn create(config: &Config, tls: T) -> Result<TestDatabase<T>, Error> {
+        TestDatabase::create_inner(config, None, tls).await
+    }
+
+    /// Creates a new database as a copy of the `template` database.
+    ///
+    /// The template must not have any other active connections while the copy is made.
+    pub async fn from_template(
+        config: &Config,
+        template: &str,
+        tls: T,
+    ) -> Result<TestDatabase<T>, Error> {
+        TestDatabase::create_inner(config, Some(template), tls).await
+    }
+
+    async fn create_inner(
+        config: &Config,
+        template: Option<&str>,
+        tls: T,
+    ) -> Result<TestDatabase<T>, Error> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let name = format!(
+            "tokio_postgres_test_{}_{}_{}",
+            process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            nanos,
+        );
+
+        let mut query = format!("CREATE DATABASE {}", escape_identifier(&name)?);
+        if let Some(template) = template {
+            query.push_str(" TEMPLATE ");
+            query.push_str(&escape_identifier(template)?);
+        }
+
+        let client = connect(config, tls.clone()).await?;
+        client.batch_execute(&query).await?;
+
+        let mut db_config = config.clone();
+        db_config.dbname(&name);
+
+        // Drop can't await, and may run inside a runtime which must not be blocked on, so the cleanup runs on a
+        // runtime of its own on a separate thread.
+        let cleanup = {
+            let admin = config.clone();
+            let name = name.clone();
+            let tls = tls.clone();
+            Box::new(move || {
+                let _ = thread::spawn(move || {
+                    let runtime = match tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                    {
+                        Ok(runtime) => runtime,
+                        Err(_) => return,
+                    };
+                    let _ = runtime.block_on(drop_database(&admin, &name, tls));
+                })
+                .join();
+            })
+        };
+
+        Ok(TestDatabase {
+            admin: config.clone(),
+            config: db_config,
+            name,
+            tls,
+            cleanup: Some(cleanup),
+        })
+    }
+
+    /// Returns the name of the database.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns a configuration connecting to the database.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Opens a new connection to the database.
+    ///
+    /// The connection is spawned onto the current tokio runtime.
+    pub async fn connect(&self) -> Result<Client, Error> {
+        connect(&self.config, self.tls.clone()).await
+    }
+
+    /// Drops the database, returning any error which occurs.
+    ///
+    /// Other sessions connected to the database are terminated first. The database is otherwise dropped when this
+    /// value is dropped, with errors being ignored.
+    pub async fn drop_database(mut self) -> Result<(), Error> {
+        self.cleanup = None;
+        drop_database(&self.admin, &self.name, self.tls.clone()).await
+    }
+}
+
+impl<T> Drop for TestDatabase<T> {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
+        }
+    }
+}
+
+async fn connect<T>(config: &Config, tls: T) -> Result<Client, Error>
+where
+    T: MakeTlsConnect<Socket>,
+    T::Stream: Send + 'static,
+{
+    let (client, connection) = config.connect(tls).await?;
+    tokio::spawn(connection);
+    Ok(client)
+}
+
+async fn drop_database<T>(admin: &Config, name: &str, tls: T) -> Result<(), Error>
+where
+    T: MakeTlsConnect<Socket>,
+    T::Stream: Send + 'static,
+{
+    let client = connect(admin, tls).await?;
+    client
+        .execute(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity \
+             WHERE datname = $1 AND pid <> pg_backend_pid()",
+            &[&name],
+        )
+        .await?;
+    client
+        .batch_execute(&format!(
+            "DROP DATABASE IF EXISTS {}",
+            escape_identifier(name)?
+        ))
+        .await
+}
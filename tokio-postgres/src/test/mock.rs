@@ -0,0 +1,267 @@
+use crate::error::DbError;
+use crate::statement::Column;
+use crate::types::{ToSql, Type};
+use crate::{DynClient, DynTransaction, Error, Row, Statement};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// A client which answers queries with canned results instead of talking to a database.
+///
+/// Results are registered up front for a query string, and each one is used for a single matching query, in the
+/// order in which they were registered. Issuing a query without a matching result panics. Parameters are not checked.
+///
+/// Statements executed through [`DynClient::batch_execute`] and the statements issued by transactions don't need a
+/// registered result, but are matched against them so that errors can be injected. Queries passed to
+/// [`DynClient::prepare`] are answered with the statement registered by [`MockClient::expect_prepare`].
+///
+/// ```
+/// use tokio_postgres::row::RowBuilder;
+/// use tokio_postgres::test::MockClient;
+/// use tokio_postgres::types::Type;
+/// use tokio_postgres::DynClient;
+///
+/// async fn user_name(client: &dyn DynClient, id: i32) -> Result<String, tokio_postgres::Error> {
+///     let row = client.query_one("SELECT name FROM users WHERE id = $1", &[&id]).await?;
+///     Ok(row.get(0))
+/// }
+///
+/// # async fn run() -> Result<(), tokio_postgres::Error> {
+/// let client = MockClient::new();
+/// client.expect_query(
+///     "SELECT name FROM users WHERE id = $1",
+///     vec![RowBuilder::new().column("name", Type::TEXT, &"Ferris").build()?],
+/// );
+///
+/// assert_eq!(user_name(&client, 1).await?, "Ferris");
+/// client.verify();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockClient {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    expectations: Vec<Expectation>,
+    queries: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    query: String,
+    response: Response,
+}
+
+#[derive(Debug)]
+enum Response {
+    Rows(Vec<Row>),
+    Count(u64),
+    Statement(Statement),
+    Error(Box<DbError>),
+}
+
+impl MockClient {
+    /// Creates a new mock client without any registered results.
+    pub fn new() -> MockClient {
+        MockClient::default()
+    }
+
+    /// Registers the rows returned by the next matching query.
+    ///
+    /// When the query is executed rather than queried, the number of rows is returned.
+    pub fn expect_query(&self, query: &str, rows: Vec<Row>) {
+        self.expect(query, Response::Rows(rows));
+    }
+
+    /// Registers the number of rows modified by the next matching statement.
+    ///
+    /// When the statement is queried rather than executed, no rows are returned.
+    pub fn expect_execute(&self, query: &str, rows: u64) {
+        self.expect(query, Response::Count(rows));
+    }
+
+    /// Registers the statement returned by the next matching prepare, with the given parameter types and columns.
+    ///
+    /// The statement isn't prepared on a connection, so it can only be used to inspect its parameters and columns.
+    pub fn expect_prepare(&self, query: &str, params: Vec<Type>, columns: Vec<(&str, Type)>) {
+        let columns = columns
+            .into_iter()
+            .map(|(name, r#type)| Column {
+                name: name.to_string(),
+                table_oid: None,
+                column_id: None,
+                r#type,
+            })
+            .collect();
+        let statement = Statement::detached(query.to_string(), params, columns);
+        self.expect(query, Response::Statement(statement));
+    }
+
+    /// Registers an error returned by the next matching query.
+    pub fn expect_error(&self, query: &str, error: DbError) {
+        self.expect(query, Response::Error(Box::new(error)));
+    }
+
+    fn expect(&self, query: &str, response: Response) {
+        self.state.lock().expectations.push(Expectation {
+            query: query.to_string(),
+            response,
+        });
+    }
+
+    /// Returns the queries which were issued so far, in order.
+    ///
+    /// This includes the statements issued by transactions, such as `BEGIN` and `COMMIT`.
+    pub fn queries(&self) -> Vec<String> {
+        self.state.lock().queries.clone()
+    }
+
+    /// Panics if any registered result has not been used.
+    #[track_caller]
+    pub fn verify(&self) {
+        let state = self.state.lock();
+        if let Some(expectation) = state.expectations.first() {
+            panic!("expected query was not issued: {}", expectation.query);
+        }
+    }
+
+    fn respond(&self, query: &str) -> Option<Response> {
+        let mut state = self.state.lock();
+        state.queries.push(query.to_string());
+        let idx = state.expectations.iter().position(|e| e.query == query)?;
+        Some(state.expectations.remove(idx).response)
+    }
+
+    #[track_caller]
+    fn respond_expected(&self, query: &str) -> Response {
+        match self.respond(query) {
+            Some(response) => response,
+            None => panic!("unexpected query: {}", query),
+        }
+    }
+
+    fn respond_simple(&self, query: &str) -> Result<(), Error> {
+        match self.respond(query) {
+            Some(Response::Error(e)) => Err(Error::from_db_error(*e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl DynClient for MockClient {
+    async fn execute(&self, query: &str, _: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        match self.respond_expected(query) {
+            Response::Rows(rows) => Ok(rows.len() as u64),
+            Response::Count(count) => Ok(count),
+            Response::Statement(_) => panic!("unexpected query: {}", query),
+            Response::Error(e) => Err(Error::from_db_error(*e)),
+        }
+    }
+
+    async fn query(&self, query: &str, _: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        match self.respond_expected(query) {
+            Response::Rows(rows) => Ok(rows),
+            Response::Count(_) => Ok(vec![]),
+            Response::Statement(_) => panic!("unexpected query: {}", query),
+            Response::Error(e) => Err(Error::from_db_error(*e)),
+        }
+    }
+
+    async fn prepare_typed(&self, query: &str, _: &[Type]) -> Result<Statement, Error> {
+        match self.respond_expected(query) {
+            Response::Statement(statement) => Ok(statement),
+            Response::Error(e) => Err(Error::from_db_error(*e)),
+            _ => panic!("unexpected prepare: {}", query),
+        }
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        self.respond_simple(query)
+    }
+
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error> {
+        self.respond_simple("BEGIN")?;
+        Ok(Box::new(MockTransaction {
+            client: self.clone(),
+            depth: 0,
+            done: false,
+        }))
+    }
+}
+
+/// A transaction of a `MockClient`.
+struct MockTransaction {
+    client: MockClient,
+    depth: u32,
+    done: bool,
+}
+
+impl MockTransaction {
+    fn finish(&mut self, commit: bool) -> Result<(), Error> {
+        self.done = true;
+        let query = match (self.depth, commit) {
+            (0, true) => "COMMIT".to_string(),
+            (0, false) => "ROLLBACK".to_string(),
+            (depth, true) => format!("RELEASE sp_{}", depth),
+            (depth, false) => format!("ROLLBACK TO sp_{}", depth),
+        };
+        self.client.respond_simple(&query)
+    }
+}
+
+impl Drop for MockTransaction {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.finish(false);
+        }
+    }
+}
+
+#[async_trait]
+impl DynClient for MockTransaction {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        self.client.execute(query, params).await
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        self.client.query(query, params).await
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        self.client.prepare_typed(query, parameter_types).await
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        self.client.batch_execute(query).await
+    }
+
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error> {
+        let depth = self.depth + 1;
+        self.client
+            .respond_simple(&format!("SAVEPOINT sp_{}", depth))?;
+        Ok(Box::new(MockTransaction {
+            client: self.client.clone(),
+            depth,
+            done: false,
+        }))
+    }
+}
+
+#[async_trait]
+impl DynTransaction for MockTransaction {
+    async fn commit(mut self: Box<Self>) -> Result<(), Error> {
+        self.finish(true)
+    }
+
+    async fn rollback(mut self: Box<Self>) -> Result<(), Error> {
+        self.finish(false)
+    }
+}
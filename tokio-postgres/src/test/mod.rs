@@ -0,0 +1,37 @@
+//! Utilities for testing code which uses the database.
+//!
+//! A [`TestDatabase`] creates a uniquely named database, optionally from a template, and drops it again when it goes
+//! out of scope, including when the test panics. This lets tests run in parallel against a single server without
+//! seeing each other's data.
+//!
+//! A [`TestTransaction`] is a cheaper alternative for tests sharing a database: it wraps a transaction which is always
+//! rolled back, even when the code under test commits.
+//!
+//! A [`MockClient`] doesn't need a server at all. It implements [`DynClient`](crate::DynClient) and answers queries with
+//! canned results, which can be built with [`RowBuilder`](crate::row::RowBuilder) and
+//! [`DbErrorBuilder`](crate::error::DbErrorBuilder), or recorded from a live database with a [`Recorder`].
+//!
+//! Requires the `test-util` Cargo feature.
+//!
+//! ```no_run
+//! use tokio_postgres::test::TestDatabase;
+//! use tokio_postgres::{Config, NoTls};
+//!
+//! # async fn run() -> Result<(), tokio_postgres::Error> {
+//! let config = "host=localhost user=postgres".parse::<Config>()?;
+//! let db = TestDatabase::create(&config, NoTls).await?;
+//!
+//! let client = db.connect().await?;
+//! client.batch_execute("CREATE TABLE foo (id INT)").await?;
+//! # Ok(())
+//! # }
+//! ```
+pub use self::database::TestDatabase;
+pub use self::mock::MockClient;
+pub use self::record::{Recorder, Recording};
+pub use self::transaction::TestTransaction;
+
+mod database;
+mod mock;
+mod record;
+mod transaction;
@@ -0,0 +1,581 @@
+use crate::error::{DbErrorBuilder, Severity, SqlState};
+use crate::row::RowBuilder;
+use crate::test::MockClient;
+use crate::types::{Kind, ToSql, Type};
+use crate::{DynClient, DynTransaction, Error, Row, Statement};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+const HEADER: &str = "# tokio-postgres recording v1";
+
+/// A client which records the queries issued through it, along with their results.
+///
+/// The [`Recording`] can be saved to a file and replayed later by a [`MockClient`], so tests can run without a
+/// database. The recording is a text file listing each query with its parameters and results, which also makes it
+/// useful when debugging.
+///
+/// ```no_run
+/// use tokio_postgres::test::{Recorder, Recording};
+/// use tokio_postgres::DynClient;
+///
+/// # async fn run(client: tokio_postgres::Client) -> Result<(), Box<dyn std::error::Error>> {
+/// let recorder = Recorder::new(client);
+/// recorder.query("SELECT 1", &[]).await?;
+/// recorder.recording().save("tests/recordings/select.txt")?;
+///
+/// // later, without a database
+/// let client = Recording::load("tests/recordings/select.txt")?.replay()?;
+/// client.query("SELECT 1", &[]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Recorder<C> {
+    client: C,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl<C> Recorder<C>
+where
+    C: DynClient,
+{
+    /// Creates a new recorder issuing queries through `client`.
+    pub fn new(client: C) -> Recorder<C> {
+        Recorder {
+            client,
+            recording: Arc::default(),
+        }
+    }
+
+    /// Returns a reference to the underlying client.
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Returns a copy of the queries recorded so far.
+    pub fn recording(&self) -> Recording {
+        self.recording.lock().clone()
+    }
+}
+
+#[async_trait]
+impl<C> DynClient for Recorder<C>
+where
+    C: DynClient,
+{
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        let result = self.client.execute(query, params).await;
+        record_count(&self.recording, query, params, result.as_ref().copied());
+        result
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        let result = self.client.query(query, params).await;
+        record_rows(&self.recording, query, params, result.as_ref());
+        result
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        let result = self.client.prepare_typed(query, parameter_types).await;
+        record_statement(&self.recording, query, result.as_ref());
+        result
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        let result = self.client.batch_execute(query).await;
+        record_count(&self.recording, query, &[], result.as_ref().map(|()| 0));
+        result
+    }
+
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error> {
+        let transaction = self.client.transaction().await?;
+        Ok(Box::new(RecordingTransaction {
+            transaction,
+            recording: self.recording.clone(),
+        }))
+    }
+}
+
+struct RecordingTransaction<'a> {
+    transaction: Box<dyn DynTransaction + 'a>,
+    recording: Arc<Mutex<Recording>>,
+}
+
+#[async_trait]
+impl DynClient for RecordingTransaction<'_> {
+    async fn execute(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, Error> {
+        let result = self.transaction.execute(query, params).await;
+        record_count(&self.recording, query, params, result.as_ref().copied());
+        result
+    }
+
+    async fn query(&self, query: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, Error> {
+        let result = self.transaction.query(query, params).await;
+        record_rows(&self.recording, query, params, result.as_ref());
+        result
+    }
+
+    async fn prepare_typed(
+        &self,
+        query: &str,
+        parameter_types: &[Type],
+    ) -> Result<Statement, Error> {
+        let result = self.transaction.prepare_typed(query, parameter_types).await;
+        record_statement(&self.recording, query, result.as_ref());
+        result
+    }
+
+    async fn batch_execute(&self, query: &str) -> Result<(), Error> {
+        let result = self.transaction.batch_execute(query).await;
+        record_count(&self.recording, query, &[], result.as_ref().map(|()| 0));
+        result
+    }
+
+    async fn transaction(&mut self) -> Result<Box<dyn DynTransaction + '_>, Error> {
+        let transaction = self.transaction.transaction().await?;
+        Ok(Box::new(RecordingTransaction {
+            transaction,
+            recording: self.recording.clone(),
+        }))
+    }
+}
+
+#[async_trait]
+impl DynTransaction for RecordingTransaction<'_> {
+    async fn commit(self: Box<Self>) -> Result<(), Error> {
+        self.transaction.commit().await
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), Error> {
+        self.transaction.rollback().await
+    }
+}
+
+fn record_count(
+    recording: &Mutex<Recording>,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    result: Result<u64, &Error>,
+) {
+    let response = match result {
+        Ok(count) => Response::Count(count),
+        Err(e) => match Response::error(e) {
+            Some(response) => response,
+            None => return,
+        },
+    };
+    recording.lock().push(query, params, response);
+}
+
+fn record_rows(
+    recording: &Mutex<Recording>,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    result: Result<&Vec<Row>, &Error>,
+) {
+    let response = match result {
+        Ok(rows) => Response::Rows {
+            columns: rows.first().map_or_else(Vec::new, |row| {
+                row.columns()
+                    .iter()
+                    .map(|c| (c.name().to_string(), c.type_().clone()))
+                    .collect()
+            }),
+            rows: rows
+                .iter()
+                .map(|row| {
+                    (0..row.len())
+                        .map(|i| row.col_buffer(i).map(|b| b.to_vec()))
+                        .collect()
+                })
+                .collect(),
+        },
+        Err(e) => match Response::error(e) {
+            Some(response) => response,
+            None => return,
+        },
+    };
+    recording.lock().push(query, params, response);
+}
+
+fn record_statement(recording: &Mutex<Recording>, query: &str, result: Result<&Statement, &Error>) {
+    let response = match result {
+        Ok(statement) => Response::Statement {
+            params: statement.params().to_vec(),
+            columns: statement
+                .columns()
+                .iter()
+                .map(|c| (c.name().to_string(), c.type_().clone()))
+                .collect(),
+        },
+        Err(e) => match Response::error(e) {
+            Some(response) => response,
+            None => return,
+        },
+    };
+    recording.lock().push(query, &[], response);
+}
+
+/// Queries and their results recorded by a [`Recorder`].
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    query: String,
+    params: Vec<String>,
+    response: Response,
+}
+
+#[derive(Debug, Clone)]
+enum Response {
+    Rows {
+        columns: Vec<(String, Type)>,
+        rows: Vec<Vec<Option<Vec<u8>>>>,
+    },
+    Count(u64),
+    Statement {
+        params: Vec<Type>,
+        columns: Vec<(String, Type)>,
+    },
+    Error {
+        severity: Severity,
+        code: SqlState,
+        message: String,
+    },
+}
+
+impl Response {
+    // Only errors reported by the database are recorded, since others like IO errors can't be replayed faithfully.
+    fn error(e: &Error) -> Option<Response> {
+        let e = e.as_db_error()?;
+        Some(Response::Error {
+            severity: e.parsed_severity().unwrap_or(Severity::Error),
+            code: e.code().clone(),
+            message: e.message().to_string(),
+        })
+    }
+}
+
+impl Recording {
+    fn push(&mut self, query: &str, params: &[&(dyn ToSql + Sync)], response: Response) {
+        self.entries.push(Entry {
+            query: query.to_string(),
+            params: params.iter().map(|p| format!("{:?}", p)).collect(),
+            response,
+        });
+    }
+
+    /// Returns the number of recorded queries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determines if no queries were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns a mock client which answers the recorded queries with the recorded results.
+    ///
+    /// Rows of types which are not built into Postgres are replayed with a simple type of the same name and OID, so
+    /// they can only be read by `FromSql` implementations which don't depend on the type's kind.
+    ///
+    /// Returns an error if a recorded row can't be built.
+    pub fn replay(&self) -> Result<MockClient, Error> {
+        let client = MockClient::new();
+        for entry in &self.entries {
+            match &entry.response {
+                Response::Rows { columns, rows } => {
+                    let rows = rows
+                        .iter()
+                        .map(|values| {
+                            columns
+                                .iter()
+                                .zip(values)
+                                .fold(RowBuilder::new(), |builder, ((name, ty), value)| {
+                                    builder.raw_column(name, ty.clone(), value.as_deref())
+                                })
+                                .build()
+                        })
+                        .collect::<Result<_, _>>()?;
+                    client.expect_query(&entry.query, rows);
+                }
+                Response::Count(count) => client.expect_execute(&entry.query, *count),
+                Response::Statement { params, columns } => client.expect_prepare(
+                    &entry.query,
+                    params.clone(),
+                    columns
+                        .iter()
+                        .map(|(name, ty)| (&**name, ty.clone()))
+                        .collect(),
+                ),
+                Response::Error {
+                    severity,
+                    code,
+                    message,
+                } => client.expect_error(
+                    &entry.query,
+                    DbErrorBuilder::new(code.clone(), message)
+                        .severity(*severity)
+                        .build(),
+                ),
+            }
+        }
+        Ok(client)
+    }
+
+    /// Saves the recording to a file.
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.encode())
+    }
+
+    /// Loads a recording from a file written by [`Recording::save`].
+    pub fn load<P>(path: P) -> io::Result<Recording>
+    where
+        P: AsRef<Path>,
+    {
+        Recording::decode(&fs::read_to_string(path)?)
+    }
+
+    fn encode(&self) -> String {
+        let mut out = String::new();
+        out.push_str(HEADER);
+        out.push('\n');
+
+        for entry in &self.entries {
+            let _ = writeln!(out, "query {}", escape(&entry.query));
+            for param in &entry.params {
+                let _ = writeln!(out, "param {}", escape(param));
+            }
+            match &entry.response {
+                Response::Rows { columns, rows } => {
+                    encode_columns(&mut out, columns);
+                    for row in rows {
+                        out.push_str("row");
+                        for value in row {
+                            out.push(' ');
+                            match value {
+                                Some(value) => {
+                                    out.push('x');
+                                    for byte in value {
+                                        let _ = write!(out, "{:02x}", byte);
+                                    }
+                                }
+                                None => out.push_str("null"),
+                            }
+                        }
+                        out.push('\n');
+                    }
+                    out.push_str("rows\n");
+                }
+                Response::Count(count) => {
+                    let _ = writeln!(out, "count {}", count);
+                }
+                Response::Statement { params, columns } => {
+                    for ty in params {
+                        let _ = writeln!(out, "type {} {}", ty.oid(), escape(ty.name()));
+                    }
+                    encode_columns(&mut out, columns);
+                    out.push_str("statement\n");
+                }
+                Response::Error {
+                    severity,
+                    code,
+                    message,
+                } => {
+                    let _ = writeln!(
+                        out,
+                        "error {} {} {}",
+                        severity,
+                        code.code(),
+                        escape(message)
+                    );
+                }
+            }
+        }
+
+        out
+    }
+
+    fn decode(s: &str) -> io::Result<Recording> {
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid("missing recording header"));
+        }
+
+        let mut recording = Recording::default();
+        let mut query = None;
+        let mut params = vec![];
+        let mut types = vec![];
+        let mut columns = vec![];
+        let mut rows = vec![];
+
+        for line in lines {
+            let mut fields = line.split(' ');
+            let response = match fields.next() {
+                Some("query") => {
+                    query = Some(unescape(field(&mut fields)?)?);
+                    continue;
+                }
+                Some("param") => {
+                    params.push(unescape(field(&mut fields)?)?);
+                    continue;
+                }
+                Some("type") => {
+                    types.push(decode_type(&mut fields)?);
+                    continue;
+                }
+                Some("column") => {
+                    let name = unescape(field(&mut fields)?)?;
+                    columns.push((name, decode_type(&mut fields)?));
+                    continue;
+                }
+                Some("row") => {
+                    let row = fields
+                        .map(|value| match value {
+                            "null" => Ok(None),
+                            value => decode_hex(value).map(Some),
+                        })
+                        .collect::<io::Result<Vec<_>>>()?;
+                    if row.len() != columns.len() {
+                        return Err(invalid("row does not match its columns"));
+                    }
+                    rows.push(row);
+                    continue;
+                }
+                Some("rows") => Response::Rows {
+                    columns: std::mem::take(&mut columns),
+                    rows: std::mem::take(&mut rows),
+                },
+                Some("statement") => Response::Statement {
+                    params: std::mem::take(&mut types),
+                    columns: std::mem::take(&mut columns),
+                },
+                Some("count") => Response::Count(
+                    field(&mut fields)?
+                        .parse()
+                        .map_err(|_| invalid("invalid row count"))?,
+                ),
+                Some("error") => Response::Error {
+                    severity: Severity::from_str(field(&mut fields)?)
+                        .ok_or_else(|| invalid("invalid error severity"))?,
+                    code: SqlState::from_code(field(&mut fields)?),
+                    message: unescape(field(&mut fields)?)?,
+                },
+                _ => return Err(invalid("unknown line")),
+            };
+
+            recording.entries.push(Entry {
+                query: query
+                    .take()
+                    .ok_or_else(|| invalid("result without query"))?,
+                params: std::mem::take(&mut params),
+                response,
+            });
+        }
+
+        if query.is_some() {
+            return Err(invalid("query without result"));
+        }
+
+        Ok(recording)
+    }
+}
+
+fn encode_columns(out: &mut String, columns: &[(String, Type)]) {
+    for (name, ty) in columns {
+        let _ = writeln!(
+            out,
+            "column {} {} {}",
+            escape(name),
+            ty.oid(),
+            escape(ty.name())
+        );
+    }
+}
+
+fn decode_type<'a>(fields: &mut impl Iterator<Item = &'a str>) -> io::Result<Type> {
+    let oid = field(fields)?
+        .parse()
+        .map_err(|_| invalid("invalid type OID"))?;
+    let name = unescape(field(fields)?)?;
+    Ok(Type::from_oid(oid)
+        .unwrap_or_else(|| Type::new(name, oid, Kind::Simple, "public".to_string())))
+}
+
+fn field<'a>(fields: &mut impl Iterator<Item = &'a str>) -> io::Result<&'a str> {
+    fields.next().ok_or_else(|| invalid("missing field"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid recording: {}", message),
+    )
+}
+
+// Fields are separated by spaces and entries by newlines, so both are escaped along with the escape character.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ' ' => out.push_str("\\s"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> io::Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            _ => return Err(invalid("invalid escape sequence")),
+        }
+    }
+    Ok(out)
+}
+
+fn decode_hex(s: &str) -> io::Result<Vec<u8>> {
+    let s = s
+        .strip_prefix('x')
+        .ok_or_else(|| invalid("invalid value"))?;
+    if s.len() % 2 != 0 {
+        return Err(invalid("invalid value"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(|| invalid("invalid value"))
+        })
+        .collect()
+}
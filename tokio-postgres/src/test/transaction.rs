@@ -0,0 +1,63 @@
+use crate::{Client, Error, Transaction};
+use std::fmt;
+use std::ops::Deref;
+
+/// A transaction which is never committed.
+///
+/// The transaction is rolled back when the `TestTransaction` is dropped. Committing it only releases a savepoint, and
+/// transactions started from it are nested within it via savepoints, so code which commits its changes can be tested
+/// without them ever becoming visible to other sessions.
+///
+/// It dereferences to a [`Transaction`] and implements [`GenericClient`](crate::GenericClient), providing the same
+/// query methods.
+#[derive(Debug)]
+pub struct TestTransaction<'a> {
+    transaction: Transaction<'a>,
+}
+
+impl<'a> TestTransaction<'a> {
+    /// Begins a new test transaction.
+    pub async fn new(client: &'a mut Client) -> Result<TestTransaction<'a>, Error> {
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute("SAVEPOINT test_transaction")
+            .await?;
+        Ok(TestTransaction { transaction })
+    }
+
+    /// Consumes the transaction, releasing its savepoint rather than committing it.
+    ///
+    /// Errors which would be reported when committing, such as a failed statement earlier in the transaction, are
+    /// still reported. The changes are rolled back afterwards.
+    pub async fn commit(self) -> Result<(), Error> {
+        self.transaction
+            .batch_execute("RELEASE test_transaction")
+            .await
+    }
+
+    /// Rolls the transaction back, discarding all changes made within it.
+    pub async fn rollback(self) -> Result<(), Error> {
+        self.transaction.rollback().await
+    }
+
+    /// Like [`Transaction::transaction`].
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
+        self.transaction.transaction().await
+    }
+
+    /// Like [`Transaction::savepoint`].
+    pub async fn savepoint<I>(&mut self, name: I) -> Result<Transaction<'_>, Error>
+    where
+        I: Into<String> + fmt::Debug,
+    {
+        self.transaction.savepoint(name).await
+    }
+}
+
+impl<'a> Deref for TestTransaction<'a> {
+    type Target = Transaction<'a>;
+
+    fn deref(&self) -> &Transaction<'a> {
+        &self.transaction
+    }
+}
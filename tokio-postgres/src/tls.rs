@@ -32,6 +32,71 @@ impl ChannelBinding {
     }
 }
 
+/// Details of a negotiated TLS session.
+///
+/// Returned by [`Client::tls_info`](crate::Client::tls_info). TLS implementations fill in the details they can
+/// report with [`TlsStream::tls_info`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+    protocol_version: Option<String>,
+    cipher: Option<String>,
+    peer_certificates: Vec<Vec<u8>>,
+    tls_server_end_point: Option<Vec<u8>>,
+}
+
+impl TlsInfo {
+    /// Creates a `TlsInfo` containing no information.
+    pub fn new() -> TlsInfo {
+        TlsInfo::default()
+    }
+
+    /// Sets the name of the negotiated protocol version, such as `TLSv1.3`.
+    pub fn protocol_version(mut self, protocol_version: &str) -> TlsInfo {
+        self.protocol_version = Some(protocol_version.to_string());
+        self
+    }
+
+    /// Sets the name of the negotiated cipher suite.
+    pub fn cipher(mut self, cipher: &str) -> TlsInfo {
+        self.cipher = Some(cipher.to_string());
+        self
+    }
+
+    /// Sets the DER-encoded certificate chain presented by the server, starting with its own certificate.
+    pub fn peer_certificates(mut self, peer_certificates: Vec<Vec<u8>>) -> TlsInfo {
+        self.peer_certificates = peer_certificates;
+        self
+    }
+
+    /// Sets the channel binding information of the session.
+    pub fn channel_binding(mut self, channel_binding: ChannelBinding) -> TlsInfo {
+        self.tls_server_end_point = channel_binding.tls_server_end_point;
+        self
+    }
+
+    /// Returns the name of the negotiated protocol version, if known.
+    pub fn get_protocol_version(&self) -> Option<&str> {
+        self.protocol_version.as_deref()
+    }
+
+    /// Returns the name of the negotiated cipher suite, if known.
+    pub fn get_cipher(&self) -> Option<&str> {
+        self.cipher.as_deref()
+    }
+
+    /// Returns the DER-encoded certificate chain presented by the server, if known.
+    ///
+    /// The chain starts with the server's own certificate, and is empty if it isn't known.
+    pub fn get_peer_certificates(&self) -> &[Vec<u8>] {
+        &self.peer_certificates
+    }
+
+    /// Returns the `tls-server-end-point` channel binding data of the session, if known.
+    pub fn get_tls_server_end_point(&self) -> Option<&[u8]> {
+        self.tls_server_end_point.as_deref()
+    }
+}
+
 /// A constructor of `TlsConnect`ors.
 ///
 /// Requires the `runtime` Cargo feature (enabled by default).
@@ -72,6 +137,13 @@ pub trait TlsConnect<S> {
 pub trait TlsStream: AsyncRead + AsyncWrite {
     /// Returns channel binding information for the session.
     fn channel_binding(&self) -> ChannelBinding;
+
+    /// Returns details of the negotiated session.
+    ///
+    /// The default implementation only reports the channel binding information.
+    fn tls_info(&self) -> TlsInfo {
+        TlsInfo::new().channel_binding(self.channel_binding())
+    }
 }
 
 /// A `MakeTlsConnect` and `TlsConnect` implementation which simply returns an error.
@@ -1,9 +1,13 @@
 use crate::to_statement::private::{Sealed, ToStatementType};
+use crate::types::Type;
 use crate::Statement;
+use std::borrow::Cow;
+use std::sync::Arc;
 
 mod private {
     use std::sync::Arc;
 
+    use crate::types::Type;
     use crate::{client::InnerClient, prepare, Error, Statement};
 
     pub trait Sealed {}
@@ -11,13 +15,18 @@ mod private {
     pub enum ToStatementType<'a> {
         Statement(&'a Statement),
         Query(&'a str),
+        TypedQuery(&'a str, &'a [Type]),
     }
 
     impl<'a> ToStatementType<'a> {
         pub async fn into_statement(self, client: &Arc<InnerClient>) -> Result<Statement, Error> {
             match self {
-                ToStatementType::Statement(s) => Ok(s.clone()),
-                ToStatementType::Query(s) => prepare::prepare(client, s, &[]).await,
+                ToStatementType::Statement(s) if s.is_prepared_on(client) => Ok(s.clone()),
+                ToStatementType::Statement(s) => prepare::reprepare(client, s).await,
+                ToStatementType::Query(s) => prepare::prepare_cached(client, s, &[]).await,
+                ToStatementType::TypedQuery(s, types) => {
+                    prepare::prepare_cached(client, s, types).await
+                }
             }
         }
     }
@@ -57,3 +66,79 @@ impl ToStatement for String {
 }
 
 impl Sealed for String {}
+
+impl ToStatement for Cow<'_, str> {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::Query(self)
+    }
+}
+
+impl Sealed for Cow<'_, str> {}
+
+impl ToStatement for Arc<str> {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::Query(self)
+    }
+}
+
+impl Sealed for Arc<str> {}
+
+impl ToStatement for Box<str> {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::Query(self)
+    }
+}
+
+impl Sealed for Box<str> {}
+
+/// A query with the types of its parameters, which is prepared like [`Client::prepare_typed`] when it is used.
+///
+/// ```no_run
+/// use tokio_postgres::types::Type;
+/// use tokio_postgres::TypedQuery;
+///
+/// # async fn run(client: &tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+/// let query = TypedQuery::new("SELECT name FROM users WHERE id = $1", &[Type::INT8][..]);
+/// let rows = client.query(&query, &[&1i64]).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client::prepare_typed`]: crate::Client::prepare_typed
+#[derive(Debug, Clone)]
+pub struct TypedQuery<'a> {
+    sql: Cow<'a, str>,
+    param_types: Cow<'a, [Type]>,
+}
+
+impl<'a> TypedQuery<'a> {
+    /// Creates a new typed query.
+    pub fn new<S, T>(sql: S, param_types: T) -> TypedQuery<'a>
+    where
+        S: Into<Cow<'a, str>>,
+        T: Into<Cow<'a, [Type]>>,
+    {
+        TypedQuery {
+            sql: sql.into(),
+            param_types: param_types.into(),
+        }
+    }
+
+    /// Returns the text of the query.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the types of the parameters of the query.
+    pub fn param_types(&self) -> &[Type] {
+        &self.param_types
+    }
+}
+
+impl ToStatement for TypedQuery<'_> {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::TypedQuery(&self.sql, &self.param_types)
+    }
+}
+
+impl Sealed for TypedQuery<'_> {}
@@ -1,6 +1,8 @@
+use crate::binary_copy::BinaryCopyOutStreamAs;
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
 use crate::copy_out::CopyOutStream;
+use crate::escape::escape_literal;
 use crate::query::RowStream;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
@@ -9,8 +11,8 @@ use crate::types::{BorrowToSql, ToSql, Type};
 #[cfg(feature = "runtime")]
 use crate::Socket;
 use crate::{
-    bind, query, slice_iter, CancelToken, Client, CopyInSink, Error, FromRow, Portal, Row,
-    SimpleQueryMessage, Statement, ToStatement,
+    bind, query, slice_iter, CancelToken, Client, CopyInSink, Error, FromRow, IntoParams, Portal,
+    QueryOptions, Row, SimpleQueryMessage, Statement, ToParams, ToStatement,
 };
 use bytes::Buf;
 use futures_util::{stream::BoxStream, TryStreamExt};
@@ -24,6 +26,10 @@ use tokio::io::{AsyncRead, AsyncWrite};
 ///
 /// Transactions will implicitly roll back when dropped. Use the `commit` method to commit the changes made in the
 /// transaction. Transactions can be nested, with inner transactions implemented via safepoints.
+///
+/// A transaction is bound to the session it was begun in. If the connection is resumed by a
+/// [`ResumingConnection`](crate::ResumingConnection), the transaction is lost with the old session, and all further
+/// requests made while it is open, including `commit`, fail with a closed connection error.
 pub struct Transaction<'a> {
     client: &'a mut Client,
     savepoint: Option<Savepoint>,
@@ -47,16 +53,43 @@ struct Savepoint {
 }
 
 impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            let query = if let Some(sp) = self.savepoint.as_ref() {
+                format!("ROLLBACK TO {}", sp.name)
+            } else {
+                "ROLLBACK".to_string()
+            };
+            let buf = self.client.inner().with_buf(|buf| {
+                frontend::query(&query, buf).unwrap();
+                buf.split().freeze()
+            });
+            let _ = self
+                .client
+                .inner()
+                .send(RequestMessages::Single(FrontendMessage::Raw(buf)));
+        }
+
+        if self.savepoint.is_none() {
+            self.client.inner().set_transaction_session(None);
+        }
+    }
+}
+
+/// Releases a savepoint whose creation was cancelled after the `SAVEPOINT` statement was sent.
+struct ReleaseIfNotDone<'me> {
+    client: &'me Client,
+    name: &'me str,
+    done: bool,
+}
+
+impl<'a> Drop for ReleaseIfNotDone<'a> {
     fn drop(&mut self) {
         if self.done {
             return;
         }
 
-        let query = if let Some(sp) = self.savepoint.as_ref() {
-            format!("ROLLBACK TO {}", sp.name)
-        } else {
-            "ROLLBACK".to_string()
-        };
+        let query = format!("ROLLBACK TO {0}; RELEASE {0}", self.name);
         let buf = self.client.inner().with_buf(|buf| {
             frontend::query(&query, buf).unwrap();
             buf.split().freeze()
@@ -69,7 +102,9 @@ impl<'a> Drop for Transaction<'a> {
 }
 
 impl<'a> Transaction<'a> {
-    pub(crate) fn new(client: &'a mut Client) -> Transaction<'a> {
+    /// Creates a transaction which was begun in `session`.
+    pub(crate) fn new(client: &'a mut Client, session: u64) -> Transaction<'a> {
+        client.inner().set_transaction_session(Some(session));
         Transaction {
             client,
             savepoint: None,
@@ -103,6 +138,39 @@ impl<'a> Transaction<'a> {
         self.client.batch_execute(&query).await
     }
 
+    /// Consumes the transaction, preparing it for two-phase commit with the global identifier `gid`.
+    ///
+    /// The transaction is dissociated from the session and stored on the server, where it survives crashes and
+    /// restarts until it is committed with [`Client::commit_prepared`] or rolled back with
+    /// [`Client::rollback_prepared`], possibly from another session. This requires the `max_prepared_transactions`
+    /// setting of the server to be above 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transaction is a nested transaction, as only a whole transaction can be prepared.
+    pub async fn prepare_transaction(mut self, gid: &str) -> Result<(), Error> {
+        assert!(
+            self.savepoint.is_none(),
+            "a nested transaction can't be prepared"
+        );
+        let query = format!("PREPARE TRANSACTION {}", escape_literal(gid)?);
+        self.done = true;
+        self.client.batch_execute(&query).await
+    }
+
+    /// Exports the snapshot of the transaction, returning its identifier.
+    ///
+    /// Transactions on other connections can use the snapshot with
+    /// [`TransactionBuilder::use_snapshot`](crate::TransactionBuilder::use_snapshot) to see the same data as this
+    /// transaction, for example to read a consistent view of the database with several connections in parallel. The
+    /// snapshot can only be used while this transaction is open.
+    pub async fn export_snapshot(&self) -> Result<String, Error> {
+        self.client
+            .query_one("SELECT pg_export_snapshot()", &[])
+            .await?
+            .try_get(0)
+    }
+
     /// Like [`Client::prepare`]
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub async fn prepare(&self, query: &str) -> Result<Statement, Error> {
@@ -184,6 +252,32 @@ impl<'a> Transaction<'a> {
         self.client.query_one_as(statement, params).await
     }
 
+    /// Like [`Client::insert_returning`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn insert_returning<R: FromRow, T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<R, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.client.insert_returning(statement, params).await
+    }
+
+    /// Like [`Client::update_returning`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn update_returning<R: FromRow, T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<R, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.client.update_returning(statement, params).await
+    }
+
     /// Like [`Client::query_one_scalar`]
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub async fn query_one_scalar<R: FromSqlOwned, T>(
@@ -248,6 +342,57 @@ impl<'a> Transaction<'a> {
         self.client.query_raw(statement, params).await
     }
 
+    /// Like [`Client::query_with_options`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_with_options<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.client
+            .query_with_options(statement, params, options)
+            .await
+    }
+
+    /// Like [`Client::query_raw_with_options`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_raw_with_options<T, P, I>(
+        &self,
+        statement: &T,
+        params: I,
+        options: &QueryOptions,
+    ) -> Result<RowStream, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: BorrowToSql,
+        I: IntoIterator<Item = P>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.client
+            .query_raw_with_options(statement, params, options)
+            .await
+    }
+
+    /// Like [`Client::execute_with_options`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_with_options<T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+        options: &QueryOptions,
+    ) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.client
+            .execute_with_options(statement, params, options)
+            .await
+    }
+
     /// Like [`Client::stream`]
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub async fn stream<T>(
@@ -274,6 +419,19 @@ impl<'a> Transaction<'a> {
         self.client.stream_as(statement, params).await
     }
 
+    /// Like [`Client::stream_scalar`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn stream_scalar<R: FromSqlOwned, T>(
+        &self,
+        statement: &T,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<BoxStream<'static, Result<R, Error>>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+    {
+        self.client.stream_scalar(statement, params).await
+    }
+
     /// Like [`Client::execute`]
     #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
     pub async fn execute<T>(
@@ -361,6 +519,75 @@ impl<'a> Transaction<'a> {
         query::query_portal(self.client.inner(), portal, max_rows).await
     }
 
+    /// Like [`Client::query_with`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_with<T, P>(&self, statement: &T, params: P) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        self.client.query_with(statement, params).await
+    }
+
+    /// Like [`Client::query_one_with`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_one_with<T, P>(&self, statement: &T, params: P) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        self.client.query_one_with(statement, params).await
+    }
+
+    /// Like [`Client::query_opt_with`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_opt_with<T, P>(&self, statement: &T, params: P) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        self.client.query_opt_with(statement, params).await
+    }
+
+    /// Like [`Client::execute_with`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_with<T, P>(&self, statement: &T, params: P) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+    {
+        self.client.execute_with(statement, params).await
+    }
+
+    /// Like [`Client::execute_many`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_many<T, P, I>(&self, statement: &T, params: I) -> Result<Vec<u64>, Error>
+    where
+        T: ?Sized + ToStatement + fmt::Debug,
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        self.client.execute_many(statement, params).await
+    }
+
+    /// Like [`Client::query_named`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn query_named<P>(&self, query: &str, params: &P) -> Result<Vec<Row>, Error>
+    where
+        P: ?Sized + ToParams,
+    {
+        self.client.query_named(query, params).await
+    }
+
+    /// Like [`Client::execute_named`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(params)))]
+    pub async fn execute_named<P>(&self, query: &str, params: &P) -> Result<u64, Error>
+    where
+        P: ?Sized + ToParams,
+    {
+        self.client.execute_named(query, params).await
+    }
+
     /// Like [`Client::copy_in`]
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub async fn copy_in<T, U>(&self, statement: &T) -> Result<CopyInSink<U>, Error>
@@ -380,6 +607,30 @@ impl<'a> Transaction<'a> {
         self.client.copy_out(statement).await
     }
 
+    /// Like [`Client::copy_insert`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(rows)))]
+    pub async fn copy_insert<P, I>(
+        &self,
+        table: &str,
+        columns: &[&str],
+        rows: I,
+    ) -> Result<u64, Error>
+    where
+        P: IntoParams,
+        I: IntoIterator<Item = P>,
+    {
+        self.client.copy_insert(table, columns, rows).await
+    }
+
+    /// Like [`Client::copy_out_as`]
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub async fn copy_out_as<R>(&self, query: &str) -> Result<BinaryCopyOutStreamAs<R>, Error>
+    where
+        R: FromRow,
+    {
+        self.client.copy_out_as(query).await
+    }
+
     /// Like `Client::simple_query`.
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub async fn simple_query(&self, query: &str) -> Result<Vec<SimpleQueryMessage>, Error> {
@@ -425,6 +676,33 @@ impl<'a> Transaction<'a> {
         self._savepoint(None).await
     }
 
+    /// Creates a scope within the transaction, returning a guard for it.
+    ///
+    /// The scope is a nested transaction, whose changes are kept by consuming the guard with `commit`. If the guard is
+    /// dropped instead, for example because an error was returned with `?` or the future running statements in the
+    /// scope was cancelled, a `ROLLBACK TO` its savepoint is sent to the server without waiting for a response, so
+    /// that the enclosing transaction continues as it was before the scope was created. Cancelling the future returned
+    /// by this method after the savepoint was sent removes it again as well.
+    ///
+    /// ```no_run
+    /// # async fn run(client: &mut tokio_postgres::Client) -> Result<(), tokio_postgres::Error> {
+    /// let mut transaction = client.transaction().await?;
+    /// transaction.execute("INSERT INTO audit (event) VALUES ('import')", &[]).await?;
+    ///
+    /// let scope = transaction.scope().await?;
+    /// match scope.execute("INSERT INTO users (name) VALUES ('steven')", &[]).await {
+    ///     Ok(_) => scope.commit().await?,
+    ///     Err(_) => drop(scope),
+    /// }
+    ///
+    /// transaction.commit().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn scope(&mut self) -> Result<Transaction<'_>, Error> {
+        self._savepoint(None).await
+    }
+
     /// Like `Client::transaction`, but creates a nested transaction via a savepoint with the specified name.
     #[cfg_attr(feature = "tracing", tracing::instrument)]
     pub async fn savepoint<I>(&mut self, name: I) -> Result<Transaction<'_>, Error>
@@ -438,7 +716,19 @@ impl<'a> Transaction<'a> {
         let depth = self.savepoint.as_ref().map_or(0, |sp| sp.depth) + 1;
         let name = name.unwrap_or_else(|| format!("sp_{}", depth));
         let query = format!("SAVEPOINT {}", name);
-        self.batch_execute(&query).await?;
+
+        // Like in `Client::transaction`, the future can be dropped after the statement was sent but before the
+        // savepoint is owned by a `Transaction`. A failed statement created no savepoint, so there is nothing to undo.
+        {
+            let mut cleaner = ReleaseIfNotDone {
+                client: self.client,
+                name: &name,
+                done: false,
+            };
+            let result = self.batch_execute(&query).await;
+            cleaner.done = true;
+            result?;
+        }
 
         Ok(Transaction {
             client: self.client,
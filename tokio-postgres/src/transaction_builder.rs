@@ -1,3 +1,4 @@
+use crate::escape::escape_literal;
 use crate::{Client, Error, Transaction};
 
 /// The isolation level of a database transaction.
@@ -25,6 +26,7 @@ pub struct TransactionBuilder<'a> {
     isolation_level: Option<IsolationLevel>,
     read_only: Option<bool>,
     deferrable: Option<bool>,
+    snapshot: Option<String>,
 }
 
 impl<'a> TransactionBuilder<'a> {
@@ -34,6 +36,7 @@ impl<'a> TransactionBuilder<'a> {
             isolation_level: None,
             read_only: None,
             deferrable: None,
+            snapshot: None,
         }
     }
 
@@ -59,6 +62,17 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
+    /// Sets the snapshot of the transaction to one exported by another transaction with
+    /// [`Transaction::export_snapshot`].
+    ///
+    /// The transaction then sees the same data as the exporting transaction, which must still be open. This requires
+    /// the `RepeatableRead` or `Serializable` isolation level, and a serializable transaction can only import a
+    /// snapshot from another serializable transaction.
+    pub fn use_snapshot(mut self, snapshot: &str) -> Self {
+        self.snapshot = Some(snapshot.to_string());
+        self
+    }
+
     /// Begins the transaction.
     ///
     /// The transaction will roll back by default - use the `commit` method to commit it.
@@ -106,8 +120,20 @@ impl<'a> TransactionBuilder<'a> {
             query.push_str(s);
         }
 
-        self.client.batch_execute(&query).await?;
+        if let Some(snapshot) = &self.snapshot {
+            query.push_str("; SET TRANSACTION SNAPSHOT ");
+            query.push_str(&escape_literal(snapshot)?);
+        }
+
+        let session = self.client.inner().session();
+        if let Err(e) = self.client.batch_execute(&query).await {
+            // the transaction has been started if only the snapshot couldn't be set
+            if self.snapshot.is_some() {
+                let _ = self.client.batch_execute("ROLLBACK").await;
+            }
+            return Err(e);
+        }
 
-        Ok(Transaction::new(self.client))
+        Ok(Transaction::new(self.client, session))
     }
 }
@@ -0,0 +1,214 @@
+//! An io_uring based connection driver.
+//!
+//! [`Config::connect_uring`] opens a connection whose socket is driven by [tokio-uring](https://crates.io/crates/tokio-uring)
+//! rather than by the readiness-based Tokio reactor. Reads and writes are submitted as io_uring operations on owned
+//! buffers, which saves a system call per operation when many requests are pipelined over a single connection.
+//!
+//! The connection has to run on a tokio-uring runtime, and as its operations are tied to the thread of that runtime,
+//! it cannot be sent to another thread. The [`Client`](crate::Client) has no such restriction.
+//!
+//! Requires the `tokio-uring` Cargo feature, and Linux 5.10 or later.
+//!
+//! ```no_run
+//! use tokio_postgres::{Config, NoTls};
+//!
+//! # fn main() -> Result<(), tokio_postgres::Error> {
+//! tokio_uring::start(async {
+//!     let config = "host=localhost user=postgres".parse::<Config>()?;
+//!     let (client, connection) = config.connect_uring(NoTls).await?;
+//!     tokio_uring::spawn(connection);
+//!
+//!     let rows = client.query("SELECT 1", &[]).await?;
+//!     # Ok(())
+//! })
+//! # }
+//! ```
+//!
+//! [`Config::connect_uring`]: crate::Config::connect_uring
+use futures_util::ready;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::net::Shutdown;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_uring::net::{TcpStream, UnixStream};
+
+const BUF_SIZE: usize = 16 * 1024;
+
+type Op = Pin<Box<dyn Future<Output = (io::Result<usize>, Vec<u8>)>>>;
+
+enum Inner {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Inner {
+    async fn read(&self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        match self {
+            Inner::Tcp(s) => s.read(buf).await,
+            Inner::Unix(s) => s.read(buf).await,
+        }
+    }
+
+    async fn write(&self, buf: Vec<u8>) -> (io::Result<usize>, Vec<u8>) {
+        match self {
+            Inner::Tcp(s) => s.write(buf).await,
+            Inner::Unix(s) => s.write(buf).await,
+        }
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Inner::Tcp(s) => s.shutdown(Shutdown::Write),
+            Inner::Unix(s) => s.shutdown(Shutdown::Write),
+        }
+    }
+}
+
+/// A socket driven by io_uring.
+///
+/// It adapts the owned-buffer operations of tokio-uring to `AsyncRead` and `AsyncWrite`: a read fills an owned buffer
+/// which is then handed out, and a write copies the data into an owned buffer and submits it, so that the next
+/// message can be encoded while the write is in flight. Only one read and one write are in flight at a time.
+pub struct UringStream {
+    inner: Rc<Inner>,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    read_op: Option<Op>,
+    write_buf: Vec<u8>,
+    write_op: Option<Op>,
+}
+
+impl UringStream {
+    pub(crate) fn new_tcp(stream: TcpStream) -> UringStream {
+        UringStream::new(Inner::Tcp(stream))
+    }
+
+    pub(crate) fn new_unix(stream: UnixStream) -> UringStream {
+        UringStream::new(Inner::Unix(stream))
+    }
+
+    fn new(inner: Inner) -> UringStream {
+        UringStream {
+            inner: Rc::new(inner),
+            read_buf: Vec::with_capacity(BUF_SIZE),
+            read_pos: 0,
+            read_op: None,
+            write_buf: Vec::with_capacity(BUF_SIZE),
+            write_op: None,
+        }
+    }
+
+    /// Waits for the write in flight, submitting what is left of it again until all of it is written.
+    fn poll_write_op(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while let Some(op) = &mut self.write_op {
+            let (result, mut buf) = ready!(op.as_mut().poll(cx));
+            self.write_op = None;
+            let n = match result {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => n,
+                Err(e) => return Poll::Ready(Err(e)),
+            };
+
+            buf.drain(..n);
+            if buf.is_empty() {
+                self.write_buf = buf;
+            } else {
+                self.submit_write(buf);
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn submit_write(&mut self, buf: Vec<u8>) {
+        let inner = self.inner.clone();
+        self.write_op = Some(Box::pin(async move { inner.write(buf).await }));
+    }
+}
+
+impl fmt::Debug for UringStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UringStream")
+            .field("reading", &self.read_op.is_some())
+            .field("writing", &self.write_op.is_some())
+            .finish()
+    }
+}
+
+impl AsyncRead for UringStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.read_pos < this.read_buf.len() {
+                let n = usize::min(buf.remaining(), this.read_buf.len() - this.read_pos);
+                buf.put_slice(&this.read_buf[this.read_pos..this.read_pos + n]);
+                this.read_pos += n;
+                return Poll::Ready(Ok(()));
+            }
+
+            let op = match &mut this.read_op {
+                Some(op) => op,
+                None => {
+                    let mut read_buf = mem::take(&mut this.read_buf);
+                    read_buf.clear();
+                    read_buf.reserve(BUF_SIZE);
+                    let inner = this.inner.clone();
+                    this.read_op
+                        .insert(Box::pin(async move { inner.read(read_buf).await }))
+                }
+            };
+
+            let (result, read_buf) = ready!(op.as_mut().poll(cx));
+            this.read_op = None;
+            this.read_buf = read_buf;
+            this.read_pos = 0;
+            if result? == 0 {
+                // the end of the stream is signaled by not filling `buf`
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl AsyncWrite for UringStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let this = &mut *self;
+        ready!(this.poll_write_op(cx))?;
+
+        let mut write_buf = mem::take(&mut this.write_buf);
+        write_buf.clear();
+        write_buf.extend_from_slice(buf);
+        this.submit_write(write_buf);
+        // the operation is submitted when it is first polled
+        match this.poll_write_op(cx) {
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            _ => Poll::Ready(Ok(buf.len())),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_write_op(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_op(cx))?;
+        Poll::Ready(self.inner.shutdown())
+    }
+}
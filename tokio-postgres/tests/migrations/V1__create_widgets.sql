@@ -0,0 +1,4 @@
+CREATE TABLE migrate_widgets (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL
+);
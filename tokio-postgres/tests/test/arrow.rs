@@ -0,0 +1,231 @@
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Date32Type, Float64Type, Int16Type, Int32Type, Int64Type, Time64MicrosecondType,
+    TimestampMicrosecondType,
+};
+use arrow_array::Array;
+use arrow_schema::{DataType, TimeUnit};
+use futures_util::{StreamExt, TryStreamExt};
+use tokio_postgres::arrow::RecordBatchStream;
+use tokio_postgres::binary_copy::BinaryCopyOutStream;
+use tokio_postgres::types::{Format, Type};
+use tokio_postgres::QueryOptions;
+
+use crate::connect;
+
+#[tokio::test]
+async fn query_types() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT
+                true AS bool,
+                1::INT2 AS int2,
+                2::INT4 AS int4,
+                3::INT8 AS int8,
+                1.5::FLOAT8 AS float8,
+                'hello'::TEXT AS text,
+                '{\"a\": 1}'::JSONB AS jsonb,
+                '\\xdeadbeef'::BYTEA AS bytea,
+                'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::UUID AS uuid,
+                '1970-01-02'::DATE AS date,
+                '00:00:01'::TIME AS time,
+                '2000-01-01 00:00:00'::TIMESTAMP AS timestamp,
+                '1970-01-01 00:00:00+00'::TIMESTAMPTZ AS timestamptz,
+                NULL::TEXT AS null",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+    let batches = RecordBatchStream::from_rows(rows, 10)
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(), 1);
+
+    let schema = batch.schema();
+    assert_eq!(schema.field(0).name(), "bool");
+    assert_eq!(schema.field(1).data_type(), &DataType::Int16);
+    assert_eq!(schema.field(8).data_type(), &DataType::FixedSizeBinary(16));
+    assert_eq!(
+        schema.field(12).data_type(),
+        &DataType::Timestamp(TimeUnit::Microsecond, Some("+00:00".into())),
+    );
+    assert!(schema.fields().iter().all(|f| f.is_nullable()));
+
+    assert!(batch.column(0).as_boolean().value(0));
+    assert_eq!(batch.column(1).as_primitive::<Int16Type>().value(0), 1);
+    assert_eq!(batch.column(2).as_primitive::<Int32Type>().value(0), 2);
+    assert_eq!(batch.column(3).as_primitive::<Int64Type>().value(0), 3);
+    assert_eq!(batch.column(4).as_primitive::<Float64Type>().value(0), 1.5);
+    assert_eq!(batch.column(5).as_string::<i32>().value(0), "hello");
+    assert_eq!(batch.column(6).as_string::<i32>().value(0), "{\"a\": 1}");
+    assert_eq!(
+        batch.column(7).as_binary::<i32>().value(0),
+        b"\xde\xad\xbe\xef"
+    );
+    assert_eq!(
+        batch.column(8).as_fixed_size_binary().value(0),
+        [
+            0xa0, 0xee, 0xbc, 0x99, 0x9c, 0x0b, 0x4e, 0xf8, 0xbb, 0x6d, 0x6b, 0xb9, 0xbd, 0x38,
+            0x0a, 0x11
+        ],
+    );
+    assert_eq!(batch.column(9).as_primitive::<Date32Type>().value(0), 1);
+    assert_eq!(
+        batch
+            .column(10)
+            .as_primitive::<Time64MicrosecondType>()
+            .value(0),
+        1_000_000,
+    );
+    assert_eq!(
+        batch
+            .column(11)
+            .as_primitive::<TimestampMicrosecondType>()
+            .value(0),
+        946_684_800_000_000,
+    );
+    assert_eq!(
+        batch
+            .column(12)
+            .as_primitive::<TimestampMicrosecondType>()
+            .value(0),
+        0,
+    );
+    assert!(batch.column(13).is_null(0));
+}
+
+#[tokio::test]
+async fn query_batches() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT g AS id, CASE WHEN g % 2 = 0 THEN NULL ELSE g::TEXT END AS name
+             FROM generate_series(1, 5) g",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+    let batches = RecordBatchStream::from_rows(rows, 2)
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let sizes = batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>();
+    assert_eq!(sizes, [2, 2, 1]);
+    let ids = batches
+        .iter()
+        .flat_map(|b| b.column(0).as_primitive::<Int32Type>().values().to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    let names = batches[0].column(1).as_string::<i32>();
+    assert_eq!(names.value(0), "1");
+    assert!(names.is_null(1));
+}
+
+#[tokio::test]
+async fn empty_query() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT 1::INT8 AS id, 'a'::TEXT AS name WHERE false",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+    let mut batches = RecordBatchStream::from_rows(rows, 10).unwrap();
+
+    let schema = batches.schema();
+    assert_eq!(schema.fields().len(), 2);
+    assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+    assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+    assert!(batches.next().await.is_none());
+}
+
+#[tokio::test]
+async fn unsupported_type() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT 1::INT4 AS id, 1.5::NUMERIC AS amount",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+    let err = RecordBatchStream::from_rows(rows, 10).unwrap_err();
+    assert!(err.to_string().contains("column 1"), "{}", err);
+
+    // in the text format, any type is read as a string
+    let options = QueryOptions::new().column_format(1, Format::Text);
+    let rows = client
+        .query_raw_with_options(
+            "SELECT 1::INT4 AS id, 1.5::NUMERIC AS amount",
+            std::iter::empty::<i32>(),
+            &options,
+        )
+        .await
+        .unwrap();
+    let batches = RecordBatchStream::from_rows(rows, 10)
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(batches[0].column(1).as_string::<i32>().value(0), "1.5");
+}
+
+#[tokio::test]
+async fn infinite_timestamp() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT 'infinity'::TIMESTAMP AS ts",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+    let mut batches = RecordBatchStream::from_rows(rows, 10).unwrap();
+
+    assert!(batches.next().await.unwrap().is_err());
+    assert!(batches.next().await.is_none());
+}
+
+#[tokio::test]
+async fn binary_copy_out() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, bar TEXT);
+             INSERT INTO foo SELECT g, 'bar' || g FROM generate_series(1, 3) g;",
+        )
+        .await
+        .unwrap();
+
+    let stream = client
+        .copy_out("COPY foo (id, bar) TO STDOUT BINARY")
+        .await
+        .unwrap();
+    let stream = BinaryCopyOutStream::new(stream, &[Type::INT4, Type::TEXT]);
+    let batches = RecordBatchStream::from_binary_copy(stream, &["id", "bar"], 2)
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].schema().field(1).name(), "bar");
+    assert_eq!(batches[1].num_rows(), 1);
+    assert_eq!(batches[1].column(0).as_primitive::<Int32Type>().value(0), 3);
+    assert_eq!(batches[1].column(1).as_string::<i32>().value(0), "bar3");
+}
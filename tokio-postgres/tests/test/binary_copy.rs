@@ -2,6 +2,7 @@ use crate::connect;
 use futures_util::{pin_mut, TryStreamExt};
 use tokio_postgres::binary_copy::{BinaryCopyInWriter, BinaryCopyOutStream};
 use tokio_postgres::types::Type;
+use tokio_postgres::{Error, FromRow, Row};
 
 #[tokio::test]
 async fn write_basic() {
@@ -201,3 +202,114 @@ async fn read_big_rows() {
         assert_eq!(row.get::<&[u8]>(1), &vec![i as u8; 128 * 1024][..]);
     }
 }
+
+#[tokio::test]
+async fn copy_insert() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE \"Foo\" (id INT, \"Bar\" TEXT, baz BYTEA)")
+        .await
+        .unwrap();
+
+    let rows = (0..10_000).map(|i| (i, (i % 2 == 0).then(|| i.to_string())));
+    let inserted = client
+        .copy_insert("Foo", &["id", "Bar"], rows)
+        .await
+        .unwrap();
+    assert_eq!(inserted, 10_000);
+
+    let rows = client
+        .query("SELECT id, \"Bar\" FROM \"Foo\" ORDER BY id LIMIT 2", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 0);
+    assert_eq!(rows[0].get::<_, Option<&str>>(1), Some("0"));
+    assert_eq!(rows[1].get::<_, i32>(0), 1);
+    assert_eq!(rows[1].get::<_, Option<&str>>(1), None);
+
+    client
+        .copy_insert("Foo", &["id", "Bar"], [(1,)])
+        .await
+        .unwrap_err();
+    client
+        .copy_insert("Foo", &["id", "baz"], [(1, "not bytes")])
+        .await
+        .unwrap_err();
+    client
+        .copy_insert("Foo", &["id\0"], [(1,)])
+        .await
+        .unwrap_err();
+    let count = client
+        .query_one("SELECT COUNT(*) FROM \"Foo\"", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 10_000);
+}
+
+#[derive(Debug, PartialEq)]
+struct Item {
+    id: i32,
+    name: Option<String>,
+}
+
+impl FromRow for Item {
+    fn from_row(row: &Row) -> Result<Item, Error> {
+        Ok(Item {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+        })
+    }
+}
+
+#[tokio::test]
+async fn read_as() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, bar TEXT);
+             INSERT INTO foo (id, bar) VALUES (1, 'foobar'), (2, NULL);",
+        )
+        .await
+        .unwrap();
+
+    let stream = client
+        .copy_out("COPY foo (bar, id) TO STDOUT BINARY")
+        .await
+        .unwrap();
+    let items = BinaryCopyOutStream::new(stream, &[Type::TEXT, Type::INT4])
+        .map_as::<Item>(&["name", "id"])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(
+        items,
+        [
+            Item {
+                id: 1,
+                name: Some("foobar".to_string())
+            },
+            Item { id: 2, name: None },
+        ]
+    );
+
+    let items = client
+        .copy_out_as::<Item>("SELECT id, bar AS name FROM foo WHERE id > 1")
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(items, [Item { id: 2, name: None }]);
+
+    let err = client
+        .copy_out_as::<Item>("SELECT id FROM foo")
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("name"), "{}", err);
+}
@@ -0,0 +1,78 @@
+use tokio_postgres::bulk::BulkInsert;
+
+use crate::connect;
+
+#[test]
+fn statements() {
+    let rows = vec![(1, "a"), (2, "b"), (3, "c")];
+    let insert = BulkInsert::new("my \"items\"", &["id", "Name"])
+        .schema("public")
+        .suffix("ON CONFLICT DO NOTHING")
+        .max_params(5);
+    assert_eq!(insert.rows_per_statement(), 2);
+
+    let statements = insert.statements(&rows).unwrap();
+    let sql = statements.iter().map(|s| s.sql()).collect::<Vec<_>>();
+    assert_eq!(
+        sql,
+        [
+            r#"INSERT INTO "public"."my ""items""" ("id", "Name") VALUES ($1, $2), ($3, $4) ON CONFLICT DO NOTHING"#,
+            r#"INSERT INTO "public"."my ""items""" ("id", "Name") VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+        ]
+    );
+    assert_eq!(statements[0].params().len(), 4);
+    assert_eq!(statements[1].params().len(), 2);
+
+    assert!(insert
+        .statements(&Vec::<(i32, &str)>::new())
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        BulkInsert::new("items", &["a", "b", "c"]).rows_per_statement(),
+        10922
+    );
+    assert_eq!(
+        BulkInsert::new("items", &["a", "b"])
+            .max_params(1)
+            .rows_per_statement(),
+        1
+    );
+    assert!(BulkInsert::new("items\0", &["id", "Name"])
+        .statements(&rows)
+        .is_err());
+}
+
+#[tokio::test]
+async fn execute() {
+    let client = connect("user=postgres").await;
+    client
+        .batch_execute("CREATE TEMPORARY TABLE items (id INT PRIMARY KEY, name TEXT)")
+        .await
+        .unwrap();
+
+    let rows = (0..40_000).map(|i| (i, i.to_string())).collect::<Vec<_>>();
+    let inserted = BulkInsert::new("items", &["id", "name"])
+        .execute(&client, &rows)
+        .await
+        .unwrap();
+    assert_eq!(inserted, 40_000);
+
+    let row = client
+        .query_one("SELECT COUNT(*), MAX(name::INT) FROM items", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 40_000);
+    assert_eq!(row.get::<_, i32>(1), 39_999);
+
+    let inserted = BulkInsert::new("items", &["id", "name"])
+        .suffix("ON CONFLICT DO NOTHING")
+        .execute(&client, &[(0, "a".to_string()), (40_000, "b".to_string())])
+        .await
+        .unwrap();
+    assert_eq!(inserted, 1);
+
+    BulkInsert::new("items", &["id", "name"])
+        .execute(&client, &[(1,)])
+        .await
+        .unwrap_err();
+}
@@ -0,0 +1,186 @@
+use futures_util::FutureExt;
+use std::time::Duration;
+use tokio::net::TcpStream;
+#[cfg(feature = "runtime")]
+use tokio_postgres::invalidate::{Invalidation, Operation};
+use tokio_postgres::{Client, Config, Error, FromRow, NoTls, Row};
+
+use crate::connect;
+
+#[derive(Debug, PartialEq)]
+struct Item {
+    id: i32,
+    name: String,
+}
+
+impl FromRow for Item {
+    fn from_row(row: &Row) -> Result<Item, Error> {
+        Ok(Item {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+        })
+    }
+}
+
+async fn setup() -> Client {
+    let client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE items (id INT PRIMARY KEY, name TEXT NOT NULL);
+             INSERT INTO items (id, name) VALUES (1, 'a'), (2, 'b');",
+        )
+        .await
+        .unwrap();
+    client
+}
+
+async fn names(client: &Client, min_id: i32) -> Vec<String> {
+    client
+        .cached(Duration::from_secs(60))
+        .tag("items")
+        .query_scalar(
+            "SELECT name FROM items WHERE id >= $1 ORDER BY id",
+            &[&min_id],
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn cached_queries() {
+    let client = setup().await;
+
+    assert_eq!(names(&client, 1).await, ["a", "b"]);
+    client
+        .batch_execute("INSERT INTO items (id, name) VALUES (3, 'c')")
+        .await
+        .unwrap();
+    assert_eq!(names(&client, 1).await, ["a", "b"]);
+    assert_eq!(names(&client, 2).await, ["b", "c"]);
+
+    let stats = client.result_cache().stats();
+    assert_eq!(stats.hits(), 1);
+    assert_eq!(stats.misses(), 2);
+    assert_eq!(stats.entries(), 2);
+    assert!(stats.bytes() > 0);
+
+    client.result_cache().invalidate_tag("other");
+    assert_eq!(client.result_cache().stats().entries(), 2);
+    client.result_cache().invalidate_tag("items");
+    let stats = client.result_cache().stats();
+    assert_eq!(stats.entries(), 0);
+    assert_eq!(stats.invalidations(), 2);
+    assert_eq!(stats.bytes(), 0);
+    assert_eq!(names(&client, 1).await, ["a", "b", "c"]);
+
+    let items = client
+        .cached(Duration::from_secs(60))
+        .query_as::<Item, _>("SELECT id, name FROM items WHERE id = $1", &[&3])
+        .await
+        .unwrap();
+    assert_eq!(
+        items,
+        [Item {
+            id: 3,
+            name: "c".to_string()
+        }]
+    );
+
+    client
+        .cached(Duration::from_secs(60))
+        .query_scalar::<String, _>("SELECT name FROM items WHERE id = $1", &[])
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn statement_cache() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.statement_cache_capacity(8);
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let query = "SELECT $1::INT + 1";
+    for _ in 0..2 {
+        let rows = client
+            .cached(Duration::from_secs(60))
+            .query_scalar::<i32, _>(query, &[&1i32])
+            .await
+            .unwrap();
+        assert_eq!(rows, [2]);
+    }
+    assert_eq!(client.statement_cache_len(), 1);
+
+    // the statement is shared with queries run outside of the result cache
+    client.query_one(query, &[&2i32]).await.unwrap();
+    assert_eq!(client.statement_cache_len(), 1);
+    let prepared: i64 = client
+        .query_one(
+            "SELECT count(*) FROM pg_prepared_statements WHERE statement = $1",
+            &[&query],
+        )
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(prepared, 1);
+}
+
+#[tokio::test]
+async fn limits() {
+    let client = setup().await;
+
+    client.result_cache().set_max_entries(1);
+    names(&client, 1).await;
+    names(&client, 2).await;
+    let stats = client.result_cache().stats();
+    assert_eq!(stats.entries(), 1);
+    assert_eq!(stats.evictions(), 1);
+
+    client.result_cache().set_max_bytes(0);
+    let stats = client.result_cache().stats();
+    assert_eq!(stats.entries(), 0);
+    assert_eq!(stats.evictions(), 2);
+    client.result_cache().set_max_bytes(1024);
+
+    let cached = || {
+        client
+            .cached(Duration::from_millis(0))
+            .query_scalar::<String, _>("SELECT name FROM items", &[])
+    };
+    cached().await.unwrap();
+    cached().await.unwrap();
+    let stats = client.result_cache().stats();
+    assert_eq!(stats.hits(), 0);
+    assert_eq!(stats.expirations(), 1);
+}
+
+#[cfg(feature = "runtime")]
+#[tokio::test]
+async fn invalidation() {
+    let client = setup().await;
+
+    names(&client, 1).await;
+    client.result_cache().invalidate(&Invalidation::Table {
+        schema: "public".to_string(),
+        table: "other".to_string(),
+        operation: Operation::Insert,
+    });
+    assert_eq!(client.result_cache().stats().entries(), 1);
+    client.result_cache().invalidate(&Invalidation::Table {
+        schema: "public".to_string(),
+        table: "items".to_string(),
+        operation: Operation::Update,
+    });
+    assert_eq!(client.result_cache().stats().entries(), 0);
+
+    names(&client, 1).await;
+    client
+        .cached(Duration::from_secs(60))
+        .query_scalar::<i32, _>("SELECT 1", &[])
+        .await
+        .unwrap();
+    assert_eq!(client.result_cache().stats().entries(), 2);
+    client.result_cache().invalidate(&Invalidation::Reconnected);
+    assert_eq!(client.result_cache().stats().entries(), 0);
+}
@@ -0,0 +1,101 @@
+use futures_channel::mpsc;
+use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::time;
+use tokio_postgres::invalidate::{Invalidation, Invalidator, Operation};
+use tokio_postgres::{Config, NoTls};
+
+use crate::connect;
+
+#[tokio::test]
+async fn invalidate() {
+    let client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS \"invalidate widgets\";
+             CREATE TABLE \"invalidate widgets\" (id INT)",
+        )
+        .await
+        .unwrap();
+
+    let config = "host=localhost port=5433 user=postgres application_name=invalidate_test"
+        .parse::<Config>()
+        .unwrap();
+    let (sender, mut receiver) = mpsc::unbounded();
+    let invalidator = Invalidator::new(&config, NoTls)
+        .table("\"invalidate widgets\"")
+        .channel("invalidate_test")
+        .reconnect_delay(Duration::from_millis(10))
+        .on_invalidation(move |invalidation| {
+            let _ = sender.unbounded_send(invalidation);
+        });
+    let handle = tokio::spawn(invalidator.run());
+
+    // wait for the listener to be set up
+    while client
+        .query_one(
+            "SELECT count(*) FROM pg_stat_activity
+             WHERE application_name = 'invalidate_test' AND query LIKE 'LISTEN%'",
+            &[],
+        )
+        .await
+        .unwrap()
+        .get::<_, i64>(0)
+        == 0
+    {
+        time::sleep(Duration::from_millis(10)).await;
+    }
+
+    client
+        .batch_execute("INSERT INTO \"invalidate widgets\" VALUES (1), (2)")
+        .await
+        .unwrap();
+    match receiver.next().await.unwrap() {
+        Invalidation::Table {
+            schema,
+            table,
+            operation,
+        } => {
+            assert_eq!(schema, "public");
+            assert_eq!(table, "invalidate widgets");
+            assert_eq!(operation, Operation::Insert);
+        }
+        invalidation => panic!("unexpected invalidation {:?}", invalidation),
+    }
+
+    client
+        .batch_execute("NOTIFY invalidate_test, 'hello'")
+        .await
+        .unwrap();
+    match receiver.next().await.unwrap() {
+        Invalidation::Notification(n) => assert_eq!(n.payload(), "hello"),
+        invalidation => panic!("unexpected invalidation {:?}", invalidation),
+    }
+
+    client
+        .batch_execute(
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+             WHERE application_name = 'invalidate_test'",
+        )
+        .await
+        .unwrap();
+    match receiver.next().await.unwrap() {
+        Invalidation::Reconnected => {}
+        invalidation => panic!("unexpected invalidation {:?}", invalidation),
+    }
+
+    client
+        .batch_execute("DELETE FROM \"invalidate widgets\"")
+        .await
+        .unwrap();
+    match receiver.next().await.unwrap() {
+        Invalidation::Table { operation, .. } => assert_eq!(operation, Operation::Delete),
+        invalidation => panic!("unexpected invalidation {:?}", invalidation),
+    }
+
+    handle.abort();
+    client
+        .batch_execute("DROP TABLE \"invalidate widgets\"")
+        .await
+        .unwrap();
+}
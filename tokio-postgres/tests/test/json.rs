@@ -0,0 +1,242 @@
+use futures_util::StreamExt;
+use serde_1::Deserialize;
+use serde_json_1::{json, Value};
+use std::collections::HashMap;
+use tokio_postgres::json::{to_value, to_values, write_ndjson};
+use tokio_postgres::SimpleQueryMessage;
+
+use crate::connect;
+
+#[tokio::test]
+async fn row_to_value() {
+    let client = connect("user=postgres").await;
+
+    let row = client
+        .query_one(
+            "SELECT
+                true AS bool,
+                1::INT2 AS int2,
+                2::INT8 AS int8,
+                1.5::FLOAT8 AS float,
+                'NaN'::FLOAT4 AS nan,
+                -12345.678900::NUMERIC AS numeric,
+                0.0001::NUMERIC AS small,
+                'hello'::TEXT AS text,
+                NULL::TEXT AS null,
+                '\\xdeadbeef'::BYTEA AS bytea,
+                'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::UUID AS uuid,
+                '10.0.0.0/8'::CIDR AS cidr,
+                '1999-12-31'::DATE AS date,
+                '0044-03-15 BC'::DATE AS bc,
+                '2024-02-29 12:34:56.5'::TIMESTAMP AS timestamp,
+                'infinity'::TIMESTAMPTZ AS infinity,
+                '{\"a\": [1, null]}'::JSONB AS jsonb,
+                ARRAY[[1, 2], [3, NULL]] AS array",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        to_value(&row).unwrap(),
+        json!({
+            "bool": true,
+            "int2": 1,
+            "int8": 2,
+            "float": 1.5,
+            "nan": "NaN",
+            "numeric": "-12345.678900",
+            "small": "0.0001",
+            "text": "hello",
+            "null": null,
+            "bytea": "\\xdeadbeef",
+            "uuid": "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "cidr": "10.0.0.0/8",
+            "date": "1999-12-31",
+            "bc": "0044-03-15 BC",
+            "timestamp": "2024-02-29T12:34:56.5",
+            "infinity": "infinity",
+            "jsonb": {"a": [1, null]},
+            "array": [[1, 2], [3, null]],
+        })
+    );
+}
+
+#[tokio::test]
+async fn composite() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TYPE pg_temp.point3 AS (x INT4, y INT4, label TEXT)")
+        .await
+        .unwrap();
+
+    let row = client
+        .query_one("SELECT ROW(1, 2, NULL)::pg_temp.point3 AS point", &[])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        to_value(&row).unwrap(),
+        json!({"point": {"x": 1, "y": 2, "label": null}})
+    );
+}
+
+#[tokio::test]
+async fn unsupported_type() {
+    let client = connect("user=postgres").await;
+
+    let row = client
+        .query_one("SELECT '1 day'::INTERVAL AS interval", &[])
+        .await
+        .unwrap();
+
+    assert!(to_value(&row).is_err());
+}
+
+#[tokio::test]
+async fn stream() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT i AS id, 'x' || i AS name FROM generate_series(1, 2) i",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+
+    let values = futures_util::TryStreamExt::try_collect::<Vec<_>>(to_values(rows))
+        .await
+        .unwrap();
+    assert_eq!(
+        values,
+        [
+            json!({"id": 1, "name": "x1"}),
+            json!({"id": 2, "name": "x2"})
+        ]
+    );
+}
+
+#[tokio::test]
+async fn ndjson() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_raw(
+            "SELECT i AS id, 'x' || i AS name, i * 2 AS id FROM generate_series(1, 2) i",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+
+    let mut out = vec![];
+    let count = write_ndjson(rows, &mut out).await.unwrap();
+
+    assert_eq!(count, 2);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "{\"id\":1,\"name\":\"x1\",\"id\":2}\n{\"id\":2,\"name\":\"x2\",\"id\":4}\n"
+    );
+}
+
+#[tokio::test]
+async fn serialize() {
+    let client = connect("user=postgres").await;
+
+    let row = client
+        .query_one("SELECT 1 AS id, 'a' AS name, NULL::INT4 AS missing", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json_1::to_string(&row).unwrap(),
+        r#"{"id":1,"name":"a","missing":null}"#
+    );
+
+    let messages = client
+        .simple_query("SELECT 1 AS id, NULL AS missing")
+        .await
+        .unwrap();
+    let row = match &messages[0] {
+        SimpleQueryMessage::Row(row) => row,
+        _ => panic!("expected a row"),
+    };
+    assert_eq!(
+        serde_json_1::to_value(row).unwrap(),
+        json!({"id": "1", "missing": null})
+    );
+}
+
+#[tokio::test]
+async fn serialize_unsupported_type() {
+    let client = connect("user=postgres").await;
+
+    let row = client
+        .query_one("SELECT '1 day'::INTERVAL AS interval", &[])
+        .await
+        .unwrap();
+
+    assert!(serde_json_1::to_string(&row).is_err());
+}
+
+#[tokio::test]
+async fn query_as_map() {
+    let client = connect("user=postgres").await;
+
+    let rows = client
+        .query_as::<HashMap<String, Value>, _>(
+            "SELECT * FROM (VALUES (1, 'a', NULL::JSONB), (2, 'b', '[1]')) AS t (id, name, extra) ORDER BY id",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["id"], json!(1));
+    assert_eq!(rows[0]["name"], json!("a"));
+    assert_eq!(rows[0]["extra"], Value::Null);
+    assert_eq!(rows[1]["extra"], json!([1]));
+
+    client
+        .query_as::<HashMap<String, Value>, _>("SELECT '1 day'::INTERVAL AS interval", &[])
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn listen_as() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(crate = "serde_1")]
+    struct Job {
+        id: i64,
+        kind: String,
+    }
+
+    let client = connect("user=postgres").await;
+
+    let mut jobs = client.listen_as::<Job>("json_jobs").await.unwrap();
+    assert_eq!(jobs.channel(), "json_jobs");
+
+    client
+        .batch_execute(
+            r#"NOTIFY json_jobs, '{"id": 1, "kind": "email"}';
+               NOTIFY json_jobs, 'not json';
+               NOTIFY json_jobs, '{"id": 2, "kind": "sms"}';"#,
+        )
+        .await
+        .unwrap();
+
+    let job = jobs.next().await.unwrap().unwrap();
+    assert_eq!(
+        job,
+        Job {
+            id: 1,
+            kind: "email".to_string()
+        }
+    );
+    let error = jobs.next().await.unwrap().unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("error deserializing notification payload"));
+    assert_eq!(jobs.next().await.unwrap().unwrap().id, 2);
+}
@@ -0,0 +1,80 @@
+use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::time;
+use tokio_postgres::listener::{Listener, OverflowPolicy};
+use tokio_postgres::{Client, Config, NoTls};
+
+use crate::connect;
+
+// Waits for the listener to be listening on a connection other than `old_pid`, returning its process ID.
+async fn wait_for_listener(client: &Client, old_pid: i32) -> i32 {
+    loop {
+        let row = client
+            .query_opt(
+                "SELECT pid FROM pg_stat_activity
+                 WHERE application_name = 'listener_test' AND query LIKE 'LISTEN%' AND pid <> $1",
+                &[&old_pid],
+            )
+            .await
+            .unwrap();
+        if let Some(row) = row {
+            return row.get(0);
+        }
+        time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn listener() {
+    let client = connect("user=postgres").await;
+
+    let config = "host=localhost port=5433 user=postgres application_name=listener_test"
+        .parse::<Config>()
+        .unwrap();
+    let mut listener = Listener::new(&config, NoTls)
+        .channels(["listener.a", "listener.b"])
+        .channel("other")
+        .reconnect_delay(Duration::from_millis(10));
+    let mut prefixed = listener.subscribe("listener.*", 10, OverflowPolicy::DropOldest);
+    let mut exact = listener.subscribe("other", 10, OverflowPolicy::DropOldest);
+    let mut oldest = listener.subscribe("*", 2, OverflowPolicy::DropOldest);
+    let mut newest = listener.subscribe("*", 2, OverflowPolicy::DropNewest);
+    let handle = tokio::spawn(listener.run());
+    let pid = wait_for_listener(&client, 0).await;
+
+    client
+        .batch_execute(
+            "NOTIFY \"listener.a\", '1';
+             NOTIFY other, '2';
+             NOTIFY \"listener.b\", '3';",
+        )
+        .await
+        .unwrap();
+
+    let n = prefixed.next().await.unwrap();
+    assert_eq!((n.channel(), n.payload()), ("listener.a", "1"));
+    let n = prefixed.next().await.unwrap();
+    assert_eq!((n.channel(), n.payload()), ("listener.b", "3"));
+    let n = exact.next().await.unwrap();
+    assert_eq!((n.channel(), n.payload()), ("other", "2"));
+
+    assert_eq!(oldest.next().await.unwrap().payload(), "2");
+    assert_eq!(oldest.next().await.unwrap().payload(), "3");
+    assert_eq!(oldest.dropped(), 1);
+    assert_eq!(newest.next().await.unwrap().payload(), "1");
+    assert_eq!(newest.next().await.unwrap().payload(), "2");
+    assert_eq!(newest.dropped(), 1);
+
+    // the channels are listened on again after a reconnect
+    client
+        .execute("SELECT pg_terminate_backend($1)", &[&pid])
+        .await
+        .unwrap();
+    wait_for_listener(&client, pid).await;
+    client.batch_execute("NOTIFY other, '4'").await.unwrap();
+    assert_eq!(exact.next().await.unwrap().payload(), "4");
+
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+    assert!(exact.next().await.is_none());
+}
@@ -13,17 +13,46 @@ use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::time;
 use tokio_postgres::error::SqlState;
+use tokio_postgres::row::FromSqlText;
 use tokio_postgres::tls::{NoTls, NoTlsStream};
-use tokio_postgres::types::{Kind, Type};
+use tokio_postgres::types::{Format, Kind, Type};
 use tokio_postgres::{
-    AsyncMessage, Client, Config, Connection, Error, IsolationLevel, SimpleQueryMessage,
+    AsyncMessage, Client, Config, Connection, ConnectionEvent, DynClient, Error, IsolationLevel,
+    OverflowPolicy, QueryOptions, SimpleQueryMessage, TypedQuery,
 };
 
+#[cfg(feature = "arrow")]
+mod arrow;
 mod binary_copy;
+mod bulk;
+mod cache;
+#[cfg(feature = "runtime")]
+mod invalidate;
+#[cfg(feature = "with-serde_json-1")]
+mod json;
+#[cfg(feature = "runtime")]
+mod listener;
+#[cfg(any(feature = "deadpool", feature = "bb8"))]
+mod manager;
+#[cfg(feature = "migrate")]
+mod migrate;
+#[cfg(feature = "runtime")]
+mod multiplex;
+mod paginate;
 mod parse;
+#[cfg(feature = "pool")]
+mod pool;
 #[cfg(feature = "runtime")]
 mod runtime;
+#[cfg(feature = "socks")]
+mod socks;
+#[cfg(feature = "sqlcommenter")]
+mod sqlcommenter;
+#[cfg(feature = "test-util")]
+mod test_util;
 mod types;
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+mod uring;
 
 pin_project! {
     /// Polls `F` at most `polls_left` times returning `Some(F::Output)` if
@@ -525,6 +554,71 @@ async fn transaction_rollback_drop() {
     assert_eq!(rows.len(), 0);
 }
 
+#[tokio::test]
+async fn transaction_scope() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY)")
+        .await
+        .unwrap();
+
+    let mut transaction = client.transaction().await.unwrap();
+    transaction
+        .batch_execute("INSERT INTO foo (id) VALUES (1)")
+        .await
+        .unwrap();
+
+    let scope = transaction.scope().await.unwrap();
+    scope
+        .batch_execute("INSERT INTO foo (id) VALUES (2)")
+        .await
+        .unwrap();
+    scope
+        .batch_execute("INSERT INTO foo (id) VALUES (1)")
+        .await
+        .unwrap_err();
+    drop(scope);
+
+    let scope = transaction.scope().await.unwrap();
+    scope
+        .batch_execute("INSERT INTO foo (id) VALUES (3)")
+        .await
+        .unwrap();
+    scope.commit().await.unwrap();
+
+    transaction.commit().await.unwrap();
+
+    let rows = client
+        .query("SELECT id FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    let ids = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(ids, [1, 3]);
+}
+
+#[tokio::test]
+async fn transaction_scope_future_cancellation() {
+    let mut client = connect("user=postgres").await;
+
+    for i in 0.. {
+        let mut transaction = client.transaction().await.unwrap();
+        let done = {
+            let scope = transaction.scope();
+            let fut = Cancellable {
+                fut: scope,
+                polls_left: i,
+            };
+            fut.await.map(|res| res.expect("scope failed")).is_some()
+        };
+
+        if done {
+            break;
+        }
+        transaction.batch_execute("RELEASE sp_1").await.unwrap_err();
+    }
+}
+
 #[tokio::test]
 async fn transaction_builder() {
     let mut client = connect("user=postgres").await;
@@ -560,6 +654,125 @@ async fn transaction_builder() {
     assert_eq!(rows[0].get::<_, &str>(0), "steven");
 }
 
+#[tokio::test]
+async fn transaction_snapshot() {
+    let mut client = connect("user=postgres").await;
+    let mut other = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS snapshot_test;
+             CREATE TABLE snapshot_test (id INT);
+             INSERT INTO snapshot_test VALUES (1);",
+        )
+        .await
+        .unwrap();
+
+    let transaction = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .start()
+        .await
+        .unwrap();
+    let snapshot = transaction.export_snapshot().await.unwrap();
+
+    other
+        .batch_execute("INSERT INTO snapshot_test VALUES (2)")
+        .await
+        .unwrap();
+
+    let imported = other
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .use_snapshot(&snapshot)
+        .start()
+        .await
+        .unwrap();
+    let count = imported
+        .query_one("SELECT count(*) FROM snapshot_test", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 1);
+    imported.commit().await.unwrap();
+    transaction.commit().await.unwrap();
+
+    other
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .use_snapshot(&snapshot)
+        .start()
+        .await
+        .unwrap_err();
+    let count = other
+        .query_one("SELECT count(*) FROM snapshot_test", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 2);
+
+    client
+        .batch_execute("DROP TABLE snapshot_test")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn two_phase_commit() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS two_phase_commit;
+             CREATE TABLE two_phase_commit (id INT);",
+        )
+        .await
+        .unwrap();
+
+    for (gid, id) in [("tpc-commit", 1), ("tpc-rollback", 2)] {
+        let transaction = client.transaction().await.unwrap();
+        transaction
+            .execute("INSERT INTO two_phase_commit VALUES ($1)", &[&id])
+            .await
+            .unwrap();
+        transaction.prepare_transaction(gid).await.unwrap();
+    }
+
+    let prepared = client.prepared_transactions().await.unwrap();
+    let prepared = prepared
+        .iter()
+        .filter(|t| t.gid().starts_with("tpc-"))
+        .collect::<Vec<_>>();
+    assert_eq!(prepared.len(), 2);
+    assert_eq!(prepared[0].gid(), "tpc-commit");
+    assert_eq!(prepared[0].owner(), "postgres");
+    assert_eq!(prepared[0].database(), "postgres");
+    assert_eq!(prepared[1].gid(), "tpc-rollback");
+
+    // nothing is visible until the prepared transactions are resolved
+    let ids = || async {
+        client
+            .query("SELECT id FROM two_phase_commit", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<_, i32>(0))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids().await, [] as [i32; 0]);
+
+    client.commit_prepared("tpc-commit").await.unwrap();
+    client.rollback_prepared("tpc-rollback").await.unwrap();
+    client.commit_prepared("tpc-rollback").await.unwrap_err();
+    assert_eq!(ids().await, [1]);
+
+    client
+        .batch_execute("DROP TABLE two_phase_commit")
+        .await
+        .unwrap();
+}
+
 #[tokio::test]
 async fn copy_in() {
     let client = connect("user=postgres").await;
@@ -768,6 +981,106 @@ async fn notifications() {
     assert_eq!(notifications[1].payload(), "world");
 }
 
+#[tokio::test]
+async fn listen() {
+    let client = connect("user=postgres").await;
+
+    let mut jobs = client.listen("Jobs").await.unwrap();
+    let mut jobs2 = client.listen("Jobs").await.unwrap();
+    let mut other = client.listen("other").await.unwrap();
+    assert_eq!(jobs.channel(), "Jobs");
+
+    client
+        .batch_execute(
+            "NOTIFY \"Jobs\", 'hello';
+             NOTIFY other, 'ignored';
+             NOTIFY \"Jobs\", 'world';",
+        )
+        .await
+        .unwrap();
+
+    for stream in [&mut jobs, &mut jobs2] {
+        let first = stream.next().await.unwrap();
+        assert_eq!(first.channel(), "Jobs");
+        assert_eq!(first.payload(), "hello");
+        assert_eq!(stream.next().await.unwrap().payload(), "world");
+    }
+    assert_eq!(other.next().await.unwrap().payload(), "ignored");
+
+    let listening = || async {
+        client
+            .query("SELECT pg_listening_channels() ORDER BY 1", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get::<_, String>(0))
+            .collect::<Vec<_>>()
+    };
+    drop(jobs);
+    assert_eq!(listening().await, ["Jobs", "other"]);
+    drop(jobs2);
+    assert_eq!(listening().await, ["other"]);
+
+    drop(client);
+    assert!(other.next().await.is_none());
+}
+
+#[tokio::test]
+async fn notification_buffer() {
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.notification_buffer_size(2);
+    assert_eq!(config.get_notification_buffer_size(), Some(2));
+    assert_eq!(
+        config.get_notification_overflow(),
+        OverflowPolicy::DropOldest
+    );
+
+    let notify = "NOTIFY buffered, '1';
+                  NOTIFY buffered, '2';
+                  NOTIFY buffered, '3';
+                  NOTIFY buffered, '4';";
+
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let mut notifications = client.listen("buffered").await.unwrap();
+    client.batch_execute(notify).await.unwrap();
+    assert_eq!(notifications.dropped(), 2);
+    assert_eq!(notifications.next().await.unwrap().payload(), "3");
+    assert_eq!(notifications.next().await.unwrap().payload(), "4");
+
+    config.notification_overflow(OverflowPolicy::DropNewest);
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    let (connection, mut messages) = connection.split_messages();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    client.batch_execute("LISTEN buffered").await.unwrap();
+    client.batch_execute(notify).await.unwrap();
+    assert_eq!(messages.dropped(), 2);
+    for payload in ["1", "2"] {
+        match messages.next().await.unwrap() {
+            AsyncMessage::Notification(n) => assert_eq!(n.payload(), payload),
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    config.notification_overflow(OverflowPolicy::Error);
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    let connection = tokio::spawn(connection);
+
+    let mut notifications = client.listen("buffered").await.unwrap();
+    assert!(client.batch_execute(notify).await.unwrap_err().is_closed());
+    let error = connection.await.unwrap().unwrap_err();
+    assert_eq!(error.to_string(), "notification buffer overflowed");
+    assert_eq!(notifications.dropped(), 1);
+    assert_eq!(notifications.next().await.unwrap().payload(), "1");
+    assert_eq!(notifications.next().await.unwrap().payload(), "2");
+    assert!(notifications.next().await.is_none());
+}
+
 #[tokio::test]
 async fn query_portal() {
     let mut client = connect("user=postgres").await;
@@ -811,6 +1124,55 @@ async fn query_portal() {
     assert_eq!(r3.len(), 0);
 }
 
+#[tokio::test]
+async fn query_raw_chunked() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (
+                id SERIAL,
+                name TEXT
+            );
+
+            INSERT INTO foo (name) VALUES ('alice'), ('bob'), ('charlie');",
+        )
+        .await
+        .unwrap();
+
+    let stream = client
+        .__private_api_query_raw_chunked(
+            "SELECT id, name FROM foo ORDER BY id",
+            std::iter::empty::<i32>(),
+            2,
+        )
+        .await
+        .unwrap();
+    pin_mut!(stream);
+
+    let mut names = vec![];
+    while let Some(row) = stream.try_next().await.unwrap() {
+        names.push(row.get::<_, String>(1));
+    }
+    assert_eq!(names, ["alice", "bob", "charlie"]);
+    assert_eq!(stream.rows_affected(), Some(3));
+
+    {
+        let stream = client
+            .__private_api_query_raw_chunked("SELECT id FROM foo", std::iter::empty::<i32>(), 1)
+            .await
+            .unwrap();
+        pin_mut!(stream);
+        stream.try_next().await.unwrap().unwrap();
+    }
+
+    let row = client
+        .query_one("SELECT count(*) FROM foo", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 3);
+}
+
 #[tokio::test]
 async fn require_channel_binding() {
     connect_raw("user=postgres channel_binding=require")
@@ -999,6 +1361,26 @@ async fn query_opt_scalar() {
     assert_eq!(age, None);
 }
 
+#[tokio::test]
+async fn stream_scalar() {
+    let client = connect("user=postgres").await;
+
+    let ids = client
+        .stream_scalar::<i32, _>("SELECT generate_series(1, $1)", &[&5i32])
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+
+    let mut stream = client
+        .stream_scalar::<i32, _>("SELECT 'a'", &[])
+        .await
+        .unwrap();
+    assert!(stream.try_next().await.is_err());
+}
+
 #[tokio::test]
 async fn records() {
     let client = connect("user=postgres").await;
@@ -1053,3 +1435,1255 @@ async fn array_of_records_wrong_type() {
         panic!("wrong type should fail with wrong type error");
     };
 }
+
+#[tokio::test]
+async fn dyn_client() {
+    async fn insert(client: &mut dyn DynClient, id: i32) -> Result<(), Error> {
+        let transaction = client.transaction().await?;
+        transaction
+            .execute("INSERT INTO foo (id) VALUES ($1)", &[&id])
+            .await?;
+        transaction.commit().await
+    }
+
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap();
+
+    insert(&mut client, 1).await.unwrap();
+    let mut transaction = client.transaction().await.unwrap();
+    insert(&mut transaction, 2).await.unwrap();
+    transaction.rollback().await.unwrap();
+
+    let client: &dyn DynClient = &client;
+    let row = client
+        .query_one("SELECT array_agg(id) FROM foo", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, Vec<i32>>(0), [1]);
+
+    let statement = client
+        .prepare_typed("SELECT id FROM foo WHERE id = $1", &[Type::INT8])
+        .await
+        .unwrap();
+    assert_eq!(statement.params(), [Type::INT8]);
+    assert_eq!(statement.columns()[0].type_(), &Type::INT4);
+}
+
+#[tokio::test]
+async fn statement_cache() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.statement_cache_capacity(2);
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let query = "SELECT $1::INT + 1";
+    for i in 0..3 {
+        let row = client.query_one(query, &[&i]).await.unwrap();
+        assert_eq!(row.get::<_, i32>(0), i + 1);
+    }
+    assert_eq!(prepared_statements(&client, query).await, 1);
+
+    // different parameter types replace the cached statement
+    let typed = TypedQuery::new(query, &[Type::INT8][..]);
+    client.query_one(&typed, &[&1i64]).await.unwrap();
+    assert_eq!(prepared_statements(&client, query).await, 1);
+
+    // the statement of `prepared_statements` is cached as well, so this evicts the least recently used one
+    client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(prepared_statements(&client, query).await, 0);
+    assert_eq!(prepared_statements(&client, "SELECT 1").await, 1);
+
+    client.clear_statement_cache();
+    assert_eq!(prepared_statements(&client, "SELECT 1").await, 0);
+
+    let client = connect("user=postgres").await;
+    client.query_one(query, &[&1i32]).await.unwrap();
+    assert_eq!(prepared_statements(&client, query).await, 0);
+}
+
+async fn prepared_statements(client: &Client, query: &str) -> i64 {
+    client
+        .query_one(
+            "SELECT count(*) FROM pg_prepared_statements WHERE statement = $1",
+            &[&query],
+        )
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[tokio::test]
+async fn statement_stats() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.statement_stats(true);
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap();
+
+    let insert = "INSERT INTO foo (id) VALUES ($1)";
+    for i in 0..3 {
+        client.execute(insert, &[&i]).await.unwrap();
+    }
+    let select = "SELECT id FROM foo";
+    client.query(select, &[]).await.unwrap();
+    let fail = "SELECT 1 / $1";
+    client.query(fail, &[&0i32]).await.unwrap_err();
+
+    let mut stats = client.statement_stats();
+    stats.sort_by(|a, b| a.query().cmp(b.query()));
+    assert_eq!(stats.len(), 3);
+
+    assert_eq!(stats[0].query(), insert);
+    assert_eq!(stats[0].calls(), 3);
+    assert_eq!(stats[0].rows(), 3);
+    assert_eq!(stats[0].errors(), 0);
+    assert!(stats[0].p95_time() <= stats[0].total_time());
+
+    assert_eq!(stats[1].query(), fail);
+    assert_eq!(stats[1].calls(), 1);
+    assert_eq!(stats[1].errors(), 1);
+
+    assert_eq!(stats[2].query(), select);
+    assert_eq!(stats[2].calls(), 1);
+    assert_eq!(stats[2].rows(), 3);
+
+    client.reset_statement_stats();
+    assert!(client.statement_stats().is_empty());
+
+    let client = connect("user=postgres").await;
+    client.query("SELECT 1", &[]).await.unwrap();
+    assert!(client.statement_stats().is_empty());
+}
+
+#[tokio::test]
+async fn custom_transport() {
+    // stands in for a transport such as a WebSocket tunnel: the client only sees an in-memory stream
+    let (stream, mut tunnel) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        let mut socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+        let _ = tokio::io::copy_bidirectional(&mut tunnel, &mut socket).await;
+    });
+
+    let config = "user=postgres".parse::<Config>().unwrap();
+    let (client, connection) = config.connect_raw(stream, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let row = client.query_one("SELECT 1::INT", &[]).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 1);
+}
+
+#[tokio::test]
+async fn statement_on_other_connection() {
+    let client = connect("user=postgres").await;
+    let stmt = client
+        .prepare_typed("SELECT $1, pg_backend_pid()", &[Type::INT8])
+        .await
+        .unwrap();
+    drop(client);
+
+    let client = connect("user=postgres").await;
+    for i in 0..2i64 {
+        let row = client.query_one(&stmt, &[&i]).await.unwrap();
+        assert_eq!(row.get::<_, i64>(0), i);
+    }
+
+    let row = client
+        .query_one(
+            "SELECT count(*) FROM pg_prepared_statements WHERE statement LIKE 'SELECT $1%'",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 1);
+}
+
+#[tokio::test]
+async fn connection_events() {
+    let mut client = connect("user=postgres").await;
+    let mut events = client.connection_events();
+
+    let transaction = client
+        .build_transaction()
+        .read_only(true)
+        .start()
+        .await
+        .unwrap();
+    let err = transaction
+        .execute("CREATE TABLE connection_events (id INT)", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        ConnectionEvent::from_error(&err),
+        Some(ConnectionEvent::Demoted)
+    );
+    transaction.rollback().await.unwrap();
+    assert_eq!(events.next().await, Some(ConnectionEvent::Demoted));
+
+    let pid = client
+        .query_one("SELECT pg_backend_pid()", &[])
+        .await
+        .unwrap()
+        .get::<_, i32>(0);
+    let other = connect("user=postgres").await;
+    other
+        .execute("SELECT pg_terminate_backend($1)", &[&pid])
+        .await
+        .unwrap();
+
+    assert_eq!(events.next().await, Some(ConnectionEvent::Terminated));
+    assert_eq!(events.next().await, None);
+
+    let mut events = client.connection_events();
+    assert_eq!(events.next().await, Some(ConnectionEvent::Terminated));
+    assert_eq!(events.next().await, None);
+}
+
+#[tokio::test]
+async fn row_iter() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TYPE pg_temp.label AS ENUM ('a', 'b');
+             CREATE TEMPORARY TABLE labels (id INT4, label pg_temp.label)",
+        )
+        .await
+        .unwrap();
+    client
+        .execute("INSERT INTO labels VALUES (1, 'a'), (2, NULL)", &[])
+        .await
+        .unwrap();
+
+    let rows = client
+        .query("SELECT id, label FROM labels ORDER BY id", &[])
+        .await
+        .unwrap();
+
+    let columns = rows[0]
+        .iter()
+        .map(|(name, ty, value)| (name, ty.name(), value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        columns,
+        [
+            ("id", "int4", Some(&[0, 0, 0, 1][..])),
+            ("label", "label", Some(&b"a"[..]))
+        ]
+    );
+    assert_eq!(rows[1].iter().next_back().unwrap().2, None);
+    assert_eq!(rows[1].iter().len(), 2);
+
+    assert_eq!(rows[0].try_get_raw("label").unwrap(), Some(&b"a"[..]));
+    assert!(rows[0].try_get_raw("missing").is_err());
+
+    assert!(rows[0].try_get::<_, String>("label").is_err());
+    assert_eq!(
+        rows[0]
+            .try_get_by_type::<_, String>("label", &Type::TEXT)
+            .unwrap(),
+        "a"
+    );
+    assert_eq!(
+        rows[1]
+            .try_get_by_type::<_, Option<String>>("label", &Type::TEXT)
+            .unwrap(),
+        None
+    );
+    assert!(rows[0]
+        .try_get_by_type::<_, i32>("label", &Type::TEXT)
+        .is_err());
+}
+
+#[tokio::test]
+async fn row_stream_chunks() {
+    let client = connect("user=postgres").await;
+
+    let stream = client
+        .query_raw(
+            "SELECT * FROM generate_series(1, 5)",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap()
+        .chunks(2);
+    pin_mut!(stream);
+
+    let mut sizes = vec![];
+    while let Some(chunk) = stream.try_next().await.unwrap() {
+        sizes.push(chunk.len());
+    }
+    assert_eq!(sizes, [2, 2, 1]);
+    assert_eq!(stream.rows_affected(), Some(5));
+
+    let stream = client
+        .query_raw(
+            "SELECT 10 / (3 - i) FROM generate_series(1, 4) i",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap()
+        .chunks(10);
+    pin_mut!(stream);
+
+    assert_eq!(stream.try_next().await.unwrap().unwrap().len(), 2);
+    assert!(stream.try_next().await.is_err());
+}
+
+#[tokio::test]
+async fn row_stream_map_as() {
+    struct Number(i32);
+
+    impl tokio_postgres::FromRow for Number {
+        fn from_row(row: &tokio_postgres::Row) -> Result<Number, Error> {
+            row.try_get("n").map(Number)
+        }
+    }
+
+    let client = connect("user=postgres").await;
+
+    let numbers = client
+        .query_raw(
+            "SELECT n FROM generate_series(1, 3) n",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap()
+        .map_as::<Number>()
+        .map_ok(|n| n.0)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(numbers, [1, 2, 3]);
+
+    let result = client
+        .query_raw("SELECT 1 AS m", std::iter::empty::<i32>())
+        .await
+        .unwrap()
+        .map_as::<Number>()
+        .try_collect::<Vec<_>>()
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn statement_metadata() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE meta (id INT4, name TEXT)")
+        .await
+        .unwrap();
+
+    let stmt = client
+        .prepare("SELECT name, 1 AS one FROM meta")
+        .await
+        .unwrap();
+    assert_eq!(stmt.sql(), "SELECT name, 1 AS one FROM meta");
+
+    let row = client
+        .query_one(
+            "SELECT count(*) FROM pg_prepared_statements WHERE name = $1",
+            &[&stmt.name()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 1);
+
+    let table_oid = client
+        .query_one("SELECT 'meta'::regclass::oid", &[])
+        .await
+        .unwrap()
+        .get::<_, u32>(0);
+    assert_eq!(stmt.columns()[0].table_oid(), Some(table_oid));
+    assert_eq!(stmt.columns()[0].column_id(), Some(2));
+    assert_eq!(stmt.columns()[1].table_oid(), None);
+}
+
+#[tokio::test]
+async fn insert_and_update_returning() {
+    #[derive(Debug, PartialEq)]
+    struct Person {
+        id: i32,
+        name: String,
+    }
+
+    impl tokio_postgres::FromRow for Person {
+        fn from_row(row: &tokio_postgres::Row) -> Result<Person, Error> {
+            Ok(Person {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+            })
+        }
+    }
+
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE people (id SERIAL, name TEXT)")
+        .await
+        .unwrap();
+
+    let person: Person = client
+        .insert_returning(
+            "INSERT INTO people (name) VALUES ($1) RETURNING id, name",
+            &[&"alice"],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        person,
+        Person {
+            id: 1,
+            name: "alice".to_string()
+        }
+    );
+
+    let person: Person = client
+        .update_returning(
+            "UPDATE people SET name = $1 WHERE id = $2 RETURNING id, name",
+            &[&"bob", &1i32],
+        )
+        .await
+        .unwrap();
+    assert_eq!(person.name, "bob");
+
+    let err = client
+        .update_returning::<Person, _>(
+            "UPDATE people SET name = $1 WHERE id = $2 RETURNING id, name",
+            &[&"carol", &2i32],
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind(), tokio_postgres::error::Kind::RowCount));
+
+    let err = client
+        .insert_returning::<Person, _>("INSERT INTO people (name) VALUES ('dave')", &[])
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind(), tokio_postgres::error::Kind::RowCount));
+}
+
+#[tokio::test]
+async fn to_statement_types() {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use tokio_postgres::TypedQuery;
+
+    let client = connect("user=postgres").await;
+
+    let query: Cow<'_, str> = Cow::Owned(format!("SELECT {}::INT4", 1));
+    assert_eq!(
+        client
+            .query_one(&query, &[])
+            .await
+            .unwrap()
+            .get::<_, i32>(0),
+        1
+    );
+
+    let query: Arc<str> = Arc::from("SELECT 2::INT4");
+    assert_eq!(
+        client
+            .query_one(&query, &[])
+            .await
+            .unwrap()
+            .get::<_, i32>(0),
+        2
+    );
+
+    let query: Box<str> = Box::from("SELECT 3::INT4");
+    assert_eq!(
+        client
+            .query_one(&query, &[])
+            .await
+            .unwrap()
+            .get::<_, i32>(0),
+        3
+    );
+
+    // without the types, the parameter would be inferred as TEXT
+    let query = TypedQuery::new("SELECT $1::TEXT", &[Type::INT8][..]);
+    assert_eq!(query.param_types(), [Type::INT8]);
+    let row = client.query_one(&query, &[&4i64]).await.unwrap();
+    assert_eq!(row.get::<_, &str>(0), "4");
+}
+
+#[tokio::test]
+async fn tuple_params() {
+    let client = connect("user=postgres").await;
+
+    let name = String::from("ferris");
+    let row = client
+        .query_one_with("SELECT $1::TEXT, $2::INT4, $3::BOOL", (&name, 7i32, true))
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "ferris");
+    assert_eq!(row.get::<_, i32>(1), 7);
+    assert!(row.get::<_, bool>(2));
+
+    let rows = client
+        .query_with("SELECT * FROM generate_series(1, $1)", (3i32,))
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+
+    let row = client
+        .query_opt_with("SELECT 1 WHERE false", ())
+        .await
+        .unwrap();
+    assert!(row.is_none());
+
+    let params: &[&(dyn tokio_postgres::types::ToSql + Sync)] = &[&1i32];
+    let count = client
+        .execute_with("SELECT * FROM generate_series(1, $1)", params)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let err = client
+        .query_one_with("SELECT $1::INT4", ("not a number",))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("error serializing parameter 0"));
+}
+
+#[tokio::test]
+async fn fragment() {
+    use tokio_postgres::Fragment;
+
+    let client = connect("user=postgres").await;
+
+    let low = 2i32;
+    let high = 4i32;
+    let label = "n";
+    let conditions = vec![
+        Fragment::new("n >= ").bind(&low),
+        Fragment::new("n <= ").bind(&high),
+    ];
+    let query = Fragment::new("SELECT ")
+        .bind(&label)
+        .push("::TEXT || n FROM generate_series(1, 5) n WHERE ")
+        .append(Fragment::join(conditions, " AND "))
+        .push(" ORDER BY n");
+
+    let (sql, params) = query.build();
+    assert_eq!(
+        sql,
+        "SELECT $1::TEXT || n FROM generate_series(1, 5) n WHERE n >= $2 AND n <= $3 ORDER BY n"
+    );
+    assert_eq!(params.len(), 3);
+
+    let rows = client.query(&sql, &params).await.unwrap();
+    let values = rows.iter().map(|r| r.get::<_, &str>(0)).collect::<Vec<_>>();
+    assert_eq!(values, ["n2", "n3", "n4"]);
+
+    let rows = client.query_with(&query.sql(), &query).await.unwrap();
+    assert_eq!(rows.len(), 3);
+
+    assert!(Fragment::join(vec![], " AND ").is_empty());
+}
+
+#[tokio::test]
+async fn named_query() {
+    use std::collections::HashMap;
+    use tokio_postgres::NamedQuery;
+
+    let query = NamedQuery::new("INSERT INTO t (a, b, c) VALUES (:a, :b_2, :a) RETURNING a::TEXT");
+    assert_eq!(
+        query.sql(),
+        "INSERT INTO t (a, b, c) VALUES ($1, $2, $1) RETURNING a::TEXT"
+    );
+    assert_eq!(query.names(), ["a", "b_2"]);
+
+    let sql = r#"SELECT ':a', E'\':a', "x:a", $$:a$$, $t$ $$:a $t$, $1 -- :a
+        /* :a /* :a */ :a */ FROM t"#;
+    let query = NamedQuery::new(sql);
+    assert_eq!(query.sql(), sql);
+    assert!(query.names().is_empty());
+
+    let query = NamedQuery::new("SELECT 'it''s', x$y, :z, 'a'':b'");
+    assert_eq!(query.sql(), "SELECT 'it''s', x$y, $1, 'a'':b'");
+
+    let client = connect("user=postgres").await;
+    let mut params = HashMap::new();
+    params.insert("low", &2i32 as &(dyn tokio_postgres::types::ToSql + Sync));
+    params.insert("label", &"n");
+    let rows = client
+        .query_named(
+            "SELECT :label::TEXT || n FROM generate_series(1, 3) n WHERE n >= :low ORDER BY n",
+            &params,
+        )
+        .await
+        .unwrap();
+    let values = rows.iter().map(|r| r.get::<_, &str>(0)).collect::<Vec<_>>();
+    assert_eq!(values, ["n2", "n3"]);
+
+    let err = client
+        .execute_named("SELECT :label::TEXT, :high::INT", &params)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing parameter `high`"));
+}
+
+#[tokio::test]
+async fn escape() {
+    use tokio_postgres::escape::{escape_identifier, escape_literal};
+
+    let client = connect("user=postgres").await;
+
+    let table = escape_identifier("odd \"table\" name").unwrap();
+    let value = escape_literal("it's a \\ test").unwrap();
+    client
+        .batch_execute(&format!(
+            "CREATE TEMPORARY TABLE {} (value TEXT); INSERT INTO {} VALUES ({})",
+            table, table, value
+        ))
+        .await
+        .unwrap();
+
+    let row = client
+        .query_one(&*format!("SELECT value FROM {}", table), &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "it's a \\ test");
+
+    assert!(escape_identifier("a\0b").is_err());
+    assert!(escape_literal("a\0b").is_err());
+}
+
+#[tokio::test]
+async fn simple_query_typed() {
+    let client = connect("user=postgres").await;
+
+    let messages = client
+        .simple_query(
+            "SELECT 42 AS int, true AS bool, 'NaN'::FLOAT8 AS nan, '\\x0aff'::BYTEA AS bytea, \
+             NULL::TEXT AS null, 'x' AS text",
+        )
+        .await
+        .unwrap();
+    let row = match &messages[0] {
+        SimpleQueryMessage::Row(row) => row,
+        _ => panic!("expected a row"),
+    };
+
+    assert_eq!(row.try_get_parse::<_, i32>("int").unwrap(), Some(42));
+    assert_eq!(row.try_get_parse::<_, i32>("null").unwrap(), None);
+    assert!(row.try_get_parse::<_, i32>("text").is_err());
+    assert!(row.try_get_parse::<_, bool>("bool").is_err());
+
+    assert_eq!(row.get_text::<_, i64>("int"), 42);
+    assert!(row.get_text::<_, bool>("bool"));
+    assert!(row.get_text::<_, f64>("nan").is_nan());
+    assert_eq!(row.get_text::<_, Vec<u8>>("bytea"), [0x0a, 0xff]);
+    assert_eq!(row.get_text::<_, Option<String>>("null"), None);
+    assert_eq!(row.get_text::<_, String>("text"), "x");
+    assert!(row.try_get_text::<_, String>("null").is_err());
+    assert!(row.try_get_text::<_, i32>("missing").is_err());
+
+    assert!(Vec::<u8>::from_sql_text("\\xaé1").is_err());
+    assert!(Vec::<u8>::from_sql_text("\\x0g").is_err());
+    assert!(Vec::<u8>::from_sql_text("\\x0").is_err());
+}
+
+#[tokio::test]
+async fn query_column_formats() {
+    let client = connect("user=postgres").await;
+
+    let query = "SELECT 1::INT4 AS id, '(1,2)'::POINT AS point, NULL::INT8 AS null";
+    let options = QueryOptions::new()
+        .column_format(1, Format::Text)
+        .column_format(2, Format::Text);
+    let rows = client
+        .query_with_options(query, &[], &options)
+        .await
+        .unwrap();
+    let row = &rows[0];
+    assert!(matches!(row.column_format(0), Format::Binary));
+    assert!(matches!(row.column_format(1), Format::Text));
+    assert_eq!(row.get::<_, i32>("id"), 1);
+    assert_eq!(row.get::<_, &str>("point"), "(1,2)");
+    assert_eq!(row.get_text::<_, String>("point"), "(1,2)");
+    assert_eq!(row.get_text::<_, Option<i64>>("null"), None);
+    assert!(row.try_get_text::<_, i32>("id").is_err());
+    assert!(row.try_get::<_, Option<i64>>("null").is_err());
+
+    let options = QueryOptions::new().result_format(Format::Text);
+    let statement = client.prepare(query).await.unwrap();
+    let rows = client
+        .query_with_options(&statement, &[], &options)
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get_text::<_, i32>("id"), 1);
+    assert_eq!(rows[0].get::<_, String>("point"), "(1,2)");
+
+    let options = QueryOptions::new().column_formats(&[
+        Format::Binary,
+        Format::Binary,
+        Format::Text,
+        Format::Text,
+    ]);
+    assert!(client
+        .query_with_options(query, &[], &options)
+        .await
+        .is_err());
+
+    // the connection is still usable
+    let rows = client.query(query, &[]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>("id"), 1);
+}
+
+#[tokio::test]
+async fn dry_run() {
+    let mut client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE dry (id INT4);
+             INSERT INTO dry SELECT * FROM generate_series(1, 10)",
+        )
+        .await
+        .unwrap();
+
+    let (deleted, stats) = client
+        .dry_run(|tx| {
+            Box::pin(async move {
+                let deleted = tx.execute("DELETE FROM dry WHERE id > 7", &[]).await?;
+                tx.execute("UPDATE dry SET id = id + 100 WHERE id < 3", &[])
+                    .await?;
+                tx.query("SELECT * FROM dry", &[]).await?;
+                Ok(deleted)
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(deleted, 3);
+    assert_eq!(stats.statements(), 3);
+    assert_eq!(stats.rows_affected(), 5);
+
+    let count = client
+        .query_one("SELECT count(*) FROM dry WHERE id <= 10", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 10);
+
+    let err = client
+        .dry_run(|tx| Box::pin(async move { tx.execute("SELECT 1/0", &[]).await }))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::DIVISION_BY_ZERO));
+    client.execute("SELECT 1", &[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn parameter_validation() {
+    let client = connect("user=postgres").await;
+
+    let stmt = client.prepare("SELECT $1::INT4, $2::TEXT").await.unwrap();
+
+    let err = client.query(&stmt, &[&1i32]).await.unwrap_err();
+    assert_eq!(err.to_string(), "expected 2 parameters but got 1");
+
+    let err = client.query(&stmt, &[&1i32, &2i32]).await.unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.starts_with("error serializing parameter 1 ($2)"),
+        "{}",
+        message
+    );
+    assert!(message.contains("`i32`"), "{}", message);
+    assert!(message.contains("`text`"), "{}", message);
+
+    // Nothing was sent for the rejected queries, so the connection is still usable.
+    let row = client.query_one(&stmt, &[&1i32, &"a"]).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 1);
+}
+
+#[tokio::test]
+async fn owned_params() {
+    let client = connect("user=postgres").await;
+
+    let mut params = tokio_postgres::ParamsVec::new();
+    params.push(String::from("ferris"));
+    params.extend([1i32, 2]);
+    assert_eq!(params.len(), 3);
+
+    // The parameters are owned, so they can be moved into a spawned task.
+    let row = tokio::spawn(async move {
+        client
+            .query_one_with("SELECT $1::TEXT, $2::INT4 + $3::INT4", &params)
+            .await
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "ferris");
+    assert_eq!(row.get::<_, i32>(1), 3);
+
+    let client = connect("user=postgres").await;
+    let params: Vec<Box<dyn tokio_postgres::types::ToSql + Send + Sync>> = vec![Box::new(4i64)];
+    let row = client
+        .query_one_with("SELECT $1::INT8", &params)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 4);
+
+    let rows: Vec<_> = client
+        .query_raw("SELECT $1::INT8", params)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i64>(0), 4);
+}
+
+#[tokio::test]
+async fn split_messages() {
+    let (client, connection) = connect_raw("user=postgres").await.unwrap();
+    let (connection, messages) = connection.split_messages();
+    let connection = tokio::spawn(connection);
+
+    client
+        .batch_execute(
+            "LISTEN test_split_messages;
+             NOTIFY test_split_messages, 'hello';",
+        )
+        .await
+        .unwrap();
+    drop(client);
+
+    let messages = messages.collect::<Vec<_>>().await;
+    assert_eq!(messages.len(), 1);
+    match &messages[0] {
+        AsyncMessage::Notification(n) => {
+            assert_eq!(n.channel(), "test_split_messages");
+            assert_eq!(n.payload(), "hello");
+        }
+        _ => panic!("unexpected message"),
+    }
+    connection.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn connection_messages() {
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let query = async move {
+        client
+            .batch_execute(
+                "SET client_min_messages = NOTICE; DROP TABLE IF EXISTS noexist_messages",
+            )
+            .await
+            .unwrap();
+    };
+    let (_, messages) = future::join(query, connection.messages().try_collect::<Vec<_>>()).await;
+    let messages = messages.unwrap();
+    assert!(matches!(messages.as_slice(), [AsyncMessage::Notice(_)]));
+}
+
+#[tokio::test]
+async fn notice_and_parameter_callbacks() {
+    use std::sync::{Arc, Mutex};
+
+    let client = connect("user=postgres").await;
+
+    let notices = Arc::new(Mutex::new(vec![]));
+    let parameters = Arc::new(Mutex::new(vec![]));
+    client.on_notice({
+        let notices = notices.clone();
+        move |notice| notices.lock().unwrap().push(notice.message().to_string())
+    });
+    client.on_parameter_change({
+        let parameters = parameters.clone();
+        move |name, value| {
+            parameters
+                .lock()
+                .unwrap()
+                .push((name.to_string(), value.to_string()))
+        }
+    });
+
+    client
+        .batch_execute(
+            "SET client_min_messages = NOTICE;
+             DO $$ BEGIN RAISE NOTICE 'hello'; END $$;
+             SET application_name = 'callbacks';",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(*notices.lock().unwrap(), ["hello"]);
+    assert_eq!(
+        *parameters.lock().unwrap(),
+        [("application_name".to_string(), "callbacks".to_string())]
+    );
+}
+
+#[cfg(feature = "runtime")]
+#[tokio::test]
+async fn pending_requests() {
+    let mut config = "host=localhost port=5433 user=postgres"
+        .parse::<tokio_postgres::Config>()
+        .unwrap();
+    config.response_buffer_size(16);
+    assert_eq!(config.get_response_buffer_size(), 16);
+    let (client, connection) = config.connect(NoTls).await.unwrap();
+    tokio::spawn(connection);
+
+    assert_eq!(client.pending_requests(), 0);
+    let sleep = client.simple_query("SELECT pg_sleep(0.2)");
+    let select = client.simple_query("SELECT 1");
+    let (sleep, select, pending) = future::join3(sleep, select, async {
+        time::sleep(Duration::from_millis(50)).await;
+        client.pending_requests()
+    })
+    .await;
+    sleep.unwrap();
+    select.unwrap();
+    assert_eq!(pending, 2);
+    assert_eq!(client.pending_requests(), 0);
+
+    let rows = client
+        .query("SELECT * FROM generate_series(1, 1000)", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1000);
+    assert_eq!(client.pending_requests(), 0);
+}
+
+#[tokio::test]
+async fn close() {
+    let (client, connection) = connect_raw("user=postgres").await.unwrap();
+    let connection = tokio::spawn(connection);
+
+    let stream = client
+        .query_raw("SELECT 1 FROM pg_sleep(0.1)", std::iter::empty::<i32>())
+        .await
+        .unwrap();
+    client.close().await;
+    connection.await.unwrap().unwrap();
+
+    // The in-flight query was completed before the connection closed.
+    let rows = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(rows.len(), 1);
+}
+
+#[tokio::test]
+async fn close_polled_connection() {
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+    let messages = async {
+        while let Some(message) = future::poll_fn(|cx| connection.poll_message(cx)).await {
+            message.unwrap();
+        }
+    };
+
+    // the connection has completed once it stops yielding messages, even though it hasn't been dropped
+    time::timeout(Duration::from_secs(5), async {
+        join!(client.close(), messages)
+    })
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "runtime")]
+#[tokio::test]
+async fn close_timeout() {
+    let (client, connection) = connect_raw("user=postgres").await.unwrap();
+    tokio::spawn(connection);
+
+    // The results are never consumed, so the connection can't finish the query.
+    let _stream = client
+        .query_raw(
+            "SELECT * FROM generate_series(1, 100000)",
+            std::iter::empty::<i32>(),
+        )
+        .await
+        .unwrap();
+    let err = client
+        .close_timeout(Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+}
+
+pin_project! {
+    /// Counts the bytes written to the inner stream.
+    struct CountingStream {
+        #[pin]
+        inner: TcpStream,
+        written: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+}
+
+impl tokio::io::AsyncRead for CountingStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        self.project().inner.poll_read(cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for CountingStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.project();
+        let n = futures_util::ready!(this.inner.poll_write(cx, buf))?;
+        this.written
+            .fetch_add(n, std::sync::atomic::Ordering::SeqCst);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+}
+
+#[cfg(feature = "runtime")]
+#[tokio::test]
+async fn idle_ping() {
+    let written = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let socket = CountingStream {
+        inner: TcpStream::connect("127.0.0.1:5433").await.unwrap(),
+        written: written.clone(),
+    };
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.idle_ping_interval(Duration::from_millis(50));
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection);
+
+    let connected = written.load(std::sync::atomic::Ordering::SeqCst);
+    time::sleep(Duration::from_millis(180)).await;
+    // each ping is a single 5 byte Sync message
+    let pinged = written.load(std::sync::atomic::Ordering::SeqCst) - connected;
+    assert!(pinged >= 10, "{}", pinged);
+    assert_eq!(pinged % 5, 0);
+    assert_eq!(client.pending_requests(), 0);
+
+    let row = client.query_one("SELECT 1", &[]).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 1);
+}
+
+#[cfg(feature = "runtime")]
+#[tokio::test]
+async fn connect_resuming() {
+    let config = "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    let (mut client, connection) = config.connect_resuming(NoTls).await.unwrap();
+    let connection = tokio::spawn(connection.reconnect_delay(Duration::from_millis(10)).run());
+    let mut events = client.connection_events();
+
+    let stmt = client.prepare("SELECT pg_backend_pid()").await.unwrap();
+    let pid = client.query_one(&stmt, &[]).await.unwrap().get::<_, i32>(0);
+
+    let admin = connect("user=postgres").await;
+    admin
+        .execute("SELECT pg_terminate_backend($1)", &[&pid])
+        .await
+        .unwrap();
+    assert_eq!(events.next().await, Some(ConnectionEvent::Resumed));
+
+    // the statement is prepared again on the new session
+    let new_pid = client.query_one(&stmt, &[]).await.unwrap().get::<_, i32>(0);
+    assert_ne!(pid, new_pid);
+    assert!(!client.is_closed());
+
+    // cancellation targets the new backend
+    let sleep = client.batch_execute("SELECT pg_sleep(100)");
+    let cancel = async {
+        time::sleep(Duration::from_millis(100)).await;
+        client.cancel_token().cancel_query(NoTls).await.unwrap();
+    };
+    let (sleep, ()) = join!(sleep, cancel);
+    assert_eq!(sleep.unwrap_err().code(), Some(&SqlState::QUERY_CANCELED));
+
+    // a transaction is lost with its session, rather than continuing outside of it on the new one
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS resuming_transaction; CREATE TABLE resuming_transaction (id INT)",
+        )
+        .await
+        .unwrap();
+    let transaction = client.transaction().await.unwrap();
+    transaction
+        .execute("INSERT INTO resuming_transaction VALUES (1)", &[])
+        .await
+        .unwrap();
+    let pid = transaction
+        .query_one("SELECT pg_backend_pid()", &[])
+        .await
+        .unwrap()
+        .get::<_, i32>(0);
+    admin
+        .execute("SELECT pg_terminate_backend($1)", &[&pid])
+        .await
+        .unwrap();
+    assert_eq!(events.next().await, Some(ConnectionEvent::Resumed));
+
+    let err = transaction
+        .execute("INSERT INTO resuming_transaction VALUES (2)", &[])
+        .await
+        .unwrap_err();
+    assert!(err.is_closed());
+    let err = transaction.commit().await.unwrap_err();
+    assert!(err.is_closed());
+
+    let count = client
+        .query_one("SELECT COUNT(*) FROM resuming_transaction", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 0);
+    client
+        .batch_execute("DROP TABLE resuming_transaction")
+        .await
+        .unwrap();
+
+    drop(stmt);
+    drop(client);
+    connection.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn tls_info_unencrypted() {
+    let client = connect("user=postgres").await;
+    assert_eq!(client.tls_info(), None);
+}
+
+#[tokio::test]
+async fn runtime_params() {
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config
+        .runtime_param("search_path", "pg_catalog")
+        .runtime_param("default_transaction_read_only", "off")
+        .runtime_param("default_transaction_read_only", "on");
+    assert_eq!(
+        config.get_runtime_param("default_transaction_read_only"),
+        Some("on")
+    );
+    assert_eq!(
+        config.get_runtime_params().collect::<Vec<_>>(),
+        [
+            ("search_path", "pg_catalog"),
+            ("default_transaction_read_only", "on")
+        ]
+    );
+
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection);
+    let row = client
+        .query_one(
+            "SELECT current_setting('search_path'), current_setting('default_transaction_read_only')",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), "pg_catalog");
+    assert_eq!(row.get::<_, &str>(1), "on");
+
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.runtime_param("client_encoding", "LATIN1");
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let err = match config.connect_raw(socket, NoTls).await {
+        Ok(_) => panic!("unexpected success"),
+        Err(e) => e,
+    };
+    assert_eq!(
+        err.to_string(),
+        "invalid configuration: invalid runtime parameter `client_encoding`"
+    );
+}
+
+#[tokio::test]
+async fn parameter_watch() {
+    let client = connect("user=postgres").await;
+
+    let version = client.parameter_watch("server_version");
+    assert!(version.borrow().is_some());
+    assert_eq!(*client.parameter_watch("noexist").borrow(), None);
+
+    client
+        .batch_execute("SET TimeZone = 'America/New_York'")
+        .await
+        .unwrap();
+    let mut time_zone = client.parameter_watch("TimeZone");
+    assert_eq!(
+        time_zone.borrow_and_update().as_deref(),
+        Some("America/New_York")
+    );
+
+    client.batch_execute("SET TimeZone = 'UTC'").await.unwrap();
+    time_zone.changed().await.unwrap();
+    assert_eq!(time_zone.borrow().as_deref(), Some("UTC"));
+}
+
+#[tokio::test]
+async fn execute_many() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY, name TEXT)")
+        .await
+        .unwrap();
+
+    let rows = client
+        .execute_many(
+            "INSERT INTO foo (id, name) VALUES ($1, $2)",
+            (1..=1000).map(|i| (i, format!("name {}", i))),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1000);
+    assert!(rows.iter().all(|&r| r == 1));
+
+    let rows = client
+        .execute_many(
+            "UPDATE foo SET name = 'x' WHERE id <= $1",
+            [(10,), (0,), (20,)],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows, [10, 0, 20]);
+
+    let rows = client
+        .execute_many("DELETE FROM foo WHERE id = $1", Vec::<(i32,)>::new())
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+
+    // a failure rolls back the executions before it
+    client
+        .execute_many(
+            "INSERT INTO foo (id, name) VALUES ($1, $2)",
+            [(1001, "a"), (1, "b")],
+        )
+        .await
+        .unwrap_err();
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM foo", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 1000);
+}
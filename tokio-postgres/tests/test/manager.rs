@@ -0,0 +1,120 @@
+use tokio_postgres::{Client, Config, NoTls};
+
+fn config() -> Config {
+    "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap()
+}
+
+async fn backend_pid(client: &Client) -> i32 {
+    client
+        .query_scalar("SELECT pg_backend_pid()", &[])
+        .await
+        .unwrap()[0]
+}
+
+#[cfg(feature = "deadpool")]
+#[tokio::test]
+async fn deadpool() {
+    use deadpool::managed::Pool;
+    use tokio_postgres::manager::DeadpoolManager;
+
+    let pool: Pool<DeadpoolManager<NoTls>> = Pool::builder(DeadpoolManager::new(&config(), NoTls))
+        .max_size(2)
+        .build()
+        .unwrap();
+
+    let client = pool.get().await.unwrap();
+    let pid = backend_pid(&client).await;
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_eq!(backend_pid(&client).await, pid);
+
+    // a connection which was closed is replaced rather than recycled
+    client
+        .batch_execute("SELECT pg_terminate_backend(pg_backend_pid())")
+        .await
+        .unwrap_err();
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_ne!(backend_pid(&client).await, pid);
+}
+
+#[cfg(feature = "deadpool")]
+#[tokio::test]
+async fn deadpool_statement_cache_recycling() {
+    use deadpool::managed::Pool;
+    use tokio_postgres::manager::{DeadpoolManager, StatementCacheRecycling};
+
+    let mut config = config();
+    config.statement_cache_capacity(8);
+
+    let pool: Pool<DeadpoolManager<NoTls>> = Pool::builder(DeadpoolManager::new(&config, NoTls))
+        .max_size(1)
+        .build()
+        .unwrap();
+    let client = pool.get().await.unwrap();
+    client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(client.statement_cache_len(), 1);
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_eq!(client.statement_cache_len(), 1);
+
+    let manager = DeadpoolManager::new(&config, NoTls)
+        .statement_cache_recycling(StatementCacheRecycling::Clear);
+    let pool: Pool<DeadpoolManager<NoTls>> = Pool::builder(manager).max_size(1).build().unwrap();
+    let client = pool.get().await.unwrap();
+    client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(client.statement_cache_len(), 1);
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_eq!(client.statement_cache_len(), 0);
+}
+
+#[cfg(feature = "bb8")]
+#[tokio::test]
+async fn bb8() {
+    use bb8::Pool;
+    use tokio_postgres::manager::Bb8Manager;
+
+    let pool = Pool::builder()
+        .max_size(2)
+        .build(Bb8Manager::new(&config(), NoTls))
+        .await
+        .unwrap();
+
+    let client = pool.get().await.unwrap();
+    let pid = backend_pid(&client).await;
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_eq!(backend_pid(&client).await, pid);
+
+    // a connection which was closed is replaced rather than recycled
+    client
+        .batch_execute("SELECT pg_terminate_backend(pg_backend_pid())")
+        .await
+        .unwrap_err();
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_ne!(backend_pid(&client).await, pid);
+}
+
+#[cfg(feature = "bb8")]
+#[tokio::test]
+async fn bb8_statement_cache_recycling() {
+    use bb8::Pool;
+    use tokio_postgres::manager::{Bb8Manager, StatementCacheRecycling};
+
+    let mut config = config();
+    config.statement_cache_capacity(8);
+
+    let manager =
+        Bb8Manager::new(&config, NoTls).statement_cache_recycling(StatementCacheRecycling::Clear);
+    let pool = Pool::builder().max_size(1).build(manager).await.unwrap();
+    let client = pool.get().await.unwrap();
+    client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(client.statement_cache_len(), 1);
+    drop(client);
+    let client = pool.get().await.unwrap();
+    assert_eq!(client.statement_cache_len(), 0);
+}
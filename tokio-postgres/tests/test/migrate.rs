@@ -0,0 +1,118 @@
+use tokio_postgres::error::Kind;
+use tokio_postgres::migrate::{embed_migrations, Migration, Migrator};
+
+use crate::connect;
+
+#[tokio::test]
+async fn embedded() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS migrate_widgets;
+             DROP TABLE IF EXISTS migrate_embedded;",
+        )
+        .await
+        .unwrap();
+
+    let migrator = embed_migrations!("tests/migrations").table("migrate_embedded");
+    let versions = migrator
+        .migrations()
+        .iter()
+        .map(Migration::version)
+        .collect::<Vec<_>>();
+    assert_eq!(versions, [1, 2]);
+
+    assert_eq!(migrator.dry_run(&client).await.unwrap().len(), 2);
+    assert_eq!(migrator.run(&mut client).await.unwrap().len(), 2);
+    assert_eq!(migrator.dry_run(&client).await.unwrap().len(), 0);
+    assert_eq!(migrator.run(&mut client).await.unwrap().len(), 0);
+
+    client
+        .execute(
+            "INSERT INTO migrate_widgets (name, color) VALUES ('bolt', 'red')",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    client
+        .batch_execute(
+            "DROP TABLE migrate_widgets;
+             DROP TABLE migrate_embedded;",
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn failed_migration_rolls_back() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute("DROP TABLE IF EXISTS migrate_rollback, migrate_rollback_foo")
+        .await
+        .unwrap();
+
+    let migrator = Migrator::new(vec![
+        Migration::new(1, "foo", "CREATE TABLE migrate_rollback_foo (id INT)"),
+        Migration::new(
+            2,
+            "broken",
+            "INSERT INTO migrate_rollback_foo VALUES (1); SELECT * FROM nonexistent",
+        ),
+    ])
+    .table("migrate_rollback");
+
+    migrator.run(&mut client).await.unwrap_err();
+
+    let pending = migrator.dry_run(&client).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].name(), "broken");
+    let count = client
+        .query_one("SELECT count(*) FROM migrate_rollback_foo", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 0);
+
+    client
+        .batch_execute("DROP TABLE migrate_rollback, migrate_rollback_foo")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn changed_migration() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute("DROP TABLE IF EXISTS migrate_changed")
+        .await
+        .unwrap();
+
+    Migrator::new(vec![Migration::new(1, "noop", "SELECT 1")])
+        .table("migrate_changed")
+        .run(&mut client)
+        .await
+        .unwrap();
+
+    let err = Migrator::new(vec![Migration::new(1, "noop", "SELECT 2")])
+        .table("migrate_changed")
+        .run(&mut client)
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind(), Kind::Migration(_)));
+
+    let err = Migrator::new(vec![
+        Migration::new(0, "early", "SELECT 0"),
+        Migration::new(1, "noop", "SELECT 1"),
+    ])
+    .table("migrate_changed")
+    .dry_run(&client)
+    .await
+    .unwrap_err();
+    assert!(matches!(err.kind(), Kind::Migration(_)));
+
+    client
+        .batch_execute("DROP TABLE migrate_changed")
+        .await
+        .unwrap();
+}
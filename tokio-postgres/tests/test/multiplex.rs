@@ -0,0 +1,111 @@
+use futures_util::join;
+use parking_lot::Mutex;
+use std::time::Duration;
+use tokio::time;
+use tokio_postgres::error::Kind;
+use tokio_postgres::multiplex::{Multiplexer, MuxHandle, Priority};
+
+use crate::connect;
+
+async fn sleep_and_log(handle: &MuxHandle, name: &'static str, log: &Mutex<Vec<&'static str>>) {
+    handle.execute("SELECT pg_sleep(0.01)", &[]).await.unwrap();
+    log.lock().push(name);
+}
+
+#[tokio::test]
+async fn fair_queuing() {
+    let multiplexer = Multiplexer::new(connect("user=postgres").await, 1);
+    let a = multiplexer.handle();
+    let b = multiplexer.handle();
+
+    let log = Mutex::new(vec![]);
+    join!(
+        sleep_and_log(&a, "a1", &log),
+        sleep_and_log(&a, "a2", &log),
+        sleep_and_log(&a, "a3", &log),
+        sleep_and_log(&b, "b1", &log),
+    );
+
+    assert_eq!(*log.lock(), ["a1", "a2", "b1", "a3"]);
+}
+
+#[tokio::test]
+async fn cancel() {
+    let multiplexer = Multiplexer::new(connect("user=postgres").await, 1);
+    let a = multiplexer.handle();
+    let b = multiplexer.handle();
+
+    let (running, queued, other, ()) = join!(
+        a.execute("SELECT pg_sleep(0.2)", &[]),
+        a.execute("SELECT 1", &[]),
+        b.query_one("SELECT 2::INT", &[]),
+        async {
+            time::sleep(Duration::from_millis(50)).await;
+            a.cancel();
+            // the canceled sleep keeps its slot while the server is still running it
+            time::sleep(Duration::from_millis(50)).await;
+            assert_eq!(multiplexer.client().pending_requests(), 1);
+        },
+    );
+
+    assert!(matches!(running.unwrap_err().kind(), Kind::Canceled));
+    assert!(matches!(queued.unwrap_err().kind(), Kind::Canceled));
+    assert_eq!(other.unwrap().get::<_, i32>(0), 2);
+
+    let row = a.query_one("SELECT 3::INT", &[]).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 3);
+}
+
+#[tokio::test]
+async fn priority() {
+    let multiplexer = Multiplexer::new(connect("user=postgres").await, 1);
+    let low = multiplexer.handle_with_priority(Priority::Low);
+    let normal = multiplexer.handle();
+    let high = multiplexer.handle_with_priority(Priority::High);
+    assert_eq!(normal.priority(), Priority::Normal);
+
+    let log = Mutex::new(vec![]);
+    join!(
+        sleep_and_log(&low, "low1", &log),
+        sleep_and_log(&low, "low2", &log),
+        sleep_and_log(&normal, "normal1", &log),
+        sleep_and_log(&normal, "normal2", &log),
+        sleep_and_log(&high, "high1", &log),
+        sleep_and_log(&high, "high2", &log),
+    );
+
+    // the first request is sent immediately, as nothing else is waiting yet
+    assert_eq!(
+        *log.lock(),
+        ["low1", "high1", "high2", "normal1", "normal2", "low2"]
+    );
+}
+
+#[tokio::test]
+async fn priority_aging() {
+    let multiplexer =
+        Multiplexer::new(connect("user=postgres").await, 1).aging(Duration::from_millis(20));
+    let low = multiplexer.handle_with_priority(Priority::Low);
+    let high = multiplexer.handle_with_priority(Priority::High);
+
+    let log = Mutex::new(vec![]);
+    join!(
+        sleep_and_log(&low, "low1", &log),
+        sleep_and_log(&low, "low2", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+        sleep_and_log(&high, "high", &log),
+    );
+
+    // each request takes at least 10ms, so the second low priority request is promoted to high priority before all of
+    // the high priority requests have been served
+    let log = log.lock();
+    let low2 = log.iter().position(|name| *name == "low2").unwrap();
+    assert_eq!(log[0], "low1");
+    assert!(low2 < log.len() - 1, "{:?}", log);
+}
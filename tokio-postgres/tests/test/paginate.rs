@@ -0,0 +1,100 @@
+use tokio_postgres::error::Kind;
+use tokio_postgres::paginate::{Cursor, Paginator};
+use tokio_postgres::{Client, Error, FromRow, Row};
+
+use crate::connect;
+
+#[derive(Debug, PartialEq)]
+struct Item {
+    name: String,
+    id: i32,
+}
+
+impl FromRow for Item {
+    fn from_row(row: &Row) -> Result<Item, Error> {
+        Ok(Item {
+            name: row.try_get("name")?,
+            id: row.try_get("id")?,
+        })
+    }
+}
+
+async fn setup() -> Client {
+    let client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE items (id INT PRIMARY KEY, name TEXT NOT NULL);
+             INSERT INTO items (id, name) VALUES
+                (1, 'b'), (2, 'a'), (3, 'b'), (4, 'c'), (5, 'a'), (6, 'b'), (7, 'd');",
+        )
+        .await
+        .unwrap();
+    client
+}
+
+async fn fetch_all(client: &Client, paginator: &Paginator, min_id: i32) -> Vec<Vec<i32>> {
+    let mut pages = vec![];
+    let mut cursor = None::<Cursor>;
+    loop {
+        let page = paginator
+            .fetch::<Item, _>(client, &[&min_id], cursor.as_ref())
+            .await
+            .unwrap();
+        pages.push(page.items().iter().map(|i| i.id).collect());
+        match page.next_cursor() {
+            Some(next) => cursor = Some(Cursor::from_token(&next.to_token()).unwrap()),
+            None => break,
+        }
+    }
+    pages
+}
+
+#[tokio::test]
+async fn keyset() {
+    let client = setup().await;
+
+    let paginator = Paginator::new(
+        "SELECT id, name FROM items WHERE id >= $1",
+        &["name", "id"],
+        3,
+    );
+    assert_eq!(
+        fetch_all(&client, &paginator, 0).await,
+        [vec![2, 5, 1], vec![3, 6, 4], vec![7]]
+    );
+    assert_eq!(
+        fetch_all(&client, &paginator, 2).await,
+        [vec![2, 5, 3], vec![6, 4, 7]]
+    );
+
+    let paginator = paginator.descending(true);
+    assert_eq!(
+        fetch_all(&client, &paginator, 0).await,
+        [vec![7, 4, 6], vec![3, 1, 5], vec![2]]
+    );
+}
+
+#[tokio::test]
+async fn invalid_cursor() {
+    let client = setup().await;
+
+    let err = Cursor::from_token("23-zz").unwrap_err();
+    assert!(matches!(err.kind(), Kind::Cursor(_)));
+
+    // an int8 value where the key is an int4
+    let cursor = Cursor::from_token("20-0000000000000001").unwrap();
+    let paginator = Paginator::new("SELECT id, name FROM items", &["id"], 3);
+    let err = paginator
+        .fetch::<Item, _>(&client, &[], Some(&cursor))
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind(), Kind::ToSql(0, _)));
+
+    let cursor = Cursor::from_token("23-00000001").unwrap();
+    let page = paginator
+        .fetch::<Item, _>(&client, &[], Some(&cursor))
+        .await
+        .unwrap();
+    assert_eq!(page.items().len(), 3);
+    assert_eq!(page.items()[0].id, 2);
+}
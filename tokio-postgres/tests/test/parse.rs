@@ -1,5 +1,6 @@
+use std::fs;
 use std::time::Duration;
-use tokio_postgres::config::{Config, TargetSessionAttrs};
+use tokio_postgres::config::{Config, SslMode, TargetSessionAttrs};
 
 fn check(s: &str, config: &Config) {
     assert_eq!(s.parse::<Config>().expect(s), *config, "`{}`", s);
@@ -42,6 +43,23 @@ fn settings() {
             .keepalives_idle(Duration::from_secs(30))
             .target_session_attrs(TargetSessionAttrs::ReadOnly),
     );
+    check(
+        "target_session_attrs=primary",
+        Config::new().target_session_attrs(TargetSessionAttrs::Primary),
+    );
+    check(
+        "target_session_attrs=standby",
+        Config::new().target_session_attrs(TargetSessionAttrs::Standby),
+    );
+    check(
+        "target_session_attrs=prefer-standby",
+        Config::new().target_session_attrs(TargetSessionAttrs::PreferStandby),
+    );
+    #[cfg(feature = "runtime")]
+    check(
+        "passfile=/etc/postgres/pgpass",
+        Config::new().passfile("/etc/postgres/pgpass"),
+    );
 }
 
 #[test]
@@ -138,3 +156,62 @@ fn url() {
             .dbname("dbname"),
     )
 }
+
+#[test]
+fn service() {
+    let path = std::env::temp_dir().join(format!("tokio_postgres_service_{}", std::process::id()));
+    fs::write(
+        &path,
+        "# connection profiles\n\
+         [other]\n\
+         dbname=other\n\
+         \n\
+         [reporting]\n\
+         host = db1,db2\n\
+         port=5433\n\
+         user=report\n\
+         dbname=reports\n\
+         sslmode=require\n\
+         [nested]\n\
+         service=other\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    check(
+        &format!("service=reporting servicefile={} dbname=sales", path),
+        Config::new()
+            .host("db1")
+            .host("db2")
+            .port(5433)
+            .user("report")
+            .dbname("sales")
+            .ssl_mode(SslMode::Require),
+    );
+    check(
+        &format!(
+            "postgresql://me@localhost/?service=reporting&servicefile={}",
+            path
+        ),
+        Config::new()
+            .user("me")
+            .host("localhost")
+            .port(5432)
+            .dbname("reports")
+            .ssl_mode(SslMode::Require),
+    );
+    format!("service=missing servicefile={}", path)
+        .parse::<Config>()
+        .err()
+        .unwrap();
+    format!("service=nested servicefile={}", path)
+        .parse::<Config>()
+        .err()
+        .unwrap();
+    format!("service=reporting servicefile={}.missing", path)
+        .parse::<Config>()
+        .err()
+        .unwrap();
+
+    fs::remove_file(path).unwrap();
+}
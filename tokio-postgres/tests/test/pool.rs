@@ -0,0 +1,132 @@
+use std::time::Duration;
+use tokio::time;
+use tokio_postgres::pool::{Pool, PooledClient};
+use tokio_postgres::{Config, GenericClient, NoTls};
+
+use crate::connect;
+
+fn config() -> Config {
+    "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap()
+}
+
+async fn backend_pid(client: &PooledClient) -> i32 {
+    client
+        .query_scalar("SELECT pg_backend_pid()", &[])
+        .await
+        .unwrap()[0]
+}
+
+#[tokio::test]
+async fn reuse() {
+    let pool = Pool::new(&config(), NoTls);
+
+    let client = pool.get().await.unwrap();
+    let pid = backend_pid(&client).await;
+    assert_eq!((pool.size(), pool.idle()), (1, 0));
+    drop(client);
+    assert_eq!((pool.size(), pool.idle()), (1, 1));
+
+    let mut client = pool.get().await.unwrap();
+    assert_eq!(backend_pid(&client).await, pid);
+
+    let transaction = client.transaction().await.unwrap();
+    transaction.batch_execute("SELECT 1").await.unwrap();
+    transaction.commit().await.unwrap();
+
+    let other = pool.get().await.unwrap();
+    assert_ne!(backend_pid(&other).await, pid);
+    assert_eq!(pool.size(), 2);
+}
+
+#[tokio::test]
+async fn max_size() {
+    let pool = Pool::builder(&config(), NoTls)
+        .max_size(1)
+        .acquire_timeout(Some(Duration::from_millis(100)))
+        .build();
+
+    let client = pool.get().await.unwrap();
+    let err = pool.get().await.unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    let pid = backend_pid(&client).await;
+    let waiting = pool.clone();
+    let waiting = tokio::spawn(async move { backend_pid(&waiting.get().await.unwrap()).await });
+    time::sleep(Duration::from_millis(10)).await;
+    drop(client);
+    assert_eq!(waiting.await.unwrap(), pid);
+    assert_eq!(pool.size(), 1);
+}
+
+#[tokio::test]
+async fn broken_connections() {
+    let admin = connect("user=postgres").await;
+    let pool = Pool::builder(&config(), NoTls)
+        .max_lifetime(Some(Duration::from_millis(100)))
+        .build();
+
+    let client = pool.get().await.unwrap();
+    let pid = backend_pid(&client).await;
+    drop(client);
+
+    admin
+        .execute("SELECT pg_terminate_backend($1)", &[&pid])
+        .await
+        .unwrap();
+    let client = pool.get().await.unwrap();
+    let pid = backend_pid(&client).await;
+    drop(client);
+    assert_eq!(pool.size(), 1);
+
+    time::sleep(Duration::from_millis(100)).await;
+    let client = pool.get().await.unwrap();
+    assert_ne!(backend_pid(&client).await, pid);
+    assert_eq!(pool.size(), 1);
+}
+
+#[tokio::test]
+async fn maintenance() {
+    let pool = Pool::builder(&config(), NoTls)
+        .min_size(2)
+        .idle_timeout(Some(Duration::from_millis(50)))
+        .reap_interval(Duration::from_millis(10))
+        .build();
+
+    while pool.idle() < 2 {
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(pool.size(), 2);
+
+    let clients = [
+        pool.get().await.unwrap(),
+        pool.get().await.unwrap(),
+        pool.get().await.unwrap(),
+    ];
+    drop(clients);
+    assert_eq!((pool.size(), pool.idle()), (3, 3));
+
+    while pool.size() > 2 {
+        time::sleep(Duration::from_millis(10)).await;
+    }
+    time::sleep(Duration::from_millis(100)).await;
+    assert_eq!((pool.size(), pool.idle()), (2, 2));
+}
+
+#[tokio::test]
+async fn generic_client() {
+    async fn count<C: GenericClient>(client: &mut C) -> i64 {
+        let transaction = client.transaction().await.unwrap();
+        let row = transaction
+            .query_one("SELECT count(*) FROM generate_series(1, 3)", &[])
+            .await
+            .unwrap();
+        transaction.commit().await.unwrap();
+        row.get(0)
+    }
+
+    let pool = Pool::new(&config(), NoTls);
+    let mut client = pool.get().await.unwrap();
+    assert_eq!(count(&mut client).await, 3);
+}
@@ -1,8 +1,18 @@
+use futures_util::future::{self, BoxFuture};
 use futures_util::{join, FutureExt};
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time;
 use tokio_postgres::error::SqlState;
-use tokio_postgres::{Client, NoTls};
+use tokio_postgres::resolve::Resolve;
+use tokio_postgres::types::PgLsn;
+use tokio_postgres::{Client, Config, NoTls, QueryOptions};
 
 async fn connect(s: &str) -> Client {
     let (client, connection) = tokio_postgres::connect(s, NoTls).await.unwrap();
@@ -66,6 +76,29 @@ async fn target_session_attrs_err() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn target_session_attrs_standby() {
+    // the first host is unreachable, and the second one is the primary
+    smoke_test(
+        "host=localhost,localhost port=5434,5433 user=postgres target_session_attrs=primary",
+    )
+    .await;
+    smoke_test("host=localhost port=5433 user=postgres target_session_attrs=prefer-standby").await;
+
+    let err = tokio_postgres::connect(
+        "host=localhost port=5433 user=postgres target_session_attrs=standby",
+        NoTls,
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(
+        err.to_string().contains("not in hot standby mode"),
+        "{}",
+        err
+    );
+}
+
 #[tokio::test]
 async fn host_only_ok() {
     let _ = tokio_postgres::connect(
@@ -96,6 +129,25 @@ async fn hostaddr_and_host_ok() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn hostaddr_mixed_ok() {
+    // the first host has an unreachable address, and the second one is looked up
+    let _ = tokio_postgres::connect(
+        "hostaddr=127.0.0.1, host=localhost,localhost port=5434,5433 user=pass_user dbname=postgres password=password",
+        NoTls,
+    )
+    .await
+    .unwrap();
+
+    let _ = tokio_postgres::connect(
+        "hostaddr=127.0.0.1, port=5433 user=pass_user dbname=postgres password=password",
+        NoTls,
+    )
+    .await
+    .err()
+    .unwrap();
+}
+
 #[tokio::test]
 async fn hostaddr_host_mismatch() {
     let _ = tokio_postgres::connect(
@@ -118,6 +170,105 @@ async fn hostaddr_host_both_missing() {
     .unwrap();
 }
 
+// resolves every host to the loopback address, recording the names it was asked for
+#[derive(Clone, Default)]
+struct Loopback(Arc<Mutex<Vec<String>>>);
+
+impl Resolve for Loopback {
+    fn resolve<'a>(
+        &'a self,
+        host: &'a str,
+        port: u16,
+    ) -> BoxFuture<'a, io::Result<Vec<SocketAddr>>> {
+        self.0.lock().unwrap().push(host.to_string());
+        if host.ends_with(".missing") {
+            return Box::pin(future::ready(Ok(vec![])));
+        }
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        Box::pin(future::ready(Ok(vec![SocketAddr::new(ip, port)])))
+    }
+}
+
+#[tokio::test]
+async fn resolver() {
+    let resolver = Loopback::default();
+    let mut config = "host=db.missing,db.internal port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config.resolver(resolver.clone());
+    let _ = config.connect(NoTls).await.unwrap();
+    assert_eq!(*resolver.0.lock().unwrap(), ["db.missing", "db.internal"]);
+}
+
+#[tokio::test]
+async fn resolver_skipped_for_hostaddr() {
+    let resolver = Loopback::default();
+    let mut config = "host=db.internal hostaddr=127.0.0.1 port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config.resolver(resolver.clone());
+    let _ = config.connect(NoTls).await.unwrap();
+    assert!(resolver.0.lock().unwrap().is_empty());
+}
+
+// writes a password file readable only by its owner, returning its path
+fn passfile(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("tokio_postgres_{}_{}", name, std::process::id()));
+    fs::write(&path, contents).unwrap();
+    #[cfg(unix)]
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn passfile_password() {
+    let path = passfile(
+        "pgpass",
+        "# comment\n\
+         localhost:5433:postgres:other_user:wrong\n\
+         localhost:5433:*:pass_user:password\n\
+         *:*:*:*:wrong\n",
+    );
+    let mut config = "host=localhost port=5433 user=pass_user dbname=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config.passfile(&path);
+    let result = config.connect(NoTls).await;
+    fs::remove_file(&path).unwrap();
+    let _ = result.unwrap();
+}
+
+#[tokio::test]
+async fn passfile_escapes() {
+    // an escaped `*` is not a wildcard, and other escaped characters stand for themselves
+    let path = passfile(
+        "pgpass_escapes",
+        "\\*:5433:*:pass_user:wrong\n\
+         local\\host:5433:*:pass_user:password\n",
+    );
+    let mut config = "host=localhost port=5433 user=pass_user dbname=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config.passfile(&path);
+    let result = config.connect(NoTls).await;
+    fs::remove_file(&path).unwrap();
+    let _ = result.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn passfile_group_readable() {
+    let path = passfile("pgpass_readable", "*:*:*:pass_user:password\n");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    let mut config = "host=localhost port=5433 user=pass_user dbname=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config.passfile(&path);
+    let result = config.connect(NoTls).await;
+    fs::remove_file(&path).unwrap();
+    result.err().unwrap();
+}
+
 #[tokio::test]
 async fn cancel_query() {
     let client = connect("host=localhost port=5433 user=postgres").await;
@@ -133,3 +284,72 @@ async fn cancel_query() {
         t => panic!("unexpected return: {:?}", t),
     }
 }
+
+#[tokio::test]
+async fn query_timeout() {
+    let client = connect("host=localhost port=5433 user=postgres").await;
+
+    let options = QueryOptions::new().timeout(Duration::from_millis(100));
+    let err = client
+        .query_with_options("SELECT pg_sleep(10)", &[], &options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    let err = client
+        .execute_with_options("SELECT pg_sleep(10)", &[], &options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    // the sleeps were cancelled rather than left running ahead of the next query
+    let rows = time::timeout(Duration::from_secs(5), client.query("SELECT 1", &[]))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+
+    let rows = client
+        .query_with_options("SELECT 1", &[], &options)
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[tokio::test]
+async fn is_valid() {
+    let client = connect("host=localhost port=5433 user=postgres").await;
+
+    client.is_valid(Duration::from_secs(5)).await.unwrap();
+
+    // the check is queued behind the running query
+    let sleep = client.batch_execute("SELECT pg_sleep(0.5)");
+    let check = client.is_valid(Duration::from_millis(100));
+    let (sleep, check) = join!(sleep, check);
+    sleep.unwrap();
+    assert_eq!(check.unwrap_err().to_string(), "timeout waiting for server");
+
+    client.is_valid(Duration::from_secs(5)).await.unwrap();
+}
+
+#[tokio::test]
+async fn wait_for_lsn() {
+    let client = connect("host=localhost port=5433 user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT); INSERT INTO foo VALUES (1)")
+        .await
+        .unwrap();
+    let lsn = client.current_wal_lsn().await.unwrap();
+    assert!(lsn > PgLsn::from(0));
+
+    // a primary never has to wait, even for locations it hasn't reached yet
+    client
+        .wait_for_lsn(lsn, Duration::from_secs(1))
+        .await
+        .unwrap();
+    client
+        .wait_for_lsn(PgLsn::from(u64::MAX), Duration::from_secs(1))
+        .await
+        .unwrap();
+}
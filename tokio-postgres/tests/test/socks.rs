@@ -0,0 +1,165 @@
+use futures_util::{join, FutureExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{Client, Config, NoTls};
+
+// a SOCKS5 proxy requiring username/password authentication, which counts the connections it tunnels
+async fn proxy(username: &'static str, password: &'static str) -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let tunnels = Arc::new(AtomicUsize::new(0));
+
+    let count = tunnels.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let count = count.clone();
+            tokio::spawn(async move {
+                if let Ok(Some(mut target)) = handshake(stream, username, password).await {
+                    count.fetch_add(1, Ordering::SeqCst);
+                    let _ = io::copy_bidirectional(&mut target.0, &mut target.1).await;
+                }
+            });
+        }
+    });
+
+    (port, tunnels)
+}
+
+async fn handshake(
+    mut stream: TcpStream,
+    username: &str,
+    password: &str,
+) -> io::Result<Option<(TcpStream, TcpStream)>> {
+    let mut header = [0; 2];
+    stream.read_exact(&mut header).await?;
+    assert_eq!(header[0], 5);
+    let mut methods = vec![0; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&2) {
+        stream.write_all(&[5, 0xff]).await?;
+        return Ok(None);
+    }
+    stream.write_all(&[5, 2]).await?;
+
+    assert_eq!(stream.read_u8().await?, 1);
+    let mut user = vec![0; stream.read_u8().await? as usize];
+    stream.read_exact(&mut user).await?;
+    let mut pass = vec![0; stream.read_u8().await? as usize];
+    stream.read_exact(&mut pass).await?;
+    if user != username.as_bytes() || pass != password.as_bytes() {
+        stream.write_all(&[1, 1]).await?;
+        return Ok(None);
+    }
+    stream.write_all(&[1, 0]).await?;
+
+    let mut request = [0; 4];
+    stream.read_exact(&mut request).await?;
+    assert_eq!(request[..3], [5, 1, 0]);
+    let host = match request[3] {
+        1 => {
+            let mut ip = [0; 4];
+            stream.read_exact(&mut ip).await?;
+            ip.iter().map(u8::to_string).collect::<Vec<_>>().join(".")
+        }
+        3 => {
+            let mut name = vec![0; stream.read_u8().await? as usize];
+            stream.read_exact(&mut name).await?;
+            String::from_utf8(name).unwrap()
+        }
+        atyp => panic!("unexpected address type {}", atyp),
+    };
+    let port = stream.read_u16().await?;
+
+    let target = match TcpStream::connect((&*host, port)).await {
+        Ok(target) => target,
+        Err(_) => {
+            stream.write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+            return Ok(None);
+        }
+    };
+    stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+    Ok(Some((stream, target)))
+}
+
+async fn connect(config: &Config) -> Result<Client, tokio_postgres::Error> {
+    let (client, connection) = config.connect(NoTls).await?;
+    let connection = connection.map(|e| e.unwrap());
+    tokio::spawn(connection);
+    Ok(client)
+}
+
+fn config(proxy_port: u16) -> Config {
+    let mut config = "host=localhost port=5433 user=postgres"
+        .parse::<Config>()
+        .unwrap();
+    config.proxy("127.0.0.1", proxy_port);
+    config
+}
+
+#[tokio::test]
+async fn connect_through_proxy() {
+    let (port, tunnels) = proxy("bastion", "secret").await;
+    let mut config = config(port);
+    config.proxy_credentials("bastion", "secret");
+    assert_eq!(config.get_proxy(), Some(("127.0.0.1", port)));
+    assert_eq!(config.get_proxy_username(), Some("bastion"));
+
+    let client = connect(&config).await.unwrap();
+    let value: i32 = client.query_one("SELECT 1", &[]).await.unwrap().get(0);
+    assert_eq!(value, 1);
+    assert_eq!(tunnels.load(Ordering::SeqCst), 1);
+
+    config.hostaddr("127.0.0.1".parse().unwrap());
+    connect(&config).await.unwrap();
+    assert_eq!(tunnels.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn cancel_query_through_proxy() {
+    let (port, tunnels) = proxy("bastion", "secret").await;
+    let mut config = config(port);
+    config.proxy_credentials("bastion", "secret");
+    let client = connect(&config).await.unwrap();
+
+    let cancel_token = client.cancel_token();
+    let cancel = async {
+        time::sleep(Duration::from_millis(100)).await;
+        cancel_token.cancel_query(NoTls).await
+    };
+    let sleep = client.batch_execute("SELECT pg_sleep(100)");
+
+    match join!(sleep, cancel) {
+        (Err(ref e), Ok(())) if e.code() == Some(&SqlState::QUERY_CANCELED) => {}
+        t => panic!("unexpected return: {:?}", t),
+    }
+    assert_eq!(tunnels.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn proxy_authentication_failure() {
+    let (port, tunnels) = proxy("bastion", "secret").await;
+    let mut config = config(port);
+
+    let err = connect(&config).await.err().unwrap();
+    assert!(
+        err.to_string()
+            .contains("unsupported authentication method"),
+        "{}",
+        err
+    );
+
+    config.proxy_credentials("bastion", "wrong");
+    let err = connect(&config).await.err().unwrap();
+    assert!(
+        err.to_string().contains("proxy authentication failed"),
+        "{}",
+        err
+    );
+    assert_eq!(tunnels.load(Ordering::SeqCst), 0);
+}
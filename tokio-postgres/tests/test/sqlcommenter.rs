@@ -0,0 +1,68 @@
+use futures_util::FutureExt;
+use tokio::net::TcpStream;
+use tokio_postgres::sqlcommenter::Context;
+use tokio_postgres::{Client, Config, NoTls};
+
+async fn connect(sql_commenter: bool) -> Client {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres application_name=api"
+        .parse::<Config>()
+        .unwrap();
+    config.sql_commenter(sql_commenter).statement_stats(true);
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+    client
+}
+
+async fn current_query(client: &Client) -> String {
+    client
+        .query_one("SELECT current_query()", &[])
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[tokio::test]
+async fn comment() {
+    let client = connect(true).await;
+
+    assert_eq!(
+        current_query(&client).await,
+        "SELECT current_query() /*application='api'*/"
+    );
+
+    let context = Context::new()
+        .route("/users/:id")
+        .tag("owner", "o'brien")
+        .traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+    let query = context.scope(current_query(&client)).await;
+    assert_eq!(
+        query,
+        "SELECT current_query() /*application='api',owner='o\\'brien',route='%2Fusers%2F%3Aid',\
+         traceparent='00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01'*/"
+    );
+
+    let query: String = client
+        .query_one("SELECT current_query() /* mine */", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(query, "SELECT current_query() /* mine */");
+
+    let mut stats = client.statement_stats();
+    stats.sort_by(|a, b| a.query().cmp(b.query()));
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].query(), "SELECT current_query()");
+    assert_eq!(stats[0].calls(), 2);
+}
+
+#[tokio::test]
+async fn disabled() {
+    let client = connect(false).await;
+
+    let query = Context::new()
+        .route("/")
+        .scope(current_query(&client))
+        .await;
+    assert_eq!(query, "SELECT current_query()");
+}
@@ -0,0 +1,306 @@
+use futures_util::FutureExt;
+use std::panic::AssertUnwindSafe;
+use tokio_postgres::error::{DbErrorBuilder, Kind, SqlState};
+use tokio_postgres::row::RowBuilder;
+use tokio_postgres::test::{MockClient, Recorder, Recording, TestDatabase, TestTransaction};
+use tokio_postgres::types::Type;
+use tokio_postgres::{Config, DynClient, Error, GenericClient, NoTls};
+
+use crate::connect;
+
+fn config() -> Config {
+    "host=localhost port=5433 user=postgres".parse().unwrap()
+}
+
+async fn exists(name: &str) -> bool {
+    let client = connect("user=postgres").await;
+    client
+        .query_one(
+            "SELECT EXISTS (SELECT 1 FROM pg_database WHERE datname = $1)",
+            &[&name],
+        )
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[tokio::test]
+async fn create_and_drop() {
+    let db = TestDatabase::create(&config(), NoTls).await.unwrap();
+    let name = db.name().to_string();
+    assert!(exists(&name).await);
+
+    let client = db.connect().await.unwrap();
+    let row = client
+        .query_one("SELECT current_database()", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, &str>(0), name);
+
+    db.drop_database().await.unwrap();
+    assert!(!exists(&name).await);
+    assert!(client.is_closed() || client.simple_query("SELECT 1").await.is_err());
+}
+
+#[tokio::test]
+async fn dropped_on_panic() {
+    let db = TestDatabase::create(&config(), NoTls).await.unwrap();
+    let name = db.name().to_string();
+
+    let result = AssertUnwindSafe(async move {
+        let _client = db.connect().await.unwrap();
+        panic!("test failure");
+    })
+    .catch_unwind()
+    .await;
+    assert!(result.is_err());
+
+    assert!(!exists(&name).await);
+}
+
+#[tokio::test]
+async fn from_template() {
+    let template = TestDatabase::create(&config(), NoTls).await.unwrap();
+    {
+        let client = template.connect().await.unwrap();
+        client
+            .batch_execute("CREATE TABLE widgets (id INT); INSERT INTO widgets VALUES (1)")
+            .await
+            .unwrap();
+    }
+    let db = TestDatabase::from_template(&config(), template.name(), NoTls)
+        .await
+        .unwrap();
+    let client = db.connect().await.unwrap();
+    let row = client
+        .query_one("SELECT count(*) FROM widgets", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 1);
+}
+
+async fn create_widget<C: GenericClient>(client: &mut C) -> Result<(), Error> {
+    let transaction = client.transaction().await?;
+    transaction
+        .execute("INSERT INTO test_transaction_widgets VALUES (1)", &[])
+        .await?;
+    transaction.commit().await
+}
+
+#[tokio::test]
+async fn test_transaction() {
+    let mut client = connect("user=postgres").await;
+    client
+        .batch_execute("CREATE TEMPORARY TABLE test_transaction_widgets (id INT)")
+        .await
+        .unwrap();
+
+    let mut transaction = TestTransaction::new(&mut client).await.unwrap();
+    create_widget(&mut transaction).await.unwrap();
+    let count = transaction
+        .query_one("SELECT count(*) FROM test_transaction_widgets", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 1);
+    transaction.commit().await.unwrap();
+
+    let count = client
+        .query_one("SELECT count(*) FROM test_transaction_widgets", &[])
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 0);
+}
+
+async fn rename_user(client: &mut dyn DynClient, id: i32, name: &str) -> Result<String, Error> {
+    let transaction = client.transaction().await?;
+    let old = transaction
+        .query_one("SELECT name FROM users WHERE id = $1", &[&id])
+        .await?
+        .get(0);
+    transaction
+        .execute("UPDATE users SET name = $2 WHERE id = $1", &[&id, &name])
+        .await?;
+    transaction.commit().await?;
+    Ok(old)
+}
+
+#[tokio::test]
+async fn mock_client() {
+    let mut client = MockClient::new();
+    client.expect_query(
+        "SELECT name FROM users WHERE id = $1",
+        vec![RowBuilder::new()
+            .column("name", Type::TEXT, &"Ferris")
+            .build()
+            .unwrap()],
+    );
+    client.expect_execute("UPDATE users SET name = $2 WHERE id = $1", 1);
+
+    let old = rename_user(&mut client, 1, "Corro").await.unwrap();
+    assert_eq!(old, "Ferris");
+    client.verify();
+    assert_eq!(
+        client.queries(),
+        [
+            "BEGIN",
+            "SELECT name FROM users WHERE id = $1",
+            "UPDATE users SET name = $2 WHERE id = $1",
+            "COMMIT",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn mock_client_error() {
+    let mut client = MockClient::new();
+    client.expect_query("SELECT name FROM users WHERE id = $1", vec![]);
+    client.expect_error(
+        "UPDATE users SET name = $2 WHERE id = $1",
+        DbErrorBuilder::new(SqlState::UNIQUE_VIOLATION, "duplicate key")
+            .constraint("users_name_key")
+            .build(),
+    );
+
+    let err = rename_user(&mut client, 1, "Corro").await.unwrap_err();
+    assert!(matches!(err.kind(), Kind::RowCount));
+
+    client.expect_query(
+        "SELECT name FROM users WHERE id = $1",
+        vec![RowBuilder::new()
+            .column("name", Type::TEXT, &"Ferris")
+            .build()
+            .unwrap()],
+    );
+    let err = rename_user(&mut client, 1, "Corro").await.unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNIQUE_VIOLATION));
+    assert_eq!(
+        err.as_db_error().unwrap().constraint(),
+        Some("users_name_key")
+    );
+    assert_eq!(client.queries().last().unwrap(), "ROLLBACK");
+    client.verify();
+}
+
+#[tokio::test]
+async fn mock_client_prepare() {
+    let client = MockClient::new();
+    client.expect_prepare(
+        "SELECT name FROM users WHERE id = $1",
+        vec![Type::INT4],
+        vec![("name", Type::TEXT)],
+    );
+
+    let statement = client
+        .prepare("SELECT name FROM users WHERE id = $1")
+        .await
+        .unwrap();
+    assert_eq!(statement.sql(), "SELECT name FROM users WHERE id = $1");
+    assert_eq!(statement.params(), [Type::INT4]);
+    assert_eq!(statement.columns()[0].name(), "name");
+    assert_eq!(statement.columns()[0].type_(), &Type::TEXT);
+    client.verify();
+}
+
+#[test]
+#[should_panic(expected = "unexpected query: SELECT 1")]
+fn mock_client_unexpected_query() {
+    let client = MockClient::new();
+    let _ = futures_executor::block_on(client.query("SELECT 1", &[]));
+}
+
+#[test]
+fn row_builder() {
+    let row = RowBuilder::new()
+        .column("id", Type::INT4, &1i32)
+        .column("name", Type::TEXT, &None::<&str>)
+        .build()
+        .unwrap();
+    assert_eq!(row.len(), 2);
+    assert_eq!(row.columns()[1].name(), "name");
+    assert_eq!(row.get::<_, i32>("id"), 1);
+    assert_eq!(row.get::<_, Option<&str>>(1), None);
+
+    let err = RowBuilder::new()
+        .column("id", Type::INT4, &"one")
+        .build()
+        .unwrap_err();
+    assert!(matches!(err.kind(), Kind::ToSql(0, _)));
+}
+
+async fn record_queries(client: &mut dyn DynClient) -> Vec<String> {
+    let mut results = vec![];
+
+    let rows = client
+        .query(
+            "SELECT 1::INT4 AS id, 'a b\nc'::TEXT AS name, NULL::BYTEA AS data \
+             UNION ALL SELECT $1, 'd', '\\x00ff'",
+            &[&2i32],
+        )
+        .await
+        .unwrap();
+    for row in &rows {
+        results.push(format!(
+            "{} {:?} {:?}",
+            row.get::<_, i32>("id"),
+            row.get::<_, &str>("name"),
+            row.get::<_, Option<&[u8]>>("data"),
+        ));
+    }
+
+    let statement = client
+        .prepare_typed("SELECT $1::TEXT AS name", &[Type::TEXT])
+        .await
+        .unwrap();
+    results.push(format!(
+        "{:?} {} {}",
+        statement.params(),
+        statement.columns()[0].name(),
+        statement.columns()[0].type_()
+    ));
+
+    let transaction = client.transaction().await.unwrap();
+    let count = transaction
+        .execute("SELECT generate_series(1, 3)", &[])
+        .await
+        .unwrap();
+    results.push(count.to_string());
+    let err = transaction
+        .batch_execute("SELECT * FROM nonexistent_table")
+        .await
+        .unwrap_err();
+    results.push(format!("{:?}", err.code()));
+    transaction.rollback().await.unwrap();
+
+    results
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let client = connect("user=postgres").await;
+    let mut recorder = Recorder::new(client);
+    let live = record_queries(&mut recorder).await;
+
+    let recording = recorder.recording();
+    assert_eq!(recording.len(), 4);
+    let path =
+        std::env::temp_dir().join(format!("tokio_postgres_recording_{}", std::process::id()));
+    recording.save(&path).unwrap();
+    let loaded = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut client = loaded.replay().unwrap();
+    let replayed = record_queries(&mut client).await;
+    client.verify();
+
+    assert_eq!(live, replayed);
+    assert_eq!(
+        live[..3],
+        [
+            "1 \"a b\\nc\" None",
+            "2 \"d\" Some([0, 255])",
+            "[Text] name text"
+        ]
+    );
+}
@@ -19,6 +19,8 @@ mod bit_vec_06;
 mod chrono_04;
 #[cfg(feature = "with-chrono-tz-0_9")]
 mod chrono_tz_09;
+#[cfg(feature = "with-chrono-tz-0_10")]
+mod chrono_tz_0_10;
 #[cfg(feature = "with-eui48-1")]
 mod eui48_1;
 #[cfg(feature = "with-geo-types-0_6")]
@@ -0,0 +1,82 @@
+use futures_util::future;
+use tokio_postgres::{Client, Config, NoTls};
+
+async fn connect(s: &str) -> Client {
+    let config = s.parse::<Config>().unwrap();
+    let (client, connection) = config.connect_uring(NoTls).await.unwrap();
+    tokio_uring::spawn(async move { connection.await.unwrap() });
+    client
+}
+
+#[test]
+fn smoke_test() {
+    tokio_uring::start(async {
+        let client = connect("host=localhost port=5433 user=postgres").await;
+
+        let stmt = client.prepare("SELECT $1::INT").await.unwrap();
+        let rows = client.query(&stmt, &[&1i32]).await.unwrap();
+        assert_eq!(rows[0].get::<_, i32>(0), 1i32);
+    });
+}
+
+#[test]
+fn pipelined() {
+    tokio_uring::start(async {
+        let client = connect("host=localhost port=5433 user=postgres").await;
+
+        let stmt = client.prepare("SELECT $1::INT").await.unwrap();
+        let queries = (0..100).map(|i| {
+            let client = &client;
+            let stmt = &stmt;
+            async move {
+                client
+                    .query_one(stmt, &[&i])
+                    .await
+                    .unwrap()
+                    .get::<_, i32>(0)
+            }
+        });
+        let results = future::join_all(queries).await;
+        assert_eq!(results, (0..100).collect::<Vec<_>>());
+    });
+}
+
+#[test]
+fn large_values() {
+    tokio_uring::start(async {
+        let client = connect("host=localhost port=5433 user=postgres").await;
+
+        // larger than the buffers of the stream, so that reads and writes take several operations
+        let value = "a".repeat(1024 * 1024);
+        let row = client
+            .query_one("SELECT $1::TEXT || $1::TEXT", &[&value])
+            .await
+            .unwrap();
+        assert_eq!(row.get::<_, &str>(0).len(), 2 * value.len());
+    });
+}
+
+#[test]
+fn closed() {
+    tokio_uring::start(async {
+        let config = "host=localhost port=5433 user=postgres"
+            .parse::<Config>()
+            .unwrap();
+        let (client, connection) = config.connect_uring(NoTls).await.unwrap();
+        let connection = tokio_uring::spawn(connection);
+
+        let pid = client
+            .query_one("SELECT pg_backend_pid()", &[])
+            .await
+            .unwrap()
+            .get::<_, i32>(0);
+        let other = connect("host=localhost port=5433 user=postgres").await;
+        other
+            .execute("SELECT pg_terminate_backend($1)", &[&pid])
+            .await
+            .unwrap();
+
+        assert!(client.simple_query("SELECT 1").await.is_err());
+        assert!(connection.await.unwrap().is_err());
+    });
+}